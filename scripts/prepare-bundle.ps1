@@ -148,6 +148,12 @@ try {
     Remove-Item -Recurse -Force $DeployDir -ErrorAction SilentlyContinue
 }
 
+# --- Step 6: Argument policy ---
+# Signed later in CI (tauri signer sign); unsigned copies are ignored at runtime.
+Copy-Item (Join-Path $AppDir "src-tauri\policy\arg-policy.json") (Join-Path $ResourcesDir "arg-policy.json")
+Remove-Item -Force (Join-Path $ResourcesDir "arg-policy.json.sig") -ErrorAction SilentlyContinue
+Write-Host "    arg-policy.json copied"
+
 # --- Summary ---
 Write-Host ""
 Write-Host "=== Bundle Preparation Complete ==="
@@ -168,6 +168,12 @@ rm -rf "$RESOURCES_DIR/openclaw/node_modules"
 cp -r "$DEPLOY_DIR/node_modules" "$RESOURCES_DIR/openclaw/node_modules"
 echo "    node_modules: $(du -sh "$RESOURCES_DIR/openclaw/node_modules" | cut -f1)"
 
+# --- Step 6: Argument policy ---
+# Signed later in CI (tauri signer sign); unsigned copies are ignored at runtime.
+cp "$APP_DIR/src-tauri/policy/arg-policy.json" "$RESOURCES_DIR/arg-policy.json"
+rm -f "$RESOURCES_DIR/arg-policy.json.sig"
+echo "    arg-policy.json copied"
+
 # --- Summary ---
 echo ""
 echo "=== Bundle Summary ==="
@@ -0,0 +1,358 @@
+/// Streaming ANSI/control-sequence stripper.
+/// Keeps parser state across chunks so sequences split between reads are
+/// still removed, and assembles the remaining text into lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    /// ESC followed by an intermediate byte (e.g. charset selection `ESC ( B`).
+    EscapeIntermediate,
+    Csi,
+    /// OSC / DCS / SOS / PM / APC strings, terminated by BEL or ST.
+    String,
+    StringEscape,
+}
+
+pub struct AnsiStripper {
+    state: State,
+    line: String,
+    /// Set after a lone CR; the next printable character starts the line over
+    /// (progress bars and spinners redraw in place).
+    pending_cr: bool,
+}
+
+impl AnsiStripper {
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            line: String::new(),
+            pending_cr: false,
+        }
+    }
+
+    /// Returns the current incomplete line, if any.
+    pub fn partial(&self) -> Option<&str> {
+        if self.line.is_empty() {
+            None
+        } else {
+            Some(&self.line)
+        }
+    }
+
+    /// Feeds a chunk of terminal output and returns the lines it completed.
+    pub fn feed(&mut self, text: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        for c in text.chars() {
+            match self.state {
+                State::Ground => self.ground(c, &mut lines),
+                State::Escape => {
+                    self.state = match c {
+                        '[' => State::Csi,
+                        ']' | 'P' | 'X' | '^' | '_' => State::String,
+                        ' '..='/' => State::EscapeIntermediate,
+                        _ => State::Ground,
+                    };
+                }
+                State::EscapeIntermediate => {
+                    if !(' '..='/').contains(&c) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::Csi => {
+                    // Final byte ends the sequence; parameters/intermediates continue it.
+                    if ('@'..='~').contains(&c) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::String => match c {
+                    '\x07' => self.state = State::Ground,
+                    '\x1b' => self.state = State::StringEscape,
+                    _ => {}
+                },
+                State::StringEscape => {
+                    self.state = if c == '\\' { State::Ground } else { State::String };
+                }
+            }
+        }
+        lines
+    }
+
+    fn ground(&mut self, c: char, lines: &mut Vec<String>) {
+        match c {
+            '\x1b' => self.state = State::Escape,
+            '\u{9b}' => self.state = State::Csi,
+            '\n' => {
+                self.pending_cr = false;
+                lines.push(std::mem::take(&mut self.line));
+            }
+            '\r' => self.pending_cr = true,
+            '\x08' => {
+                self.line.pop();
+            }
+            '\t' => self.push(c),
+            c if c.is_control() => {}
+            c => self.push(c),
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if self.pending_cr {
+            self.line.clear();
+            self.pending_cr = false;
+        }
+        self.line.push(c);
+    }
+}
+
+/// DEC private mode for bracketed paste (`CSI ? 2004 h` / `l`).
+const BRACKETED_PASTE_MODE: &str = "2004";
+/// Longest parameter list kept while parsing a mode sequence.
+const MAX_MODE_PARAMS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModeState {
+    Ground,
+    Escape,
+    Csi,
+    /// Inside `CSI ?`, collecting parameters.
+    Private,
+}
+
+/// Tracks terminal modes the application toggles with DEC private mode
+/// sequences (`CSI ? Pm h` / `CSI ? Pm l`), across chunk boundaries.
+pub struct ModeTracker {
+    state: ModeState,
+    params: String,
+    bracketed_paste: bool,
+}
+
+impl ModeTracker {
+    pub fn new() -> Self {
+        Self {
+            state: ModeState::Ground,
+            params: String::new(),
+            bracketed_paste: false,
+        }
+    }
+
+    /// True while the application has bracketed paste enabled.
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    pub fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            self.state = match (self.state, c) {
+                (_, '\x1b') => ModeState::Escape,
+                (ModeState::Ground, '\u{9b}') => ModeState::Csi,
+                (ModeState::Ground, _) => ModeState::Ground,
+                (ModeState::Escape, '[') => ModeState::Csi,
+                (ModeState::Escape, _) => ModeState::Ground,
+                (ModeState::Csi, '?') => {
+                    self.params.clear();
+                    ModeState::Private
+                }
+                (ModeState::Csi, _) => ModeState::Ground,
+                (ModeState::Private, '0'..='9' | ';') if self.params.len() < MAX_MODE_PARAMS => {
+                    self.params.push(c);
+                    ModeState::Private
+                }
+                (ModeState::Private, 'h' | 'l') => {
+                    self.apply(c == 'h');
+                    ModeState::Ground
+                }
+                (ModeState::Private, _) => ModeState::Ground,
+            };
+        }
+    }
+
+    fn apply(&mut self, enabled: bool) {
+        for param in self.params.split(';') {
+            if param == BRACKETED_PASTE_MODE {
+                self.bracketed_paste = enabled;
+            }
+        }
+    }
+}
+
+/// Longest OSC string kept while scanning for events.
+const MAX_OSC_LEN: usize = 4096;
+/// Longest hyperlink text kept; the rest of the text is dropped.
+const MAX_LINK_TEXT: usize = 1024;
+
+/// Out-of-band signals carried in a session's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermEvent {
+    /// A bare BEL (0x07).
+    Bell,
+    /// A desktop notification request (OSC 9 or OSC 777 `notify`).
+    Notify(String),
+    /// A window title change (OSC 0 or OSC 2).
+    Title(String),
+    /// An OSC 8 hyperlink, reported once its closing sequence arrives.
+    Hyperlink { url: String, text: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+    /// DCS / SOS / PM / APC strings, skipped until BEL or ST.
+    Ignored,
+    IgnoredEscape,
+}
+
+/// Picks bells and OSC strings out of terminal output, across chunk
+/// boundaries. A BEL that terminates an OSC string is not a bell.
+pub struct EventScanner {
+    state: ScanState,
+    osc: String,
+    /// Open OSC 8 hyperlink: its URL and the text printed so far.
+    link: Option<(String, String)>,
+}
+
+impl EventScanner {
+    pub fn new() -> Self {
+        Self {
+            state: ScanState::Ground,
+            osc: String::new(),
+            link: None,
+        }
+    }
+
+    pub fn feed(&mut self, text: &str) -> Vec<TermEvent> {
+        let mut events = Vec::new();
+        for c in text.chars() {
+            self.state = match (self.state, c) {
+                (ScanState::Ground, '\x07') => {
+                    events.push(TermEvent::Bell);
+                    ScanState::Ground
+                }
+                (ScanState::Ground | ScanState::Escape | ScanState::Csi, '\x1b') => ScanState::Escape,
+                (ScanState::Ground, '\u{9b}') => ScanState::Csi,
+                (ScanState::Ground, _) => {
+                    if let Some((_, text)) = self.link.as_mut() {
+                        if !c.is_control() && text.len() < MAX_LINK_TEXT {
+                            text.push(c);
+                        }
+                    }
+                    ScanState::Ground
+                }
+                (ScanState::Escape, '[') => ScanState::Csi,
+                (ScanState::Escape, ']') => {
+                    self.osc.clear();
+                    ScanState::Osc
+                }
+                (ScanState::Escape, 'P' | 'X' | '^' | '_') => ScanState::Ignored,
+                (ScanState::Escape, _) => ScanState::Ground,
+                (ScanState::Csi, '@'..='~') => ScanState::Ground,
+                (ScanState::Csi, _) => ScanState::Csi,
+                (ScanState::Osc, '\x07') => {
+                    events.extend(self.finish_osc());
+                    ScanState::Ground
+                }
+                (ScanState::Osc, '\x1b') => ScanState::OscEscape,
+                (ScanState::Osc, _) => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(c);
+                    }
+                    ScanState::Osc
+                }
+                (ScanState::OscEscape, '\\') => {
+                    events.extend(self.finish_osc());
+                    ScanState::Ground
+                }
+                (ScanState::OscEscape, _) => ScanState::Osc,
+                (ScanState::Ignored, '\x07') => ScanState::Ground,
+                (ScanState::Ignored, '\x1b') => ScanState::IgnoredEscape,
+                (ScanState::Ignored, _) => ScanState::Ignored,
+                (ScanState::IgnoredEscape, '\\') => ScanState::Ground,
+                (ScanState::IgnoredEscape, _) => ScanState::Ignored,
+            };
+        }
+        events
+    }
+
+    fn finish_osc(&mut self) -> Option<TermEvent> {
+        let osc = std::mem::take(&mut self.osc);
+        let (command, payload) = osc.split_once(';')?;
+        match command {
+            "0" | "2" => Some(TermEvent::Title(payload.to_string())),
+            // `8;params;url` opens a link; an empty url closes it.
+            "8" => {
+                let url = payload.split_once(';').map(|(_, url)| url).unwrap_or("");
+                let finished = self.link.take().map(|(url, text)| TermEvent::Hyperlink { url, text });
+                if !url.is_empty() {
+                    self.link = Some((url.to_string(), String::new()));
+                }
+                finished
+            }
+            // `9;4;...` is ConEmu's progress report, not a notification.
+            "9" if !payload.starts_with("4;") => Some(TermEvent::Notify(payload.to_string())),
+            "777" => {
+                let mut parts = payload.splitn(3, ';');
+                if parts.next() != Some("notify") {
+                    return None;
+                }
+                let title = parts.next().unwrap_or("");
+                let body = parts.next().unwrap_or("");
+                let message = match (title.is_empty(), body.is_empty()) {
+                    (false, false) => format!("{}: {}", title, body),
+                    (false, true) => title.to_string(),
+                    _ => body.to_string(),
+                };
+                Some(TermEvent::Notify(message))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(chunks: &[&str]) -> Vec<String> {
+        let mut stripper = AnsiStripper::new();
+        chunks.iter().flat_map(|chunk| stripper.feed(chunk)).collect()
+    }
+
+    #[test]
+    fn strips_colors_and_cursor_movement() {
+        assert_eq!(strip(&["\x1b[1;31merror\x1b[0m: \x1b[2Kfailed\x1b[?25l\n"]), ["error: failed"]);
+        assert_eq!(strip(&["\u{9b}32mok\n"]), ["ok"]);
+        assert_eq!(strip(&["\x1b(Bcharset\x1b=\n"]), ["charset"]);
+    }
+
+    #[test]
+    fn strips_osc_strings_with_either_terminator() {
+        assert_eq!(strip(&["\x1b]0;title\x07a\x1b]8;;https://x.test\x1b\\b\x1b]8;;\x1b\\\n"]), ["ab"]);
+        assert_eq!(strip(&["\x1bPdevice control\x1b\\text\n"]), ["text"]);
+    }
+
+    #[test]
+    fn keeps_state_across_chunks() {
+        assert_eq!(strip(&["one\x1b", "[3", "1mtwo\x1b]0;ti", "tle\x07three\n"]), ["onetwothree"]);
+    }
+
+    #[test]
+    fn applies_carriage_returns_and_backspaces() {
+        assert_eq!(strip(&["10%\r50%\r100%\n"]), ["100%"]);
+        assert_eq!(strip(&["line\r\n"]), ["line"]);
+        assert_eq!(strip(&["tyop\x08\x08po\n"]), ["typo"]);
+        assert_eq!(strip(&["a\tb\x01\n"]), ["a\tb"]);
+    }
+
+    #[test]
+    fn holds_the_unfinished_line() {
+        let mut stripper = AnsiStripper::new();
+        assert!(stripper.partial().is_none());
+        assert_eq!(stripper.feed("done\npart"), ["done"]);
+        assert_eq!(stripper.partial(), Some("part"));
+        assert_eq!(stripper.feed("ial\n"), ["partial"]);
+        assert!(stripper.partial().is_none());
+    }
+}
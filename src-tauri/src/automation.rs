@@ -0,0 +1,166 @@
+//! Optional local REST API for scripting the app (shell scripts, Raycast,
+//! Alfred). Off by default; when enabled it listens on loopback only and
+//! every request needs `Authorization: Bearer <token>`, where the token is
+//! kept in `automation-token` in the settings dir.
+//!
+//! - `GET /v1/status` — app version, gateway health, session count
+//! - `GET /v1/sessions` — live sessions, as `pty_list` returns them
+//! - `POST /v1/sessions` — `{ "mode": LaunchMode, "profile"?, "cols"?, "rows"? }`
+//!   spawns a session, subject to the same argument policy as the UI
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::health::GatewayMonitor;
+use crate::http::{self, LoopbackServer, Request};
+use crate::openclaw::LaunchMode;
+use crate::pty_manager::SessionOptions;
+use crate::settings::{self, Settings};
+use crate::AppState;
+
+const DEFAULT_PORT: u16 = 18780;
+const TOKEN_FILE: &str = "automation-token";
+const DEFAULT_COLS: u16 = 120;
+const DEFAULT_ROWS: u16 = 40;
+/// Request bodies are small JSON objects (see `SpawnRequest`).
+const MAX_BODY_BYTES: usize = 8 * 1024;
+
+/// Reported by `automation_api_info` so the settings UI can show how to
+/// call the API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationInfo {
+    pub running: bool,
+    pub url: Option<String>,
+    pub token_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpawnRequest {
+    mode: LaunchMode,
+    profile: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+pub struct AutomationApi {
+    server: Mutex<Option<LoopbackServer>>,
+}
+
+fn token_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join(TOKEN_FILE))
+}
+
+impl AutomationApi {
+    pub fn new() -> Self {
+        Self {
+            server: Mutex::new(None),
+        }
+    }
+
+    /// Starts, stops or moves the server to match `settings`.
+    pub fn apply(&self, app: &AppHandle, settings: &Settings) -> Result<(), String> {
+        let wanted = settings.automation_api.then(|| settings.automation_api_port.unwrap_or(DEFAULT_PORT));
+        let mut server = self.server.lock().map_err(|e| e.to_string())?;
+        if server.as_ref().map(LoopbackServer::port) == wanted {
+            return Ok(());
+        }
+        // Dropping the old server stops it.
+        *server = None;
+        let Some(port) = wanted else { return Ok(()) };
+        let token = http::load_or_create_token(&token_path()?)?;
+        let app = app.clone();
+        let started = LoopbackServer::start("automation", port, move |stream| serve(&app, stream, &token))?;
+        *server = Some(started);
+        Ok(())
+    }
+
+    pub fn info(&self) -> Result<AutomationInfo, String> {
+        let port = self.server.lock().map_err(|e| e.to_string())?.as_ref().map(LoopbackServer::port);
+        Ok(AutomationInfo {
+            running: port.is_some(),
+            url: port.map(|p| format!("http://127.0.0.1:{}", p)),
+            token_path: token_path()?.to_string_lossy().into_owned(),
+        })
+    }
+}
+
+fn respond_json(stream: &mut TcpStream, status: &str, body: &impl Serialize) -> Result<(), String> {
+    let body = serde_json::to_vec(body).map_err(|e| e.to_string())?;
+    http::respond(stream, status, "", "application/json", &body)
+}
+
+fn serve(app: &AppHandle, stream: &mut TcpStream, token: &str) -> Result<(), String> {
+    let mut request = match http::read_request(stream) {
+        Ok(request) => request,
+        Err(e) => return respond_json(stream, "400 Bad Request", &json!({ "error": e })),
+    };
+    // Browsers attach an Origin; refuse them so web pages can't drive the API.
+    if request.header("origin").is_some() {
+        return respond_json(stream, "403 Forbidden", &json!({ "error": "Cross-origin requests are not allowed" }));
+    }
+    if !http::tokens_match(request.bearer_token(), token) {
+        return respond_json(stream, "401 Unauthorized", &json!({ "error": "Missing or invalid token" }));
+    }
+    if let Err(e) = request.read_body(MAX_BODY_BYTES) {
+        return respond_json(stream, "413 Payload Too Large", &json!({ "error": e }));
+    }
+    let path = request.target.split('?').next().unwrap_or("");
+    match (request.method.as_str(), path) {
+        ("GET", "/v1/status") => respond_json(stream, "200 OK", &status(app)),
+        ("GET", "/v1/sessions") => match app.state::<AppState>().pty.list() {
+            Ok(sessions) => respond_json(stream, "200 OK", &sessions),
+            Err(e) => respond_json(stream, "500 Internal Server Error", &json!({ "error": e })),
+        },
+        ("POST", "/v1/sessions") => spawn(app, stream, &request),
+        (_, "/v1/status" | "/v1/sessions") => {
+            respond_json(stream, "405 Method Not Allowed", &json!({ "error": "Method not allowed" }))
+        }
+        _ => respond_json(stream, "404 Not Found", &json!({ "error": "Not found" })),
+    }
+}
+
+fn status(app: &AppHandle) -> serde_json::Value {
+    let sessions = app.state::<AppState>().pty.list().map(|s| s.len()).unwrap_or(0);
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "sessions": sessions,
+        "gateways": app.state::<GatewayMonitor>().current(),
+    })
+}
+
+fn spawn(app: &AppHandle, stream: &mut TcpStream, request: &Request) -> Result<(), String> {
+    let body: SpawnRequest = match serde_json::from_slice(&request.body) {
+        Ok(body) => body,
+        Err(e) => return respond_json(stream, "400 Bad Request", &json!({ "error": e.to_string() })),
+    };
+    let settings = match app.state::<AppState>().settings.lock() {
+        Ok(settings) => settings.clone(),
+        Err(e) => return respond_json(stream, "500 Internal Server Error", &json!({ "error": e.to_string() })),
+    };
+    let launched = crate::launch_session(
+        app,
+        settings,
+        body.mode,
+        body.profile.as_deref(),
+        body.cols.unwrap_or(DEFAULT_COLS),
+        body.rows.unwrap_or(DEFAULT_ROWS),
+        SessionOptions {
+            hold_output: true,
+            ..Default::default()
+        },
+    );
+    // Nothing is attached; output goes to the scrollback until a UI reattaches.
+    let launched = launched.and_then(|id| {
+        app.state::<AppState>().pty.release_output(id)?;
+        Ok(id)
+    });
+    match launched {
+        Ok(session_id) => respond_json(stream, "201 Created", &json!({ "sessionId": session_id })),
+        Err(e) => respond_json(stream, "422 Unprocessable Entity", &json!({ "error": e })),
+    }
+}
@@ -0,0 +1,133 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::ansi::AnsiStripper;
+
+/// What a rule does when its pattern matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoAction {
+    /// Write `text` to the session, verbatim (use "\r" for Enter).
+    Write,
+    /// Raise a notification with `text` (or the matched line) as its body.
+    Notify,
+}
+
+/// A user-defined rule reacting to session output, stored in settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoResponseRule {
+    /// Regular expression matched against ANSI-stripped output lines,
+    /// including the incomplete line a prompt sits on.
+    pub pattern: String,
+    pub action: AutoAction,
+    #[serde(default)]
+    pub text: String,
+    /// Only apply to sessions running this subcommand (e.g. "onboard").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subcommand: Option<String>,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+pub struct CompiledRule {
+    rule: AutoResponseRule,
+    regex: Regex,
+}
+
+/// Rules shared between the manager and every session, so edits apply to
+/// sessions that are already running.
+pub type SharedRules = Arc<Mutex<Arc<Vec<CompiledRule>>>>;
+
+/// Compiles the enabled rules, failing on the first invalid pattern.
+pub fn compile(rules: &[AutoResponseRule]) -> Result<Vec<CompiledRule>, String> {
+    rules
+        .iter()
+        .filter(|r| r.enabled)
+        .map(|rule| {
+            let regex = Regex::new(&rule.pattern)
+                .map_err(|e| format!("Invalid auto-response pattern '{}': {}", rule.pattern, e))?;
+            Ok(CompiledRule {
+                rule: rule.clone(),
+                regex,
+            })
+        })
+        .collect()
+}
+
+/// A rule that matched.
+pub struct Fired {
+    pub pattern: String,
+    pub action: AutoAction,
+    /// Bytes to write, or the notification body.
+    pub text: String,
+}
+
+/// Applies the shared rules to one session's output. Each rule fires at
+/// most once per line, so a prompt that is redrawn or echoes the response
+/// doesn't trigger it again.
+pub struct AutoResponder {
+    rules: SharedRules,
+    subcommand: Option<String>,
+    stripper: AnsiStripper,
+    /// Indices of rules that already fired on the current (partial) line.
+    fired: HashSet<usize>,
+}
+
+impl AutoResponder {
+    pub fn new(rules: SharedRules, args: &[String]) -> Self {
+        Self {
+            rules,
+            subcommand: args.first().cloned(),
+            stripper: AnsiStripper::new(),
+            fired: HashSet::new(),
+        }
+    }
+
+    pub fn feed(&mut self, data: &str) -> Vec<Fired> {
+        let rules = match self.rules.lock() {
+            Ok(rules) => Arc::clone(&rules),
+            Err(_) => return Vec::new(),
+        };
+        if rules.is_empty() {
+            return Vec::new();
+        }
+        let mut fired = Vec::new();
+        for line in self.stripper.feed(data) {
+            self.check(&rules, &line, &mut fired);
+            self.fired.clear();
+        }
+        if let Some(partial) = self.stripper.partial().map(str::to_string) {
+            self.check(&rules, &partial, &mut fired);
+        }
+        fired
+    }
+
+    fn check(&mut self, rules: &[CompiledRule], line: &str, fired: &mut Vec<Fired>) {
+        for (index, compiled) in rules.iter().enumerate() {
+            let rule = &compiled.rule;
+            if self.fired.contains(&index)
+                || rule.subcommand.as_ref().is_some_and(|s| Some(s) != self.subcommand.as_ref())
+                || !compiled.regex.is_match(line)
+            {
+                continue;
+            }
+            self.fired.insert(index);
+            let text = match rule.action {
+                AutoAction::Notify if rule.text.is_empty() => line.trim().to_string(),
+                _ => rule.text.clone(),
+            };
+            fired.push(Fired {
+                pattern: rule.pattern.clone(),
+                action: rule.action,
+                text,
+            });
+        }
+    }
+}
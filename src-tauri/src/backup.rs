@@ -0,0 +1,408 @@
+//! Zip backups of the OpenClaw state dir, and restoring from them.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::CONFIG_FILE;
+use crate::gatewaylock;
+use crate::openclaw;
+use crate::state_crypt;
+use crate::AppState;
+
+/// Describes the archive; its presence marks a zip as a state backup.
+const MANIFEST_NAME: &str = "clawrunner-backup.json";
+const BACKUP_VERSION: u32 = 1;
+/// Directories that only hold regenerable data.
+const EXCLUDED_DIRS: &[&str] = &["cache", ".cache", "tmp", "node_modules"];
+/// Limits checked before anything is extracted. Declared sizes can lie, so
+/// extraction enforces the byte limit again on what it actually writes.
+const MAX_RESTORE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+const MAX_RESTORE_ENTRIES: usize = 100_000;
+/// Largest manifest and `openclaw.json` read into memory during validation.
+const MAX_MANIFEST_BYTES: u64 = 64 * 1024;
+const MAX_CONFIG_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: u64,
+    pub app_version: String,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_excluded(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    EXCLUDED_DIRS.contains(&name) || name.ends_with(".tmp") || name == gatewaylock::LOCK_FILE
+}
+
+/// Seals a protected file's contents for the archive.
+type Sealer<'a> = &'a dyn Fn(&Path) -> Result<Vec<u8>, String>;
+
+/// Writes the state dir to a zip at `dest`. Protected files
+/// (`encrypted_files`) sit unsealed while sessions run, so their plaintext
+/// is never archived: each goes in as a freshly sealed `.enc` copy, which
+/// only restores on a machine with the same keychain key.
+pub fn write_archive(app_version: &str, encrypted_files: &[String], dest: &Path) -> Result<(), String> {
+    let state_dir = openclaw::openclaw_state_dir()?;
+    let protected = state_crypt::protected_paths(&state_dir, encrypted_files);
+    archive_dir(&state_dir, app_version, &protected, &state_crypt::seal_contents, dest)
+}
+
+fn archive_dir(
+    state_dir: &Path,
+    app_version: &str,
+    protected: &[PathBuf],
+    seal: Sealer,
+    dest: &Path,
+) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| format!("Cannot create {}: {}", dest.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o600);
+
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: now_secs(),
+        app_version: app_version.to_string(),
+    };
+    zip.start_file(MANIFEST_NAME, options).map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    add_dir(&mut zip, state_dir, state_dir, options, protected, seal)?;
+    zip.finish().map_err(|e| format!("Cannot write {}: {}", dest.display(), e))?;
+    Ok(())
+}
+
+fn add_dir(
+    zip: &mut ZipWriter<File>,
+    root: &Path,
+    dir: &Path,
+    options: SimpleFileOptions,
+    protected: &[PathBuf],
+    seal: Sealer,
+) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Cannot read {:?}: {}", dir, e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_symlink() || is_excluded(&path) {
+            continue;
+        }
+        let Ok(rel) = path.strip_prefix(root) else { continue };
+        // Zip entry names always use forward slashes.
+        let name = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        if file_type.is_dir() {
+            zip.add_directory(format!("state/{}", name), options.unix_permissions(0o700))
+                .map_err(|e| e.to_string())?;
+            add_dir(zip, root, &path, options, protected, seal)?;
+        } else if protected.contains(&path) {
+            let sealed = seal(&path)?;
+            zip.start_file(format!("state/{}{}", name, state_crypt::SEALED_SUFFIX), options)
+                .map_err(|e| e.to_string())?;
+            zip.write_all(&sealed).map_err(|e| format!("Cannot archive {:?}: {}", path, e))?;
+        } else if protected.iter().any(|p| p.exists() && state_crypt::sealed_path(p) == path) {
+            // Stale next to its plaintext; the fresh copy is archived above.
+            continue;
+        } else if file_type.is_file() {
+            let mut file = File::open(&path).map_err(|e| format!("Cannot read {:?}: {}", path, e))?;
+            zip.start_file(format!("state/{}", name), options).map_err(|e| e.to_string())?;
+            std::io::copy(&mut file, zip).map_err(|e| format!("Cannot archive {:?}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `path` is a state backup that is safe to extract: it has a
+/// manifest of a known version, every entry stays inside the state dir,
+/// its size is within limits, and its `openclaw.json` (if any) parses.
+pub fn validate_archive(path: &Path) -> Result<BackupManifest, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a valid backup: {}", e))?;
+    if zip.len() > MAX_RESTORE_ENTRIES {
+        return Err("Backup has too many entries".to_string());
+    }
+
+    let manifest: BackupManifest = {
+        let entry = zip
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| "Not a ClawRunner state backup (no manifest)".to_string())?;
+        let data = read_bounded(entry, MAX_MANIFEST_BYTES, MANIFEST_NAME)?;
+        serde_json::from_slice(&data).map_err(|e| format!("Invalid backup manifest: {}", e))?
+    };
+    if manifest.version != BACKUP_VERSION {
+        return Err(format!("Unsupported backup version: {}", manifest.version));
+    }
+
+    let mut total = 0u64;
+    for i in 0..zip.len() {
+        let entry = zip.by_index(i).map_err(|e| e.to_string())?;
+        if entry.name() == MANIFEST_NAME {
+            continue;
+        }
+        let safe = entry.enclosed_name().is_some_and(|p| p.starts_with("state"));
+        if !safe {
+            return Err(format!("Unsafe path in backup: {}", entry.name()));
+        }
+        total = total.saturating_add(entry.size());
+    }
+    if total > MAX_RESTORE_BYTES {
+        return Err("Backup is too large to restore".to_string());
+    }
+
+    if let Ok(config) = zip.by_name(&format!("state/{}", CONFIG_FILE)) {
+        let data = read_bounded(config, MAX_CONFIG_BYTES, CONFIG_FILE)?;
+        serde_json::from_slice::<serde_json::Value>(&data)
+            .map_err(|e| format!("Backup has an invalid {}: {}", CONFIG_FILE, e))?;
+    }
+    Ok(manifest)
+}
+
+/// Reads an archive entry into memory, failing if it holds more than
+/// `limit` bytes whatever its declared size.
+fn read_bounded(entry: impl Read, limit: u64, name: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Cannot read {}: {}", name, e))?;
+    if data.len() as u64 > limit {
+        return Err(format!("Backup has an oversized {}", name));
+    }
+    Ok(data)
+}
+
+/// Replaces the state dir with the contents of a validated backup. The
+/// current state is kept as `openclaw-state.pre-restore` until the next
+/// restore. Callers must make sure no OpenClaw process is using it.
+pub fn restore_archive(path: &Path) -> Result<BackupManifest, String> {
+    let manifest = validate_archive(path)?;
+    let state_dir = openclaw::openclaw_state_dir()?;
+    let staging = sibling(&state_dir, "restore");
+    let previous = sibling(&state_dir, "pre-restore");
+
+    let _ = std::fs::remove_dir_all(&staging);
+    if let Err(e) = extract(path, &staging) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let _ = std::fs::remove_dir_all(&previous);
+    std::fs::rename(&state_dir, &previous).map_err(|e| format!("Cannot move current state aside: {}", e))?;
+    if let Err(e) = std::fs::rename(&staging, &state_dir) {
+        let _ = std::fs::rename(&previous, &state_dir);
+        return Err(format!("Cannot move restored state into place: {}", e));
+    }
+    // Re-applies the state dir's permissions.
+    openclaw::openclaw_state_dir()?;
+    Ok(manifest)
+}
+
+/// `<state dir>.<suffix>`, next to the state dir.
+fn sibling(state_dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = state_dir.as_os_str().to_owned();
+    name.push(format!(".{}", suffix));
+    PathBuf::from(name)
+}
+
+fn extract(path: &Path, dest: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(dest).map_err(|e| format!("Cannot create {:?}: {}", dest, e))?;
+    let mut remaining = MAX_RESTORE_BYTES;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| e.to_string())?;
+        let Some(rel) = entry.enclosed_name() else { continue };
+        let Ok(rel) = rel.strip_prefix("state") else { continue };
+        if rel.as_os_str().is_empty() {
+            continue;
+        }
+        let out = dest.join(rel);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&out).map_err(|e| format!("Cannot create {:?}: {}", out, e))?;
+            continue;
+        }
+        if let Some(parent) = out.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {:?}: {}", parent, e))?;
+        }
+        let mut file = private_file(&out)?;
+        // One byte past the limit tells an entry that overflows it apart
+        // from one that fills it exactly.
+        let written = std::io::copy(&mut (&mut entry).take(remaining + 1), &mut file)
+            .map_err(|e| format!("Cannot extract {:?}: {}", out, e))?;
+        if written > remaining {
+            return Err("Backup is too large to restore".to_string());
+        }
+        remaining -= written;
+    }
+    Ok(())
+}
+
+fn private_file(path: &Path) -> Result<File, String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path).map_err(|e| format!("Cannot create {:?}: {}", path, e))
+}
+
+/// Asks for a destination and writes a backup there. Returns the path, or
+/// `None` if the dialog was cancelled. Blocks on the dialog and the zip, so
+/// call it off the async runtime.
+pub fn backup_with_dialog(app: &AppHandle) -> Result<Option<String>, String> {
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title("Back Up OpenClaw State")
+        .set_file_name(format!("openclaw-state-{}.zip", now_secs()))
+        .add_filter("Zip archive", &["zip"])
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let path: PathBuf = path.into_path().map_err(|e| format!("Invalid backup path: {}", e))?;
+    let version = app.package_info().version.to_string();
+    let encrypted_files = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .encrypted_state_files
+        .clone();
+    write_archive(&version, &encrypted_files, &path)?;
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(version: u32) -> Vec<u8> {
+        serde_json::to_vec(&BackupManifest {
+            version,
+            created_at: 0,
+            app_version: "0.0.0".to_string(),
+        })
+        .unwrap()
+    }
+
+    /// Writes a zip with the given entries to a fresh temp file.
+    fn archive(name: &str, entries: &[(&str, &[u8])]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("clawrunner-{}-{}.zip", name, std::process::id()));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (entry, data) in entries {
+            zip.start_file(*entry, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    fn validate(name: &str, entries: &[(&str, &[u8])]) -> Result<BackupManifest, String> {
+        let path = archive(name, entries);
+        let result = validate_archive(&path);
+        let _ = std::fs::remove_file(&path);
+        result
+    }
+
+    #[test]
+    fn accepts_a_well_formed_backup() {
+        let manifest = manifest(BACKUP_VERSION);
+        let config = format!("state/{}", CONFIG_FILE);
+        let result = validate(
+            "backup-ok",
+            &[(MANIFEST_NAME, &manifest), (&config, b"{}"), ("state/agents/notes.md", b"hi")],
+        );
+        assert_eq!(result.unwrap().version, BACKUP_VERSION);
+    }
+
+    #[test]
+    fn requires_a_known_manifest() {
+        assert!(validate("backup-no-manifest", &[("state/a.txt", b"x")]).is_err());
+        let future = manifest(BACKUP_VERSION + 1);
+        assert!(validate("backup-future", &[(MANIFEST_NAME, &future)]).is_err());
+        assert!(validate("backup-bad-manifest", &[(MANIFEST_NAME, b"not json")]).is_err());
+    }
+
+    #[test]
+    fn rejects_entries_outside_the_state_dir() {
+        let manifest = manifest(BACKUP_VERSION);
+        for entry in ["../evil", "state/../../evil", "/etc/evil", "other/file", "statefoo/file"] {
+            let result = validate("backup-unsafe", &[(MANIFEST_NAME, &manifest), (entry, b"x")]);
+            assert!(result.is_err(), "{}", entry);
+        }
+    }
+
+    #[test]
+    fn rejects_an_invalid_config() {
+        let manifest = manifest(BACKUP_VERSION);
+        let config = format!("state/{}", CONFIG_FILE);
+        assert!(validate("backup-bad-config", &[(MANIFEST_NAME, &manifest), (&config, b"{ nope")]).is_err());
+    }
+
+    #[test]
+    fn rejects_an_oversized_manifest() {
+        let padding = vec![b' '; MAX_MANIFEST_BYTES as usize + 1];
+        let mut manifest = manifest(BACKUP_VERSION);
+        manifest.extend(padding);
+        assert!(validate("backup-big-manifest", &[(MANIFEST_NAME, &manifest)]).is_err());
+    }
+
+    #[test]
+    fn archives_protected_files_only_sealed() {
+        let root = std::env::temp_dir().join(format!("clawrunner-backup-sealed-{}", std::process::id()));
+        let state_dir = root.join("state");
+        std::fs::create_dir_all(state_dir.join("agents")).unwrap();
+        let secret = state_dir.join("agents").join("auth.json");
+        std::fs::write(&secret, b"TOPSECRET").unwrap();
+        std::fs::write(state_crypt::sealed_path(&secret), b"stale").unwrap();
+        std::fs::write(state_dir.join("notes.md"), b"plain").unwrap();
+        let dest = root.join("backup.zip");
+
+        let seal = |_: &Path| Ok(b"sealed".to_vec());
+        archive_dir(&state_dir, "0.0.0", std::slice::from_ref(&secret), &seal, &dest).unwrap();
+
+        let mut zip = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        let mut sealed_entries = 0;
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i).unwrap();
+            let name = entry.name().to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            assert_ne!(name, "state/agents/auth.json");
+            assert!(!data.windows(9).any(|w| w == b"TOPSECRET"), "{}", name);
+            if name == "state/agents/auth.json.enc" {
+                assert_eq!(data, b"sealed");
+                sealed_entries += 1;
+            }
+        }
+        assert_eq!(sealed_entries, 1);
+        assert!(zip.by_name("state/notes.md").is_ok());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn rejects_a_file_that_is_not_a_zip() {
+        let path = std::env::temp_dir().join(format!("clawrunner-not-zip-{}.zip", std::process::id()));
+        std::fs::write(&path, b"plain text").unwrap();
+        assert!(validate_archive(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
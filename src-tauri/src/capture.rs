@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::settings;
+
+/// Maximum size of a single capture file (16 MB). Bytes past the cap are dropped.
+const MAX_CAPTURE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Tees a session's unmodified PTY byte stream into a file under
+/// <data dir>/captures, for reproducing escape-sequence and encoding bugs.
+/// Captures can contain anything the session printed, so files are 0600.
+pub struct RawCapture {
+    file: File,
+    path: PathBuf,
+    written: u64,
+}
+
+impl RawCapture {
+    pub fn start(session_id: u64) -> Result<Self, String> {
+        let dir = settings::ensure_settings_dir()?.join("captures");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Cannot create capture dir: {}", e))?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("session-{}-{}.raw", session_id, stamp));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&path)
+            .map_err(|e| format!("Cannot create capture file: {}", e))?;
+        Ok(Self { file, path, written: 0 })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Appends raw bytes, up to the size cap. Write errors are ignored so a
+    /// full disk never disturbs the session itself.
+    pub fn write(&mut self, data: &[u8]) {
+        let room = MAX_CAPTURE_SIZE.saturating_sub(self.written) as usize;
+        let chunk = &data[..data.len().min(room)];
+        if chunk.is_empty() {
+            return;
+        }
+        if self.file.write_all(chunk).is_ok() {
+            self.written += chunk.len() as u64;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Command line of the app binary. The flags change how the app starts;
+//! the subcommands (shell completions, a man page and a JSON description
+//! of the interface) print to stdout and exit without starting the app.
+
+use clap::error::ErrorKind;
+use clap::{Arg, ArgAction, Command};
+use clap_complete::Shell;
+use serde_json::{json, Value};
+
+use crate::headless;
+use crate::paths;
+
+const BIN_NAME: &str = "clawrunner";
+
+fn command() -> Command {
+    Command::new(BIN_NAME)
+        .about("Desktop runner for OpenClaw")
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(
+            Arg::new("headless")
+                .long(headless::FLAG.trim_start_matches('-'))
+                .action(ArgAction::SetTrue)
+                .help("Start the gateway in the tray without opening the window"),
+        )
+        .arg(
+            Arg::new("portable")
+                .long(paths::PORTABLE_ARG.trim_start_matches('-'))
+                .action(ArgAction::SetTrue)
+                .help("Keep settings and state in data/ beside the executable"),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a completion script for a shell")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(clap::value_parser!(Shell)),
+                ),
+        )
+        .subcommand(Command::new("man").about("Print the man page (roff)"))
+        .subcommand(Command::new("describe").about("Print the command line interface as JSON"))
+}
+
+/// Handles the command line before the app starts. Returns the exit code
+/// if the process should exit instead of starting. Unrecognized arguments
+/// outside a subcommand are only logged, since the OS may add its own
+/// (e.g. macOS `-psn_…`).
+pub fn handle() -> Option<i32> {
+    let mut cmd = command();
+    let matches = match cmd.try_get_matches_from_mut(std::env::args_os()) {
+        Ok(matches) => matches,
+        Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+            let _ = e.print();
+            return Some(0);
+        }
+        Err(e) if invoked_subcommand(&cmd) => {
+            let _ = e.print();
+            return Some(e.exit_code());
+        }
+        Err(e) => {
+            eprintln!("[cli] Ignoring arguments: {}", e.kind());
+            return None;
+        }
+    };
+
+    let mut out = std::io::stdout();
+    match matches.subcommand() {
+        Some(("completions", sub)) => {
+            let shell = *sub.get_one::<Shell>("shell")?;
+            clap_complete::generate(shell, &mut cmd, BIN_NAME, &mut out);
+        }
+        Some(("man", _)) => {
+            if let Err(e) = clap_mangen::Man::new(cmd).render(&mut out) {
+                eprintln!("[cli] Cannot write man page: {}", e);
+                return Some(1);
+            }
+        }
+        Some(("describe", _)) => {
+            cmd.build();
+            let description = serde_json::to_string_pretty(&describe(&cmd)).unwrap_or_default();
+            println!("{}", description);
+        }
+        _ => return None,
+    }
+    Some(0)
+}
+
+/// Whether the first argument names one of the subcommands, so a parse
+/// error is the user's to see rather than noise from the OS.
+fn invoked_subcommand(cmd: &Command) -> bool {
+    std::env::args()
+        .nth(1)
+        .is_some_and(|first| cmd.get_subcommands().any(|sub| sub.get_name() == first))
+}
+
+/// Machine-readable form of `--help`: every flag, argument and subcommand.
+fn describe(cmd: &Command) -> Value {
+    let args: Vec<Value> = cmd
+        .get_arguments()
+        .map(|arg| {
+            let values: Vec<String> = arg
+                .get_possible_values()
+                .iter()
+                .map(|v| v.get_name().to_string())
+                .collect();
+            json!({
+                "name": arg.get_id().as_str(),
+                "long": arg.get_long(),
+                "short": arg.get_short(),
+                "positional": arg.is_positional(),
+                "required": arg.is_required_set(),
+                "help": arg.get_help().map(|h| h.to_string()),
+                "values": values,
+            })
+        })
+        .collect();
+    json!({
+        "name": cmd.get_name(),
+        "version": cmd.get_version(),
+        "about": cmd.get_about().map(|a| a.to_string()),
+        "args": args,
+        "subcommands": cmd.get_subcommands().map(describe).collect::<Vec<_>>(),
+    })
+}
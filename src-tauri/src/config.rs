@@ -0,0 +1,202 @@
+//! Reads and edits OpenClaw's own configuration (`openclaw.json` in the
+//! state dir), so the frontend can show and change gateway settings
+//! without going through the TUI.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::openclaw;
+use crate::settings::{self, Settings};
+use crate::state_crypt;
+
+pub const CONFIG_FILE: &str = "openclaw.json";
+const MAX_CONFIG_SIZE: u64 = 1024 * 1024;
+
+/// Serializes read-modify-write cycles so concurrent patches don't lose
+/// each other's changes.
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+fn config_path() -> Result<PathBuf, String> {
+    Ok(openclaw::openclaw_state_dir()?.join(CONFIG_FILE))
+}
+
+/// Reads `openclaw.json`, decrypting it first if it is an encrypted state
+/// file. A missing file reads as an empty object.
+fn read(settings: &Settings) -> Result<Value, String> {
+    state_crypt::unseal(&openclaw::openclaw_state_dir()?, &settings.encrypted_state_files)?;
+    let path = config_path()?;
+    let meta = match std::fs::metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Value::Object(Map::new())),
+        Err(e) => return Err(format!("Cannot stat {:?}: {}", path, e)),
+    };
+    if meta.len() > MAX_CONFIG_SIZE {
+        return Err(format!("{} is too large", CONFIG_FILE));
+    }
+    let data = std::fs::read(&path).map_err(|e| format!("Cannot read {:?}: {}", path, e))?;
+    let value: Value =
+        serde_json::from_slice(&data).map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?;
+    if !value.is_object() {
+        return Err(format!("Invalid {}: not a JSON object", CONFIG_FILE));
+    }
+    Ok(value)
+}
+
+/// Returns the current OpenClaw configuration.
+pub fn get(settings: &Settings) -> Result<Value, String> {
+    let _guard = CONFIG_LOCK.lock().map_err(|e| e.to_string())?;
+    read(settings)
+}
+
+/// The token clients authenticate to the gateway with
+/// (`gateway.auth.token`), falling back to `OPENCLAW_GATEWAY_TOKEN`.
+pub fn gateway_token(settings: &Settings) -> Option<String> {
+    get(settings)
+        .ok()
+        .and_then(|c| c.pointer("/gateway/auth/token")?.as_str().map(str::to_string))
+        .filter(|t| !t.is_empty())
+        .or_else(|| std::env::var("OPENCLAW_GATEWAY_TOKEN").ok().filter(|t| !t.is_empty()))
+}
+
+/// Applies a JSON merge patch (RFC 7396: objects merge recursively, `null`
+/// removes a key) and writes the result atomically. Returns the new config.
+pub fn set(settings: &Settings, patch: Value) -> Result<Value, String> {
+    if !patch.is_object() {
+        return Err("Config patch must be a JSON object".to_string());
+    }
+    let _guard = CONFIG_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut config = read(settings)?;
+    merge(&mut config, patch);
+    let data = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    if data.len() as u64 > MAX_CONFIG_SIZE {
+        return Err(format!("{} would be too large", CONFIG_FILE));
+    }
+    settings::write_private_file(&config_path()?, &data)?;
+    Ok(config)
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else { return };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Files in the state dir that make up an OpenClaw setup.
+const STATUS_FILES: &[&str] = &[CONFIG_FILE, ".env", "credentials"];
+
+/// One state file or directory and whether it is present.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFile {
+    pub name: String,
+    pub exists: bool,
+    /// Present only as an encrypted `.enc` copy.
+    pub sealed: bool,
+}
+
+/// What is (and isn't) set up, so the UI can say why setup isn't done.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigStatus {
+    /// `openclaw.json` exists and parses.
+    pub configured: bool,
+    pub files: Vec<ConfigFile>,
+    /// Providers with models or auth profiles configured.
+    pub providers: Vec<String>,
+    /// Channels that are configured and not disabled.
+    pub channels: Vec<String>,
+    /// When `openclaw onboard` last completed (as OpenClaw recorded it).
+    pub last_onboard_at: Option<String>,
+    /// OpenClaw version that last wrote the config.
+    pub schema_version: Option<String>,
+    pub errors: Vec<String>,
+}
+
+/// Inspects the state dir and `openclaw.json`. Problems are reported in
+/// `errors` rather than failing the whole call.
+pub fn status(settings: &Settings) -> ConfigStatus {
+    let mut status = ConfigStatus {
+        configured: false,
+        files: Vec::new(),
+        providers: Vec::new(),
+        channels: Vec::new(),
+        last_onboard_at: None,
+        schema_version: None,
+        errors: Vec::new(),
+    };
+    let state_dir = match openclaw::openclaw_state_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            status.errors.push(e);
+            return status;
+        }
+    };
+    for name in STATUS_FILES {
+        let path = state_dir.join(name);
+        let mut sealed = path.as_os_str().to_owned();
+        sealed.push(".enc");
+        let exists = path.exists();
+        status.files.push(ConfigFile {
+            name: name.to_string(),
+            exists,
+            sealed: !exists && PathBuf::from(sealed).exists(),
+        });
+    }
+    if !status.files.iter().any(|f| f.name == CONFIG_FILE && (f.exists || f.sealed)) {
+        return status;
+    }
+
+    let config = match get(settings) {
+        Ok(config) => config,
+        Err(e) => {
+            status.errors.push(e);
+            return status;
+        }
+    };
+    status.configured = true;
+
+    let mut providers: Vec<String> = config
+        .pointer("/models/providers")
+        .and_then(Value::as_object)
+        .map(|p| p.keys().cloned().collect())
+        .unwrap_or_default();
+    if let Some(profiles) = config.pointer("/auth/profiles").and_then(Value::as_object) {
+        for (id, profile) in profiles {
+            let provider = profile
+                .get("provider")
+                .and_then(Value::as_str)
+                .unwrap_or_else(|| id.split(':').next().unwrap_or(id));
+            providers.push(provider.to_string());
+        }
+    }
+    providers.sort();
+    providers.dedup();
+    status.providers = providers;
+
+    if let Some(channels) = config.get("channels").and_then(Value::as_object) {
+        status.channels = channels
+            .iter()
+            .filter(|(_, channel)| channel.get("enabled").and_then(Value::as_bool) != Some(false))
+            .map(|(name, _)| name.clone())
+            .collect();
+        status.channels.sort();
+    }
+    let text = |pointer: &str| config.pointer(pointer).and_then(Value::as_str).map(str::to_string);
+    status.last_onboard_at = text("/wizard/lastRunAt");
+    status.schema_version = text("/meta/lastTouchedVersion");
+    status
+}
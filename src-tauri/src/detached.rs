@@ -0,0 +1,378 @@
+//! Opt-in detached gateway: spawned outside any PTY, in its own session, so
+//! it keeps running after the app quits. A state file records it so the
+//! next launch can re-adopt it; its output goes to a log file that the
+//! frontend tails instead of a terminal.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::AppHandle;
+
+use crate::external;
+use crate::gatewaylock;
+use crate::openclaw;
+use crate::ports;
+use crate::settings::{self, Settings};
+use crate::state_crypt;
+
+const STATE_FILE: &str = "detached.json";
+const LOG_FILE: &str = "detached-gateway.log";
+const GATEWAY_ARGS: &[&str] = &["gateway"];
+/// Most log output returned by one `read_log` call (64 KB).
+const MAX_LOG_CHUNK: u64 = 64 * 1024;
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A gateway running outside the app's process tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetachedSession {
+    pub pid: u32,
+    pub args: Vec<String>,
+    pub started_at: u64,
+    pub log_path: String,
+    /// Process start time (seconds since the epoch) and executable, checked
+    /// before adopting or signalling `pid` in case it was reused. Missing
+    /// from state files written by older versions, which are never adopted.
+    #[serde(default)]
+    pub process_started_at: u64,
+    #[serde(default)]
+    pub exe: Option<String>,
+}
+
+/// Log output from `offset` on; pass `next_offset` to the next call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogChunk {
+    pub data: String,
+    pub next_offset: u64,
+}
+
+fn state_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join(STATE_FILE))
+}
+
+fn read_state() -> Option<DetachedSession> {
+    let content = std::fs::read_to_string(state_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn clear_state() {
+    if let Ok(path) = state_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Start time and executable of a running process.
+fn identity(pid: u32) -> Option<(u64, Option<String>)> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+    sys.process(pid)
+        .map(|p| (p.start_time(), p.exe().map(|e| e.to_string_lossy().into_owned())))
+}
+
+/// True if the gateway's pid is alive and still belongs to the process that
+/// was started, not to a later one that reused it.
+fn still_running(session: &DetachedSession) -> bool {
+    identity(session.pid).is_some_and(|(started, exe)| {
+        session.process_started_at != 0 && started == session.process_started_at && exe == session.exe
+    })
+}
+
+/// Pid of the detached gateway recorded on disk, if it is still running.
+pub fn recorded_pid() -> Option<u32> {
+    read_state().filter(still_running).map(|s| s.pid)
+}
+
+pub struct Detached {
+    current: Mutex<Option<DetachedSession>>,
+}
+
+impl Detached {
+    /// Re-adopts a gateway left running by a previous launch. A state file
+    /// whose process has since exited, or whose pid now belongs to another
+    /// process, is removed.
+    pub fn adopt() -> Self {
+        let current = match read_state() {
+            Some(session) if still_running(&session) => {
+                eprintln!("[detached] Re-adopted gateway (pid {})", session.pid);
+                Some(session)
+            }
+            Some(_) => {
+                clear_state();
+                None
+            }
+            None => None,
+        };
+        Self {
+            current: Mutex::new(current),
+        }
+    }
+
+    /// The detached gateway, if one is running. Forgets it once it exits.
+    pub fn status(&self) -> Option<DetachedSession> {
+        let mut current = self.current.lock().ok()?;
+        if current.as_ref().is_some_and(|s| !still_running(s)) {
+            *current = None;
+            clear_state();
+        }
+        current.clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status().is_some()
+    }
+
+    /// Starts the gateway detached from the app. Callers are responsible for
+    /// state-lock checks.
+    pub fn start(&self, app: &AppHandle, settings: &Settings) -> Result<DetachedSession, String> {
+        if let Some(session) = self.status() {
+            return Err(format!("A detached gateway is already running (pid {})", session.pid));
+        }
+        let state_dir = openclaw::profile_state_dir(settings.active_profile.as_deref())?;
+        state_crypt::unseal(&state_dir, &settings.encrypted_state_files)?;
+
+        let args: Vec<String> = GATEWAY_ARGS.iter().map(|a| a.to_string()).collect();
+        if let Some(gateway) = external::find(&state_dir, None) {
+            return Err(format!(
+                "An OpenClaw gateway started outside the app (pid {}) is already using this state directory",
+                gateway.pid
+            ));
+        }
+        if let Some(conflict) = ports::check(openclaw::gateway_port(&args)) {
+            return Err(format!("{}. {}", conflict.message, conflict.remediation));
+        }
+        let builder = openclaw::build_openclaw_command(app, settings, &args)?;
+        let argv = builder.get_argv();
+        let program = argv.first().ok_or("Empty OpenClaw command")?;
+        let mut cmd = Command::new(program);
+        cmd.args(&argv[1..]).env_clear();
+        for (key, value) in builder.iter_full_env_as_str() {
+            cmd.env(key, value);
+        }
+        if let Some(cwd) = builder.get_cwd() {
+            cmd.current_dir(cwd);
+        }
+
+        let log_path = settings::ensure_settings_dir()?.join(LOG_FILE);
+        let log = open_log(&log_path)?;
+        let log_err = log.try_clone().map_err(|e| format!("Cannot open log: {}", e))?;
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::from(log))
+            .stderr(Stdio::from(log_err));
+        detach(&mut cmd);
+
+        gatewaylock::acquire(&state_dir)?;
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                gatewaylock::release(&state_dir, std::process::id());
+                return Err(format!("Failed to spawn detached gateway: {}", e));
+            }
+        };
+        // The lock follows the gateway, which may outlive the app.
+        if let Err(e) = gatewaylock::assign(&state_dir, child.id()) {
+            eprintln!("[detached] Cannot hand over gateway lock: {}", e);
+        }
+        // spawn() returns after the exec, so this is the gateway's own
+        // executable rather than ours.
+        let (process_started_at, exe) = identity(child.id()).unwrap_or_default();
+        let session = DetachedSession {
+            pid: child.id(),
+            args,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            log_path: log_path.to_string_lossy().into_owned(),
+            process_started_at,
+            exe,
+        };
+        // Reap it if it exits while we're still running; otherwise it simply
+        // outlives us.
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+
+        let content =
+            serde_json::to_string(&session).map_err(|e| format!("Serialize error: {}", e))?;
+        settings::write_private_file(&state_path()?, content.as_bytes())?;
+        *self.current.lock().map_err(|e| e.to_string())? = Some(session.clone());
+        Ok(session)
+    }
+
+    /// Asks the gateway to exit, force-killing it after `grace`. The pid is
+    /// re-checked before each signal so a reused one is never signalled.
+    pub fn stop(&self, grace: Duration) -> Result<(), String> {
+        let Some(session) = self.status() else {
+            return Ok(());
+        };
+        terminate(session.pid);
+        let deadline = Instant::now() + grace;
+        while still_running(&session) && Instant::now() < deadline {
+            std::thread::sleep(STOP_POLL_INTERVAL);
+        }
+        if still_running(&session) {
+            force_kill(session.pid);
+        }
+        if let Ok(state_dir) = openclaw::openclaw_state_dir() {
+            gatewaylock::release(&state_dir, session.pid);
+        }
+        *self.current.lock().map_err(|e| e.to_string())? = None;
+        clear_state();
+        Ok(())
+    }
+
+    /// Reads the gateway's log from `offset`. Starts over from the beginning
+    /// if the log was truncated since the last read.
+    pub fn read_log(&self, offset: u64) -> Result<LogChunk, String> {
+        let path = settings::ensure_settings_dir()?.join(LOG_FILE);
+        let mut file = match File::open(&path) {
+            Ok(f) => f,
+            Err(_) => {
+                return Ok(LogChunk {
+                    data: String::new(),
+                    next_offset: 0,
+                })
+            }
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let offset = if offset > len { 0 } else { offset };
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Cannot read log: {}", e))?;
+        let mut buf = Vec::new();
+        file.take(MAX_LOG_CHUNK)
+            .read_to_end(&mut buf)
+            .map_err(|e| format!("Cannot read log: {}", e))?;
+        Ok(LogChunk {
+            data: String::from_utf8_lossy(&buf).into_owned(),
+            next_offset: offset + buf.len() as u64,
+        })
+    }
+}
+
+/// Opens the log for appending with owner-only permissions.
+fn open_log(path: &PathBuf) -> Result<File, String> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .map_err(|e| format!("Cannot open log: {}", e))
+}
+
+/// Puts the child in a new session so it survives the app (and any
+/// terminal the app was started from) going away.
+#[cfg(unix)]
+fn detach(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    // SAFETY: setsid() is async-signal-safe and touches no parent state.
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+fn detach(cmd: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_cmd: &mut Command) {}
+
+/// setsid() made the gateway a process group leader, so its group id is
+/// its pid and includes anything it started.
+#[cfg(unix)]
+fn terminate(pid: u32) {
+    // SAFETY: killpg() has no memory-safety preconditions.
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGTERM);
+    }
+}
+
+#[cfg(unix)]
+fn force_kill(pid: u32) {
+    // SAFETY: killpg() has no memory-safety preconditions.
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+/// Windows has no SIGTERM for a detached console-less process; both
+/// requests terminate it.
+#[cfg(not(unix))]
+fn terminate(pid: u32) {
+    force_kill(pid);
+}
+
+#[cfg(not(unix))]
+fn force_kill(pid: u32) {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    if let Some(process) = sys.process(pid) {
+        process.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_for(pid: u32) -> DetachedSession {
+        let (process_started_at, exe) = identity(pid).unwrap_or_default();
+        DetachedSession {
+            pid,
+            args: Vec::new(),
+            started_at: 0,
+            log_path: String::new(),
+            process_started_at,
+            exe,
+        }
+    }
+
+    #[test]
+    fn recognises_the_recorded_process() {
+        assert!(still_running(&session_for(std::process::id())));
+    }
+
+    #[test]
+    fn rejects_a_reused_pid() {
+        let mut session = session_for(std::process::id());
+        session.process_started_at -= 1;
+        assert!(!still_running(&session));
+
+        let mut session = session_for(std::process::id());
+        session.exe = Some("/not/the/gateway".to_string());
+        assert!(!still_running(&session));
+    }
+
+    #[test]
+    fn never_adopts_state_without_an_identity() {
+        let mut session = session_for(std::process::id());
+        session.process_started_at = 0;
+        session.exe = None;
+        assert!(!still_running(&session));
+    }
+}
@@ -0,0 +1,303 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+/// Default and maximum run time of a one-shot command.
+pub const DEFAULT_EXEC_TIMEOUT: Duration = Duration::from_secs(60);
+pub const MAX_EXEC_TIMEOUT: Duration = Duration::from_secs(600);
+/// Output kept per stream; the rest is discarded.
+const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+/// Longest line sent to a streaming listener; the rest is cut off.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+/// Streaming commands allowed to run at once.
+const MAX_STREAMS: usize = 8;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How long output readers may keep going once the child is gone. A
+/// background process that left the group can hold the pipes open forever.
+const DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+/// Result of a one-shot (non-PTY) command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` if the process was killed by a signal or timed out.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+}
+
+/// Runs a command with piped stdout/stderr to completion, killing it and
+/// everything it started if it runs longer than `timeout`.
+pub fn run(mut cmd: Command, timeout: Duration) -> Result<ExecOutput, String> {
+    own_process_group(&mut cmd);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Cannot start {:?}: {}", cmd.get_program(), e))?;
+    // Drained on threads so a chatty child can't block on a full pipe.
+    let stdout = child.stdout.take().map(drain);
+    let stderr = child.stderr.take().map(drain);
+
+    let (exit_code, timed_out) = match wait(&mut child, Instant::now() + timeout, None)? {
+        Ending::Exited(code) => (code, false),
+        Ending::TimedOut | Ending::Cancelled => (None, true),
+    };
+    let deadline = Instant::now() + DRAIN_GRACE;
+    Ok(ExecOutput {
+        stdout: join_by(stdout, deadline).unwrap_or_default(),
+        stderr: join_by(stderr, deadline).unwrap_or_default(),
+        exit_code,
+        timed_out,
+    })
+}
+
+/// How a waited-on child finished.
+enum Ending {
+    Exited(Option<i32>),
+    TimedOut,
+    Cancelled,
+}
+
+/// Waits for the child to exit, killing it once the deadline passes or
+/// `cancel` is set. If its status can't be read it is killed as well, so
+/// an error never leaves it running.
+fn wait(child: &mut Child, deadline: Instant, cancel: Option<&AtomicBool>) -> Result<Ending, String> {
+    loop {
+        let ending = if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            Ending::Cancelled
+        } else if Instant::now() >= deadline {
+            Ending::TimedOut
+        } else {
+            match child.try_wait() {
+                Ok(Some(status)) => return Ok(Ending::Exited(status.code())),
+                Ok(None) => {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => {
+                    kill_group(child);
+                    let _ = child.wait();
+                    return Err(e.to_string());
+                }
+            }
+        };
+        kill_group(child);
+        let _ = child.wait();
+        return Ok(ending);
+    }
+}
+
+/// Makes the child a process group leader, so `kill_group` reaches
+/// everything it starts.
+#[cfg(unix)]
+pub(crate) fn own_process_group(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn own_process_group(_cmd: &mut Command) {}
+
+/// Kills a child started with `own_process_group`, along with its group.
+#[cfg(unix)]
+pub(crate) fn kill_group(child: &mut Child) {
+    // SAFETY: killpg() has no memory-safety preconditions.
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Joins a reader thread, abandoning it if it is still running at
+/// `deadline`.
+pub(crate) fn join_by<T>(handle: Option<JoinHandle<T>>, deadline: Instant) -> Option<T> {
+    let handle = handle?;
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    handle.join().ok()
+}
+
+/// Reads a pipe to the end on a background thread, keeping at most
+/// `MAX_OUTPUT_BYTES`.
+fn drain(mut pipe: impl Read + Send + 'static) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let room = MAX_OUTPUT_BYTES.saturating_sub(kept.len());
+                    kept.extend_from_slice(&buf[..n.min(room)]);
+                }
+            }
+        }
+        String::from_utf8_lossy(&kept).into_owned()
+    })
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecStream {
+    Stdout,
+    Stderr,
+}
+
+/// Messages sent to a streaming command's channel. `exit` is always last.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum ExecEvent {
+    Line { stream: ExecStream, line: String },
+    #[serde(rename_all = "camelCase")]
+    Exit {
+        exit_code: Option<i32>,
+        timed_out: bool,
+        cancelled: bool,
+        error: Option<String>,
+    },
+}
+
+/// Streaming one-shot commands, keyed by id, with their cancel flags.
+pub struct ExecManager {
+    next_id: AtomicU64,
+    running: Arc<Mutex<HashMap<u64, Arc<AtomicBool>>>>,
+}
+
+impl ExecManager {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Starts a command and streams its output lines to `channel` as they
+    /// arrive, followed by an `exit` event. Returns the id to cancel it with.
+    pub fn spawn(&self, mut cmd: Command, timeout: Duration, channel: Channel<ExecEvent>) -> Result<u64, String> {
+        own_process_group(&mut cmd);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let id = {
+            let mut running = self.running.lock().map_err(|e| e.to_string())?;
+            if running.len() >= MAX_STREAMS {
+                return Err(format!("Too many running commands (max {})", MAX_STREAMS));
+            }
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            running.insert(id, cancel.clone());
+            id
+        };
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                if let Ok(mut running) = self.running.lock() {
+                    running.remove(&id);
+                }
+                return Err(format!("Cannot start {:?}: {}", cmd.get_program(), e));
+            }
+        };
+        let stdout = child
+            .stdout
+            .take()
+            .map(|pipe| stream_lines(pipe, ExecStream::Stdout, channel.clone()));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|pipe| stream_lines(pipe, ExecStream::Stderr, channel.clone()));
+
+        let running = self.running.clone();
+        std::thread::spawn(move || {
+            let ending = wait(&mut child, Instant::now() + timeout, Some(&cancel));
+            // Let the readers send the last lines before the exit event.
+            let deadline = Instant::now() + DRAIN_GRACE;
+            for reader in [stdout, stderr] {
+                join_by(reader, deadline);
+            }
+            if let Ok(mut running) = running.lock() {
+                running.remove(&id);
+            }
+            let (exit_code, timed_out, cancelled, error) = match ending {
+                Ok(Ending::Exited(code)) => (code, false, false, None),
+                Ok(Ending::TimedOut) => (None, true, false, None),
+                Ok(Ending::Cancelled) => (None, false, true, None),
+                Err(e) => (None, false, false, Some(e)),
+            };
+            let _ = channel.send(ExecEvent::Exit {
+                exit_code,
+                timed_out,
+                cancelled,
+                error,
+            });
+        });
+        Ok(id)
+    }
+
+    /// Kills a streaming command. Its `exit` event reports `cancelled`.
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        let running = self.running.lock().map_err(|e| e.to_string())?;
+        let flag = running.get(&id).ok_or_else(|| format!("No running command {}", id))?;
+        flag.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Sends each line read from a pipe to the channel, on a background thread.
+/// Once the channel is gone the pipe is still drained, so the child never
+/// blocks on a full pipe.
+fn stream_lines(pipe: impl Read + Send + 'static, stream: ExecStream, channel: Channel<ExecEvent>) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        let mut listening = true;
+        loop {
+            buf.clear();
+            // Never buffers more than MAX_LINE_BYTES of one line.
+            match (&mut reader).take(MAX_LINE_BYTES as u64).read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if buf.last() != Some(&b'\n') {
+                        skip_line(&mut reader);
+                    }
+                    if !listening {
+                        continue;
+                    }
+                    while buf.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+                        buf.pop();
+                    }
+                    let line = String::from_utf8_lossy(&buf).into_owned();
+                    listening = channel.send(ExecEvent::Line { stream, line }).is_ok();
+                }
+            }
+        }
+    })
+}
+
+/// Discards the rest of an over-long line, up to and including its newline.
+pub(crate) fn skip_line(reader: &mut impl BufRead) {
+    loop {
+        let (used, done) = match reader.fill_buf() {
+            Ok([]) | Err(_) => return,
+            Ok(available) => match available.iter().position(|b| *b == b'\n') {
+                Some(pos) => (pos + 1, true),
+                None => (available.len(), false),
+            },
+        };
+        reader.consume(used);
+        if done {
+            return;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::html;
+use crate::pty_manager::PtyManager;
+
+/// File formats `export_transcript` can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// ANSI-stripped text, one terminal line per line.
+    Plain,
+    /// Styled HTML with colors and links preserved. Covers the scrollback,
+    /// since transcript logs are stored stripped.
+    Html,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Plain => "txt",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    fn filter_name(self) -> &'static str {
+        match self {
+            ExportFormat::Plain => "Text",
+            ExportFormat::Html => "HTML",
+        }
+    }
+}
+
+/// Renders a session's output in `format` and writes it to a path the user
+/// picks in a save dialog. Returns the path, or `None` if the dialog was
+/// cancelled. Blocks on the dialog, so call from an async command.
+pub fn export_transcript(
+    app: &AppHandle,
+    pty: &PtyManager,
+    session_id: u64,
+    format: ExportFormat,
+) -> Result<Option<String>, String> {
+    let content = match format {
+        ExportFormat::Plain => {
+            let mut text = pty.output_lines(session_id)?.join("\n");
+            text.push('\n');
+            text
+        }
+        ExportFormat::Html => {
+            let title = format!("OpenClaw session {}", session_id);
+            html::to_html(&pty.scrollback(session_id)?, &title)
+        }
+    };
+
+    let extension = format.extension();
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title("Export Transcript")
+        .set_file_name(format!("openclaw-session-{}.{}", session_id, extension))
+        .add_filter(format.filter_name(), &[extension])
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let path: PathBuf = path.into_path().map_err(|e| format!("Invalid export path: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
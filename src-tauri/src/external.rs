@@ -0,0 +1,108 @@
+//! Finds OpenClaw gateways started outside the app (a system service, the
+//! CLI) that run against the app's state directory. Two gateways driving
+//! one state dir corrupt it, so the app uses such a gateway instead of
+//! spawning its own.
+
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::migrate;
+use crate::openclaw;
+
+const STATE_DIR_VAR: &str = "OPENCLAW_STATE_DIR";
+
+/// A gateway running outside the app.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalGateway {
+    pub pid: u32,
+    pub port: u16,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub command: Vec<String>,
+}
+
+/// Returns a gateway running against `state_dir` that neither this process
+/// nor one of its descendants started, and that isn't `exclude` (the
+/// detached gateway, which the app already tracks).
+pub fn find(state_dir: &Path, exclude: Option<u32>) -> Option<ExternalGateway> {
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_cmd(UpdateKind::Always)
+            .with_environ(UpdateKind::Always),
+    );
+    let own_pid = Pid::from_u32(std::process::id());
+    sys.processes().iter().find_map(|(pid, process)| {
+        if Some(pid.as_u32()) == exclude || is_descendant(&sys, *pid, own_pid) {
+            return None;
+        }
+        let args = strings(process.cmd());
+        let gateway_at = gateway_position(&args)?;
+        if !runs_against(&strings(process.environ()), state_dir) {
+            return None;
+        }
+        Some(ExternalGateway {
+            pid: pid.as_u32(),
+            port: openclaw::gateway_port(&args[gateway_at..]),
+            started_at: process.start_time(),
+            command: args,
+        })
+    })
+}
+
+fn strings(values: &[OsString]) -> Vec<String> {
+    values.iter().map(|v| v.to_string_lossy().into_owned()).collect()
+}
+
+/// Index of the `gateway` subcommand in an OpenClaw command line, e.g.
+/// `node /usr/lib/openclaw/openclaw.mjs gateway --port 18789`.
+fn gateway_position(args: &[String]) -> Option<usize> {
+    let entry = args.iter().position(|a| {
+        Path::new(a)
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("openclaw"))
+    })?;
+    args.iter()
+        .skip(entry + 1)
+        .position(|a| a == "gateway")
+        .map(|i| entry + 1 + i)
+}
+
+/// True if the environment points OpenClaw at `state_dir`. Without
+/// `OPENCLAW_STATE_DIR` it uses the CLI's default state dir.
+fn runs_against(environ: &[String], state_dir: &Path) -> bool {
+    let configured = environ.iter().find_map(|var| {
+        var.split_once('=')
+            .filter(|(name, _)| *name == STATE_DIR_VAR)
+            .map(|(_, value)| PathBuf::from(value))
+    });
+    match configured.or_else(|| migrate::cli_state_dir().ok()) {
+        Some(dir) => same_path(&dir, state_dir),
+        None => false,
+    }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn is_descendant(sys: &System, pid: Pid, ancestor: Pid) -> bool {
+    let mut current = Some(pid);
+    // Bounded in case of a parent cycle from pid reuse.
+    for _ in 0..64 {
+        match current {
+            Some(p) if p == ancestor => return true,
+            Some(p) => current = sys.process(p).and_then(|proc| proc.parent()),
+            None => return false,
+        }
+    }
+    false
+}
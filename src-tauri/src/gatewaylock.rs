@@ -0,0 +1,119 @@
+//! Advisory lock file inside an OpenClaw state directory, held for as long
+//! as a gateway runs against it. Two app instances, or the app and the CLI,
+//! must not both drive the same state directory.
+//!
+//! The lock records the gateway's pid and the process's start time, so a
+//! lock whose process died, or whose pid was since reused by an unrelated
+//! process, is recognized as stale and replaced.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+pub const LOCK_FILE: &str = "gateway.lock";
+
+/// Contents of the lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Holder {
+    pid: u32,
+    /// Process start time in seconds since the Unix epoch.
+    started_at: u64,
+    /// What took the lock, e.g. "clawrunner 0.0.1".
+    owner: String,
+}
+
+fn lock_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(LOCK_FILE)
+}
+
+fn start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    sys.process(pid).map(|p| p.start_time())
+}
+
+fn holder_for(pid: u32) -> Holder {
+    Holder {
+        pid,
+        started_at: start_time(pid).unwrap_or(0),
+        owner: format!("clawrunner {}", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+fn read_holder(state_dir: &Path) -> Option<Holder> {
+    let content = std::fs::read_to_string(lock_path(state_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// True if the holder's process is gone, or its pid now belongs to a
+/// process started at a different time.
+fn is_stale(holder: &Holder) -> bool {
+    match start_time(holder.pid) {
+        Some(started_at) => holder.started_at != 0 && started_at != holder.started_at,
+        None => true,
+    }
+}
+
+fn create(state_dir: &Path, holder: &Holder) -> std::io::Result<()> {
+    let content = serde_json::to_string(holder).map_err(std::io::Error::other)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(lock_path(state_dir))?;
+    file.write_all(content.as_bytes())
+}
+
+/// Takes the lock for this process before a gateway is spawned. Fails if a
+/// live gateway holds it; a stale lock is replaced.
+pub fn acquire(state_dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(state_dir)
+        .map_err(|e| format!("Cannot create state directory: {}", e))?;
+    let own = holder_for(std::process::id());
+    // Two attempts: the second follows removing a stale lock.
+    for _ in 0..2 {
+        match create(state_dir, &own) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(format!("Cannot write gateway lock: {}", e)),
+        }
+        match read_holder(state_dir) {
+            Some(holder) if holder == own => return Ok(()),
+            Some(holder) if !is_stale(&holder) => {
+                return Err(format!(
+                    "Another gateway ({}, pid {}) is already running against this state directory",
+                    holder.owner, holder.pid
+                ));
+            }
+            _ => {
+                eprintln!("[gatewaylock] Removing stale lock in {}", state_dir.display());
+                let _ = std::fs::remove_file(lock_path(state_dir));
+            }
+        }
+    }
+    Err("Could not take the gateway lock".to_string())
+}
+
+/// Hands a lock this process holds over to the spawned gateway, so it stays
+/// valid while the gateway outlives the app (detached) and goes stale with it.
+pub fn assign(state_dir: &Path, pid: u32) -> Result<(), String> {
+    if read_holder(state_dir).map(|h| h.pid) != Some(std::process::id()) {
+        return Err("Gateway lock is not held by this process".to_string());
+    }
+    let content = serde_json::to_string(&holder_for(pid)).map_err(|e| format!("Serialize error: {}", e))?;
+    crate::settings::write_private_file(&lock_path(state_dir), content.as_bytes())
+}
+
+/// Removes the lock if `pid` holds it.
+pub fn release(state_dir: &Path, pid: u32) {
+    if read_holder(state_dir).is_some_and(|h| h.pid == pid) {
+        let _ = std::fs::remove_file(lock_path(state_dir));
+    }
+}
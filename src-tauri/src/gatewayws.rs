@@ -0,0 +1,239 @@
+//! Connects to the running gateway's local WebSocket API and re-emits its
+//! events as typed Tauri events, so the UI can show messages and channel
+//! state without scraping terminal output.
+//!
+//! Gateway frames are JSON: `{"type":"event","event":<name>,"payload":{..}}`.
+//! The client authenticates with a `connect` request carrying the gateway
+//! token before any events are sent.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tungstenite::{Message, WebSocket};
+
+use crate::config;
+use crate::health::GatewayMonitor;
+use crate::AppState;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Reads time out this often so the loop notices the gateway moving ports.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+/// Wait between attempts while no gateway is up or after a disconnect.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Payload of `gateway:message`: a message received on a channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayMessage {
+    pub channel: Option<String>,
+    pub session_key: Option<String>,
+    pub from: Option<String>,
+    pub text: String,
+}
+
+/// Payload of `gateway:channel`: a channel connected or disconnected.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelState {
+    pub channel: String,
+    pub account: Option<String>,
+    pub connected: bool,
+}
+
+/// Payload of `gateway:error`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayError {
+    pub message: String,
+    pub code: Option<String>,
+}
+
+/// Payload of `gateway:ws-status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStatus {
+    pub connected: bool,
+    pub port: Option<u16>,
+}
+
+/// Keeps one event-stream connection to whichever gateway is up.
+pub struct GatewayEvents {
+    port: Mutex<Option<u16>>,
+}
+
+impl GatewayEvents {
+    pub fn new() -> Self {
+        Self {
+            port: Mutex::new(None),
+        }
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        let port = self.port.lock().ok().and_then(|p| *p);
+        ConnectionStatus {
+            connected: port.is_some(),
+            port,
+        }
+    }
+
+    /// Connects to the first healthy gateway the monitor reports and
+    /// reconnects whenever the connection drops.
+    pub fn start(&self, app: &AppHandle) {
+        let app = app.clone();
+        thread::spawn(move || loop {
+            if let Some(port) = app.state::<GatewayMonitor>().healthy_port() {
+                match connect(&app, port) {
+                    Ok(socket) => {
+                        set_connected(&app, Some(port));
+                        let result = pump(&app, socket, port);
+                        set_connected(&app, None);
+                        if let Err(e) = result {
+                            eprintln!("[gatewayws] Connection to port {} closed: {}", port, e);
+                        }
+                    }
+                    Err(e) => eprintln!("[gatewayws] Cannot connect to port {}: {}", port, e),
+                }
+            }
+            thread::sleep(RETRY_INTERVAL);
+        });
+    }
+}
+
+fn set_connected(app: &AppHandle, port: Option<u16>) {
+    if let Ok(mut current) = app.state::<GatewayEvents>().port.lock() {
+        *current = port;
+    }
+    let _ = app.emit(
+        "gateway:ws-status",
+        ConnectionStatus {
+            connected: port.is_some(),
+            port,
+        },
+    );
+}
+
+fn connect(app: &AppHandle, port: u16) -> Result<WebSocket<TcpStream>, String> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT)).map_err(|e| e.to_string())?;
+    let url = format!("ws://127.0.0.1:{}/", port);
+    let (mut socket, _) = tungstenite::client(url.as_str(), stream).map_err(|e| e.to_string())?;
+
+    let token = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.for_profile(None).ok())
+        .and_then(|s| config::gateway_token(&s));
+    let hello = json!({
+        "type": "req",
+        "id": "connect",
+        "method": "connect",
+        "params": {
+            "client": { "id": "clawrunner", "version": env!("CARGO_PKG_VERSION") },
+            "auth": { "token": token },
+        },
+    });
+    socket
+        .send(Message::text(hello.to_string()))
+        .map_err(|e| e.to_string())?;
+    socket
+        .get_mut()
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Reads frames until the connection closes or the gateway stops being the
+/// healthy one.
+fn pump(app: &AppHandle, mut socket: WebSocket<TcpStream>, port: u16) -> Result<(), String> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if let Ok(frame) = serde_json::from_str::<Value>(text.as_str()) {
+                    dispatch(app, &frame);
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                if app.state::<GatewayMonitor>().healthy_port() != Some(port) {
+                    let _ = socket.close(None);
+                    return Ok(());
+                }
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn string_at(value: &Value, pointers: &[&str]) -> Option<String> {
+    pointers
+        .iter()
+        .find_map(|p| value.pointer(p)?.as_str().map(str::to_string))
+}
+
+/// Maps one gateway frame onto a Tauri event. Frames of other types and
+/// events the UI doesn't use are dropped.
+fn dispatch(app: &AppHandle, frame: &Value) {
+    if frame.get("type").and_then(Value::as_str) == Some("res") && frame.get("ok") == Some(&Value::Bool(false)) {
+        let _ = app.emit(
+            "gateway:error",
+            GatewayError {
+                message: string_at(frame, &["/error/message"]).unwrap_or_else(|| "Request failed".to_string()),
+                code: string_at(frame, &["/error/code"]),
+            },
+        );
+        return;
+    }
+    if frame.get("type").and_then(Value::as_str) != Some("event") {
+        return;
+    }
+    let Some(event) = frame.get("event").and_then(Value::as_str) else { return };
+    let payload = frame.get("payload").unwrap_or(&Value::Null);
+    match event {
+        "chat" | "message" => {
+            let Some(text) = string_at(payload, &["/text", "/message/text", "/message/content"]) else {
+                return;
+            };
+            let _ = app.emit(
+                "gateway:message",
+                GatewayMessage {
+                    channel: string_at(payload, &["/channel", "/message/channel"]),
+                    session_key: string_at(payload, &["/sessionKey"]),
+                    from: string_at(payload, &["/from", "/sender", "/message/from"]),
+                    text,
+                },
+            );
+        }
+        "channel.connected" | "channel.disconnected" => {
+            let Some(channel) = string_at(payload, &["/channel", "/id"]) else { return };
+            let _ = app.emit(
+                "gateway:channel",
+                ChannelState {
+                    channel,
+                    account: string_at(payload, &["/account", "/accountId"]),
+                    connected: event == "channel.connected",
+                },
+            );
+        }
+        "error" => {
+            let _ = app.emit(
+                "gateway:error",
+                GatewayError {
+                    message: string_at(payload, &["/message"]).unwrap_or_else(|| "Gateway error".to_string()),
+                    code: string_at(payload, &["/code"]),
+                },
+            );
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,98 @@
+//! `--headless` startup: no window is opened at launch, but the gateway is
+//! started (and supervised) anyway, the tray icon is shown and the local
+//! APIs run as configured. For servers, kiosks and launch-at-login setups;
+//! the window can still be opened from the tray.
+
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder};
+
+use crate::detached::Detached;
+use crate::openclaw::{self, LaunchMode};
+use crate::pty_manager::SessionOptions;
+use crate::settings::Settings;
+use crate::tray;
+use crate::AppState;
+
+pub const FLAG: &str = "--headless";
+const MAIN_WINDOW: &str = "main";
+const GATEWAY_COLS: u16 = 120;
+const GATEWAY_ROWS: u16 = 40;
+
+static HEADLESS: OnceLock<bool> = OnceLock::new();
+
+/// Whether the app was launched with `--headless`.
+pub fn is_enabled() -> bool {
+    *HEADLESS.get_or_init(|| std::env::args().skip(1).any(|a| a == FLAG))
+}
+
+/// Opens the main window as configured in `tauri.conf.json`. The config
+/// doesn't create it on its own, so a headless start can skip it.
+pub fn create_main_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == MAIN_WINDOW)
+        .ok_or("The app config has no main window")?;
+    WebviewWindowBuilder::from_config(app, config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Cannot open window: {}", e))
+}
+
+/// Shows and focuses the main window, opening it first if the app started
+/// headless.
+pub fn show_main_window(app: &AppHandle) {
+    let window = match app.get_webview_window(MAIN_WINDOW) {
+        Some(window) => window,
+        None => match create_main_window(app) {
+            Ok(window) => window,
+            Err(e) => {
+                eprintln!("[headless] {}", e);
+                return;
+            }
+        },
+    };
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Runs in place of opening the window: parks the app in the tray and
+/// starts the gateway with its output going to the scrollback.
+pub fn start(app: &AppHandle, settings: &Settings) {
+    if let Err(e) = tray::park(app) {
+        eprintln!("[headless] {}", e);
+    }
+    if !openclaw::is_configured() {
+        eprintln!("[headless] OpenClaw is not set up; open the window from the tray to onboard");
+        return;
+    }
+    if app.state::<Detached>().is_running() {
+        eprintln!("[headless] A detached gateway is already running; not starting another");
+        return;
+    }
+    let launched = crate::launch_session(
+        app,
+        settings.clone(),
+        LaunchMode::Gateway {
+            port: None,
+            verbose: false,
+        },
+        None,
+        GATEWAY_COLS,
+        GATEWAY_ROWS,
+        SessionOptions {
+            hold_output: true,
+            ..Default::default()
+        },
+    );
+    match launched {
+        Ok(session_id) => {
+            if let Err(e) = app.state::<AppState>().pty.release_output(session_id) {
+                eprintln!("[headless] {}", e);
+            }
+            eprintln!("[headless] Gateway started as session {}", session_id);
+        }
+        Err(e) => eprintln!("[headless] Cannot start gateway: {}", e),
+    }
+}
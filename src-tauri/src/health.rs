@@ -0,0 +1,200 @@
+//! Periodically probes running gateways over their local HTTP endpoint and
+//! reports `gateway:health`, so the UI needn't guess from terminal output.
+//! Results also feed the supervisor, which restarts unresponsive gateways.
+//! A gateway started outside the app against the same state dir is probed
+//! like the app's own.
+
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::detached::Detached;
+use crate::external::{self, ExternalGateway};
+use crate::openclaw;
+use crate::supervisor::Supervisor;
+use crate::AppState;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Responses slower than this count as degraded.
+const SLOW_RESPONSE: Duration = Duration::from_secs(1);
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+const HEALTH_PATH: &str = "/health";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Answered quickly with a success status.
+    Up,
+    /// Answered, but slowly or with a server error.
+    Degraded,
+    /// Not accepting connections or not answering.
+    Down,
+}
+
+/// Payload of `gateway:health`: the result of one probe.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayHealth {
+    /// The gateway's session, or `None` for the detached or an external
+    /// gateway.
+    pub session_id: Option<u64>,
+    /// Started outside the app; see `gateway_external`.
+    pub external: bool,
+    pub port: u16,
+    pub status: HealthStatus,
+    /// Time to a complete response; `None` when down.
+    pub latency_ms: Option<u64>,
+    /// Version the gateway reports, if it does.
+    pub version: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub checked_at: u64,
+}
+
+/// Probes every running gateway on a background thread.
+pub struct GatewayMonitor {
+    last: Mutex<Vec<GatewayHealth>>,
+    external: Mutex<Option<ExternalGateway>>,
+}
+
+impl GatewayMonitor {
+    pub fn new() -> Self {
+        Self {
+            last: Mutex::new(Vec::new()),
+            external: Mutex::new(None),
+        }
+    }
+
+    /// Port of a gateway that answered its latest probe.
+    pub fn healthy_port(&self) -> Option<u16> {
+        self.current()
+            .into_iter()
+            .find(|h| h.status != HealthStatus::Down)
+            .map(|h| h.port)
+    }
+
+    /// The external gateway seen in the latest round, if any.
+    pub fn external(&self) -> Option<ExternalGateway> {
+        self.external.lock().ok().and_then(|e| e.clone())
+    }
+
+    /// Results of the latest round of probes.
+    pub fn current(&self) -> Vec<GatewayHealth> {
+        self.last.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    /// Emits `gateway:health` for each gateway session (and the detached
+    /// and external gateways) every `PROBE_INTERVAL`, and
+    /// `gateway:external` when an external gateway appears or goes away.
+    pub fn start(&self, app: &AppHandle) {
+        let app = app.clone();
+        thread::spawn(move || loop {
+            let monitor = app.state::<GatewayMonitor>();
+            let external = find_external(&app);
+            if let Ok(mut current) = monitor.external.lock() {
+                if *current != external {
+                    let _ = app.emit("gateway:external", &external);
+                    *current = external.clone();
+                }
+            }
+            let mut results: Vec<GatewayHealth> = gateways(&app)
+                .into_iter()
+                .map(|(session_id, port)| probe(session_id, port, false))
+                .collect();
+            results.extend(external.map(|e| probe(None, e.port, true)));
+            for health in &results {
+                let _ = app.emit("gateway:health", health);
+            }
+            app.state::<Supervisor>().on_health(&app, &results);
+            if let Ok(mut last) = monitor.last.lock() {
+                *last = results;
+            }
+            thread::sleep(PROBE_INTERVAL);
+        });
+    }
+}
+
+/// Running gateways as (session id, port) pairs.
+fn gateways(app: &AppHandle) -> Vec<(Option<u64>, u16)> {
+    let mut gateways: Vec<(Option<u64>, u16)> = app
+        .state::<AppState>()
+        .pty
+        .list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.args.first().is_some_and(|a| a == "gateway"))
+        .map(|s| (Some(s.session_id), openclaw::gateway_port(&s.args)))
+        .collect();
+    if let Some(detached) = app.try_state::<Detached>().and_then(|d| d.status()) {
+        gateways.push((None, openclaw::gateway_port(&detached.args)));
+    }
+    gateways
+}
+
+/// An external gateway running against the active profile's state dir.
+pub fn find_external(app: &AppHandle) -> Option<ExternalGateway> {
+    let state_dir = openclaw::openclaw_state_dir().ok()?;
+    let detached = app.try_state::<Detached>().and_then(|d| d.status()).map(|d| d.pid);
+    external::find(&state_dir, detached)
+}
+
+fn probe(session_id: Option<u64>, port: u16, external: bool) -> GatewayHealth {
+    let started = Instant::now();
+    let response = request(port);
+    let latency = started.elapsed();
+    let (status, latency_ms, version) = match response {
+        Some((code, body)) => {
+            let status = if code >= 500 || latency > SLOW_RESPONSE {
+                HealthStatus::Degraded
+            } else {
+                HealthStatus::Up
+            };
+            (status, Some(latency.as_millis() as u64), version_from(&body))
+        }
+        None => (HealthStatus::Down, None, None),
+    };
+    GatewayHealth {
+        session_id,
+        external,
+        port,
+        status,
+        latency_ms,
+        version,
+        checked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    }
+}
+
+/// Sends `GET /health` to the gateway on loopback. Returns the HTTP status
+/// code and body, or `None` if it didn't answer.
+fn request(port: u16) -> Option<(u16, String)> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(READ_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(READ_TIMEOUT)).ok()?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+        HEALTH_PATH, port
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut response = Vec::new();
+    let _ = stream.take(MAX_RESPONSE_BYTES).read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    // "HTTP/1.1 200 OK"
+    let code = response.lines().next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let body = response.split_once("\r\n\r\n").map(|(_, b)| b.to_string()).unwrap_or_default();
+    Some((code, body))
+}
+
+/// Reads `version` from a JSON health response, if there is one.
+fn version_from(body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body.trim()).ok()?;
+    json.get("version")?.as_str().map(str::to_string)
+}
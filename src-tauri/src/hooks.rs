@@ -0,0 +1,468 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::exec;
+use crate::openclaw;
+use crate::settings;
+
+/// Maximum wall-clock time a hook may run before it is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long output capture may continue once the hook has exited or been
+/// killed; anything that left its process group can hold the pipes open.
+const HOOK_DRAIN_GRACE: Duration = Duration::from_secs(2);
+/// Maximum captured output per stream (64 KB); the rest is discarded.
+const MAX_HOOK_OUTPUT: usize = 65536;
+const MAX_HOOKS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    SessionStart,
+    GatewayStart,
+    /// The gateway was stopped, or exited cleanly.
+    GatewayStop,
+    /// The gateway exited with an error, or was killed for being unresponsive.
+    GatewayCrash,
+    OnboardingComplete,
+}
+
+impl HookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::SessionStart => "session-start",
+            HookEvent::GatewayStart => "gateway-start",
+            HookEvent::GatewayStop => "gateway-stop",
+            HookEvent::GatewayCrash => "gateway-crash",
+            HookEvent::OnboardingComplete => "onboarding-complete",
+        }
+    }
+}
+
+/// A registered hook script, pinned to the checksum the user confirmed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub event: HookEvent,
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Describes the session an event is about; passed to hooks as
+/// environment variables.
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext {
+    pub session_id: u64,
+    /// "running", "stopped", "crashed" or "completed".
+    pub status: &'static str,
+    pub exit_code: Option<u32>,
+}
+
+/// What the user is shown before confirming a hook registration.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookPreview {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Hooks live in their own file (<data dir>/hooks.json), not in Settings,
+/// so they can only be changed through the register/remove commands.
+fn hooks_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join("hooks.json"))
+}
+
+fn hook_log_path() -> Result<PathBuf, String> {
+    let dir = settings::ensure_settings_dir()?.join("logs");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create log dir: {}", e))?;
+    Ok(dir.join("hooks.log"))
+}
+
+pub fn load_hooks() -> Vec<Hook> {
+    let path = match hooks_path() {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_hooks(hooks: &[Hook]) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(hooks).map_err(|e| format!("Serialize error: {}", e))?;
+    settings::write_private_file(&hooks_path()?, content.as_bytes())
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Cannot open {:?}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("Cannot read {:?}: {}", path, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Resolves a script path to an absolute, canonical regular file.
+fn resolve_script(path: &str) -> Result<PathBuf, String> {
+    let p = Path::new(path);
+    if !p.is_absolute() {
+        return Err("Hook script path must be absolute".to_string());
+    }
+    let canonical = p
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve {:?}: {}", p, e))?;
+    if !canonical.is_file() {
+        return Err(format!("Hook script is not a file: {:?}", canonical));
+    }
+    Ok(canonical)
+}
+
+/// Returns the checksum of a script so the user can confirm it before registering.
+pub fn inspect(path: &str) -> Result<HookPreview, String> {
+    let canonical = resolve_script(path)?;
+    let size = std::fs::metadata(&canonical)
+        .map_err(|e| format!("Cannot stat {:?}: {}", canonical, e))?
+        .len();
+    Ok(HookPreview {
+        path: canonical.to_string_lossy().to_string(),
+        sha256: sha256_file(&canonical)?,
+        size,
+    })
+}
+
+/// Registers a hook once the user approves it in a native dialog. `sha256`
+/// must be the checksum returned by `inspect`; registration fails if the
+/// file changed in between. Blocks on the dialog, so call it off the async
+/// runtime.
+pub fn register(app: &AppHandle, event: HookEvent, path: &str, sha256: &str) -> Result<Hook, String> {
+    let canonical = resolve_script(path)?;
+    let actual = sha256_file(&canonical)?;
+    if !actual.eq_ignore_ascii_case(sha256) {
+        return Err("Hook script changed since it was confirmed".to_string());
+    }
+    let message = format!(
+        "Run this script on every {} event?\n\n{}\nSHA-256: {}\n\nIt runs sandboxed, without network access or access to your home folder.",
+        event.as_str(),
+        canonical.display(),
+        actual
+    );
+    let approved = app
+        .dialog()
+        .message(message)
+        .title("Register Hook")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Register".to_string(), "Cancel".to_string()))
+        .blocking_show();
+    if !approved {
+        return Err("Hook registration was cancelled".to_string());
+    }
+
+    let hook = Hook {
+        event,
+        path: canonical.to_string_lossy().to_string(),
+        sha256: actual,
+    };
+    let mut hooks = load_hooks();
+    hooks.retain(|h| !(h.event == hook.event && h.path == hook.path));
+    if hooks.len() >= MAX_HOOKS {
+        return Err(format!("Too many hooks (max {})", MAX_HOOKS));
+    }
+    hooks.push(hook.clone());
+    save_hooks(&hooks)?;
+    Ok(hook)
+}
+
+pub fn remove(event: HookEvent, path: &str) -> Result<(), String> {
+    let mut hooks = load_hooks();
+    hooks.retain(|h| !(h.event == event && h.path == path));
+    save_hooks(&hooks)
+}
+
+/// Runs all hooks registered for `event` on background threads.
+pub fn fire(event: HookEvent, context: HookContext) {
+    for hook in load_hooks().into_iter().filter(|h| h.event == event) {
+        thread::spawn(move || {
+            let result = run_hook(&hook, context);
+            log_hook_result(event, &hook.path, &result);
+        });
+    }
+}
+
+struct HookOutput {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    stdout: String,
+    stderr: String,
+}
+
+/// Reads up to MAX_HOOK_OUTPUT bytes, then drains the rest so the child never
+/// blocks on a full pipe.
+fn capture<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let room = MAX_HOOK_OUTPUT.saturating_sub(captured.len());
+            captured.extend_from_slice(&buf[..n.min(room)]);
+        }
+        String::from_utf8_lossy(&captured).to_string()
+    })
+}
+
+/// A private copy of a hook script, removed with its directory on drop.
+struct StagedHook {
+    dir: PathBuf,
+    script: PathBuf,
+    /// Scratch directory the hook may write to.
+    tmp: PathBuf,
+}
+
+impl Drop for StagedHook {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn private_dir(path: &Path) -> Result<(), String> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(path)
+        .map_err(|e| format!("Cannot create {:?}: {}", path, e))
+}
+
+/// Copies a hook script into a fresh owner-only directory
+/// (<data dir>/hook-runs/<pid>-<n>), so the file that is checksummed and run
+/// cannot be swapped out from under us.
+fn stage(path: &Path) -> Result<StagedHook, String> {
+    static NEXT_RUN: AtomicU64 = AtomicU64::new(1);
+    let runs = settings::ensure_settings_dir()?.join("hook-runs");
+    if !runs.is_dir() {
+        private_dir(&runs)?;
+    }
+    let dir = runs.join(format!("{}-{}", std::process::id(), NEXT_RUN.fetch_add(1, Ordering::Relaxed)));
+    // Left behind by an earlier process with the same pid.
+    let _ = std::fs::remove_dir_all(&dir);
+    private_dir(&dir)?;
+    let staged = StagedHook {
+        script: dir.join("hook"),
+        tmp: dir.join("tmp"),
+        dir,
+    };
+    private_dir(&staged.tmp)?;
+
+    let contents = std::fs::read(path).map_err(|e| format!("Cannot read {:?}: {}", path, e))?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o700);
+    }
+    // Closed before exec; an open write handle fails it with ETXTBSY.
+    options
+        .open(&staged.script)
+        .and_then(|mut file| file.write_all(&contents))
+        .map_err(|e| format!("Cannot copy hook script: {}", e))?;
+    Ok(staged)
+}
+
+/// Runs a registered hook script. The script is copied to a private file
+/// first, and it is the copy that is checksummed and run.
+fn run_hook(hook: &Hook, context: HookContext) -> Result<HookOutput, String> {
+    let staged = stage(Path::new(&hook.path))?;
+    if sha256_file(&staged.script)? != hook.sha256 {
+        return Err("Checksum mismatch; hook skipped".to_string());
+    }
+    run_sanitized(&staged, hook.event, context)
+}
+
+/// Where a staged script is mounted inside the bubblewrap sandbox.
+#[cfg(target_os = "linux")]
+const SANDBOX_SCRIPT: &str = "/tmp/clawrunner-hook";
+
+/// Wraps a staged hook in bubblewrap: a read-only view of the filesystem
+/// with the home and data directories hidden, a private /tmp, and no
+/// network. Hooks do not run at all if bwrap is missing. Returns the command
+/// and the hook's temp directory.
+#[cfg(target_os = "linux")]
+fn sandboxed(staged: &StagedHook) -> Result<(Command, PathBuf), String> {
+    const BWRAP: &[&str] = &["/usr/bin/bwrap", "/usr/local/bin/bwrap", "/bin/bwrap"];
+    let bwrap = BWRAP
+        .iter()
+        .map(Path::new)
+        .find(|p| p.is_file())
+        .ok_or("Hooks run sandboxed with bubblewrap (bwrap), which is not installed")?;
+    let mut cmd = Command::new(bwrap);
+    cmd.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"]);
+    let hidden = [dirs::home_dir(), Some(settings::ensure_settings_dir()?)];
+    for dir in hidden.iter().flatten() {
+        cmd.arg("--tmpfs").arg(dir);
+    }
+    cmd.arg("--ro-bind").arg(&staged.script).arg(SANDBOX_SCRIPT);
+    cmd.args(["--chdir", "/tmp", "--unshare-all", "--die-with-parent", "--new-session", SANDBOX_SCRIPT]);
+    Ok((cmd, PathBuf::from("/tmp")))
+}
+
+/// Seatbelt profile for hooks: no network, no writes outside the hook's temp
+/// directory, and no reads from the home or data directories. Later rules
+/// take precedence.
+#[cfg(target_os = "macos")]
+const SANDBOX_PROFILE: &str = r#"(version 1)
+(allow default)
+(deny network*)
+(deny file-write*)
+(allow file-write* (literal "/dev/null"))
+(deny file-read* (subpath (param "HOME")) (subpath (param "DATA")))
+(allow file-read* file-write* (subpath (param "TMP")))
+(allow file-read* (literal (param "SCRIPT")))
+"#;
+
+/// Wraps a staged hook in sandbox-exec with `SANDBOX_PROFILE`. Returns the
+/// command and the hook's temp directory.
+#[cfg(target_os = "macos")]
+fn sandboxed(staged: &StagedHook) -> Result<(Command, PathBuf), String> {
+    let real = |p: &Path| p.canonicalize().map_err(|e| format!("Cannot resolve {:?}: {}", p, e));
+    let home = dirs::home_dir().ok_or("Cannot find the home directory")?;
+    let tmp = real(&staged.tmp)?;
+    let script = real(&staged.script)?;
+    let mut cmd = Command::new("/usr/bin/sandbox-exec");
+    cmd.arg("-p").arg(SANDBOX_PROFILE);
+    for (name, path) in [
+        ("HOME", real(&home)?),
+        ("DATA", real(&settings::ensure_settings_dir()?)?),
+        ("TMP", tmp.clone()),
+        ("SCRIPT", script.clone()),
+    ] {
+        let mut define = std::ffi::OsString::from(format!("{}=", name));
+        define.push(path);
+        cmd.arg("-D").arg(define);
+    }
+    cmd.arg(&script).current_dir(&tmp);
+    Ok((cmd, tmp))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn sandboxed(_staged: &StagedHook) -> Result<(Command, PathBuf), String> {
+    Err("Hooks run sandboxed, which is not supported on this platform".to_string())
+}
+
+/// Runs a staged hook sandboxed, env-scrubbed and time-limited: a minimal
+/// environment, no stdin, and its whole process group killed at the timeout.
+fn run_sanitized(staged: &StagedHook, event: HookEvent, context: HookContext) -> Result<HookOutput, String> {
+    let (mut cmd, tmp) = sandboxed(staged)?;
+    cmd.env_clear();
+    for var in openclaw::PASSTHROUGH_ENV_VARS {
+        if let Ok(val) = std::env::var(var) {
+            cmd.env(var, val);
+        }
+    }
+    if let Ok(path_val) = std::env::var("PATH") {
+        cmd.env("PATH", path_val);
+    }
+    for var in ["TMPDIR", "TMP", "TEMP"] {
+        cmd.env(var, &tmp);
+    }
+    cmd.env("CLAWRUNNER_HOOK_EVENT", event.as_str());
+    cmd.env("CLAWRUNNER_SESSION_ID", context.session_id.to_string());
+    cmd.env("CLAWRUNNER_SESSION_STATUS", context.status);
+    if let Some(code) = context.exit_code {
+        cmd.env("CLAWRUNNER_EXIT_CODE", code.to_string());
+    }
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    exec::own_process_group(&mut cmd);
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to run hook: {}", e))?;
+    let stdout = child.stdout.take().map(capture);
+    let stderr = child.stderr.take().map(capture);
+
+    let started = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= HOOK_TIMEOUT => {
+                timed_out = true;
+                exec::kill_group(&mut child);
+                break child.wait().ok();
+            }
+            Ok(None) => thread::sleep(HOOK_POLL_INTERVAL),
+            Err(e) => return Err(format!("Wait error: {}", e)),
+        }
+    };
+
+    let deadline = Instant::now() + HOOK_DRAIN_GRACE;
+    Ok(HookOutput {
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        stdout: exec::join_by(stdout, deadline).unwrap_or_default(),
+        stderr: exec::join_by(stderr, deadline).unwrap_or_default(),
+    })
+}
+
+fn log_hook_result(event: HookEvent, label: &str, result: &Result<HookOutput, String>) {
+    let Ok(path) = hook_log_path() else { return };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut entry = format!("[{}] {} {}\n", timestamp, event.as_str(), label);
+    match result {
+        Ok(out) => {
+            let status = if out.timed_out {
+                "timed out".to_string()
+            } else {
+                match out.exit_code {
+                    Some(code) => format!("exit {}", code),
+                    None => "terminated by signal".to_string(),
+                }
+            };
+            entry.push_str(&format!("  status: {}\n", status));
+            if !out.stdout.is_empty() {
+                entry.push_str(&format!("  stdout:\n{}\n", out.stdout.trim_end()));
+            }
+            if !out.stderr.is_empty() {
+                entry.push_str(&format!("  stderr:\n{}\n", out.stderr.trim_end()));
+            }
+        }
+        Err(e) => entry.push_str(&format!("  error: {}\n", e)),
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    if let Ok(mut file) = options.open(&path) {
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
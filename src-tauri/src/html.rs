@@ -0,0 +1,407 @@
+//! Converts terminal output into a standalone, styled HTML document: SGR
+//! colors and attributes become inline styles and OSC 8 hyperlinks become
+//! anchors. Cursor movement and other sequences are dropped, and a lone CR
+//! starts the line over, as `ansi::AnsiStripper` does.
+
+/// Longest CSI parameter list or OSC string kept while parsing.
+const MAX_SEQUENCE_LEN: usize = 4096;
+
+const DEFAULT_FG: &str = "#d4d4d4";
+const DEFAULT_BG: &str = "#1e1e1e";
+
+/// xterm's 16 base colors.
+const BASE_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// RGB value of an xterm 256-color palette index.
+pub(crate) fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => BASE_COLORS[i as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            (level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+impl Color {
+    fn css(self) -> String {
+        let (r, g, b) = match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Indexed(i) => indexed_rgb(i),
+        };
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    inverse: bool,
+    link: Option<String>,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let (mut fg, mut bg) = (self.fg.map(Color::css), self.bg.map(Color::css));
+        if self.inverse {
+            let swapped_fg = bg.unwrap_or_else(|| DEFAULT_BG.to_string());
+            let swapped_bg = fg.unwrap_or_else(|| DEFAULT_FG.to_string());
+            fg = Some(swapped_fg);
+            bg = Some(swapped_bg);
+        }
+        let mut css = String::new();
+        if let Some(fg) = fg {
+            css.push_str(&format!("color:{};", fg));
+        }
+        if let Some(bg) = bg {
+            css.push_str(&format!("background:{};", bg));
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.dim {
+            css.push_str("opacity:0.7;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        match (self.underline, self.strike) {
+            (true, true) => css.push_str("text-decoration:underline line-through;"),
+            (true, false) => css.push_str("text-decoration:underline;"),
+            (false, true) => css.push_str("text-decoration:line-through;"),
+            (false, false) => {}
+        }
+        css
+    }
+
+    /// Applies one SGR sequence's parameters.
+    fn apply_sgr(&mut self, params: &str) {
+        let codes: Vec<u16> = params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => {
+                    let link = self.link.take();
+                    *self = Style { link, ..Style::default() };
+                }
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strike = true,
+                21 | 22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strike = false,
+                c @ 30..=37 => self.fg = Some(Color::Indexed((c - 30) as u8)),
+                39 => self.fg = None,
+                c @ 40..=47 => self.bg = Some(Color::Indexed((c - 40) as u8)),
+                49 => self.bg = None,
+                c @ 90..=97 => self.fg = Some(Color::Indexed((c - 90 + 8) as u8)),
+                c @ 100..=107 => self.bg = Some(Color::Indexed((c - 100 + 8) as u8)),
+                c @ (38 | 48) => {
+                    let (color, used) = extended_color(&codes[i + 1..]);
+                    if c == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Parses the arguments of SGR 38/48 (`5;n` or `2;r;g;b`). Returns the color
+/// and how many codes it consumed.
+fn extended_color(codes: &[u16]) -> (Option<Color>, usize) {
+    let byte = |i: usize| codes.get(i).map(|v| (*v).min(255) as u8);
+    match codes.first() {
+        Some(5) => (byte(1).map(Color::Indexed), 2),
+        Some(2) => match (byte(1), byte(2), byte(3)) {
+            (Some(r), Some(g), Some(b)) => (Some(Color::Rgb(r, g, b)), 4),
+            _ => (None, codes.len()),
+        },
+        _ => (None, 0),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+    /// DCS / SOS / PM / APC strings, skipped until BEL or ST.
+    Ignored,
+    IgnoredEscape,
+}
+
+struct Converter {
+    state: State,
+    sequence: String,
+    style: Style,
+    /// The current line as runs of identically styled text.
+    line: Vec<(Style, String)>,
+    pending_cr: bool,
+    html: String,
+}
+
+impl Converter {
+    fn feed(&mut self, c: char) {
+        match self.state {
+            State::Ground => self.ground(c),
+            State::Escape => {
+                self.state = match c {
+                    '[' => State::Csi,
+                    ']' => State::Osc,
+                    'P' | 'X' | '^' | '_' => State::Ignored,
+                    _ => State::Ground,
+                };
+                self.sequence.clear();
+            }
+            State::Csi => {
+                if ('@'..='~').contains(&c) {
+                    if c == 'm' && !self.sequence.starts_with(['?', '>', '<', '=']) {
+                        self.style.apply_sgr(&self.sequence);
+                    }
+                    self.state = State::Ground;
+                } else if self.sequence.len() < MAX_SEQUENCE_LEN {
+                    self.sequence.push(c);
+                }
+            }
+            State::Osc => match c {
+                '\x07' => self.end_osc(),
+                '\x1b' => self.state = State::OscEscape,
+                c if self.sequence.len() < MAX_SEQUENCE_LEN => self.sequence.push(c),
+                _ => {}
+            },
+            State::OscEscape => {
+                if c == '\\' {
+                    self.end_osc();
+                } else {
+                    self.state = State::Osc;
+                }
+            }
+            State::Ignored => match c {
+                '\x07' => self.state = State::Ground,
+                '\x1b' => self.state = State::IgnoredEscape,
+                _ => {}
+            },
+            State::IgnoredEscape => {
+                self.state = if c == '\\' { State::Ground } else { State::Ignored };
+            }
+        }
+    }
+
+    fn ground(&mut self, c: char) {
+        match c {
+            '\x1b' => self.state = State::Escape,
+            '\n' => {
+                self.pending_cr = false;
+                self.finish_line();
+            }
+            '\r' => self.pending_cr = true,
+            '\x08' => {
+                if let Some((_, text)) = self.line.last_mut() {
+                    text.pop();
+                }
+            }
+            '\t' => self.push(c),
+            c if c.is_control() => {}
+            c => self.push(c),
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if self.pending_cr {
+            self.line.clear();
+            self.pending_cr = false;
+        }
+        match self.line.last_mut() {
+            Some((style, text)) if *style == self.style => text.push(c),
+            _ => self.line.push((self.style.clone(), c.to_string())),
+        }
+    }
+
+    /// Handles OSC 8 (`8;params;uri`); other OSC strings are dropped.
+    fn end_osc(&mut self) {
+        self.state = State::Ground;
+        let Some(rest) = self.sequence.strip_prefix("8;") else { return };
+        let uri = rest.split_once(';').map(|(_, uri)| uri).unwrap_or("");
+        let allowed = ["http://", "https://", "mailto:"];
+        self.style.link = allowed
+            .iter()
+            .any(|scheme| uri.starts_with(scheme))
+            .then(|| uri.to_string());
+    }
+
+    fn finish_line(&mut self) {
+        for (style, text) in self.line.drain(..) {
+            if text.is_empty() {
+                continue;
+            }
+            let css = style.css();
+            let mut run = escape(&text);
+            if !css.is_empty() {
+                run = format!("<span style=\"{}\">{}</span>", css, run);
+            }
+            if let Some(link) = &style.link {
+                run = format!("<a href=\"{}\">{}</a>", escape(link), run);
+            }
+            self.html.push_str(&run);
+        }
+        self.html.push('\n');
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Renders terminal output as a complete HTML document titled `title`.
+pub fn to_html(output: &str, title: &str) -> String {
+    let mut converter = Converter {
+        state: State::Ground,
+        sequence: String::new(),
+        style: Style::default(),
+        line: Vec::new(),
+        pending_cr: false,
+        html: String::new(),
+    };
+    for c in output.chars() {
+        converter.feed(c);
+    }
+    if !converter.line.is_empty() {
+        converter.finish_line();
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+         body {{ margin: 0; background: {bg}; }}\n\
+         pre {{ margin: 0; padding: 16px; color: {fg}; background: {bg}; \
+         font: 13px/1.4 Menlo, Consolas, \"DejaVu Sans Mono\", monospace; white-space: pre-wrap; }}\n\
+         a {{ color: inherit; }}\n</style>\n</head>\n<body>\n<pre>{body}</pre>\n</body>\n</html>\n",
+        title = escape(title),
+        fg = DEFAULT_FG,
+        bg = DEFAULT_BG,
+        body = converter.html,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `<pre>` contents of the rendered document.
+    fn body(output: &str) -> String {
+        let html = to_html(output, "test");
+        let start = html.find("<pre>").unwrap() + "<pre>".len();
+        let end = html.rfind("</pre>").unwrap();
+        html[start..end].to_string()
+    }
+
+    #[test]
+    fn maps_sgr_colors_to_styles() {
+        assert_eq!(body("\x1b[31mred\x1b[0m plain\n"), "<span style=\"color:#cd0000;\">red</span> plain\n");
+        assert_eq!(body("\x1b[1;44mX\n"), "<span style=\"background:#0000ee;font-weight:bold;\">X</span>\n");
+        assert_eq!(body("\x1b[38;5;196mX\n"), "<span style=\"color:#ff0000;\">X</span>\n");
+        assert_eq!(body("\x1b[38;2;1;2;3mX\n"), "<span style=\"color:#010203;\">X</span>\n");
+        assert_eq!(body("\x1b[7mX\n"), "<span style=\"color:#1e1e1e;background:#d4d4d4;\">X</span>\n");
+    }
+
+    #[test]
+    fn merges_runs_with_the_same_style() {
+        assert_eq!(body("\x1b[32ma\x1b[32mb\x1b[39mc\n"), "<span style=\"color:#00cd00;\">ab</span>c\n");
+    }
+
+    #[test]
+    fn ignores_private_and_non_sgr_sequences() {
+        assert_eq!(body("\x1b[?1m\x1b[2Ka\x1b[Hb\x1bPdcs\x1b\\c\n"), "abc\n");
+    }
+
+    #[test]
+    fn escapes_text_and_title() {
+        assert_eq!(body("<b>&\"'\n"), "&lt;b&gt;&amp;&quot;&#39;\n");
+        assert!(to_html("", "<script>").contains("<title>&lt;script&gt;</title>"));
+    }
+
+    #[test]
+    fn links_only_safe_schemes() {
+        assert_eq!(
+            body("\x1b]8;;https://x.test/?a=1&b=2\x07link\x1b]8;;\x07 after\n"),
+            "<a href=\"https://x.test/?a=1&amp;b=2\">link</a> after\n"
+        );
+        assert_eq!(body("\x1b]8;;javascript:alert(1)\x1b\\link\x1b]8;;\x1b\\\n"), "link\n");
+    }
+
+    #[test]
+    fn applies_carriage_returns() {
+        assert_eq!(body("50%\r100%\r\ndone"), "100%\ndone\n");
+    }
+
+    #[test]
+    fn computes_the_256_color_palette() {
+        assert_eq!(indexed_rgb(9), (255, 0, 0));
+        assert_eq!(indexed_rgb(16), (0, 0, 0));
+        assert_eq!(indexed_rgb(231), (255, 255, 255));
+        assert_eq!(indexed_rgb(232), (8, 8, 8));
+        assert_eq!(indexed_rgb(255), (238, 238, 238));
+    }
+}
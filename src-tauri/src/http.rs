@@ -0,0 +1,334 @@
+//! Minimal HTTP/1.1 request parsing and responses for the app's loopback
+//! servers (the gateway proxy, the automation API and the MCP server). One
+//! request per connection; bodies need a Content-Length and are only read
+//! once the caller has checked the head (e.g. the token) and the size.
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::settings;
+
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// Connections a server handles at once, each on its own thread. Further
+/// ones are closed right away.
+const MAX_CONNECTIONS: usize = 32;
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often an accept loop checks whether its server was stopped.
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    /// Empty until `read_body`.
+    pub body: Vec<u8>,
+    content_length: usize,
+    reader: BufReader<TcpStream>,
+}
+
+impl Request {
+    /// Reads the body, refusing one over `max` bytes without reading it.
+    pub fn read_body(&mut self, max: usize) -> Result<(), String> {
+        if self.content_length > max {
+            return Err(format!("Request body too large (limit {} bytes)", max));
+        }
+        let mut body = vec![0; self.content_length];
+        self.reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+        self.body = body;
+        Ok(())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The bearer token from `Authorization`, if any.
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("authorization")?.strip_prefix("Bearer ").map(str::trim)
+    }
+}
+
+/// Reads the request line and headers. The body is left on the stream for
+/// `Request::read_body`.
+pub fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut head_len = 0;
+    let mut lines = Vec::new();
+    loop {
+        // Bounded, so a line that never ends can't grow without limit.
+        let mut buf = Vec::new();
+        let limit = (MAX_HEAD_BYTES - head_len + 1) as u64;
+        let n = (&mut reader)
+            .take(limit)
+            .read_until(b'\n', &mut buf)
+            .map_err(|e| e.to_string())?;
+        head_len += n;
+        if n == 0 || head_len > MAX_HEAD_BYTES || buf.last() != Some(&b'\n') {
+            return Err("Malformed request head".to_string());
+        }
+        let line = String::from_utf8(buf).map_err(|_| "Request head is not UTF-8".to_string())?;
+        let line = line.trim_end_matches(['\r', '\n']).to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let mut request_line = lines.first().ok_or("Empty request")?.split_whitespace();
+    let method = request_line.next().ok_or("Missing method")?.to_string();
+    let target = request_line.next().ok_or("Missing target")?.to_string();
+    let headers: Vec<(String, String)> = lines[1..]
+        .iter()
+        .filter_map(|l| l.split_once(':'))
+        .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+        .collect();
+    if headers.iter().any(|(n, _)| n.eq_ignore_ascii_case("transfer-encoding")) {
+        return Err("Chunked bodies are not supported".to_string());
+    }
+    let content_length = match headers.iter().find(|(n, _)| n.eq_ignore_ascii_case("content-length")) {
+        Some((_, v)) => v.parse().map_err(|_| "Invalid Content-Length".to_string())?,
+        None => 0,
+    };
+    Ok(Request {
+        method,
+        target,
+        headers,
+        body: Vec::new(),
+        content_length,
+        reader,
+    })
+}
+
+/// Counts a server's open connections so a flood of them can't spawn
+/// unbounded threads.
+#[derive(Default, Clone)]
+pub struct ConnectionLimit {
+    open: Arc<AtomicUsize>,
+}
+
+impl ConnectionLimit {
+    /// A slot for one more connection, or `None` at `MAX_CONNECTIONS`.
+    /// The slot is given back when dropped.
+    pub fn acquire(&self) -> Option<ConnectionSlot> {
+        self.open
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < MAX_CONNECTIONS).then_some(n + 1))
+            .ok()
+            .map(|_| ConnectionSlot(Arc::clone(&self.open)))
+    }
+}
+
+pub struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Writes a complete response. `headers` are extra header lines, each
+/// ending in CRLF.
+pub fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    headers: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), String> {
+    let head = format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        headers,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).map_err(|e| e.to_string())?;
+    stream.write_all(body).map_err(|e| e.to_string())
+}
+
+/// A server on a fixed loopback port that hands each connection to
+/// `handler` on its own thread. Stops accepting when dropped.
+pub struct LoopbackServer {
+    port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl LoopbackServer {
+    pub fn start<F>(name: &'static str, port: u16, handler: F) -> Result<Self, String>
+    where
+        F: Fn(&mut TcpStream) -> Result<(), String> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| format!("Cannot start {} on port {}: {}", name, port, e))?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let handler = Arc::new(handler);
+        let limit = ConnectionLimit::default();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    thread::sleep(ACCEPT_POLL);
+                    continue;
+                };
+                let Some(slot) = limit.acquire() else {
+                    eprintln!("[{}] Too many connections; closing one", name);
+                    continue;
+                };
+                let handler = Arc::clone(&handler);
+                thread::spawn(move || {
+                    let _slot = slot;
+                    let prepared = stream
+                        .set_nonblocking(false)
+                        .and_then(|_| stream.set_read_timeout(Some(IO_TIMEOUT)))
+                        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)));
+                    let result = prepared.map_err(|e| e.to_string()).and_then(|_| handler(&mut stream));
+                    if let Err(e) = result {
+                        eprintln!("[{}] {}", name, e);
+                    }
+                });
+            }
+        });
+        eprintln!("[{}] Listening on 127.0.0.1:{}", name, port);
+        Ok(Self { port, stop })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for LoopbackServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Reads the bearer token kept at `path`, creating one on first use.
+pub fn load_or_create_token(path: &Path) -> Result<String, String> {
+    if let Ok(token) = std::fs::read_to_string(path) {
+        let token = token.trim().to_string();
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+    let token = random_token();
+    settings::write_private_file(path, token.as_bytes())?;
+    Ok(token)
+}
+
+/// A random 256-bit secret, hex-encoded.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compares secrets without short-circuiting on the first difference.
+pub fn tokens_match(given: Option<&str>, expected: &str) -> bool {
+    let Some(given) = given else { return false };
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends `raw` from a client socket and reads it back as a request.
+    fn parse(raw: &[u8]) -> Result<Request, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(raw).unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        read_request(&server)
+    }
+
+    #[test]
+    fn parses_the_head_without_the_body() {
+        let mut request =
+            parse(b"POST /v1/sessions?x=1 HTTP/1.1\r\nAuthorization: Bearer abc \r\nContent-Length: 5\r\n\r\nhello")
+                .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/v1/sessions?x=1");
+        assert_eq!(request.header("AUTHORIZATION"), Some("Bearer abc"));
+        assert_eq!(request.bearer_token(), Some("abc"));
+        assert!(request.body.is_empty());
+        request.read_body(5).unwrap();
+        assert_eq!(request.body, b"hello");
+    }
+
+    #[test]
+    fn refuses_a_body_over_the_limit_without_reading_it() {
+        let mut request = parse(b"POST / HTTP/1.1\r\nContent-Length: 100\r\n\r\n").unwrap();
+        assert!(request.read_body(99).is_err());
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn no_content_length_means_no_body() {
+        let mut request = parse(b"GET /v1/status HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        request.read_body(0).unwrap();
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_framing() {
+        assert!(parse(b"POST / HTTP/1.1\r\nContent-Length: nope\r\n\r\n").is_err());
+        assert!(parse(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n").is_err());
+        assert!(parse(b"GET / HTTP/1.1\r\n").is_err());
+        assert!(parse(b"\r\n\r\n").is_err());
+        assert!(parse(b"GET\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn rejects_an_oversized_head() {
+        let mut raw = b"GET / HTTP/1.1\r\n".to_vec();
+        raw.extend(format!("X-Padding: {}\r\n\r\n", "a".repeat(MAX_HEAD_BYTES)).as_bytes());
+        assert!(parse(&raw).is_err());
+    }
+
+    #[test]
+    fn rejects_a_head_line_that_never_ends() {
+        let mut raw = b"GET / HTTP/1.1\r\nX-Padding: ".to_vec();
+        raw.extend(vec![b'a'; MAX_HEAD_BYTES + 1024]);
+        assert!(parse(&raw).is_err());
+    }
+
+    #[test]
+    fn short_body_is_an_error() {
+        let mut request = parse(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").unwrap();
+        assert!(request.read_body(10).is_err());
+    }
+
+    #[test]
+    fn connection_limit_hands_back_slots() {
+        let limit = ConnectionLimit::default();
+        let slots: Vec<_> = (0..MAX_CONNECTIONS).map(|_| limit.acquire().unwrap()).collect();
+        assert!(limit.acquire().is_none());
+        drop(slots);
+        assert!(limit.acquire().is_some());
+    }
+
+    #[test]
+    fn tokens_match_exactly() {
+        assert!(tokens_match(Some("secret"), "secret"));
+        assert!(!tokens_match(Some("secreT"), "secret"));
+        assert!(!tokens_match(Some("secret2"), "secret"));
+        assert!(!tokens_match(None, "secret"));
+    }
+}
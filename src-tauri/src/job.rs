@@ -0,0 +1,63 @@
+//! Windows Job Object containment: every session's child is placed in its own
+//! job with kill-on-close, so node.exe and everything it started dies when the
+//! session is killed or the app exits (the OS closes our handles on exit).
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE};
+
+/// Owned job handle. Dropping it closes the handle, which terminates every
+/// process still in the job.
+pub struct JobObject(HANDLE);
+
+// SAFETY: a job handle is a kernel object handle with no thread affinity.
+unsafe impl Send for JobObject {}
+unsafe impl Sync for JobObject {}
+
+impl JobObject {
+    /// Creates a kill-on-close job and assigns the process to it. Processes
+    /// the child starts afterwards inherit the job. Anything it spawned in
+    /// the brief window before assignment is not contained.
+    pub fn contain(pid: u32) -> Result<Self, String> {
+        // SAFETY: all pointers passed are valid for the duration of each call,
+        // and every handle opened here is either owned by the returned
+        // JobObject or closed before returning.
+        unsafe {
+            let job = CreateJobObjectW(None, PCWSTR::null())
+                .map_err(|e| format!("CreateJobObject failed: {}", e))?;
+            let job = JobObject(job);
+
+            let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const core::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+            .map_err(|e| format!("SetInformationJobObject failed: {}", e))?;
+
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, pid)
+                .map_err(|e| format!("OpenProcess({}) failed: {}", pid, e))?;
+            let assigned = AssignProcessToJobObject(job.0, process);
+            let _ = CloseHandle(process);
+            assigned.map_err(|e| format!("AssignProcessToJobObject failed: {}", e))?;
+
+            Ok(job)
+        }
+    }
+}
+
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        // SAFETY: self.0 is a job handle we own and close exactly once.
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
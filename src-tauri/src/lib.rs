@@ -1,131 +1,1646 @@
+mod ansi;
+mod automation;
+mod autorespond;
+mod backup;
+mod capture;
+mod cli;
+mod config;
+mod detached;
+mod exec;
+mod export;
+mod external;
+mod headless;
+mod health;
+mod gatewaylock;
+mod gatewayws;
+mod hooks;
+mod html;
+mod http;
+#[cfg(windows)]
+mod job;
+mod links;
+mod mcp;
+mod migrate;
+mod network;
+mod notify;
+mod oom;
 mod openclaw;
+mod paths;
+mod plugins;
+mod policy;
+mod pool;
+mod ports;
+mod prewarm;
+mod providers;
+mod proxy;
+mod proctree;
+mod prompts;
 mod pty_manager;
+mod recording;
+mod scheduler;
+mod scrollback;
+mod search;
 mod settings;
+mod shortcuts;
+mod snapshots;
+mod state_crypt;
+mod statelock;
+mod statewatch;
+mod stats;
+mod supervisor;
+mod transcript;
+mod tray;
+mod urls;
+mod video;
 
-use pty_manager::PtyManager;
+use automation::{AutomationApi, AutomationInfo};
+use backup::BackupManifest;
+use config::ConfigStatus;
+use detached::{Detached, DetachedSession, LogChunk};
+use exec::{ExecEvent, ExecManager, ExecOutput};
+use export::ExportFormat;
+use external::ExternalGateway;
+use gatewayws::{ConnectionStatus, GatewayEvents};
+use health::{GatewayHealth, GatewayMonitor};
+use hooks::{Hook, HookContext, HookEvent, HookPreview};
+use mcp::{McpInfo, McpServer};
+use migrate::{ImportMode, ImportReport, ImportScan};
+use network::{NetworkAdvice, NetworkMonitor};
+use openclaw::{LaunchMode, OpenClawVersion};
+use plugins::{PluginHost, PluginInfo};
+use policy::ArgPolicy;
+use pool::{PoolManager, PoolStatus};
+use ports::PortConflict;
+use prewarm::Prewarmed;
+use proctree::ProcessInfo;
+use providers::{KeyCheck, ProviderStatus};
+use proxy::{GatewayProxy, ProxyInfo};
+use pty_manager::{
+    AccessibilityMode, BroadcastResult, CloseReason, ExitCallback, PtyManager, PtySignal, ScreenSnapshot, SessionInfo,
+    SessionLabel, SessionOptions,
+};
+use scheduler::{Scheduler, TaskRun};
+use search::SearchResult;
 use settings::Settings;
-use std::sync::Mutex;
-use tauri::Manager;
+use shortcuts::ShortcutRegistry;
+use snapshots::Snapshot;
+use statelock::{LockStatus, StateLock};
+use statewatch::StateWatcher;
+use stats::{SessionStats, StatsSampler};
+use supervisor::Supervisor;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{Emitter, Manager};
 use tauri_plugin_autostart::MacosLauncher;
+use tauri_plugin_global_shortcut::ShortcutState;
+use urls::DetectedUrl;
+use video::{VideoFormat, VideoOptions};
 
-/// Allowed OpenClaw subcommands that can be passed from the frontend.
-const ALLOWED_ARGS: &[&str] = &[
-    "onboard",
-    "--skip-daemon",
-    "gateway",
-    "tui",
-];
+pub(crate) struct AppState {
+    pub(crate) pty: PtyManager,
+    pub(crate) settings: Mutex<Settings>,
+    /// What each running OpenClaw session was spawned with, for `pty_clone`.
+    pub(crate) launches: Mutex<HashMap<u64, Launch>>,
+}
 
-fn validate_args(args: &[String]) -> Result<(), String> {
-    for arg in args {
-        if !ALLOWED_ARGS.contains(&arg.as_str()) {
-            return Err(format!("Disallowed argument: {}", arg));
-        }
+/// The profile-resolved settings (and so the environment) and arguments
+/// of a session.
+#[derive(Clone)]
+pub(crate) struct Launch {
+    settings: Settings,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+}
+
+/// Error returned by `pty_spawn`: a plain message, a port conflict the
+/// frontend can present with its remediation, or a gateway already running
+/// outside the app that should be used instead.
+#[derive(Debug, serde::Serialize)]
+#[serde(untagged)]
+pub(crate) enum SpawnError {
+    Message(String),
+    PortInUse(PortConflict),
+    ExternalGateway { message: String, gateway: ExternalGateway },
+}
+
+impl From<String> for SpawnError {
+    fn from(message: String) -> Self {
+        SpawnError::Message(message)
     }
-    Ok(())
 }
 
-struct AppState {
-    pty: PtyManager,
-    settings: Mutex<Settings>,
+impl std::fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnError::Message(message) | SpawnError::ExternalGateway { message, .. } => f.write_str(message),
+            SpawnError::PortInUse(conflict) => f.write_str(&conflict.message),
+        }
+    }
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn pty_spawn(
     app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
     settings: Settings,
-    args: Vec<String>,
+    mode: LaunchMode,
+    profile: Option<String>,
+    cols: u16,
+    rows: u16,
+    options: Option<SessionOptions>,
+    output: Channel<InvokeResponseBody>,
+) -> Result<u64, SpawnError> {
+    // The webview's settings become the stored settings, so they get the
+    // same checks as `save_settings`.
+    validate_settings(&app, &settings)?;
+    let mut options = options.unwrap_or_default();
+    let hold_output = options.hold_output;
+    // Output is held until the channel is attached below, so none is lost.
+    options.hold_output = true;
+    let session_id = launch_session(&app, settings, mode, profile.as_deref(), cols, rows, options)?;
+    state.pty.set_output_channel(session_id, output)?;
+    if !hold_output {
+        state.pty.release_output(session_id)?;
+    }
+    Ok(session_id)
+}
+
+/// Spawns another session like `session_id`: same arguments, profile,
+/// environment and terminal size. Output is attached to `output` as with
+/// `pty_spawn`.
+#[tauri::command]
+fn pty_clone(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+    options: Option<SessionOptions>,
+    output: Channel<InvokeResponseBody>,
+) -> Result<u64, String> {
+    let launch = state
+        .launches
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&session_id)
+        .cloned()
+        .ok_or_else(|| format!("No running session with id {}", session_id))?;
+    if launch.args.first().map(String::as_str) == Some("gateway") {
+        return Err("A gateway can't be cloned: the copy would need the same port and state directory".to_string());
+    }
+    app.state::<ArgPolicy>().validate(&launch.args)?;
+    app.state::<StateLock>().ensure_writer()?;
+    let (cols, rows) = state.pty.size(session_id)?;
+    let mut options = options.unwrap_or_default();
+    let hold_output = options.hold_output;
+    options.hold_output = true;
+    options.cwd = launch.cwd;
+    let clone_id = spawn_openclaw_session(&app, &state, &launch.settings, &launch.args, cols, rows, options)?;
+    state.pty.set_output_channel(clone_id, output)?;
+    if !hold_output {
+        state.pty.release_output(clone_id)?;
+    }
+    Ok(clone_id)
+}
+
+/// Spawns the user's login shell with OpenClaw's environment, for
+/// inspecting state or running `node` by hand. Only with `developerMode`.
+#[tauri::command]
+fn pty_spawn_shell(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    profile: Option<String>,
     cols: u16,
     rows: u16,
+    options: Option<SessionOptions>,
+    output: Channel<InvokeResponseBody>,
 ) -> Result<u64, String> {
     if cols == 0 || rows == 0 {
         return Err("cols and rows must be non-zero".to_string());
     }
-    validate_args(&args)?;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?.for_profile(profile.as_deref())?;
+    if !settings.developer_mode {
+        return Err("Developer shells are only available with Developer Mode enabled in settings".to_string());
+    }
+    let mut options = options.unwrap_or_default();
+    if let Some(term) = &options.term {
+        openclaw::validate_term(Some(term))?;
+        settings.term = Some(term.clone());
+    }
+    openclaw::validate_env_overrides(&options.env)?;
+    settings.api_keys.extend(options.env.drain());
+    let mut cmd = openclaw::build_shell_command(&app, &settings)?;
+    if let Some(cwd) = &options.cwd {
+        cmd.cwd(openclaw::resolve_cwd(&app, &cwd.to_string_lossy())?);
+    }
+    // Reported by `pty_list`, so the UI can tell shells from OpenClaw.
+    options.args = vec!["shell".to_string()];
+    let hold_output = options.hold_output;
+    options.hold_output = true;
+    let session_id = state.pty.spawn(&app, cmd, cols, rows, options, Box::new(|_, _, _| {}))?;
+    state.pty.set_output_channel(session_id, output)?;
+    if !hold_output {
+        state.pty.release_output(session_id)?;
+    }
+    Ok(session_id)
+}
+
+/// Checks `mode` against the argument policy and the state lock, stores
+/// `settings` as the current settings, and starts the session (claiming a
+/// prewarmed one if it matches). Shared by `pty_spawn` and the automation
+/// API; the caller decides when to release the session's output.
+pub(crate) fn launch_session(
+    app: &tauri::AppHandle,
+    mut settings: Settings,
+    mode: LaunchMode,
+    profile: Option<&str>,
+    cols: u16,
+    rows: u16,
+    mut options: SessionOptions,
+) -> Result<u64, SpawnError> {
+    if cols == 0 || rows == 0 {
+        return Err("cols and rows must be non-zero".to_string().into());
+    }
+    let state = app.state::<AppState>();
+    let mut mode = mode;
+    let profile_port = profile
+        .or(settings.active_profile.as_deref())
+        .and_then(|id| settings.profile(id))
+        .and_then(|p| p.gateway_port);
+    if let LaunchMode::Gateway { port: port @ None, .. } = &mut mode {
+        *port = profile_port;
+    }
+    app.state::<ArgPolicy>().validate_mode(&mode)?;
+    app.state::<StateLock>().ensure_writer()?;
+    let args = mode.args();
 
-    // Update stored settings
+    // Update stored settings. Developer Mode and the state location only
+    // change through `save_settings`, so a spawn keeps the stored values.
     {
         let mut s = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.developer_mode = s.developer_mode;
+        settings.node_inspect_port = s.node_inspect_port;
+        settings.state_directory = s.state_directory.clone();
         *s = settings.clone();
     }
+    apply_session_settings(&state.pty, &settings);
+    let mut settings = settings.for_profile(profile)?;
+    if let Some(term) = &options.term {
+        openclaw::validate_term(Some(term))?;
+        settings.term = Some(term.clone());
+    }
+    if !options.env.is_empty() {
+        openclaw::validate_env_overrides(&options.env)?;
+        // Launches apply them with the same allowlist as the settings keys.
+        settings.api_keys.extend(options.env.drain());
+    }
+    if let Some(cwd) = &options.cwd {
+        // Gateways serve every session, and a supervised restart or a
+        // prewarmed gateway wouldn't carry the directory along.
+        if matches!(mode, LaunchMode::Gateway { .. }) {
+            return Err("A gateway can't be started in a custom working directory".to_string().into());
+        }
+        options.cwd = Some(openclaw::resolve_cwd(app, &cwd.to_string_lossy())?);
+    }
 
-    let cmd = openclaw::build_openclaw_command(&app, &settings, &args)?;
-    state.pty.spawn(&app, cmd, cols, rows)
+    let supervised = matches!(mode, LaunchMode::Gateway { .. }) && settings.auto_restart_gateway();
+    let claimed = app
+        .state::<Prewarmed>()
+        .claim(&state, &settings, &args, cols, rows, &options);
+    if let Some(session_id) = claimed {
+        state.pty.set_accessibility(session_id, options.accessibility)?;
+        if supervised {
+            app.state::<Supervisor>().watch(session_id, &settings, &args, cols, rows);
+        }
+        return Ok(session_id);
+    }
+
+    // A prewarmed gateway holds the port itself, so only check here.
+    if matches!(mode, LaunchMode::Gateway { .. }) {
+        let state_dir = openclaw::profile_state_dir(settings.active_profile.as_deref())?;
+        let detached = app.try_state::<Detached>().and_then(|d| d.status()).map(|d| d.pid);
+        if let Some(gateway) = external::find(&state_dir, detached) {
+            return Err(SpawnError::ExternalGateway {
+                message: format!(
+                    "An OpenClaw gateway started outside the app (pid {}) is already using this state directory on port {}; the app will use it instead of starting another",
+                    gateway.pid, gateway.port
+                ),
+                gateway,
+            });
+        }
+        if let Some(conflict) = ports::check(openclaw::gateway_port(&args)) {
+            return Err(SpawnError::PortInUse(conflict));
+        }
+    }
+    // Watched before spawning: a gateway that exits at once must already be
+    // known to the supervisor when its exit comes in.
+    let session_id = pty_manager::next_session_id();
+    if supervised {
+        app.state::<Supervisor>().watch(session_id, &settings, &args, cols, rows);
+    }
+    options.session_id = Some(session_id);
+    if let Err(e) = spawn_openclaw_session(app, &state, &settings, &args, cols, rows, options) {
+        app.state::<Supervisor>().forget(session_id);
+        return Err(e.into());
+    }
+    Ok(session_id)
 }
 
+/// Spawns an OpenClaw session and wires up its lifecycle hooks. Callers are
+/// responsible for policy and state-lock checks.
+pub(crate) fn spawn_openclaw_session(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    settings: &Settings,
+    args: &[String],
+    cols: u16,
+    rows: u16,
+    mut options: SessionOptions,
+) -> Result<u64, String> {
+    let state_dir = openclaw::profile_state_dir(settings.active_profile.as_deref())?;
+    state_crypt::unseal(&state_dir, &settings.encrypted_state_files)?;
+    let mut cmd = openclaw::build_openclaw_command(app, settings, args)?;
+    if let Some(cwd) = &options.cwd {
+        cmd.cwd(cwd);
+    }
+    let subcommand = args.first().cloned().unwrap_or_default();
+    let is_gateway = subcommand == "gateway";
+    // Gateways are long-lived and quiet by design; never reap them as idle.
+    options.idle_exempt = is_gateway;
+    options.args = args.to_vec();
+    let cwd = options.cwd.clone();
+    if is_gateway {
+        gatewaylock::acquire(&state_dir)?;
+    }
+    // The lock is handed to the gateway's pid once it is known.
+    let lock_pid = Arc::new(AtomicU32::new(std::process::id()));
+    let exit_lock_pid = Arc::clone(&lock_pid);
+    let exit_state_dir = state_dir.clone();
+    let exit_app = app.clone();
+    let heap_mb = settings.node_heap_mb;
+    let on_exit: ExitCallback = Box::new(move |session_id, reason, exit_code| {
+        if subcommand == "gateway" {
+            gatewaylock::release(&exit_state_dir, exit_lock_pid.load(Ordering::SeqCst));
+        }
+        if let Ok(mut launches) = exit_app.state::<AppState>().launches.lock() {
+            launches.remove(&session_id);
+        }
+        if reason == CloseReason::ChildExit && exit_code != Some(0) {
+            oom::check_exit(&exit_app, session_id, &subcommand, heap_mb);
+        }
+        fire_exit_hooks(&subcommand, session_id, reason, exit_code);
+        exit_app
+            .state::<Supervisor>()
+            .on_exit(&exit_app, session_id, reason, exit_code);
+    });
+    let session_id = match state.pty.spawn(app, cmd, cols, rows, options, on_exit) {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            if is_gateway {
+                gatewaylock::release(&state_dir, std::process::id());
+            }
+            return Err(e);
+        }
+    };
+    if let Ok(mut launches) = state.launches.lock() {
+        launches.insert(
+            session_id,
+            Launch {
+                settings: settings.clone(),
+                args: args.to_vec(),
+                cwd: cwd.clone(),
+            },
+        );
+    }
+    // A child that exited before the insert may already have run `on_exit`,
+    // which then had nothing to remove.
+    if !state.pty.is_running(session_id) {
+        if let Ok(mut launches) = state.launches.lock() {
+            launches.remove(&session_id);
+        }
+    }
+    if is_gateway {
+        if let Ok(Some(pid)) = state.pty.child_pid(session_id) {
+            if gatewaylock::assign(&state_dir, pid).is_ok() {
+                lock_pid.store(pid, Ordering::SeqCst);
+            }
+        }
+    }
+    let context = HookContext {
+        session_id,
+        status: "running",
+        exit_code: None,
+    };
+    hooks::fire(HookEvent::SessionStart, context);
+    if is_gateway {
+        hooks::fire(HookEvent::GatewayStart, context);
+    }
+    Ok(session_id)
+}
+
+/// Pushes the session-related settings into the PTY manager.
+fn apply_session_settings(pty: &PtyManager, settings: &Settings) {
+    pty.set_idle_policy(settings.idle_policy());
+    pty.set_scrollback_limit(settings.scrollback_limit());
+    pty.set_kill_grace(settings.kill_grace());
+    pty.set_idle_threshold(settings.idle_threshold());
+    pty.set_reader_limits(settings.read_buffer_size(), settings.leftover_cap());
+    pty.set_transcript_policy(settings.transcript_policy());
+    pty.set_window_title_sync(settings.sync_window_title);
+    if let Err(e) = pty.set_auto_responses(&settings.auto_responses) {
+        eprintln!("[settings] Auto-responses disabled: {}", e);
+    }
+}
+
+/// Maps a session exit onto lifecycle hook events.
+fn fire_exit_hooks(subcommand: &str, session_id: u64, reason: CloseReason, exit_code: Option<u32>) {
+    let context = |status| HookContext {
+        session_id,
+        status,
+        exit_code,
+    };
+    match (subcommand, reason) {
+        ("gateway", CloseReason::ChildExit) if exit_code != Some(0) => {
+            hooks::fire(HookEvent::GatewayCrash, context("crashed"))
+        }
+        ("gateway", CloseReason::SupervisorRestart | CloseReason::Watchdog) => {
+            hooks::fire(HookEvent::GatewayCrash, context("crashed"))
+        }
+        ("gateway", _) => hooks::fire(HookEvent::GatewayStop, context("stopped")),
+        ("onboard", CloseReason::ChildExit) if openclaw::is_configured() => {
+            hooks::fire(HookEvent::OnboardingComplete, context("completed"))
+        }
+        _ => {}
+    }
+}
+
+/// Bound on a single write IPC call; the backend streams what it accepts to
+/// the PTY in chunks, and the frontend splits larger writes into calls.
 const MAX_WRITE_SIZE: usize = 1_048_576; // 1 MB
 
 #[tauri::command]
-fn pty_write(state: tauri::State<'_, AppState>, session_id: u64, data: String) -> Result<(), String> {
+async fn pty_write(state: tauri::State<'_, AppState>, session_id: u64, data: String) -> Result<(), String> {
     if data.len() > MAX_WRITE_SIZE {
         return Err(format!("Write data too large: {} bytes", data.len()));
     }
-    state.pty.write(session_id, &data)
+    state.pty.write(session_id, &data).await
+}
+
+/// Writes the same input to several sessions, e.g. one command for every
+/// profile's gateway. Returns each session's outcome.
+#[tauri::command]
+async fn pty_broadcast(
+    state: tauri::State<'_, AppState>,
+    session_ids: Vec<u64>,
+    data: String,
+) -> Result<Vec<BroadcastResult>, String> {
+    if data.len() > MAX_WRITE_SIZE {
+        return Err(format!("Write data too large: {} bytes", data.len()));
+    }
+    state.pty.broadcast(Some(&session_ids), &data).await
+}
+
+/// `pty_broadcast` to every session.
+#[tauri::command]
+async fn pty_broadcast_all(state: tauri::State<'_, AppState>, data: String) -> Result<Vec<BroadcastResult>, String> {
+    if data.len() > MAX_WRITE_SIZE {
+        return Err(format!("Write data too large: {} bytes", data.len()));
+    }
+    state.pty.broadcast(None, &data).await
+}
+
+/// Inputs accepted by one `pty_enqueue` call.
+const MAX_QUEUED_INPUTS: usize = 256;
+
+/// Writes each input once the session is waiting at a prompt, instead of
+/// all at once; resolves after the last one is written.
+#[tauri::command]
+async fn pty_enqueue(state: tauri::State<'_, AppState>, session_id: u64, inputs: Vec<String>) -> Result<(), String> {
+    if inputs.len() > MAX_QUEUED_INPUTS {
+        return Err(format!("Too many inputs: {} (max {})", inputs.len(), MAX_QUEUED_INPUTS));
+    }
+    let size: usize = inputs.iter().map(String::len).sum();
+    if size > MAX_WRITE_SIZE {
+        return Err(format!("Write data too large: {} bytes", size));
+    }
+    state.pty.enqueue(session_id, &inputs).await
+}
+
+/// Like `pty_write`, for binary input such as invalid UTF-8 or answerback
+/// sequences.
+#[tauri::command]
+async fn pty_write_bytes(state: tauri::State<'_, AppState>, session_id: u64, data: Vec<u8>) -> Result<(), String> {
+    if data.len() > MAX_WRITE_SIZE {
+        return Err(format!("Write data too large: {} bytes", data.len()));
+    }
+    state.pty.write_bytes(session_id, &data).await
+}
+
+/// Pastes text, using bracketed paste when the application supports it.
+#[tauri::command]
+async fn pty_paste(state: tauri::State<'_, AppState>, session_id: u64, text: String) -> Result<(), String> {
+    if text.len() > MAX_WRITE_SIZE {
+        return Err(format!("Paste too large: {} bytes", text.len()));
+    }
+    state.pty.paste(session_id, &text).await
 }
 
+/// Pixel dimensions are optional; omitted ones are reported as 0.
 #[tauri::command]
-fn pty_resize(state: tauri::State<'_, AppState>, session_id: u64, cols: u16, rows: u16) -> Result<(), String> {
+async fn pty_resize(
+    state: tauri::State<'_, AppState>,
+    supervisor: tauri::State<'_, Supervisor>,
+    session_id: u64,
+    cols: u16,
+    rows: u16,
+    pixel_width: Option<u16>,
+    pixel_height: Option<u16>,
+) -> Result<(), String> {
     if cols == 0 || rows == 0 {
         return Err("cols and rows must be non-zero".to_string());
     }
-    state.pty.resize(session_id, cols, rows)
+    state
+        .pty
+        .resize(session_id, cols, rows, pixel_width.unwrap_or(0), pixel_height.unwrap_or(0))?;
+    supervisor.note_resize(session_id, cols, rows);
+    Ok(())
+}
+
+#[tauri::command]
+fn pty_set_accessibility(
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+    mode: AccessibilityMode,
+) -> Result<(), String> {
+    state.pty.set_accessibility(session_id, mode)
+}
+
+/// Sends SIGINT/SIGTERM/SIGHUP/SIGWINCH/SIGKILL to a session.
+#[tauri::command]
+async fn pty_signal(state: tauri::State<'_, AppState>, session_id: u64, signal: PtySignal) -> Result<(), String> {
+    state.pty.signal(session_id, signal).await
+}
+
+/// Starts delivering output for a session spawned with `holdOutput`, once
+/// the frontend is listening on its `pty:data:{id}` event or output channel.
+#[tauri::command]
+fn pty_attach(state: tauri::State<'_, AppState>, session_id: u64) -> Result<(), String> {
+    state.pty.release_output(session_id)
+}
+
+/// Resumes streaming a session that outlived its frontend (webview reload
+/// or renderer crash), replaying buffered output first.
+#[tauri::command]
+fn pty_reattach(
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+    output: Channel<InvokeResponseBody>,
+) -> Result<(), String> {
+    state.pty.reattach(session_id, output)
+}
+
+/// Acknowledges output messages up to `seq` for backpressure. Channel
+/// messages carry no sequence number; the n-th message received has seq n.
+#[tauri::command]
+fn pty_ack(state: tauri::State<'_, AppState>, session_id: u64, seq: u64) -> Result<(), String> {
+    state.pty.ack(session_id, seq)
+}
+
+/// Freezes a session (SIGSTOP) without losing its state.
+#[tauri::command]
+fn pty_pause(state: tauri::State<'_, AppState>, session_id: u64) -> Result<(), String> {
+    state.pty.pause(session_id)
+}
+
+#[tauri::command]
+fn pty_resume(state: tauri::State<'_, AppState>, session_id: u64) -> Result<(), String> {
+    state.pty.resume(session_id)
+}
+
+/// Lists live sessions so the frontend can rediscover ones it lost track of.
+#[tauri::command]
+fn pty_list(state: tauri::State<'_, AppState>) -> Result<Vec<SessionInfo>, String> {
+    state.pty.list()
+}
+
+/// Attaches a display name, color and frontend metadata to a session.
+#[tauri::command]
+fn pty_set_label(state: tauri::State<'_, AppState>, session_id: u64, label: SessionLabel) -> Result<(), String> {
+    state.pty.set_label(session_id, label)
+}
+
+/// Returns the session's recent output so a terminal can be repopulated
+/// after a reload or tab switch.
+#[tauri::command]
+fn pty_get_scrollback(state: tauri::State<'_, AppState>, session_id: u64) -> Result<String, String> {
+    state.pty.scrollback(session_id)
+}
+
+/// Debug toggle: tees a session's raw byte stream to <data dir>/captures.
+/// Returns the capture file path.
+#[tauri::command]
+fn pty_set_raw_capture(
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+    enabled: bool,
+) -> Result<Option<String>, String> {
+    state.pty.set_raw_capture(session_id, enabled)
+}
+
+/// Starts an asciicast recording of the session under <data dir>/recordings.
+/// Returns the recording's path.
+#[tauri::command]
+fn pty_record_start(state: tauri::State<'_, AppState>, session_id: u64) -> Result<String, String> {
+    state.pty.record_start(session_id)
+}
+
+/// Finishes the session's recording. Returns its path, if one was active.
+#[tauri::command]
+fn pty_record_stop(state: tauri::State<'_, AppState>, session_id: u64) -> Result<Option<String>, String> {
+    state.pty.record_stop(session_id)
+}
+
+/// Plays a recorded .cast file from the recordings folder back over
+/// `pty:data` events at `speed` times the recorded pace (default 1.0).
+/// Returns the replay's session ID.
+#[tauri::command]
+fn pty_replay(app: tauri::AppHandle, path: String, speed: Option<f64>) -> Result<u64, String> {
+    let path = recording::resolve_recording(&path)?;
+    recording::replay(&app, &path, speed.unwrap_or(1.0))
+}
+
+/// Renders a recording from the recordings folder to a GIF or MP4 chosen
+/// in a save dialog, emitting `recording:export-progress`. Returns the path
+/// written, or `None` if the user cancelled.
+#[tauri::command]
+async fn recording_export_video(
+    app: tauri::AppHandle,
+    path: String,
+    format: VideoFormat,
+    options: Option<VideoOptions>,
+) -> Result<Option<String>, String> {
+    let path = recording::resolve_recording(&path)?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || video::export_with_dialog(&app, &path, format, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Searches a session's output, including what scrolled out of the
+/// terminal, and returns each match with `context` lines around it.
+#[tauri::command]
+async fn pty_search(
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+    pattern: String,
+    regex: bool,
+    context: Option<usize>,
+) -> Result<SearchResult, String> {
+    let lines = state.pty.output_lines(session_id)?;
+    search::search(&lines, &pattern, regex, context.unwrap_or(search::DEFAULT_CONTEXT_LINES))
+}
+
+/// Saves a session's output to a file chosen in a save dialog. Returns the
+/// path written, or `None` if the user cancelled.
+#[tauri::command]
+async fn export_transcript(
+    app: tauri::AppHandle,
+    session_id: u64,
+    format: ExportFormat,
+) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        export::export_transcript(&app, &app.state::<AppState>().pty, session_id, format)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Makes the window title follow this session's title (the visible tab);
+/// 0 clears it. Only has an effect with `syncWindowTitle` enabled.
+#[tauri::command]
+fn pty_set_title_source(app: tauri::AppHandle, state: tauri::State<'_, AppState>, session_id: u64) -> Result<(), String> {
+    state.pty.set_title_source(&app, session_id)
+}
+
+/// Opens a link from terminal output in the default browser or mail
+/// client. Only http, https and mailto URLs are accepted.
+#[tauri::command]
+fn open_link(url: String) -> Result<(), String> {
+    links::open(&url)
+}
+
+/// Returns the session's current screen as rendered by the backend's
+/// terminal emulator, e.g. for tab previews.
+#[tauri::command]
+fn pty_get_screen(state: tauri::State<'_, AppState>, session_id: u64) -> Result<ScreenSnapshot, String> {
+    state.pty.screen(session_id)
+}
+
+/// Returns the http(s) URLs the session printed recently, oldest first.
+#[tauri::command]
+fn pty_get_links(state: tauri::State<'_, AppState>, session_id: u64) -> Result<Vec<DetectedUrl>, String> {
+    state.pty.links(session_id)
+}
+
+/// CPU, memory and open files of each session's process tree. Pass a
+/// session ID for just that session; omit it for all of them.
+#[tauri::command]
+async fn pty_stats(
+    state: tauri::State<'_, AppState>,
+    sampler: tauri::State<'_, StatsSampler>,
+    session_id: Option<u64>,
+) -> Result<Vec<SessionStats>, String> {
+    let mut pids = state.pty.child_pids();
+    if let Some(session_id) = session_id {
+        pids.retain(|(id, _)| *id == session_id);
+        if pids.is_empty() {
+            return Err(format!("Session {} has no running process", session_id));
+        }
+    }
+    Ok(sampler.sample(&pids))
+}
+
+/// Runs a short-lived, non-interactive OpenClaw command through pipes
+/// instead of a PTY, e.g. status checks. Arguments are checked against the
+/// argument policy; `timeoutMs` defaults to 60s (max 10 min).
+#[tauri::command]
+async fn openclaw_exec(
+    app: tauri::AppHandle,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+) -> Result<ExecOutput, String> {
+    // Can run for minutes; keep it off the async runtime.
+    tauri::async_runtime::spawn_blocking(move || run_openclaw(&app, &args, exec_timeout(timeout_ms)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Runs a one-shot OpenClaw command to completion, subject to the argument
+/// policy and the state lock. Shared by `openclaw_exec` and the scheduler.
+pub(crate) fn run_openclaw(
+    app: &tauri::AppHandle,
+    args: &[String],
+    timeout: std::time::Duration,
+) -> Result<ExecOutput, String> {
+    app.state::<ArgPolicy>().validate(args)?;
+    app.state::<StateLock>().ensure_writer()?;
+    let settings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .for_profile(None)?;
+    let state_dir = openclaw::profile_state_dir(settings.active_profile.as_deref())?;
+    state_crypt::unseal(&state_dir, &settings.encrypted_state_files)?;
+    let cmd = openclaw::build_openclaw_process(app, &settings, args)?;
+    exec::run(cmd, timeout)
+}
+
+/// Like `openclaw_exec`, but streams stdout/stderr lines to `on_event` as
+/// they arrive and returns an id for `openclaw_exec_cancel`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn openclaw_exec_stream(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    policy: tauri::State<'_, ArgPolicy>,
+    lock: tauri::State<'_, StateLock>,
+    exec: tauri::State<'_, ExecManager>,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+    on_event: Channel<ExecEvent>,
+) -> Result<u64, String> {
+    policy.validate(&args)?;
+    lock.ensure_writer()?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.for_profile(None)?;
+    let state_dir = openclaw::profile_state_dir(settings.active_profile.as_deref())?;
+    state_crypt::unseal(&state_dir, &settings.encrypted_state_files)?;
+    let cmd = openclaw::build_openclaw_process(&app, &settings, &args)?;
+    exec.spawn(cmd, exec_timeout(timeout_ms), on_event)
+}
+
+/// Runs recorded by the scheduler, oldest first.
+#[tauri::command]
+fn scheduler_history(scheduler: tauri::State<'_, Scheduler>) -> Vec<TaskRun> {
+    scheduler.history()
+}
+
+/// Runs a scheduled task now, regardless of its schedule. The result
+/// arrives as `scheduler:run`.
+#[tauri::command]
+fn scheduler_run_now(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    scheduler: tauri::State<'_, Scheduler>,
+    task_id: String,
+) -> Result<(), String> {
+    let task = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .scheduled_tasks
+        .iter()
+        .find(|t| t.id == task_id)
+        .cloned()
+        .ok_or_else(|| format!("No scheduled task {:?}", task_id))?;
+    scheduler.launch(&app, task);
+    Ok(())
 }
 
+/// Kills a command started with `openclaw_exec_stream`.
 #[tauri::command]
-fn pty_kill(state: tauri::State<'_, AppState>, session_id: u64) -> Result<(), String> {
+fn openclaw_exec_cancel(exec: tauri::State<'_, ExecManager>, id: u64) -> Result<(), String> {
+    exec.cancel(id)
+}
+
+fn exec_timeout(timeout_ms: Option<u64>) -> std::time::Duration {
+    timeout_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(exec::DEFAULT_EXEC_TIMEOUT)
+        .min(exec::MAX_EXEC_TIMEOUT)
+}
+
+/// Latest health probe of each running gateway (see `gateway:health`).
+#[tauri::command]
+fn gateway_health(monitor: tauri::State<'_, GatewayMonitor>) -> Vec<GatewayHealth> {
+    monitor.current()
+}
+
+/// Address and token for calling the gateway's HTTP API through the
+/// loopback proxy.
+#[tauri::command]
+fn gateway_proxy(proxy: tauri::State<'_, GatewayProxy>) -> Result<ProxyInfo, String> {
+    proxy.info()
+}
+
+/// Whether the automation API is running, where, and where its token is.
+#[tauri::command]
+fn automation_api_info(api: tauri::State<'_, AutomationApi>) -> Result<AutomationInfo, String> {
+    api.info()
+}
+
+/// Whether the MCP server is running, its endpoint, and where its token is.
+#[tauri::command]
+fn mcp_server_info(mcp: tauri::State<'_, McpServer>) -> Result<McpInfo, String> {
+    mcp.info()
+}
+
+/// Whether the gateway event stream is connected, and to which port.
+#[tauri::command]
+fn gateway_events_status(events: tauri::State<'_, GatewayEvents>) -> ConnectionStatus {
+    events.status()
+}
+
+/// Gateway started outside the app against the active state dir, as of the
+/// latest health round.
+#[tauri::command]
+fn gateway_external(monitor: tauri::State<'_, GatewayMonitor>) -> Option<ExternalGateway> {
+    monitor.external()
+}
+
+/// Known providers and services, with whether the profile (or the default
+/// settings) has a key for each. Keys are only returned masked.
+#[tauri::command]
+fn providers_list(state: tauri::State<'_, AppState>, profile: Option<String>) -> Result<Vec<ProviderStatus>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.for_profile(profile.as_deref())?;
+    Ok(providers::status(&settings))
+}
+
+/// Checks an API key with a minimal request to its provider. Checks `key`
+/// if given (a value typed but not yet saved), otherwise the saved key.
+#[tauri::command]
+async fn validate_api_key(
+    state: tauri::State<'_, AppState>,
+    provider: String,
+    profile: Option<String>,
+    key: Option<String>,
+) -> Result<KeyCheck, String> {
+    let provider = providers::find(&provider).ok_or_else(|| format!("Unknown provider: {}", provider))?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.for_profile(profile.as_deref())?;
+    let key = key
+        .or_else(|| provider.key_var.and_then(|var| settings.api_keys.get(var).cloned()))
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .ok_or_else(|| format!("No {} API key is set", provider.name))?;
+    providers::check_key(provider, &key, &settings).await
+}
+
+/// Reports the OpenClaw and Node.js versions the app runs.
+#[tauri::command]
+async fn openclaw_version(app: tauri::AppHandle) -> Result<OpenClawVersion, String> {
+    // Two child processes with a timeout each; keep them off the async runtime.
+    tauri::async_runtime::spawn_blocking(move || openclaw::version(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Lists the session's child and every process it has spawned.
+#[tauri::command]
+async fn pty_process_tree(
+    state: tauri::State<'_, AppState>,
+    session_id: u64,
+) -> Result<Vec<ProcessInfo>, String> {
+    let pid = state
+        .pty
+        .child_pid(session_id)?
+        .ok_or_else(|| format!("Session {} has no running process", session_id))?;
+    Ok(proctree::process_tree(pid))
+}
+
+/// Async so the grace period before force-killing doesn't block the main thread.
+#[tauri::command]
+async fn pty_kill(
+    state: tauri::State<'_, AppState>,
+    supervisor: tauri::State<'_, Supervisor>,
+    session_id: u64,
+) -> Result<(), String> {
     if session_id == 0 {
         return Err("Invalid session_id: 0 is reserved".to_string());
     }
-    state.pty.kill(session_id)
+    // Also cancels a pending restart of a gateway that already exited.
+    supervisor.forget(session_id);
+    state.pty.kill(session_id, CloseReason::UserKill).await
+}
+
+/// Checks everything in `settings` that is validated before it is stored;
+/// shared by `save_settings` and `pty_spawn`.
+fn validate_settings(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    settings.validate_profiles()?;
+    // Reject conflicting shortcuts before anything is persisted.
+    shortcuts::validate(&shortcuts::effective_bindings(settings))?;
+    autorespond::compile(&settings.auto_responses)?;
+    openclaw::validate_env_passthrough(&settings.env_passthrough)?;
+    openclaw::validate_path_dirs(settings)?;
+    openclaw::validate_node_flags(settings)?;
+    openclaw::validate_term(settings.term.as_deref())?;
+    openclaw::validate_locale(settings)?;
+    providers::validate(settings)?;
+    scheduler::validate(&settings.scheduled_tasks, &app.state::<ArgPolicy>())
+}
+
+/// Drops the cached state encryption key so the next launch reads it from
+/// the keychain again.
+#[tauri::command]
+fn lock_credentials() {
+    state_crypt::lock();
 }
 
 #[tauri::command]
 fn save_settings(
+    app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
     settings: Settings,
 ) -> Result<(), String> {
+    lock.ensure_writer()?;
+    let mut settings = settings;
+    // The state location only changes through `state_dir_move`, which also
+    // moves the data and is the only writer of the value on disk.
+    settings.state_directory = settings::load_settings().state_directory;
+    validate_settings(&app, &settings)?;
     settings::save_settings_to_disk(&settings)?;
-    let mut s = state.settings.lock().map_err(|e| e.to_string())?;
-    *s = settings;
+    shortcuts::apply(&app, &settings)?;
+    apply_session_settings(&state.pty, &settings);
+    state.pty.refresh_window_title(&app);
+    app.state::<StatsSampler>().set_interval(settings.stats_interval());
+    state_crypt::set_cache_ttl(settings.credential_cache_ttl());
+    app.state::<AutomationApi>().apply(&app, &settings)?;
+    app.state::<McpServer>().apply(&app, &settings)?;
+    let profile_changed = state.settings.lock().map_err(|e| e.to_string())?.active_profile != settings.active_profile;
+    apply_active_profile(&settings);
+    {
+        let mut s = state.settings.lock().map_err(|e| e.to_string())?;
+        *s = settings;
+    }
+    if profile_changed {
+        app.state::<StateWatcher>().restart(&app);
+    }
+    tray::refresh(&app);
     Ok(())
 }
 
+/// Sets and saves `nodeHeapMb`, e.g. after `openclaw:oom`, and applies it
+/// to pending gateway restarts. Returns the updated settings.
+#[tauri::command]
+fn openclaw_set_heap(
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    supervisor: tauri::State<'_, Supervisor>,
+    heap_mb: u32,
+) -> Result<Settings, String> {
+    lock.ensure_writer()?;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut updated = settings.clone();
+    updated.node_heap_mb = Some(heap_mb);
+    openclaw::validate_node_flags(&updated)?;
+    settings::save_settings_to_disk(&updated)?;
+    *settings = updated.clone();
+    supervisor.set_node_heap(Some(heap_mb));
+    Ok(updated)
+}
+
+/// Makes `profile` (or the default profile, for `None`) the one new
+/// sessions and state commands use, and saves the choice.
+#[tauri::command]
+fn profile_switch(app: tauri::AppHandle, lock: tauri::State<'_, StateLock>, profile: Option<String>) -> Result<(), String> {
+    lock.ensure_writer()?;
+    switch_profile(&app, profile)
+}
+
+/// Switches the active profile; shared by `profile_switch` and the tray.
+/// Running sessions keep the profile they were started with.
+pub(crate) fn switch_profile(app: &tauri::AppHandle, profile: Option<String>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if let Some(id) = &profile {
+            settings.profile(id).ok_or_else(|| format!("Unknown profile: {}", id))?;
+        }
+        settings.active_profile = profile.clone();
+        settings::save_settings_to_disk(&settings)?;
+    }
+    openclaw::set_active_profile(profile.clone());
+    app.state::<StateWatcher>().restart(app);
+    tray::refresh(app);
+    let _ = app.emit("profile:changed", ProfileChanged { profile });
+    Ok(())
+}
+
+/// Payload of `profile:changed`.
+#[derive(Clone, serde::Serialize)]
+struct ProfileChanged {
+    profile: Option<String>,
+}
+
 #[tauri::command]
 fn load_settings_cmd() -> Settings {
     settings::load_settings()
 }
 
 #[tauri::command]
-fn check_openclaw_configured() -> bool {
-    openclaw::is_configured()
+fn state_lock_status(lock: tauri::State<'_, StateLock>) -> LockStatus {
+    lock.status()
+}
+
+#[tauri::command]
+fn state_lock_take_over(lock: tauri::State<'_, StateLock>) -> Result<LockStatus, String> {
+    lock.take_over()
+}
+
+#[tauri::command]
+fn state_lock_read_only(lock: tauri::State<'_, StateLock>) -> Result<LockStatus, String> {
+    lock.open_read_only()
+}
+
+#[tauri::command]
+fn network_status(
+    state: tauri::State<'_, AppState>,
+    network: tauri::State<'_, NetworkMonitor>,
+) -> Result<NetworkAdvice, String> {
+    let policy = state.settings.lock().map_err(|e| e.to_string())?.metered_policy;
+    Ok(NetworkAdvice::new(network.current(), policy))
+}
+
+/// Reports which OpenClaw state files exist and what they configure.
+#[tauri::command]
+fn openclaw_config_status(state: tauri::State<'_, AppState>) -> Result<ConfigStatus, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    Ok(config::status(&settings))
+}
+
+/// Returns OpenClaw's `openclaw.json` (an empty object if not created yet).
+#[tauri::command]
+fn openclaw_config_get(state: tauri::State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    config::get(&settings)
+}
+
+/// Merges `patch` into `openclaw.json` (JSON merge patch: `null` removes a
+/// key) and returns the updated config.
+#[tauri::command]
+fn openclaw_config_set(
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    patch: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    lock.ensure_writer()?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    config::set(&settings, patch)
+}
+
+/// Looks for a standalone OpenClaw CLI install (`~/.openclaw`) and lists
+/// what `openclaw_import` would bring over, flagging conflicts.
+#[tauri::command]
+fn openclaw_import_scan() -> Result<ImportScan, String> {
+    migrate::scan()
+}
+
+/// Copies or links the CLI's state into the app's state dir. Refused while
+/// any OpenClaw process might be using it.
+#[tauri::command]
+fn openclaw_import(
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    detached: tauri::State<'_, Detached>,
+    mode: ImportMode,
+    overwrite: bool,
+) -> Result<ImportReport, String> {
+    lock.ensure_writer()?;
+    ensure_state_unused(&state, &detached)?;
+    migrate::import(mode, overwrite)
+}
+
+/// Zips the OpenClaw state dir (without caches) to a path picked in a save
+/// dialog. Returns the path, or `None` if the dialog was cancelled.
+#[tauri::command]
+async fn state_backup(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || backup::backup_with_dialog(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Replaces the OpenClaw state dir with a backup after validating it. The
+/// replaced state is kept beside it as `openclaw-state.pre-restore`.
+#[tauri::command]
+async fn state_restore(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    detached: tauri::State<'_, Detached>,
+    watcher: tauri::State<'_, StateWatcher>,
+    path: String,
+) -> Result<BackupManifest, String> {
+    lock.ensure_writer()?;
+    ensure_state_unused(&state, &detached)?;
+    let manifest = backup::restore_archive(std::path::Path::new(&path))?;
+    watcher.restart(&app);
+    Ok(manifest)
+}
+
+/// Moves the OpenClaw state dir into `directory` (as `openclaw-state/`), or
+/// back to the data dir for `null`, and saves the new location. Returns the
+/// new state dir path.
+#[tauri::command]
+async fn state_dir_move(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    detached: tauri::State<'_, Detached>,
+    watcher: tauri::State<'_, StateWatcher>,
+    directory: Option<String>,
+) -> Result<String, String> {
+    lock.ensure_writer()?;
+    ensure_state_unused(&state, &detached)?;
+    let base = directory.as_deref().map(openclaw::validate_state_base).transpose()?;
+    let state_dir = migrate::move_state(base.as_deref())?;
+    watcher.restart(&app);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.state_directory = base.map(|b| b.to_string_lossy().into_owned());
+    settings::save_settings_to_disk(&settings)?;
+    Ok(state_dir.to_string_lossy().into_owned())
+}
+
+/// Lists the snapshots taken before OpenClaw updates, newest first.
+#[tauri::command]
+fn state_snapshots() -> Result<Vec<Snapshot>, String> {
+    snapshots::list()
+}
+
+/// Rolls the OpenClaw state dir back to a pre-update snapshot.
+#[tauri::command]
+async fn rollback_state(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    lock: tauri::State<'_, StateLock>,
+    detached: tauri::State<'_, Detached>,
+    watcher: tauri::State<'_, StateWatcher>,
+    snapshot_id: String,
+) -> Result<BackupManifest, String> {
+    lock.ensure_writer()?;
+    ensure_state_unused(&state, &detached)?;
+    let manifest = snapshots::rollback(&snapshot_id)?;
+    watcher.restart(&app);
+    Ok(manifest)
+}
+
+/// Errors if a session or the detached gateway could be using the state
+/// dir, which must not be replaced under a running OpenClaw.
+fn ensure_state_unused(state: &AppState, detached: &Detached) -> Result<(), String> {
+    if !state.pty.child_pids().is_empty() || detached.is_running() {
+        return Err("Stop all sessions and the detached gateway first".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn hooks_list() -> Vec<Hook> {
+    hooks::load_hooks()
+}
+
+#[tauri::command]
+fn hook_inspect(path: String) -> Result<HookPreview, String> {
+    hooks::inspect(&path)
+}
+
+#[tauri::command]
+async fn hook_register(
+    app: tauri::AppHandle,
+    event: HookEvent,
+    path: String,
+    sha256: String,
+) -> Result<Hook, String> {
+    // Waits on the user's answer to the confirmation dialog.
+    tauri::async_runtime::spawn_blocking(move || hooks::register(&app, event, &path, &sha256))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn hook_remove(event: HookEvent, path: String) -> Result<(), String> {
+    hooks::remove(event, &path)
+}
+
+#[tauri::command]
+async fn pool_scale(
+    app: tauri::AppHandle,
+    pools: tauri::State<'_, PoolManager>,
+    lock: tauri::State<'_, StateLock>,
+    name: String,
+    size: usize,
+    base_port: Option<u16>,
+) -> Result<PoolStatus, String> {
+    lock.ensure_writer()?;
+    pools.scale(&app, &name, size, base_port)
+}
+
+#[tauri::command]
+fn pool_status(pools: tauri::State<'_, PoolManager>, name: String) -> Result<PoolStatus, String> {
+    pools.status(&name).ok_or_else(|| format!("No pool named {}", name))
+}
+
+#[tauri::command]
+fn pool_list(pools: tauri::State<'_, PoolManager>) -> Vec<PoolStatus> {
+    pools.list()
+}
+
+#[tauri::command]
+async fn pool_destroy(
+    app: tauri::AppHandle,
+    pools: tauri::State<'_, PoolManager>,
+    name: String,
+) -> Result<(), String> {
+    pools.destroy(&app, &name)
+}
+
+#[tauri::command]
+fn plugins_list(plugins: tauri::State<'_, PluginHost>) -> Vec<PluginInfo> {
+    plugins.list()
+}
+
+#[tauri::command]
+async fn plugin_grant(app: tauri::AppHandle, id: String, capabilities: Vec<String>) -> Result<(), String> {
+    // Waits on the user's answers to the permission dialogs.
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<PluginHost>().grant(&app, &id, capabilities)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn plugin_revoke(plugins: tauri::State<'_, PluginHost>, id: String) -> Result<(), String> {
+    plugins.revoke(&id)
+}
+
+#[tauri::command]
+async fn plugin_invoke(
+    app: tauri::AppHandle,
+    plugins: tauri::State<'_, PluginHost>,
+    id: String,
+    command: String,
+    payload: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    if !plugins.exposes_command(&id, &command) {
+        return Err(format!("Plugin {} does not expose command {}", id, command));
+    }
+    // Waiting on the plugin's response can take up to the invoke timeout.
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<PluginHost>().invoke(&id, &command, payload)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Current detached gateway, if any (including one re-adopted at launch).
+#[tauri::command]
+fn detached_status(detached: tauri::State<'_, Detached>) -> Option<DetachedSession> {
+    detached.status()
+}
+
+/// Starts the gateway outside the app so it keeps running after quitting.
+#[tauri::command]
+fn detached_start(
+    app: tauri::AppHandle,
+    detached: tauri::State<'_, Detached>,
+    lock: tauri::State<'_, StateLock>,
+    settings: Settings,
+) -> Result<DetachedSession, String> {
+    lock.ensure_writer()?;
+    detached.start(&app, &settings.for_profile(None)?)
+}
+
+/// Async so the grace period before force-killing doesn't block the main thread.
+#[tauri::command]
+async fn detached_stop(
+    state: tauri::State<'_, AppState>,
+    detached: tauri::State<'_, Detached>,
+) -> Result<(), String> {
+    let grace = state.settings.lock().map_err(|e| e.to_string())?.kill_grace();
+    detached.stop(grace)
+}
+
+#[tauri::command]
+fn detached_log(detached: tauri::State<'_, Detached>, offset: u64) -> Result<LogChunk, String> {
+    detached.read_log(offset)
+}
+
+/// Tears down sessions, state and plugins before the app exits.
+pub(crate) fn shutdown(app: &tauri::AppHandle) {
+    let owns_state = app
+        .try_state::<StateLock>()
+        .is_some_and(|lock| lock.ensure_writer().is_ok());
+    let detached_running = app
+        .try_state::<Detached>()
+        .is_some_and(|detached| detached.is_running());
+    if let Some(state) = app.try_state::<AppState>() {
+        let _ = tauri::async_runtime::block_on(state.pty.kill(0, CloseReason::WindowClose));
+        // If another instance owns the state dir, or a detached
+        // gateway is still using it, leave its files alone.
+        if owns_state && !detached_running {
+            if let Ok(settings) = state.settings.lock() {
+                if let Err(e) = seal_state_files(&settings) {
+                    eprintln!("[state_crypt] {}", e);
+                }
+            }
+        }
+    }
+    if let Some(plugins) = app.try_state::<PluginHost>() {
+        plugins.stop_all();
+    }
+    if let Some(lock) = app.try_state::<StateLock>() {
+        lock.release();
+    }
+}
+
+/// Points OpenClaw at the configured state location. An unusable one falls
+/// back to the data dir rather than failing every launch.
+fn apply_state_directory(settings: &Settings) {
+    let base = settings.state_directory.as_deref().and_then(|dir| {
+        openclaw::validate_state_base(dir)
+            .map_err(|e| eprintln!("[settings] Ignoring state directory: {}", e))
+            .ok()
+    });
+    openclaw::set_state_base(base);
+}
+
+/// Makes the saved active profile the one whose state commands act on. An
+/// unknown profile falls back to the default one.
+fn apply_active_profile(settings: &Settings) {
+    let profile = settings.active_profile.clone().filter(|id| {
+        let known = settings.profile(id).is_some();
+        if !known {
+            eprintln!("[settings] Ignoring unknown active profile: {}", id);
+        }
+        known
+    });
+    openclaw::set_active_profile(profile);
+}
+
+/// Encrypts the protected OpenClaw state files once no session needs them.
+/// Covers every profile, since sessions of any of them may have run.
+fn seal_state_files(settings: &Settings) -> Result<(), String> {
+    let profiles = std::iter::once(None).chain(settings.profiles.iter().map(|p| Some(p.id.as_str())));
+    for profile in profiles {
+        state_crypt::seal(&openclaw::profile_state_dir(profile)?, &settings.encrypted_state_files)?;
+    }
+    Ok(())
 }
 
 pub fn run() {
+    if let Some(code) = cli::handle() {
+        std::process::exit(code);
+    }
     let initial_settings = settings::load_settings();
+    let startup_settings = initial_settings.clone();
+    apply_state_directory(&initial_settings);
+    apply_active_profile(&initial_settings);
 
     tauri::Builder::default()
         .plugin(tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, None))
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        shortcuts::on_global_shortcut(app, shortcut);
+                    }
+                })
+                .build(),
+        )
         .manage(AppState {
-            pty: PtyManager::new(),
+            pty: {
+                let pty = PtyManager::new();
+                apply_session_settings(&pty, &initial_settings);
+                pty
+            },
             settings: Mutex::new(initial_settings),
+            launches: Mutex::new(HashMap::new()),
+        })
+        .manage(ShortcutRegistry::new())
+        .manage(PoolManager::new())
+        .manage(NetworkMonitor::new())
+        .manage(StatsSampler::new())
+        .manage(GatewayMonitor::new())
+        .manage(Supervisor::new())
+        .manage(GatewayEvents::new())
+        .manage(AutomationApi::new())
+        .manage(McpServer::new())
+        .manage(ExecManager::new())
+        .manage(Prewarmed::new())
+        .manage(Scheduler::new())
+        .setup(move |app| {
+            if !headless::is_enabled() {
+                headless::create_main_window(app.handle())?;
+            }
+
+            // Argument policy is loaded and verified once at startup.
+            app.manage(policy::load(app.handle()));
+
+            let lock = StateLock::acquire();
+            let owns_state = lock.ensure_writer().is_ok();
+            app.manage(lock);
+
+            // A detached gateway from a previous run still needs its state
+            // decrypted, and occupies the gateway's port.
+            let detached = Detached::adopt();
+            let detached_running = detached.is_running();
+            app.manage(detached);
+
+            let plugins = PluginHost::new();
+            plugins.start_all(app.handle());
+            app.manage(plugins);
+
+            app.state::<NetworkMonitor>().start(app.handle());
+            let sampler = app.state::<StatsSampler>();
+            sampler.set_interval(startup_settings.stats_interval());
+            state_crypt::set_cache_ttl(startup_settings.credential_cache_ttl());
+            sampler.start(app.handle());
+            app.state::<GatewayMonitor>().start(app.handle());
+            app.state::<GatewayEvents>().start(app.handle());
+            app.state::<Scheduler>().start(app.handle());
+            app.manage(GatewayProxy::start(app.handle()));
+            if let Err(e) = app.state::<AutomationApi>().apply(app.handle(), &startup_settings) {
+                eprintln!("[automation] {}", e);
+            }
+            if let Err(e) = app.state::<McpServer>().apply(app.handle(), &startup_settings) {
+                eprintln!("[mcp] {}", e);
+            }
+
+            // Re-seal anything left in plaintext by an unclean shutdown.
+            if owns_state && !detached_running {
+                if let Err(e) = seal_state_files(&startup_settings) {
+                    eprintln!("[state_crypt] {}", e);
+                }
+            }
+
+            app.manage(StateWatcher::start(app.handle()));
+
+            if let Err(e) = shortcuts::apply(app.handle(), &startup_settings) {
+                eprintln!("[shortcuts] {}", e);
+            }
+
+            if owns_state && !detached_running {
+                match startup_settings.for_profile(None) {
+                    Ok(settings) => app.state::<Prewarmed>().start(app.handle(), &settings),
+                    Err(e) => eprintln!("[prewarm] {}", e),
+                }
+            }
+
+            if headless::is_enabled() {
+                headless::start(app.handle(), &startup_settings);
+            }
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             pty_spawn,
+            pty_clone,
+            pty_spawn_shell,
             pty_write,
+            pty_write_bytes,
+            pty_enqueue,
+            pty_broadcast,
+            pty_broadcast_all,
+            pty_paste,
             pty_resize,
             pty_kill,
+            pty_set_accessibility,
+            pty_process_tree,
+            pty_get_scrollback,
+            pty_list,
+            pty_set_label,
+            pty_signal,
+            detached_status,
+            detached_start,
+            detached_stop,
+            detached_log,
+            pty_attach,
+            pty_reattach,
+            pty_ack,
+            pty_pause,
+            pty_resume,
+            pty_set_raw_capture,
+            pty_record_start,
+            pty_record_stop,
+            pty_replay,
+            recording_export_video,
+            pty_search,
+            export_transcript,
+            pty_set_title_source,
+            open_link,
+            pty_get_screen,
+            pty_get_links,
+            pty_stats,
+            openclaw_version,
+            gateway_health,
+            gateway_external,
+            gateway_events_status,
+            gateway_proxy,
+            automation_api_info,
+            mcp_server_info,
+            providers_list,
+            validate_api_key,
+            openclaw_set_heap,
+            openclaw_exec,
+            openclaw_exec_stream,
+            openclaw_exec_cancel,
+            scheduler_history,
+            scheduler_run_now,
             save_settings,
+            lock_credentials,
             load_settings_cmd,
-            check_openclaw_configured,
+            network_status,
+            state_lock_status,
+            state_lock_take_over,
+            state_lock_read_only,
+            openclaw_config_status,
+            openclaw_import_scan,
+            openclaw_import,
+            state_backup,
+            state_restore,
+            state_snapshots,
+            state_dir_move,
+            profile_switch,
+            rollback_state,
+            openclaw_config_get,
+            openclaw_config_set,
+            hooks_list,
+            hook_inspect,
+            hook_register,
+            hook_remove,
+            pool_scale,
+            pool_status,
+            pool_list,
+            pool_destroy,
+            plugins_list,
+            plugin_grant,
+            plugin_revoke,
+            plugin_invoke,
         ])
+        .on_menu_event(|app, event| shortcuts::on_menu_event(app, event.id().as_ref()))
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                if let Some(state) = window.try_state::<AppState>() {
-                    let _ = state.pty.kill(0);
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let app = window.app_handle();
+                let close_to_tray = app
+                    .try_state::<AppState>()
+                    .and_then(|state| state.settings.lock().ok().map(|s| s.close_to_tray))
+                    .unwrap_or(false);
+                // A headless app goes back to the tray rather than quitting.
+                if (close_to_tray || headless::is_enabled()) && !tray::is_quitting() {
+                    match tray::park(app) {
+                        Ok(()) => {
+                            api.prevent_close();
+                            let _ = window.hide();
+                            return;
+                        }
+                        Err(e) => eprintln!("[tray] {}; closing instead", e),
+                    }
                 }
+                shutdown(app);
             }
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,40 @@
+use std::process::{Command, Stdio};
+
+/// URL schemes output is allowed to open. Anything else (`file:`, custom
+/// protocol handlers, ...) could run code or leak local paths.
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+const MAX_URL_LEN: usize = 2048;
+
+/// True if `url` may be opened from terminal output.
+pub fn is_allowed(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else { return false };
+    url.len() <= MAX_URL_LEN
+        && !rest.is_empty()
+        && !url.chars().any(|c| c.is_control() || c.is_whitespace())
+        && ALLOWED_SCHEMES.iter().any(|s| s.eq_ignore_ascii_case(scheme))
+}
+
+/// Opens an allowlisted URL with the platform's default handler.
+pub fn open(url: &str) -> Result<(), String> {
+    if !is_allowed(url) {
+        return Err(format!("Refusing to open link: {}", url));
+    }
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("rundll32");
+        cmd.arg("url.dll,FileProtocolHandler");
+        cmd
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = Command::new("xdg-open");
+
+    cmd.arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Cannot open link: {}", e))
+}
@@ -0,0 +1,246 @@
+//! Model Context Protocol server that lets agent tooling drive sessions:
+//! list them, type into them and read what they printed. Off by default;
+//! when enabled it serves MCP's Streamable HTTP transport (JSON-RPC 2.0
+//! over `POST /mcp`) on loopback, and every request needs
+//! `Authorization: Bearer <token>` from `mcp-token` in the settings dir.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::http::{self, LoopbackServer};
+use crate::pty_manager::{PtyManager, SessionInfo};
+use crate::settings::{self, Settings};
+use crate::AppState;
+
+const DEFAULT_PORT: u16 = 18781;
+const TOKEN_FILE: &str = "mcp-token";
+const ENDPOINT: &str = "/mcp";
+/// Protocol revisions this server speaks, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+const DEFAULT_OUTPUT_LINES: usize = 50;
+const MAX_OUTPUT_LINES: usize = 1000;
+/// Sanity bound on one `send_input` call.
+const MAX_INPUT_BYTES: usize = 64 * 1024;
+/// Room for one JSON-RPC message carrying a full `send_input`.
+const MAX_BODY_BYTES: usize = MAX_INPUT_BYTES + 16 * 1024;
+
+/// Reported by `mcp_server_info`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpInfo {
+    pub running: bool,
+    pub url: Option<String>,
+    pub token_path: String,
+}
+
+pub struct McpServer {
+    server: Mutex<Option<LoopbackServer>>,
+}
+
+fn token_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join(TOKEN_FILE))
+}
+
+impl McpServer {
+    pub fn new() -> Self {
+        Self {
+            server: Mutex::new(None),
+        }
+    }
+
+    /// Starts, stops or moves the server to match `settings`.
+    pub fn apply(&self, app: &AppHandle, settings: &Settings) -> Result<(), String> {
+        let wanted = settings.mcp_server.then(|| settings.mcp_server_port.unwrap_or(DEFAULT_PORT));
+        let mut server = self.server.lock().map_err(|e| e.to_string())?;
+        if server.as_ref().map(LoopbackServer::port) == wanted {
+            return Ok(());
+        }
+        // Dropping the old server stops it.
+        *server = None;
+        let Some(port) = wanted else { return Ok(()) };
+        let token = http::load_or_create_token(&token_path()?)?;
+        let app = app.clone();
+        let started = LoopbackServer::start("mcp", port, move |stream| serve(&app, stream, &token))?;
+        *server = Some(started);
+        Ok(())
+    }
+
+    pub fn info(&self) -> Result<McpInfo, String> {
+        let port = self.server.lock().map_err(|e| e.to_string())?.as_ref().map(LoopbackServer::port);
+        Ok(McpInfo {
+            running: port.is_some(),
+            url: port.map(|p| format!("http://127.0.0.1:{}{}", p, ENDPOINT)),
+            token_path: token_path()?.to_string_lossy().into_owned(),
+        })
+    }
+}
+
+fn respond_json(stream: &mut TcpStream, status: &str, body: &Value) -> Result<(), String> {
+    let body = serde_json::to_vec(body).map_err(|e| e.to_string())?;
+    http::respond(stream, status, "", "application/json", &body)
+}
+
+fn serve(app: &AppHandle, stream: &mut TcpStream, token: &str) -> Result<(), String> {
+    let mut request = match http::read_request(stream) {
+        Ok(request) => request,
+        Err(e) => return respond_json(stream, "400 Bad Request", &json!({ "error": e })),
+    };
+    // Browsers attach an Origin; refuse them so web pages can't drive sessions.
+    if request.header("origin").is_some() {
+        return respond_json(stream, "403 Forbidden", &json!({ "error": "Cross-origin requests are not allowed" }));
+    }
+    if !http::tokens_match(request.bearer_token(), token) {
+        return respond_json(stream, "401 Unauthorized", &json!({ "error": "Missing or invalid token" }));
+    }
+    if let Err(e) = request.read_body(MAX_BODY_BYTES) {
+        return respond_json(stream, "413 Payload Too Large", &json!({ "error": e }));
+    }
+    if request.target.split('?').next() != Some(ENDPOINT) {
+        return respond_json(stream, "404 Not Found", &json!({ "error": "Not found" }));
+    }
+    if request.method != "POST" {
+        // No server-initiated stream is offered.
+        return respond_json(stream, "405 Method Not Allowed", &json!({ "error": "Method not allowed" }));
+    }
+    let message: Value = match serde_json::from_slice(&request.body) {
+        Ok(message) => message,
+        Err(e) => return respond_json(stream, "400 Bad Request", &rpc_error(Value::Null, -32700, &e.to_string())),
+    };
+    // Notifications (no id) get no response body.
+    let Some(id) = message.get("id").cloned() else {
+        return http::respond(stream, "202 Accepted", "", "application/json", b"");
+    };
+    let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let response = match handle(app, method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => rpc_error(id, code, &message),
+    };
+    respond_json(stream, "200 OK", &response)
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn handle(app: &AppHandle, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|v| PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "clawrunner", "version": env!("CARGO_PKG_VERSION") },
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            // Tool failures are results with isError, so the agent sees them.
+            Ok(match call_tool(app, name, &arguments) {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+            })
+        }
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    }
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_sessions",
+            "description": "Lists the running OpenClaw terminal sessions (gateway, TUI, onboarding) with their ids. Developer shells are not included.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "send_input",
+            "description": "Types text into a session's terminal. Include \"\\r\" to press Enter.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "sessionId": { "type": "integer" },
+                    "text": { "type": "string" },
+                },
+                "required": ["sessionId", "text"],
+            },
+        },
+        {
+            "name": "read_output",
+            "description": "Returns the last lines a session printed, with terminal escape codes removed.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "sessionId": { "type": "integer" },
+                    "lines": { "type": "integer", "minimum": 1, "maximum": MAX_OUTPUT_LINES },
+                },
+                "required": ["sessionId"],
+            },
+        },
+    ])
+}
+
+fn session_id(arguments: &Value) -> Result<u64, String> {
+    arguments
+        .get("sessionId")
+        .and_then(Value::as_u64)
+        .filter(|id| *id != 0)
+        .ok_or_else(|| "sessionId must be a positive integer".to_string())
+}
+
+/// Developer shells (see `shell_spawn`) run with the user's full rights, so
+/// they are never listed to or driven by MCP clients.
+fn is_shell(session: &SessionInfo) -> bool {
+    session.args == ["shell"]
+}
+
+/// Reads `sessionId` and checks it names a running OpenClaw session.
+fn openclaw_session_id(pty: &PtyManager, arguments: &Value) -> Result<u64, String> {
+    let session_id = session_id(arguments)?;
+    match pty.list()?.iter().find(|s| s.session_id == session_id) {
+        Some(session) if !is_shell(session) => Ok(session_id),
+        _ => Err(format!("No OpenClaw session with id {}", session_id)),
+    }
+}
+
+fn call_tool(app: &AppHandle, name: &str, arguments: &Value) -> Result<String, String> {
+    let pty = &app.state::<AppState>().pty;
+    match name {
+        "list_sessions" => {
+            let sessions: Vec<SessionInfo> = pty.list()?.into_iter().filter(|s| !is_shell(s)).collect();
+            serde_json::to_string_pretty(&sessions).map_err(|e| e.to_string())
+        }
+        "send_input" => {
+            let session_id = openclaw_session_id(pty, arguments)?;
+            let text = arguments
+                .get("text")
+                .and_then(Value::as_str)
+                .ok_or("text must be a string")?;
+            if text.len() > MAX_INPUT_BYTES {
+                return Err(format!("text is longer than {} bytes", MAX_INPUT_BYTES));
+            }
+            tauri::async_runtime::block_on(pty.write(session_id, text))?;
+            Ok(format!("Sent {} bytes to session {}", text.len(), session_id))
+        }
+        "read_output" => {
+            let session_id = openclaw_session_id(pty, arguments)?;
+            let count = arguments
+                .get("lines")
+                .and_then(Value::as_u64)
+                .map(|n| (n as usize).clamp(1, MAX_OUTPUT_LINES))
+                .unwrap_or(DEFAULT_OUTPUT_LINES);
+            let lines = pty.output_lines(session_id)?;
+            Ok(lines[lines.len().saturating_sub(count)..].join("\n"))
+        }
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
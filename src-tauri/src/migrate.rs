@@ -0,0 +1,236 @@
+//! Imports state from a standalone OpenClaw CLI install (`~/.openclaw`) so
+//! existing users don't have to onboard again.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::openclaw;
+use crate::paths;
+
+/// How imported entries end up in the app's state dir.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Independent copies; the CLI install is left untouched.
+    Copy,
+    /// Symlinks, so the app and the CLI share state. Unix only.
+    Link,
+}
+
+/// One top-level entry of the CLI state dir.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// The app's state dir already has an entry with this name.
+    pub conflict: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportScan {
+    pub source: String,
+    pub found: bool,
+    pub entries: Vec<ImportEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    /// Conflicting entries left as they were.
+    pub skipped: Vec<String>,
+}
+
+/// Path of the standalone CLI's state dir.
+pub(crate) fn cli_state_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
+    Ok(home.join(".openclaw"))
+}
+
+/// True if `name` (or its encrypted copy) already exists in `dir`.
+fn exists_in(dir: &Path, name: &str) -> bool {
+    let path = dir.join(name);
+    path.symlink_metadata().is_ok() || dir.join(format!("{}.enc", name)).exists()
+}
+
+/// Lists what an import would bring over and which entries conflict.
+pub fn scan() -> Result<ImportScan, String> {
+    let source = cli_state_dir()?;
+    let mut scan = ImportScan {
+        source: source.to_string_lossy().into_owned(),
+        found: source.is_dir(),
+        entries: Vec::new(),
+    };
+    if !scan.found {
+        return Ok(scan);
+    }
+    let target = openclaw::openclaw_state_dir()?;
+    let dir = std::fs::read_dir(&source).map_err(|e| format!("Cannot read {:?}: {}", source, e))?;
+    for entry in dir.flatten() {
+        let Ok(file_type) = entry.file_type() else { continue };
+        // Symlinks could point anywhere; only real files and dirs are imported.
+        if file_type.is_symlink() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        scan.entries.push(ImportEntry {
+            conflict: exists_in(&target, &name),
+            is_dir: file_type.is_dir(),
+            name,
+        });
+    }
+    scan.entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(scan)
+}
+
+/// Copies or links the CLI state into the app's state dir. Conflicting
+/// entries are replaced only if `overwrite` is set, otherwise skipped.
+/// Callers must make sure no OpenClaw process is using the state dir.
+pub fn import(mode: ImportMode, overwrite: bool) -> Result<ImportReport, String> {
+    if mode == ImportMode::Link && !cfg!(unix) {
+        return Err("Linking CLI state is only supported on macOS and Linux".to_string());
+    }
+    let scan = scan()?;
+    if !scan.found {
+        return Err(format!("No OpenClaw CLI state found at {}", scan.source));
+    }
+    let source = PathBuf::from(&scan.source);
+    let target = openclaw::openclaw_state_dir()?;
+    let mut report = ImportReport::default();
+    for entry in scan.entries {
+        let from = source.join(&entry.name);
+        let to = target.join(&entry.name);
+        if entry.conflict {
+            if !overwrite {
+                report.skipped.push(entry.name);
+                continue;
+            }
+            remove(&to)?;
+            let _ = std::fs::remove_file(target.join(format!("{}.enc", entry.name)));
+        }
+        match mode {
+            ImportMode::Copy => copy_tree(&from, &to)?,
+            ImportMode::Link => link(&from, &to)?,
+        }
+        report.imported.push(entry.name);
+    }
+    Ok(report)
+}
+
+/// Moves all OpenClaw state (every profile) into `new_base`, or back to
+/// the data dir for `None`, and points OpenClaw at it. The destination must
+/// not already hold state. Callers must make sure no OpenClaw process is
+/// using the state dir.
+pub fn move_state(new_base: Option<&Path>) -> Result<PathBuf, String> {
+    let current = openclaw::state_base()?;
+    let target = match new_base {
+        Some(base) => base.to_path_buf(),
+        None => paths::data_dir()?,
+    };
+    if target == current {
+        return openclaw::openclaw_state_dir();
+    }
+    let moves: Vec<(PathBuf, PathBuf)> = openclaw::STATE_ENTRIES
+        .iter()
+        .map(|name| (current.join(name), target.join(name)))
+        .filter(|(from, _)| from.exists())
+        .collect();
+    for (from, to) in &moves {
+        if to.starts_with(from) {
+            return Err("The new location can't be inside the current state directory".to_string());
+        }
+        let occupied = std::fs::read_dir(to).is_ok_and(|mut dir| dir.next().is_some());
+        if occupied {
+            return Err(format!("{} already contains data", to.display()));
+        }
+    }
+    std::fs::create_dir_all(&target).map_err(|e| format!("Cannot create {:?}: {}", target, e))?;
+
+    for (i, (from, to)) in moves.iter().enumerate() {
+        if let Err(e) = move_dir(from, to) {
+            // Put back what was already moved so the state stays in one place.
+            for (from, to) in &moves[..i] {
+                if let Err(e) = move_dir(to, from) {
+                    eprintln!("[migrate] Cannot move {:?} back: {}", to, e);
+                }
+            }
+            return Err(e);
+        }
+    }
+    openclaw::set_state_base(new_base.map(Path::to_path_buf));
+    // Re-applies the state dir's permissions.
+    openclaw::openclaw_state_dir()
+}
+
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    let _ = std::fs::remove_dir(to);
+    // A rename fails across filesystems; fall back to copy and delete.
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_all(from, to) {
+        let _ = std::fs::remove_dir_all(to);
+        return Err(e);
+    }
+    std::fs::remove_dir_all(from).map_err(|e| format!("Moved, but cannot remove {:?}: {}", from, e))
+}
+
+/// Recursively copies a directory, keeping symlinks (e.g. from a linked
+/// CLI import) as symlinks.
+fn copy_all(from: &Path, to: &Path) -> Result<(), String> {
+    let meta = from.symlink_metadata().map_err(|e| format!("Cannot stat {:?}: {}", from, e))?;
+    if meta.is_symlink() {
+        let dest = std::fs::read_link(from).map_err(|e| format!("Cannot read link {:?}: {}", from, e))?;
+        return link(&dest, to);
+    }
+    if meta.is_dir() {
+        std::fs::create_dir_all(to).map_err(|e| format!("Cannot create {:?}: {}", to, e))?;
+        let dir = std::fs::read_dir(from).map_err(|e| format!("Cannot read {:?}: {}", from, e))?;
+        for entry in dir.flatten() {
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    std::fs::copy(from, to).map_err(|e| format!("Cannot copy {:?}: {}", from, e))?;
+    Ok(())
+}
+
+fn remove(path: &Path) -> Result<(), String> {
+    let meta = match path.symlink_metadata() {
+        Ok(meta) => meta,
+        Err(_) => return Ok(()),
+    };
+    let result = if meta.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    result.map_err(|e| format!("Cannot remove {:?}: {}", path, e))
+}
+
+/// Recursively copies a file or directory, skipping symlinks.
+fn copy_tree(from: &Path, to: &Path) -> Result<(), String> {
+    let meta = from.symlink_metadata().map_err(|e| format!("Cannot stat {:?}: {}", from, e))?;
+    if meta.is_dir() {
+        std::fs::create_dir_all(to).map_err(|e| format!("Cannot create {:?}: {}", to, e))?;
+        let dir = std::fs::read_dir(from).map_err(|e| format!("Cannot read {:?}: {}", from, e))?;
+        for entry in dir.flatten() {
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if meta.is_file() {
+        std::fs::copy(from, to).map_err(|e| format!("Cannot copy {:?}: {}", from, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link(from: &Path, to: &Path) -> Result<(), String> {
+    std::os::unix::fs::symlink(from, to).map_err(|e| format!("Cannot link {:?}: {}", to, e))
+}
+
+#[cfg(not(unix))]
+fn link(_from: &Path, _to: &Path) -> Result<(), String> {
+    Err("Linking CLI state is only supported on macOS and Linux".to_string())
+}
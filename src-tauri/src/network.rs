@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What to do while the connection is metered or roaming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeteredPolicy {
+    /// Behave as on an unmetered connection.
+    Ignore,
+    /// Warn before bandwidth-heavy operations.
+    #[default]
+    Warn,
+    /// Warn, and also defer automatic downloads and update checks.
+    Defer,
+}
+
+/// Current connection cost, as far as the OS can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    /// `None` if the platform gives no answer (no NetworkManager, macOS).
+    pub metered: Option<bool>,
+    pub roaming: bool,
+}
+
+impl NetworkStatus {
+    /// Metered or roaming; unknown counts as unmetered.
+    pub fn is_costly(&self) -> bool {
+        self.metered == Some(true) || self.roaming
+    }
+}
+
+/// Reads the metered flag from NetworkManager over the system bus.
+#[cfg(target_os = "linux")]
+fn detect() -> NetworkStatus {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    use dbus::blocking::Connection;
+
+    // NMMetered: 0 unknown, 1 yes, 2 no, 3 guess-yes, 4 guess-no.
+    let metered = Connection::new_system().ok().and_then(|conn| {
+        let proxy = conn.with_proxy(
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            Duration::from_secs(2),
+        );
+        let value: u32 = proxy.get("org.freedesktop.NetworkManager", "Metered").ok()?;
+        match value {
+            1 | 3 => Some(true),
+            2 | 4 => Some(false),
+            _ => None,
+        }
+    });
+    NetworkStatus { metered, roaming: false }
+}
+
+/// Reads the connection cost of the internet profile from WinRT.
+#[cfg(windows)]
+fn detect() -> NetworkStatus {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let cost = NetworkInformation::GetInternetConnectionProfile()
+        .and_then(|profile| profile.GetConnectionCost());
+    let Ok(cost) = cost else {
+        return NetworkStatus::default();
+    };
+    let metered = cost.NetworkCostType().ok().and_then(|t| match t {
+        NetworkCostType::Fixed | NetworkCostType::Variable => Some(true),
+        NetworkCostType::Unrestricted => Some(false),
+        _ => None,
+    });
+    NetworkStatus {
+        metered,
+        roaming: cost.Roaming().unwrap_or(false),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn detect() -> NetworkStatus {
+    NetworkStatus::default()
+}
+
+/// Connection status combined with the user's policy, for the UI and
+/// anything that starts downloads on its own.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkAdvice {
+    #[serde(flatten)]
+    pub status: NetworkStatus,
+    /// Skip automatic update checks and runtime downloads.
+    pub defer_downloads: bool,
+    /// Ask before starting bandwidth-heavy operations.
+    pub warn: bool,
+}
+
+impl NetworkAdvice {
+    pub fn new(status: NetworkStatus, policy: MeteredPolicy) -> Self {
+        let costly = status.is_costly();
+        Self {
+            status,
+            defer_downloads: costly && policy == MeteredPolicy::Defer,
+            warn: costly && policy != MeteredPolicy::Ignore,
+        }
+    }
+}
+
+pub struct NetworkMonitor {
+    status: Mutex<NetworkStatus>,
+}
+
+impl NetworkMonitor {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(NetworkStatus::default()),
+        }
+    }
+
+    pub fn current(&self) -> NetworkStatus {
+        self.status.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    /// Polls the connection cost and emits `network:metered` whenever it changes.
+    pub fn start(&self, app: &AppHandle) {
+        let app = app.clone();
+        thread::spawn(move || loop {
+            let status = detect();
+            let monitor = app.state::<NetworkMonitor>();
+            let changed = match monitor.status.lock() {
+                Ok(mut current) if *current != status => {
+                    *current = status;
+                    true
+                }
+                _ => false,
+            };
+            if changed {
+                let _ = app.emit("network:metered", status);
+            }
+            thread::sleep(POLL_INTERVAL);
+        });
+    }
+}
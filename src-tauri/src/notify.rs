@@ -0,0 +1,27 @@
+use notify_rust::Notification;
+use tauri::{AppHandle, Manager};
+
+/// Shows a native notification on behalf of a session, unless the main
+/// window is focused (the user is already looking at the terminal).
+/// Delivery happens on a background thread; failures are only logged.
+pub fn session_alert(app: &AppHandle, title: &str, body: &str) {
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return;
+    }
+    let title = title.to_string();
+    let body = body.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = Notification::new()
+            .appname("ClawRunner")
+            .summary(&title)
+            .body(&body)
+            .show()
+        {
+            eprintln!("[notify] Cannot show notification: {}", e);
+        }
+    });
+}
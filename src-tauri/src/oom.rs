@@ -0,0 +1,79 @@
+//! Recognizes OpenClaw dying of Node.js heap exhaustion and tells the UI,
+//! with a bigger heap to restart with (see `nodeHeapMb`), instead of
+//! leaving a V8 stack dump in the terminal as the only clue.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+/// Lines V8 prints when the heap runs out.
+const SIGNATURES: &[&str] = &[
+    "JavaScript heap out of memory",
+    "Reached heap limit Allocation failed",
+    "Ineffective mark-compacts near heap limit",
+];
+/// Only the end of the output is searched; the crash is always last.
+const TAIL_LINES: usize = 200;
+/// Suggested when no heap size was set; Node's default is about 2-4 GB
+/// depending on the machine.
+const DEFAULT_SUGGESTED_HEAP_MB: u32 = 4096;
+const MAX_SUGGESTED_HEAP_MB: u32 = 16384;
+
+/// Payload of `openclaw:oom`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OomEvent {
+    pub session_id: u64,
+    /// e.g. "gateway" or "tui".
+    pub subcommand: String,
+    /// The `nodeHeapMb` the session ran with.
+    pub heap_mb: Option<u32>,
+    pub suggested_heap_mb: u32,
+    pub message: String,
+}
+
+fn is_oom(lines: &[String]) -> bool {
+    lines[lines.len().saturating_sub(TAIL_LINES)..]
+        .iter()
+        .any(|line| SIGNATURES.iter().any(|sig| line.contains(sig)))
+}
+
+/// Double the current heap, within bounds.
+fn suggested_heap(current: Option<u32>) -> u32 {
+    match current {
+        Some(mb) => mb.saturating_mul(2).clamp(DEFAULT_SUGGESTED_HEAP_MB, MAX_SUGGESTED_HEAP_MB).max(mb),
+        None => DEFAULT_SUGGESTED_HEAP_MB,
+    }
+}
+
+/// Called when a session's child exits on its own: emits `openclaw:oom` if
+/// its output ends with a heap exhaustion crash.
+pub fn check_exit(app: &AppHandle, session_id: u64, subcommand: &str, heap_mb: Option<u32>) {
+    let Ok(lines) = app.state::<AppState>().pty.output_lines(session_id) else { return };
+    if !is_oom(&lines) {
+        return;
+    }
+    let suggested_heap_mb = suggested_heap(heap_mb);
+    let message = match heap_mb {
+        Some(mb) => format!(
+            "OpenClaw ran out of memory with a {} MB heap. Restart it with {} MB, or close other sessions.",
+            mb, suggested_heap_mb
+        ),
+        None => format!(
+            "OpenClaw ran out of memory with Node's default heap. Restart it with {} MB.",
+            suggested_heap_mb
+        ),
+    };
+    eprintln!("[oom] Session {} ({}) ran out of heap", session_id, subcommand);
+    let _ = app.emit(
+        "openclaw:oom",
+        OomEvent {
+            session_id,
+            subcommand: subcommand.to_string(),
+            heap_mb,
+            suggested_heap_mb,
+            message,
+        },
+    );
+}
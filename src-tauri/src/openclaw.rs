@@ -1,27 +1,40 @@
 use portable_pty::CommandBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use tauri::AppHandle;
 use tauri::Manager;
 
+use crate::exec;
+use crate::paths;
+use crate::providers;
 use crate::settings::Settings;
+use crate::snapshots;
 
 /// Allowlist of env var names that may be set from user settings.
 /// Prevents injection of dangerous vars like PATH, LD_PRELOAD, etc.
 fn is_allowed_env_key(key: &str) -> bool {
-    key.len() <= 64
-        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
-        && key.ends_with("_API_KEY")
+    providers::setting(key).is_some()
+        || (key.len() <= 64
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && key.ends_with("_API_KEY"))
 }
 
 /// Environment variables safe to pass through from the parent process.
 /// This prevents leaking sensitive credentials (AWS_SECRET_ACCESS_KEY,
 /// DATABASE_URL, etc.) to the child Node.js process.
-const PASSTHROUGH_ENV_VARS: &[&str] = &[
+pub const PASSTHROUGH_ENV_VARS: &[&str] = &[
     // System identity
     "HOME", "USER", "LOGNAME", "SHELL",
     // Locale
     "LANG", "LC_ALL", "LC_CTYPE", "LC_MESSAGES", "LC_COLLATE",
     "LC_MONETARY", "LC_NUMERIC", "LC_TIME", "LANGUAGE",
+    // Timezone (otherwise times OpenClaw prints are UTC)
+    "TZ",
     // Temp directories
     "TMPDIR", "TMP", "TEMP",
     // Linux display (needed if OpenClaw spawns GUI tools)
@@ -37,6 +50,236 @@ const PASSTHROUGH_ENV_VARS: &[&str] = &[
     "NODE_EXTRA_CA_CERTS",
 ];
 
+/// Names `envPassthrough` in settings may never add: loader and runtime
+/// hooks that would inject code into the child, and variables the app sets
+/// itself.
+const DENIED_PASSTHROUGH_VARS: &[&str] = &[
+    "PATH", "PATHEXT", "TERM", "COMSPEC", "COLORTERM", "FORCE_COLOR", "NO_COLOR",
+    "NODE_OPTIONS", "NODE_PATH", "NODE_REPL_EXTERNAL_MODULE", "NODE_TLS_REJECT_UNAUTHORIZED",
+    "BASH_ENV", "ENV", "PROMPT_COMMAND",
+];
+const DENIED_PASSTHROUGH_PREFIXES: &[&str] = &["LD_", "DYLD_", "OPENCLAW_"];
+
+/// Checks one name from `envPassthrough`. Names are compared in upper case,
+/// since Windows treats env var names case-insensitively.
+fn check_passthrough_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if !valid {
+        return Err(format!("{:?} is not a valid environment variable name", name));
+    }
+    let upper = name.to_ascii_uppercase();
+    if DENIED_PASSTHROUGH_VARS.contains(&upper.as_str())
+        || DENIED_PASSTHROUGH_PREFIXES.iter().any(|p| upper.starts_with(p))
+    {
+        return Err(format!("{} cannot be passed through to OpenClaw", name));
+    }
+    Ok(())
+}
+
+/// Rejects `envPassthrough` entries before settings are saved. Launches
+/// skip rejected names too, in case the settings file was edited by hand.
+pub fn validate_env_passthrough(names: &[String]) -> Result<(), String> {
+    names.iter().try_for_each(|name| check_passthrough_name(name))
+}
+
+/// Checks one `pathPrepend`/`pathAppend` entry: an absolute path to an
+/// existing directory that can't smuggle in extra PATH entries.
+fn check_path_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(dir);
+    let sep = if cfg!(target_os = "windows") { ';' } else { ':' };
+    // Drive letters contain ':' on Windows, but ';' is the separator there.
+    if dir.contains(sep) || dir.contains('\0') {
+        return Err(format!("PATH entry '{}' contains '{}'", dir, sep));
+    }
+    if !path.is_absolute() {
+        return Err(format!("PATH entry '{}' is not an absolute path", dir));
+    }
+    if !path.is_dir() {
+        return Err(format!("PATH entry '{}' is not an existing directory", dir));
+    }
+    Ok(path)
+}
+
+/// Rejects extra PATH directories before settings are saved. Launches
+/// skip entries that fail later, e.g. a directory since removed.
+pub fn validate_path_dirs(settings: &Settings) -> Result<(), String> {
+    settings
+        .path_prepend
+        .iter()
+        .chain(&settings.path_append)
+        .try_for_each(|dir| check_path_dir(dir).map(drop))
+}
+
+/// Valid entries of `dirs`, logging the rest.
+fn usable_path_dirs(dirs: &[String]) -> Vec<PathBuf> {
+    dirs.iter()
+        .filter_map(|dir| {
+            check_path_dir(dir)
+                .map_err(|e| eprintln!("[openclaw] Skipping {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// TERM values a session may be started with. The embedded terminal
+/// emulates xterm, so only xterm entries are offered; any other terminfo
+/// would promise sequences it doesn't understand.
+const TERM_VALUES: &[&str] = &["xterm-256color", "xterm", "xterm-direct"];
+const DEFAULT_TERM: &str = "xterm-256color";
+
+/// Rejects a `term` setting or spawn option outside `TERM_VALUES`.
+pub fn validate_term(term: Option<&str>) -> Result<(), String> {
+    match term {
+        Some(term) if !TERM_VALUES.contains(&term) => Err(format!(
+            "Unsupported TERM '{}'; use one of {}",
+            term,
+            TERM_VALUES.join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Rejects per-spawn env overrides that settings couldn't set either:
+/// names outside the API key/provider allowlist, or invalid values.
+pub fn validate_env_overrides(env: &HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in env {
+        if !is_allowed_env_key(key) {
+            return Err(format!("{} can't be set for a session; only API keys and provider settings can", key));
+        }
+        providers::check_value(key, value)?;
+    }
+    Ok(())
+}
+
+/// Canonicalizes a session working directory and checks it is an existing
+/// directory outside the app's own files: the bundled resources, the data
+/// dir (settings, tokens) and the OpenClaw state location.
+pub fn resolve_cwd(app: &AppHandle, dir: &str) -> Result<PathBuf, String> {
+    let protected = [
+        app.path().resource_dir().map_err(|e| e.to_string()),
+        paths::data_dir(),
+        state_base(),
+    ];
+    check_cwd(dir, protected.into_iter().flatten())
+}
+
+fn check_cwd(dir: &str, protected: impl IntoIterator<Item = PathBuf>) -> Result<PathBuf, String> {
+    let cwd = std::fs::canonicalize(dir).map_err(|e| format!("Working directory '{}': {}", dir, e))?;
+    if !cwd.is_dir() {
+        return Err(format!("Working directory '{}' is not a directory", dir));
+    }
+    for root in protected {
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        if cwd.starts_with(&root) {
+            return Err(format!("Working directory '{}' is inside the app's own files", dir));
+        }
+    }
+    Ok(cwd)
+}
+
+fn locale_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(C|POSIX|[a-z]{2,3}(_[A-Z]{2})?)(\.[A-Za-z0-9-]+)?(@[A-Za-z0-9]+)?$").expect("valid locale pattern")
+    })
+}
+
+/// Rejects a `locale` setting that isn't a locale name such as
+/// `en_US.UTF-8`, `de_DE@euro` or `C.UTF-8`.
+fn check_locale(locale: &str) -> Result<(), String> {
+    if !locale_pattern().is_match(locale) {
+        return Err(format!("'{}' is not a locale name like en_US.UTF-8", locale));
+    }
+    Ok(())
+}
+
+/// Rejects a `timezone` setting that isn't an IANA zone name such as
+/// `Europe/Berlin` or `UTC`. Whether the zone exists is up to the system's
+/// tz database.
+fn check_timezone(tz: &str) -> Result<(), String> {
+    let valid = !tz.is_empty()
+        && tz.len() <= 64
+        && !tz.starts_with('/')
+        && !tz.split('/').any(|part| part.is_empty() || part == "." || part == "..")
+        && tz.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'));
+    if !valid {
+        return Err(format!("'{}' is not a timezone name like Europe/Berlin", tz));
+    }
+    Ok(())
+}
+
+/// Rejects invalid `locale`/`timezone` settings before settings are saved.
+pub fn validate_locale(settings: &Settings) -> Result<(), String> {
+    settings.locale.as_deref().map_or(Ok(()), check_locale)?;
+    settings.timezone.as_deref().map_or(Ok(()), check_timezone)
+}
+
+/// How much color OpenClaw is told it may use (`colorMode` in settings).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Advertise 24-bit color (`COLORTERM=truecolor`), which the embedded
+    /// terminal renders; tools still check for a TTY.
+    #[default]
+    Truecolor,
+    /// Also set `FORCE_COLOR=3`, for tools that disable color when they
+    /// think they aren't on a terminal.
+    Force,
+    /// Set `NO_COLOR=1`, for plain transcripts.
+    Off,
+}
+
+impl ColorMode {
+    fn env(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ColorMode::Truecolor => &[("COLORTERM", "truecolor")],
+            ColorMode::Force => &[("COLORTERM", "truecolor"), ("FORCE_COLOR", "3")],
+            ColorMode::Off => &[("NO_COLOR", "1")],
+        }
+    }
+}
+
+/// Bounds on `nodeHeapMb`: below the minimum OpenClaw can't start, above
+/// the maximum is almost certainly a typo.
+const MIN_NODE_HEAP_MB: u32 = 512;
+const MAX_NODE_HEAP_MB: u32 = 65536;
+
+/// Rejects out-of-range Node.js flag settings before settings are saved.
+pub fn validate_node_flags(settings: &Settings) -> Result<(), String> {
+    if let Some(mb) = settings.node_heap_mb {
+        if !(MIN_NODE_HEAP_MB..=MAX_NODE_HEAP_MB).contains(&mb) {
+            return Err(format!(
+                "Node.js heap size must be between {} and {} MB",
+                MIN_NODE_HEAP_MB, MAX_NODE_HEAP_MB
+            ));
+        }
+    }
+    if settings.node_inspect_port.is_some_and(|port| port < 1024) {
+        return Err("Node.js inspector port must be 1024 or higher".to_string());
+    }
+    Ok(())
+}
+
+/// The allowlisted Node.js flags `settings` turns on, for every OpenClaw
+/// process. Out-of-range values (a hand-edited file) are dropped.
+fn node_flags(settings: &Settings) -> Vec<String> {
+    let mut flags = Vec::new();
+    if let Some(mb) = settings.node_heap_mb {
+        if (MIN_NODE_HEAP_MB..=MAX_NODE_HEAP_MB).contains(&mb) {
+            flags.push(format!("--max-old-space-size={}", mb));
+        } else {
+            eprintln!("[openclaw] Ignoring nodeHeapMb {}", mb);
+        }
+    }
+    if settings.node_source_maps {
+        flags.push("--enable-source-maps".to_string());
+    }
+    flags
+}
+
 /// Resolves the path to the bundled Node.js binary inside Tauri resources.
 fn node_binary_path(app: &AppHandle) -> Result<PathBuf, String> {
     let resource_dir = app
@@ -110,22 +353,93 @@ fn openclaw_entry_path(app: &AppHandle) -> Result<PathBuf, String> {
     ))
 }
 
-/// Returns the OpenClaw state directory (~/.clawrunner/openclaw-state/).
-fn openclaw_state_dir() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    let base_dir = home.join(".clawrunner");
-    let state_dir = base_dir.join("openclaw-state");
+const STATE_DIR_NAME: &str = "openclaw-state";
+const PROFILES_DIR_NAME: &str = "openclaw-profiles";
+/// Everything in the state location that belongs to OpenClaw.
+pub const STATE_ENTRIES: &[&str] = &[STATE_DIR_NAME, PROFILES_DIR_NAME];
+
+/// Custom parent of the state dir (the `stateDirectory` setting), or `None`
+/// for the data dir.
+static STATE_BASE: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Profile whose state dir `openclaw_state_dir` returns; `None` for the
+/// default profile.
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Switches where `openclaw_state_dir` points. Does not move any data.
+pub fn set_state_base(base: Option<PathBuf>) {
+    if let Ok(mut current) = STATE_BASE.lock() {
+        *current = base;
+    }
+}
+
+/// Checks that `path` can hold the state dir: absolute, a directory (created
+/// if missing) and writable.
+pub fn validate_state_base(path: &str) -> Result<PathBuf, String> {
+    let base = PathBuf::from(path);
+    if !base.is_absolute() {
+        return Err(format!("State directory must be an absolute path: {}", path));
+    }
+    if base.exists() && !base.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    std::fs::create_dir_all(&base).map_err(|e| format!("Cannot create {}: {}", path, e))?;
+    let probe = base.join(".clawrunner-write-test");
+    std::fs::write(&probe, b"").map_err(|e| format!("{} is not writable: {}", path, e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(base)
+}
+
+/// Custom state location if set, otherwise the data dir. Holds the default
+/// profile's `openclaw-state/` and the `openclaw-profiles/` dir.
+pub fn state_base() -> Result<PathBuf, String> {
+    match STATE_BASE.lock().map_err(|e| e.to_string())?.clone() {
+        Some(base) => Ok(base),
+        None => paths::data_dir(),
+    }
+}
+
+/// Switches the profile whose state `openclaw_state_dir` returns.
+pub fn set_active_profile(profile: Option<String>) {
+    if let Ok(mut current) = ACTIVE_PROFILE.lock() {
+        *current = profile;
+    }
+}
+
+/// Returns the active profile's OpenClaw state directory.
+pub fn openclaw_state_dir() -> Result<PathBuf, String> {
+    let profile = ACTIVE_PROFILE.lock().map_err(|e| e.to_string())?.clone();
+    profile_state_dir(profile.as_deref())
+}
+
+/// Returns a profile's OpenClaw state directory: `openclaw-state/` in the
+/// state location for the default profile (`None`), otherwise
+/// `openclaw-profiles/<id>/`.
+pub fn profile_state_dir(profile: Option<&str>) -> Result<PathBuf, String> {
+    let custom = STATE_BASE.lock().map_err(|e| e.to_string())?.is_some();
+    let base_dir = state_base()?;
+    let state_dir = match profile {
+        Some(id) => base_dir.join(PROFILES_DIR_NAME).join(id),
+        None => base_dir.join(STATE_DIR_NAME),
+    };
 
     if !state_dir.exists() {
         std::fs::create_dir_all(&state_dir)
             .map_err(|e| format!("Cannot create state dir: {}", e))?;
     }
 
-    // Always enforce permissions (handles both fresh and pre-existing directories)
+    // Always enforce permissions (handles both fresh and pre-existing
+    // directories). A custom base is the user's directory and left alone.
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        for dir in &[&base_dir, &state_dir] {
+        let mut dirs = vec![state_dir.clone()];
+        if profile.is_some() {
+            dirs.push(base_dir.join(PROFILES_DIR_NAME));
+        }
+        if !custom {
+            dirs.push(base_dir.clone());
+        }
+        for dir in dirs {
             let perms = std::fs::Permissions::from_mode(0o700);
             let _ = std::fs::set_permissions(dir, perms);
         }
@@ -142,73 +456,532 @@ pub fn is_configured() -> bool {
     }
 }
 
-/// Builds the CommandBuilder for spawning OpenClaw CLI with given args.
-/// Example args: ["onboard", "--skip-daemon"], ["gateway"]
-pub fn build_openclaw_command(
-    app: &AppHandle,
-    settings: &Settings,
-    args: &[String],
-) -> Result<CommandBuilder, String> {
+/// Port the gateway listens on unless `--port` says otherwise.
+pub const DEFAULT_GATEWAY_PORT: u16 = 18789;
+
+/// The port a gateway started with `args` listens on.
+pub fn gateway_port(args: &[String]) -> u16 {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--port") {
+            Some("") => args.next().map(String::as_str),
+            Some(rest) => rest.strip_prefix('='),
+            None => None,
+        };
+        if let Some(port) = value.and_then(|v| v.parse().ok()) {
+            return port;
+        }
+    }
+    DEFAULT_GATEWAY_PORT
+}
+
+/// Longest `tui --session` key accepted.
+const MAX_SESSION_KEY_LEN: usize = 128;
+
+/// What the frontend may launch in a terminal session. Each mode is
+/// validated and expanded into OpenClaw CLI args here, so the frontend
+/// never passes raw arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum LaunchMode {
+    #[serde(rename_all = "camelCase")]
+    Onboard {
+        #[serde(default)]
+        skip_daemon: bool,
+    },
+    Gateway {
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        verbose: bool,
+    },
+    Tui {
+        /// Session key to open instead of the default one.
+        #[serde(default)]
+        session: Option<String>,
+    },
+}
+
+impl LaunchMode {
+    pub fn subcommand(&self) -> &'static str {
+        match self {
+            LaunchMode::Onboard { .. } => "onboard",
+            LaunchMode::Gateway { .. } => "gateway",
+            LaunchMode::Tui { .. } => "tui",
+        }
+    }
+
+    /// Checks the mode's options beyond what the argument policy covers.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            LaunchMode::Onboard { .. } => Ok(()),
+            LaunchMode::Gateway { port, .. } => match port {
+                Some(port) if *port < 1024 => Err(format!("Gateway port must be 1024 or higher, got {}", port)),
+                _ => Ok(()),
+            },
+            LaunchMode::Tui { session } => match session {
+                Some(key) if key.is_empty() || key.len() > MAX_SESSION_KEY_LEN => {
+                    Err(format!("Session key must be 1-{} characters", MAX_SESSION_KEY_LEN))
+                }
+                Some(key) if !key.chars().all(|c| c.is_ascii_alphanumeric() || "-_:.".contains(c)) => {
+                    Err(format!("Invalid session key: {}", key))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Expands the mode into OpenClaw CLI args, e.g. `["gateway", "--port", "18789"]`.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![self.subcommand().to_string()];
+        match self {
+            LaunchMode::Onboard { skip_daemon } => {
+                if *skip_daemon {
+                    args.push("--skip-daemon".to_string());
+                }
+            }
+            LaunchMode::Gateway { port, verbose } => {
+                if let Some(port) = port {
+                    args.extend(["--port".to_string(), port.to_string()]);
+                }
+                if *verbose {
+                    args.push("--verbose".to_string());
+                }
+            }
+            LaunchMode::Tui { session } => {
+                if let Some(session) = session {
+                    args.extend(["--session".to_string(), session.clone()]);
+                }
+            }
+        }
+        args
+    }
+}
+
+/// Everything needed to start OpenClaw, independent of whether it runs in
+/// a PTY (`build_openclaw_command`) or through pipes (`build_openclaw_process`).
+struct Launch {
+    program: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    cwd: Option<PathBuf>,
+}
+
+/// Resolves the node binary, entry point and hardened environment for
+/// running OpenClaw with `args`. The environment starts empty (nothing is
+/// inherited beyond `PASSTHROUGH_ENV_VARS`) to prevent leaking sensitive
+/// vars (AWS_SECRET_ACCESS_KEY, DATABASE_URL, etc.) to the child process.
+fn launch(app: &AppHandle, settings: &Settings, args: &[String]) -> Result<Launch, String> {
     let node_path = node_binary_path(app)?;
     let entry_path = openclaw_entry_path(app)?;
-    let state_dir = openclaw_state_dir()?;
+    snapshots::before_launch(&app.package_info().version.to_string(), &settings.encrypted_state_files, &entry_path);
+    let state_dir = profile_state_dir(settings.active_profile.as_deref())?;
 
-    let mut cmd = CommandBuilder::new(&node_path);
-
-    // Clear inherited environment to prevent leaking sensitive vars
-    // (AWS_SECRET_ACCESS_KEY, DATABASE_URL, etc.) to the child process.
-    cmd.env_clear();
+    let mut env: Vec<(String, String)> = Vec::new();
 
     // Pass through only safe system env vars from parent process
     for var in PASSTHROUGH_ENV_VARS {
         if let Ok(val) = std::env::var(var) {
-            cmd.env(var, &val);
+            env.push((var.to_string(), val));
+        }
+    }
+    // Plus the names the user added in settings
+    for var in &settings.env_passthrough {
+        if PASSTHROUGH_ENV_VARS.contains(&var.as_str()) {
+            continue;
+        }
+        if let Err(e) = check_passthrough_name(var) {
+            eprintln!("[openclaw] Skipping env passthrough: {}", e);
+            continue;
+        }
+        if let Ok(val) = std::env::var(var) {
+            env.push((var.clone(), val));
         }
     }
 
-    // Terminal type
-    cmd.env("TERM", "xterm-256color");
+    // Locale and timezone forced by settings replace the inherited ones
+    if let Some(locale) = &settings.locale {
+        match check_locale(locale) {
+            Ok(()) => {
+                env.retain(|(key, _)| key != "LANG" && key != "LANGUAGE" && !key.starts_with("LC_"));
+                env.push(("LANG".to_string(), locale.clone()));
+                env.push(("LC_ALL".to_string(), locale.clone()));
+            }
+            Err(e) => eprintln!("[openclaw] Ignoring locale: {}", e),
+        }
+    }
+    if let Some(tz) = &settings.timezone {
+        match check_timezone(tz) {
+            Ok(()) => {
+                env.retain(|(key, _)| key != "TZ");
+                env.push(("TZ".to_string(), tz.clone()));
+            }
+            Err(e) => eprintln!("[openclaw] Ignoring timezone: {}", e),
+        }
+    }
 
-    // PATH: start with parent's PATH, prepend bundled node dir if available
-    let mut path_val = std::env::var("PATH").unwrap_or_default();
+    // Terminal type and color support
+    let term = match settings.term.as_deref() {
+        Some(term) if TERM_VALUES.contains(&term) => term,
+        Some(term) => {
+            eprintln!("[openclaw] Ignoring unsupported TERM '{}'", term);
+            DEFAULT_TERM
+        }
+        None => DEFAULT_TERM,
+    };
+    env.push(("TERM".to_string(), term.to_string()));
+    for (key, value) in settings.color_mode.env() {
+        env.push((key.to_string(), value.to_string()));
+    }
+
+    // PATH: bundled node dir first (so user dirs can't shadow it), then the
+    // user's prepended dirs, the parent's PATH and the appended dirs
+    let mut path_dirs: Vec<PathBuf> = Vec::new();
     if let Some(node_dir) = node_path.parent() {
         if node_dir != std::path::Path::new("") {
-            let sep = if cfg!(target_os = "windows") { ";" } else { ":" };
-            path_val = if path_val.is_empty() {
-                node_dir.to_string_lossy().to_string()
-            } else {
-                format!("{}{}{}", node_dir.to_string_lossy(), sep, path_val)
-            };
+            path_dirs.push(node_dir.to_path_buf());
         }
     }
-    if !path_val.is_empty() {
-        cmd.env("PATH", &path_val);
+    path_dirs.extend(usable_path_dirs(&settings.path_prepend));
+    if let Some(parent_path) = std::env::var_os("PATH") {
+        path_dirs.extend(std::env::split_paths(&parent_path));
+    }
+    path_dirs.extend(usable_path_dirs(&settings.path_append));
+    if !path_dirs.is_empty() {
+        let path_val = std::env::join_paths(path_dirs).map_err(|e| format!("Cannot build PATH: {}", e))?;
+        env.push(("PATH".to_string(), path_val.to_string_lossy().into_owned()));
     }
 
     // Core env vars for OpenClaw isolation
-    cmd.env("OPENCLAW_NO_RESPAWN", "1");
-    cmd.env("OPENCLAW_STATE_DIR", state_dir.to_string_lossy().as_ref());
+    env.push(("OPENCLAW_NO_RESPAWN".to_string(), "1".to_string()));
+    env.push(("OPENCLAW_STATE_DIR".to_string(), state_dir.to_string_lossy().into_owned()));
 
-    // Inject API keys from settings as env vars (only known safe key names)
+    // Inject API keys and provider settings as env vars (only known safe
+    // key names, and only valid values)
     for (key, value) in &settings.api_keys {
-        if !value.is_empty() && is_allowed_env_key(key) {
-            cmd.env(key, value);
+        if value.is_empty() || !is_allowed_env_key(key) {
+            continue;
+        }
+        match providers::check_value(key, value) {
+            Ok(()) => env.push((key.clone(), value.clone())),
+            Err(e) => eprintln!("[openclaw] Skipping {}", e),
         }
     }
 
-    // Node.js flags + openclaw entry point
-    cmd.arg("--disable-warning=ExperimentalWarning");
-    cmd.arg(&entry_path);
+    // Node.js flags + openclaw entry point, then the openclaw subcommand
+    // args (e.g. "onboard", "gateway")
+    let mut node_args = vec!["--disable-warning=ExperimentalWarning".to_string()];
+    node_args.extend(node_flags(settings));
+    node_args.push(entry_path.to_string_lossy().into_owned());
+    node_args.extend(args.iter().cloned());
+
+    Ok(Launch {
+        program: node_path,
+        args: node_args,
+        env,
+        // Working directory is the user's home
+        cwd: dirs::home_dir(),
+    })
+}
+
+/// Builds the CommandBuilder for spawning OpenClaw CLI with given args.
+/// Example args: ["onboard", "--skip-daemon"], ["gateway"]
+pub fn build_openclaw_command(
+    app: &AppHandle,
+    settings: &Settings,
+    args: &[String],
+) -> Result<CommandBuilder, String> {
+    let mut launch = launch(app, settings, args)?;
+    // Only interactive sessions get the inspector, and only on loopback.
+    if let Some(port) = settings.node_inspect_port.filter(|p| settings.developer_mode && *p >= 1024) {
+        launch.args.insert(0, format!("--inspect=127.0.0.1:{}", port));
+    }
+    let mut cmd = CommandBuilder::new(&launch.program);
+    cmd.env_clear();
+    for (key, value) in &launch.env {
+        cmd.env(key, value);
+    }
+    cmd.args(&launch.args);
+    if let Some(cwd) = launch.cwd {
+        cmd.cwd(cwd);
+    }
+    Ok(cmd)
+}
 
-    // Append openclaw subcommand args (e.g. "onboard", "gateway")
-    for arg in args {
-        cmd.arg(arg);
+/// The user's login shell: `$SHELL` if it names an existing absolute
+/// path, otherwise the platform default.
+fn login_shell() -> PathBuf {
+    let configured = if cfg!(target_os = "windows") { "COMSPEC" } else { "SHELL" };
+    if let Some(shell) = std::env::var_os(configured).map(PathBuf::from) {
+        if shell.is_absolute() && shell.is_file() {
+            return shell;
+        }
     }
+    if cfg!(target_os = "windows") {
+        PathBuf::from("cmd.exe")
+    } else {
+        PathBuf::from("/bin/sh")
+    }
+}
 
-    // Set working directory to user's home
-    if let Some(home) = dirs::home_dir() {
-        cmd.cwd(home);
+/// Builds the CommandBuilder for a developer shell: the user's login shell
+/// with the environment OpenClaw would get (bundled node on PATH,
+/// `OPENCLAW_STATE_DIR`, provider settings), for debugging state by hand.
+/// API keys are left out: anything run in the shell, and its history and
+/// child processes, would otherwise see them.
+pub fn build_shell_command(app: &AppHandle, settings: &Settings) -> Result<CommandBuilder, String> {
+    let launch = launch(app, settings, &[])?;
+    let mut cmd = CommandBuilder::new(login_shell());
+    cmd.env_clear();
+    for (key, value) in &launch.env {
+        if settings.api_keys.contains_key(key) && providers::setting(key).is_none() {
+            continue;
+        }
+        cmd.env(key, value);
+    }
+    if !cfg!(target_os = "windows") {
+        cmd.arg("-l");
+    }
+    if let Some(cwd) = launch.cwd {
+        cmd.cwd(cwd);
     }
+    Ok(cmd)
+}
 
+/// Builds a piped (non-PTY) process running OpenClaw with the same
+/// environment hardening as `build_openclaw_command`.
+pub fn build_openclaw_process(app: &AppHandle, settings: &Settings, args: &[String]) -> Result<Command, String> {
+    let launch = launch(app, settings, args)?;
+    let mut cmd = Command::new(&launch.program);
+    cmd.env_clear()
+        .envs(launch.env)
+        .args(&launch.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = launch.cwd {
+        cmd.current_dir(cwd);
+    }
     Ok(cmd)
 }
+
+/// How long `version()` waits for each `--version` call.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What the app is actually running.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenClawVersion {
+    pub openclaw_version: Option<String>,
+    pub node_version: Option<String>,
+    /// Both node and OpenClaw come from the app bundle (not a dev fallback).
+    pub bundled: bool,
+}
+
+/// Runs `openclaw --version` and `node --version` and parses their output.
+/// A version that cannot be determined is `None` rather than an error.
+/// This is a query, not a launch: it bypasses `launch()` so no snapshot is
+/// taken and no API keys or state dir are passed.
+pub fn version(app: &AppHandle) -> Result<OpenClawVersion, String> {
+    let resource_dir = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Cannot resolve resource dir: {}", e))?;
+    let node_path = node_binary_path(app)?;
+    let entry_path = openclaw_entry_path(app)?;
+    let bundled = node_path.starts_with(&resource_dir) && entry_path.starts_with(&resource_dir);
+
+    let mut openclaw = Command::new(&node_path);
+    openclaw
+        .arg("--disable-warning=ExperimentalWarning")
+        .arg(&entry_path)
+        .arg("--version")
+        .env_clear()
+        .envs(PASSTHROUGH_ENV_VARS.iter().filter_map(|var| Some((var, std::env::var(var).ok()?))))
+        .env("OPENCLAW_NO_RESPAWN", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let openclaw_version = version_output(openclaw, "openclaw");
+
+    let mut node = Command::new(&node_path);
+    node.arg("--version")
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let node_version = version_output(node, "node");
+
+    Ok(OpenClawVersion {
+        openclaw_version,
+        node_version,
+        bundled,
+    })
+}
+
+/// Runs a `--version` command and parses its output, logging failures.
+fn version_output(cmd: Command, name: &str) -> Option<String> {
+    match exec::run(cmd, VERSION_TIMEOUT) {
+        Ok(out) if out.exit_code == Some(0) => parse_version(&out.stdout),
+        Ok(out) => {
+            eprintln!("[openclaw] {} --version failed: {:?} {}", name, out.exit_code, out.stderr.trim());
+            None
+        }
+        Err(e) => {
+            eprintln!("[openclaw] {} --version failed: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Extracts the first dotted version number (e.g. "v22.12.0" -> "22.12.0").
+fn parse_version(output: &str) -> Option<String> {
+    let pattern = Regex::new(r"\bv?(\d+\.\d+(?:\.\d+)?(?:[-+][0-9A-Za-z.-]+)?)").ok()?;
+    pattern.captures(output).map(|c| c[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clawrunner-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn allows_api_keys_and_provider_settings() {
+        assert!(is_allowed_env_key("OPENAI_API_KEY"));
+        assert!(is_allowed_env_key("SOME_NEW_PROVIDER_API_KEY"));
+        assert!(is_allowed_env_key("OPENAI_BASE_URL"));
+    }
+
+    #[test]
+    fn refuses_other_env_keys() {
+        for key in ["PATH", "LD_PRELOAD", "NODE_OPTIONS", "OPENAI_API_KEY=1", "BAD-NAME_API_KEY", "API_KEY_SUFFIX"] {
+            assert!(!is_allowed_env_key(key), "{}", key);
+        }
+        assert!(!is_allowed_env_key(&format!("{}_API_KEY", "X".repeat(60))));
+    }
+
+    #[test]
+    fn env_overrides_are_checked_by_name_and_value() {
+        assert!(validate_env_overrides(&env(&[("OPENAI_API_KEY", "sk-test")])).is_ok());
+        assert!(validate_env_overrides(&env(&[("OPENAI_BASE_URL", "https://example.com/v1")])).is_ok());
+        assert!(validate_env_overrides(&env(&[("PATH", "/tmp")])).is_err());
+        assert!(validate_env_overrides(&env(&[("OPENAI_BASE_URL", "file:///etc/passwd")])).is_err());
+        assert!(validate_env_overrides(&env(&[("OPENAI_ORG_ID", "org 1; rm -rf")])).is_err());
+    }
+
+    #[test]
+    fn cwd_must_be_an_existing_directory() {
+        let dir = temp_dir("cwd-exists");
+        let file = dir.join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+        assert!(check_cwd(&dir.join("missing").to_string_lossy(), []).is_err());
+        assert!(check_cwd(&file.to_string_lossy(), []).is_err());
+        let resolved = check_cwd(&dir.to_string_lossy(), []).unwrap();
+        assert_eq!(resolved, std::fs::canonicalize(&dir).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cwd_may_not_be_inside_protected_dirs() {
+        let dir = temp_dir("cwd-protected");
+        let protected = dir.join("app-data");
+        let inside = protected.join("nested");
+        let outside = dir.join("work");
+        std::fs::create_dir_all(&inside).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+
+        assert!(check_cwd(&protected.to_string_lossy(), [protected.clone()]).is_err());
+        assert!(check_cwd(&inside.to_string_lossy(), [protected.clone()]).is_err());
+        // `..` is resolved before the check.
+        let sneaky = outside.join("..").join("app-data");
+        assert!(check_cwd(&sneaky.to_string_lossy(), [protected.clone()]).is_err());
+        assert!(check_cwd(&outside.to_string_lossy(), [protected.clone()]).is_ok());
+
+        #[cfg(unix)]
+        {
+            let link = dir.join("link");
+            std::os::unix::fs::symlink(&inside, &link).unwrap();
+            assert!(check_cwd(&link.to_string_lossy(), [protected.clone()]).is_err());
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn launch_modes_validate_their_options() {
+        assert!(LaunchMode::Onboard { skip_daemon: true }.validate().is_ok());
+        assert!(LaunchMode::Gateway { port: None, verbose: false }.validate().is_ok());
+        assert!(LaunchMode::Gateway { port: Some(1024), verbose: false }.validate().is_ok());
+        assert!(LaunchMode::Gateway { port: Some(80), verbose: false }.validate().is_err());
+        assert!(LaunchMode::Tui { session: None }.validate().is_ok());
+        assert!(LaunchMode::Tui { session: Some("agent:main.v2_x-y".to_string()) }.validate().is_ok());
+    }
+
+    #[test]
+    fn launch_modes_reject_bad_session_keys() {
+        let too_long = "k".repeat(MAX_SESSION_KEY_LEN + 1);
+        for key in ["", "a b", "key;rm", "key/../x", "ключ", too_long.as_str()] {
+            let mode = LaunchMode::Tui { session: Some(key.to_string()) };
+            assert!(mode.validate().is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn launch_modes_expand_to_args() {
+        let mode: LaunchMode = serde_json::from_str(r#"{"mode": "gateway", "port": 18789, "verbose": true}"#).unwrap();
+        assert_eq!(mode.args(), ["gateway", "--port", "18789", "--verbose"]);
+        let mode: LaunchMode = serde_json::from_str(r#"{"mode": "onboard", "skipDaemon": true}"#).unwrap();
+        assert_eq!(mode.args(), ["onboard", "--skip-daemon"]);
+        assert_eq!(LaunchMode::Tui { session: Some("main".to_string()) }.args(), ["tui", "--session", "main"]);
+        assert!(serde_json::from_str::<LaunchMode>(r#"{"mode": "shell"}"#).is_err());
+    }
+
+    #[test]
+    fn accepts_locale_names() {
+        for locale in ["C", "POSIX", "C.UTF-8", "en_US.UTF-8", "de_DE@euro", "ast_ES.utf8", "fr"] {
+            assert!(check_locale(locale).is_ok(), "{}", locale);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_locales() {
+        for locale in ["", "en-US", "EN_us", "en_US.UTF 8", "en_US;rm", "../en_US", "en_US.UTF-8\n"] {
+            assert!(check_locale(locale).is_err(), "{:?}", locale);
+        }
+    }
+
+    #[test]
+    fn accepts_timezone_names() {
+        for tz in ["UTC", "Europe/Berlin", "America/Argentina/Buenos_Aires", "Etc/GMT+5", "America/Port-au-Prince"] {
+            assert!(check_timezone(tz).is_ok(), "{}", tz);
+        }
+    }
+
+    #[test]
+    fn rejects_timezones_that_escape_the_zone_database() {
+        let long = "A".repeat(65);
+        let invalid = [
+            "",
+            "/etc/passwd",
+            "../../etc/passwd",
+            "Europe/../../x",
+            "Europe//Berlin",
+            "Europe/",
+            ":Europe/Berlin",
+            "Europe/Berlin ",
+            long.as_str(),
+        ];
+        for tz in invalid {
+            assert!(check_timezone(tz).is_err(), "{:?}", tz);
+        }
+    }
+}
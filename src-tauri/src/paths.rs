@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Flag file that enables portable mode when placed next to the executable.
+const PORTABLE_FLAG_FILE: &str = "clawrunner.portable";
+/// Command-line flag that enables portable mode.
+pub const PORTABLE_ARG: &str = "--portable";
+
+/// Returns the portable data root (`data/` beside the executable) if portable
+/// mode is enabled. Resolved once per process.
+fn portable_root() -> Option<&'static PathBuf> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let exe = std::env::current_exe().ok()?;
+        let exe_dir = exe.parent()?;
+        let enabled = std::env::args().any(|a| a == PORTABLE_ARG)
+            || exe_dir.join(PORTABLE_FLAG_FILE).exists();
+        enabled.then(|| exe_dir.join("data"))
+    })
+    .as_ref()
+}
+
+/// Base directory for settings, OpenClaw state, and logs:
+/// `data/` beside the executable in portable mode, otherwise ~/.clawrunner.
+pub fn data_dir() -> Result<PathBuf, String> {
+    if let Some(root) = portable_root() {
+        return Ok(root.clone());
+    }
+    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
+    Ok(home.join(".clawrunner"))
+}
@@ -0,0 +1,516 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::exec;
+use crate::hooks::sha256_file;
+use crate::openclaw;
+use crate::settings;
+
+const MANIFEST_FILE: &str = "plugin.json";
+const INVOKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum size of a single JSON line from a plugin (1 MB).
+const MAX_MESSAGE_SIZE: usize = 1_048_576;
+
+/// Capabilities a plugin may request. Each one is granted separately by the user.
+const KNOWN_CAPABILITIES: &[&str] = &[
+    // Expose commands callable from the frontend via `plugin_invoke`
+    "commands",
+    // Emit events to the frontend (as `plugin:{id}:{event}`)
+    "events",
+];
+
+/// What granting a capability allows, as shown in the permission prompt.
+fn describe_capability(capability: &str) -> &'static str {
+    match capability {
+        "commands" => "receive commands from the ClawRunner window",
+        "events" => "send events to the ClawRunner window",
+        _ => "use an unknown capability",
+    }
+}
+
+/// Parsed `plugin.json`. Only subprocess plugins are supported: the host
+/// talks to the executable over JSON lines on stdin/stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// Path to the executable, relative to the plugin directory.
+    pub executable: String,
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PluginGrant {
+    sha256: String,
+    /// Consent to run the executable at all, independent of capabilities.
+    #[serde(default)]
+    run: bool,
+    capabilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub sha256: String,
+    /// Whether the user has allowed this exact executable to run.
+    pub run_granted: bool,
+    pub granted: Vec<String>,
+    /// Requested capabilities the user has not granted yet; `grant` asks
+    /// for each of them in a native dialog.
+    pub pending: Vec<String>,
+    pub running: bool,
+}
+
+struct DiscoveredPlugin {
+    manifest: PluginManifest,
+    executable: PathBuf,
+    sha256: String,
+}
+
+type PendingMap = Arc<Mutex<HashMap<u64, mpsc::Sender<Result<Value, String>>>>>;
+
+struct RunningPlugin {
+    stdin: Mutex<ChildStdin>,
+    child: Mutex<Child>,
+    pending: PendingMap,
+    next_id: AtomicU64,
+    /// Commands callable via `plugin_invoke`; empty unless the "commands"
+    /// capability was granted when the plugin started.
+    commands: Vec<String>,
+}
+
+impl Drop for RunningPlugin {
+    fn drop(&mut self) {
+        if let Ok(mut child) = self.child.lock() {
+            exec::kill_group(&mut child);
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Messages a plugin writes to stdout, one JSON object per line.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginMessage {
+    Response {
+        id: u64,
+        #[serde(default)]
+        result: Value,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    Event {
+        event: String,
+        #[serde(default)]
+        payload: Value,
+    },
+}
+
+pub struct PluginHost {
+    running: Mutex<HashMap<String, Arc<RunningPlugin>>>,
+}
+
+fn plugins_dir() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join("plugins"))
+}
+
+fn grants_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join("plugin-grants.json"))
+}
+
+fn load_grants() -> HashMap<String, PluginGrant> {
+    grants_path()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_grants(grants: &HashMap<String, PluginGrant>) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(grants).map_err(|e| format!("Serialize error: {}", e))?;
+    settings::write_private_file(&grants_path()?, content.as_bytes())
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Loads and validates one plugin directory. The executable must resolve to
+/// a file inside the plugin directory.
+fn load_plugin(dir: &Path) -> Result<DiscoveredPlugin, String> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Cannot read manifest in {:?}: {}", dir, e))?;
+    let manifest: PluginManifest =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid manifest in {:?}: {}", dir, e))?;
+
+    if !is_valid_id(&manifest.id) {
+        return Err(format!("Invalid plugin id: {}", manifest.id));
+    }
+    if let Some(cap) = manifest
+        .capabilities
+        .iter()
+        .find(|c| !KNOWN_CAPABILITIES.contains(&c.as_str()))
+    {
+        return Err(format!("Plugin {} requests unknown capability: {}", manifest.id, cap));
+    }
+
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve {:?}: {}", dir, e))?;
+    let executable = dir
+        .join(&manifest.executable)
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve plugin executable: {}", e))?;
+    if !executable.starts_with(&dir) || !executable.is_file() {
+        return Err(format!("Plugin {} executable must be a file inside its directory", manifest.id));
+    }
+
+    let sha256 = sha256_file(&executable)?;
+    Ok(DiscoveredPlugin { manifest, executable, sha256 })
+}
+
+/// Scans <data dir>/plugins/*/plugin.json. Invalid plugins are skipped.
+fn discover() -> Vec<DiscoveredPlugin> {
+    let Ok(dir) = plugins_dir() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return Vec::new() };
+
+    let mut plugins: Vec<DiscoveredPlugin> = Vec::new();
+    for entry in entries.flatten() {
+        if !entry.path().is_dir() {
+            continue;
+        }
+        match load_plugin(&entry.path()) {
+            Ok(p) if plugins.iter().any(|o| o.manifest.id == p.manifest.id) => {
+                eprintln!("[plugins] Duplicate plugin id: {}", p.manifest.id);
+            }
+            Ok(p) => plugins.push(p),
+            Err(e) => eprintln!("[plugins] {}", e),
+        }
+    }
+    plugins
+}
+
+/// Capabilities granted for this exact executable. A changed executable
+/// invalidates all previous grants.
+fn granted_for(plugin: &DiscoveredPlugin, grants: &HashMap<String, PluginGrant>) -> Vec<String> {
+    match grants.get(&plugin.manifest.id) {
+        Some(g) if g.sha256 == plugin.sha256 => plugin
+            .manifest
+            .capabilities
+            .iter()
+            .filter(|c| g.capabilities.contains(c))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Shows a blocking native confirmation; true only if the user allowed it.
+fn confirm(app: &AppHandle, title: &str, message: String) -> bool {
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Allow".to_string(), "Don't Allow".to_string()))
+        .blocking_show()
+}
+
+/// Whether the user allowed this exact executable to run.
+fn run_granted(plugin: &DiscoveredPlugin, grants: &HashMap<String, PluginGrant>) -> bool {
+    matches!(grants.get(&plugin.manifest.id), Some(g) if g.run && g.sha256 == plugin.sha256)
+}
+
+/// A plugin only starts with an explicit run grant for its current
+/// checksum, even when it requests no capabilities.
+fn fully_granted(plugin: &DiscoveredPlugin, grants: &HashMap<String, PluginGrant>) -> bool {
+    run_granted(plugin, grants)
+        && granted_for(plugin, grants).len() == plugin.manifest.capabilities.len()
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts every discovered plugin the user allowed to run and whose
+    /// capabilities are all granted.
+    pub fn start_all(&self, app: &AppHandle) {
+        let grants = load_grants();
+        for plugin in discover() {
+            if fully_granted(&plugin, &grants) {
+                if let Err(e) = self.start(app, &plugin) {
+                    eprintln!("[plugins] Failed to start {}: {}", plugin.manifest.id, e);
+                }
+            }
+        }
+    }
+
+    pub fn stop_all(&self) {
+        if let Ok(mut running) = self.running.lock() {
+            running.clear();
+        }
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        let grants = load_grants();
+        let running = self.running.lock().map(|r| r.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        discover()
+            .into_iter()
+            .map(|p| {
+                let granted = granted_for(&p, &grants);
+                let pending = p
+                    .manifest
+                    .capabilities
+                    .iter()
+                    .filter(|c| !granted.contains(c))
+                    .cloned()
+                    .collect();
+                PluginInfo {
+                    running: running.contains(&p.manifest.id),
+                    run_granted: run_granted(&p, &grants),
+                    sha256: p.sha256,
+                    manifest: p.manifest,
+                    granted,
+                    pending,
+                }
+            })
+            .collect()
+    }
+
+    /// Asks the user, in a native dialog, to let the plugin's current
+    /// executable run and to allow each of the given capabilities, records
+    /// what they approved, and starts the plugin once everything it requests
+    /// has been granted. Blocks on the dialogs, so call it off the async
+    /// runtime.
+    pub fn grant(&self, app: &AppHandle, id: &str, capabilities: Vec<String>) -> Result<(), String> {
+        let plugin = discover()
+            .into_iter()
+            .find(|p| p.manifest.id == id)
+            .ok_or_else(|| format!("No plugin with id {}", id))?;
+        if let Some(cap) = capabilities.iter().find(|c| !plugin.manifest.capabilities.contains(c)) {
+            return Err(format!("Plugin {} did not request capability {}", id, cap));
+        }
+
+        let mut grants = load_grants();
+        if !run_granted(&plugin, &grants) {
+            let message = format!(
+                "Allow the plugin \"{}\" {} to run on this computer?\n\n{}\nSHA-256: {}",
+                plugin.manifest.name,
+                plugin.manifest.version,
+                plugin.executable.display(),
+                plugin.sha256
+            );
+            if !confirm(app, "Run Plugin", message) {
+                return Err(format!("Plugin {} was not allowed to run", id));
+            }
+        }
+        let mut granted = granted_for(&plugin, &grants);
+        for cap in capabilities {
+            if granted.contains(&cap) {
+                continue;
+            }
+            let message = format!(
+                "Allow the plugin \"{}\" to {}?",
+                plugin.manifest.name,
+                describe_capability(&cap)
+            );
+            if confirm(app, "Plugin Permission", message) {
+                granted.push(cap);
+            }
+        }
+        grants.insert(
+            id.to_string(),
+            PluginGrant {
+                sha256: plugin.sha256.clone(),
+                run: true,
+                capabilities: granted,
+            },
+        );
+        save_grants(&grants)?;
+
+        let already_running = self.running.lock().map_err(|e| e.to_string())?.contains_key(id);
+        if !already_running && fully_granted(&plugin, &grants) {
+            self.start(app, &plugin)?;
+        }
+        Ok(())
+    }
+
+    /// Revokes all grants for a plugin and stops it.
+    pub fn revoke(&self, id: &str) -> Result<(), String> {
+        let mut grants = load_grants();
+        grants.remove(id);
+        save_grants(&grants)?;
+        self.running.lock().map_err(|e| e.to_string())?.remove(id);
+        Ok(())
+    }
+
+    /// Sends a command to a running plugin and waits for its response.
+    pub fn invoke(&self, id: &str, command: &str, payload: Value) -> Result<Value, String> {
+        let plugin = {
+            let running = self.running.lock().map_err(|e| e.to_string())?;
+            running
+                .get(id)
+                .cloned()
+                .ok_or_else(|| format!("Plugin {} is not running", id))?
+        };
+
+        let request_id = plugin.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        plugin
+            .pending
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(request_id, tx);
+
+        let line = serde_json::json!({
+            "id": request_id,
+            "command": command,
+            "payload": payload,
+        })
+        .to_string();
+        let write_result = {
+            let mut stdin = plugin.stdin.lock().map_err(|e| e.to_string())?;
+            writeln!(stdin, "{}", line).and_then(|_| stdin.flush())
+        };
+        if let Err(e) = write_result {
+            if let Ok(mut pending) = plugin.pending.lock() {
+                pending.remove(&request_id);
+            }
+            return Err(format!("Plugin write error: {}", e));
+        }
+
+        let result = rx.recv_timeout(INVOKE_TIMEOUT);
+        if let Ok(mut pending) = plugin.pending.lock() {
+            pending.remove(&request_id);
+        }
+        result.map_err(|_| format!("Plugin {} did not respond", id))?
+    }
+
+    /// Checked against the state captured when the plugin started, so an
+    /// invoke never re-scans or re-hashes the plugins directory.
+    pub fn exposes_command(&self, id: &str, command: &str) -> bool {
+        self.running
+            .lock()
+            .map(|r| r.get(id).is_some_and(|p| p.commands.iter().any(|c| c == command)))
+            .unwrap_or(false)
+    }
+
+    fn start(&self, app: &AppHandle, plugin: &DiscoveredPlugin) -> Result<(), String> {
+        let grants = load_grants();
+        if !fully_granted(plugin, &grants) {
+            return Err(format!("Plugin {} is not allowed to run", plugin.manifest.id));
+        }
+        let commands = if granted_for(plugin, &grants).iter().any(|c| c == "commands") {
+            plugin.manifest.commands.clone()
+        } else {
+            Vec::new()
+        };
+
+        let mut cmd = Command::new(&plugin.executable);
+        cmd.env_clear();
+        for var in openclaw::PASSTHROUGH_ENV_VARS {
+            if let Ok(val) = std::env::var(var) {
+                cmd.env(var, val);
+            }
+        }
+        if let Ok(path_val) = std::env::var("PATH") {
+            cmd.env("PATH", path_val);
+        }
+        if let Some(dir) = plugin.executable.parent() {
+            cmd.current_dir(dir);
+        }
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        exec::own_process_group(&mut cmd);
+        let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn plugin: {}", e))?;
+        let stdin = child.stdin.take().ok_or("Plugin stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("Plugin stdout unavailable")?;
+
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let running = Arc::new(RunningPlugin {
+            stdin: Mutex::new(stdin),
+            child: Mutex::new(child),
+            pending: Arc::clone(&pending),
+            next_id: AtomicU64::new(1),
+            commands,
+        });
+
+        let id = plugin.manifest.id.clone();
+        let events = if plugin.manifest.capabilities.iter().any(|c| c == "events") {
+            plugin.manifest.events.clone()
+        } else {
+            Vec::new()
+        };
+        let app_handle = app.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                // Never buffers more than one byte past MAX_MESSAGE_SIZE.
+                match (&mut reader).take(MAX_MESSAGE_SIZE as u64 + 1).read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                if line.len() > MAX_MESSAGE_SIZE {
+                    if line.last() != Some(&b'\n') {
+                        exec::skip_line(&mut reader);
+                    }
+                    continue;
+                }
+                match serde_json::from_slice::<PluginMessage>(&line) {
+                    Ok(PluginMessage::Response { id: req_id, result, error }) => {
+                        let sender = pending.lock().ok().and_then(|mut p| p.remove(&req_id));
+                        if let Some(tx) = sender {
+                            let _ = tx.send(match error {
+                                Some(e) => Err(e),
+                                None => Ok(result),
+                            });
+                        }
+                    }
+                    // Only events declared in the manifest are forwarded.
+                    Ok(PluginMessage::Event { event, payload }) if events.contains(&event) => {
+                        let _ = app_handle.emit(&format!("plugin:{}:{}", id, event), payload);
+                    }
+                    _ => {}
+                }
+            }
+            // Fail any in-flight requests once the plugin exits.
+            if let Ok(mut p) = pending.lock() {
+                p.clear();
+            }
+        });
+
+        self.running
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(plugin.manifest.id.clone(), running);
+        Ok(())
+    }
+}
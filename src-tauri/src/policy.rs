@@ -0,0 +1,477 @@
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+
+use crate::openclaw::LaunchMode;
+
+/// Built-in policy compiled into the binary. Used when no valid bundled
+/// policy is found, so a missing or tampered payload can never widen access.
+const BUILTIN_POLICY: &str = include_str!("../policy/arg-policy.json");
+
+/// Minisign public key used to verify the bundled policy.
+/// Same key as the updater (`plugins.updater.pubkey` in tauri.conf.json).
+const POLICY_PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IEI3NkRGQkY2OUQ0MjREMkIKUldRclRVS2Q5dnR0dDI4VFQrQVJJWjB0ZmNJTlVuYTd1Qk5hNFBRZWZJRElMaFVBUThrdTd0bSsK";
+
+const POLICY_FILE: &str = "arg-policy.json";
+/// Subcommand manifest shipped with the OpenClaw resources, signed with the
+/// same key, so a new OpenClaw release can expose CLI features without a
+/// desktop release.
+const MANIFEST_FILE: &str = "desktop-manifest.json";
+const POLICY_VERSION: u32 = 1;
+const MAX_POLICY_SIZE: u64 = 256 * 1024;
+
+/// Longest string option value accepted, whatever its schema says.
+const MAX_VALUE_LEN: usize = 1024;
+
+/// The type and allowed range of a flag's value.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ValueSchema {
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    /// One of a fixed set of values.
+    Enum {
+        values: Vec<String>,
+    },
+    /// Free text matching `pattern` (implicitly anchored at both ends).
+    #[serde(rename_all = "camelCase")]
+    String {
+        pattern: String,
+        #[serde(default = "default_max_len")]
+        max_len: usize,
+    },
+}
+
+fn default_max_len() -> usize {
+    MAX_VALUE_LEN
+}
+
+impl ValueSchema {
+    fn check(&self, flag: &str, value: &str) -> Result<(), String> {
+        let ok = match self {
+            ValueSchema::Integer { min, max } => value.parse::<i64>().is_ok_and(|v| (*min..=*max).contains(&v)),
+            ValueSchema::Enum { values } => values.iter().any(|v| v == value),
+            ValueSchema::String { pattern, max_len } => {
+                value.len() <= (*max_len).min(MAX_VALUE_LEN)
+                    && anchored(pattern).is_ok_and(|re| re.is_match(value))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("Invalid value for {}: {}", flag, value))
+        }
+    }
+
+    /// The stricter of two schemas for the same flag, or `None` if they
+    /// can't be combined (which drops the flag).
+    fn intersect(&self, other: &ValueSchema) -> Option<ValueSchema> {
+        match (self, other) {
+            (ValueSchema::Integer { min, max }, ValueSchema::Integer { min: min2, max: max2 }) => {
+                let (min, max) = ((*min).max(*min2), (*max).min(*max2));
+                (min <= max).then_some(ValueSchema::Integer { min, max })
+            }
+            (ValueSchema::Enum { values }, ValueSchema::Enum { values: values2 }) => {
+                let values: Vec<String> = values.iter().filter(|v| values2.contains(v)).cloned().collect();
+                (!values.is_empty()).then_some(ValueSchema::Enum { values })
+            }
+            (
+                ValueSchema::String { pattern, max_len },
+                ValueSchema::String { pattern: pattern2, max_len: max_len2 },
+            ) if pattern == pattern2 => Some(ValueSchema::String {
+                pattern: pattern.clone(),
+                max_len: (*max_len).min(*max_len2),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn anchored(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| format!("Invalid pattern {:?}: {}", pattern, e))
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SubcommandPolicy {
+    /// Flags that take no value.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// Flags that take a value, passed as `--flag value` or `--flag=value`.
+    #[serde(default)]
+    pub options: HashMap<String, ValueSchema>,
+}
+
+/// Which OpenClaw subcommands (and flags per subcommand) the frontend may request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArgPolicy {
+    pub version: u32,
+    pub subcommands: HashMap<String, SubcommandPolicy>,
+}
+
+impl ArgPolicy {
+    /// Validates an argument list: the first arg must be an allowed subcommand,
+    /// and every following arg must be a flag allowed for that subcommand,
+    /// with a value matching its schema if it takes one.
+    pub fn validate(&self, args: &[String]) -> Result<(), String> {
+        let (subcommand, rest) = args
+            .split_first()
+            .ok_or("Missing OpenClaw subcommand")?;
+        let policy = self
+            .subcommands
+            .get(subcommand.as_str())
+            .ok_or_else(|| format!("Disallowed argument: {}", subcommand))?;
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            if policy.flags.contains(arg) {
+                continue;
+            }
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            let schema = policy
+                .options
+                .get(flag)
+                .ok_or_else(|| format!("Disallowed argument: {}", arg))?;
+            let value = match value {
+                Some(value) => value,
+                None => rest.next().ok_or_else(|| format!("Missing value for {}", flag))?,
+            };
+            schema.check(flag, value)?;
+        }
+        Ok(())
+    }
+
+    /// Checks a launch mode against the policy, after its own validation.
+    pub fn validate_mode(&self, mode: &LaunchMode) -> Result<(), String> {
+        mode.validate()?;
+        self.validate(&mode.args())
+    }
+
+    /// Adds the subcommands, flags and options of `other` that this policy
+    /// lacks. Existing option schemas are kept as they are.
+    fn extend(&mut self, other: ArgPolicy) {
+        for (name, extra) in other.subcommands {
+            let policy = self.subcommands.entry(name).or_default();
+            for flag in extra.flags {
+                if !policy.flags.contains(&flag) {
+                    policy.flags.push(flag);
+                }
+            }
+            for (flag, schema) in extra.options {
+                policy.options.entry(flag).or_insert(schema);
+            }
+        }
+    }
+
+    /// Restricts this policy to what `other` also allows.
+    /// Used to apply the enterprise layer, which may only tighten the surface.
+    fn intersect(&mut self, other: &ArgPolicy) {
+        self.subcommands.retain(|name, policy| match other.subcommands.get(name) {
+            Some(allowed) => {
+                policy.flags.retain(|f| allowed.flags.contains(f));
+                policy.options = policy
+                    .options
+                    .iter()
+                    .filter_map(|(flag, schema)| {
+                        let allowed = allowed.options.get(flag)?;
+                        Some((flag.clone(), schema.intersect(allowed)?))
+                    })
+                    .collect();
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+fn parse_policy(data: &[u8]) -> Result<ArgPolicy, String> {
+    let policy: ArgPolicy =
+        serde_json::from_slice(data).map_err(|e| format!("Invalid policy file: {}", e))?;
+    if policy.version != POLICY_VERSION {
+        return Err(format!("Unsupported policy version: {}", policy.version));
+    }
+    for subcommand in policy.subcommands.values() {
+        for schema in subcommand.options.values() {
+            if let ValueSchema::String { pattern, .. } = schema {
+                anchored(pattern)?;
+            }
+        }
+    }
+    Ok(policy)
+}
+
+fn builtin_policy() -> ArgPolicy {
+    serde_json::from_str(BUILTIN_POLICY).expect("built-in argument policy is valid JSON")
+}
+
+/// Decodes a base64-wrapped minisign key/signature (the format used by `tauri signer`).
+fn decode_base64(value: &str) -> Result<String, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|e| format!("Invalid base64: {}", e))?;
+    String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 in signature".to_string())
+}
+
+fn verify_signature(data: &[u8], signature_b64: &str) -> Result<(), String> {
+    verify_with_key(POLICY_PUBKEY, data, signature_b64)
+}
+
+fn verify_with_key(public_key_b64: &str, data: &[u8], signature_b64: &str) -> Result<(), String> {
+    let public_key = PublicKey::decode(&decode_base64(public_key_b64)?)
+        .map_err(|e| format!("Invalid policy public key: {}", e))?;
+    let signature = Signature::decode(&decode_base64(signature_b64)?)
+        .map_err(|e| format!("Invalid policy signature: {}", e))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| format!("Policy signature verification failed: {}", e))
+}
+
+fn read_capped(path: &PathBuf) -> Result<Vec<u8>, String> {
+    let meta = std::fs::metadata(path).map_err(|e| format!("Cannot stat {:?}: {}", path, e))?;
+    if meta.len() > MAX_POLICY_SIZE {
+        return Err(format!("Policy file too large: {:?}", path));
+    }
+    std::fs::read(path).map_err(|e| format!("Cannot read {:?}: {}", path, e))
+}
+
+/// Reads a policy file under the resource dir and verifies its detached
+/// signature (`<file>.sig`). Returns `None` if the file doesn't exist.
+fn load_signed(app: &AppHandle, relative: &[&str]) -> Result<Option<ArgPolicy>, String> {
+    let resource_dir = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Cannot resolve resource dir: {}", e))?;
+    let path = relative.iter().fold(resource_dir, |path, part| path.join(part));
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let data = read_capped(&path)?;
+    let sig_path = path.with_extension("json.sig");
+    let signature = std::fs::read_to_string(&sig_path)
+        .map_err(|e| format!("Cannot read policy signature {:?}: {}", sig_path, e))?;
+    verify_signature(&data, &signature)?;
+
+    parse_policy(&data).map(Some)
+}
+
+/// Loads the bundled policy (resources/arg-policy.json) and verifies its
+/// detached signature (resources/arg-policy.json.sig).
+fn load_bundled(app: &AppHandle) -> Result<Option<ArgPolicy>, String> {
+    load_signed(app, &["resources", POLICY_FILE])
+}
+
+/// Loads the subcommand manifest (resources/openclaw/desktop-manifest.json,
+/// signed like the bundled policy).
+fn load_manifest(app: &AppHandle) -> Result<Option<ArgPolicy>, String> {
+    load_signed(app, &["resources", "openclaw", MANIFEST_FILE])
+}
+
+/// Returns the machine-wide enterprise policy path, writable only by admins.
+fn enterprise_policy_path() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        std::env::var_os("ProgramData")
+            .map(|p| PathBuf::from(p).join("ClawRunner").join(POLICY_FILE))
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support/ClawRunner").join(POLICY_FILE))
+    } else {
+        Some(PathBuf::from("/etc/clawrunner").join(POLICY_FILE))
+    }
+}
+
+fn load_enterprise() -> Result<Option<ArgPolicy>, String> {
+    let path = match enterprise_policy_path() {
+        Some(p) if p.exists() => p,
+        _ => return Ok(None),
+    };
+    let data = read_capped(&path)?;
+    parse_policy(&data)
+        .map(Some)
+        .map_err(|e| format!("Enterprise policy {:?}: {}", path, e))
+}
+
+/// Loads the effective argument policy at startup.
+/// Order: verified bundled policy (else built-in), extended by the verified
+/// OpenClaw subcommand manifest, then narrowed by the enterprise policy if
+/// present. The enterprise policy thus also applies to manifest additions.
+/// An unreadable enterprise policy denies all subcommands rather than
+/// silently falling back to the wider default.
+pub fn load(app: &AppHandle) -> ArgPolicy {
+    let mut policy = match load_bundled(app) {
+        Ok(Some(p)) => p,
+        Ok(None) => builtin_policy(),
+        Err(e) => {
+            eprintln!("[policy] Ignoring bundled argument policy: {}", e);
+            builtin_policy()
+        }
+    };
+
+    match load_manifest(app) {
+        Ok(Some(manifest)) => policy.extend(manifest),
+        Ok(None) => {}
+        Err(e) => eprintln!("[policy] Ignoring OpenClaw subcommand manifest: {}", e),
+    }
+
+    match load_enterprise() {
+        Ok(Some(enterprise)) => policy.intersect(&enterprise),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("[policy] {}", e);
+            policy.subcommands.clear();
+        }
+    }
+
+    policy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key pair and signature of `test` from minisign's own test vectors.
+    const TEST_PUBKEY: &str = "untrusted comment: minisign public key\nRWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\n";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+        RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n\
+        trusted comment: timestamp:1555779966\tfile:test\n\
+        QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==\n";
+
+    fn wrap(text: &str) -> String {
+        base64::engine::general_purpose::STANDARD.encode(text)
+    }
+
+    fn parse(json: &str) -> ArgPolicy {
+        parse_policy(json.as_bytes()).unwrap()
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    const GATEWAY: &str = r#"{"version": 1, "subcommands": {"gateway": {
+        "flags": ["--verbose"],
+        "options": {
+            "--port": {"type": "integer", "min": 1024, "max": 65535},
+            "--bind": {"type": "enum", "values": ["loopback", "lan"]},
+            "--name": {"type": "string", "pattern": "[a-z]+", "maxLen": 8}
+        }
+    }}}"#;
+
+    #[test]
+    fn validate_accepts_allowed_flags_and_values() {
+        let policy = parse(GATEWAY);
+        assert!(policy.validate(&args(&["gateway"])).is_ok());
+        assert!(policy.validate(&args(&["gateway", "--verbose", "--port", "18789"])).is_ok());
+        assert!(policy.validate(&args(&["gateway", "--port=18789", "--bind=lan", "--name", "home"])).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_subcommands_and_flags() {
+        let policy = parse(GATEWAY);
+        assert!(policy.validate(&[]).is_err());
+        assert!(policy.validate(&args(&["config"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--dev"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--verbose=1"])).is_err());
+    }
+
+    #[test]
+    fn validate_checks_option_values() {
+        let policy = parse(GATEWAY);
+        assert!(policy.validate(&args(&["gateway", "--port"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--port", "80"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--port=abc"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--bind", "all"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--name", "Home"])).is_err());
+        assert!(policy.validate(&args(&["gateway", "--name", "homeserver"])).is_err());
+        // The pattern is anchored, so a match inside the value is not enough.
+        assert!(policy.validate(&args(&["gateway", "--name", "home;rm"])).is_err());
+    }
+
+    #[test]
+    fn extend_adds_missing_entries_and_keeps_existing_schemas() {
+        let mut policy = parse(GATEWAY);
+        policy.extend(parse(
+            r#"{"version": 1, "subcommands": {
+                "gateway": {"flags": ["--verbose", "--force"], "options": {
+                    "--port": {"type": "integer", "min": 1, "max": 65535},
+                    "--token": {"type": "string", "pattern": "[a-f0-9]+"}
+                }},
+                "doctor": {"flags": ["--fix"]}
+            }}"#,
+        ));
+        assert!(policy.validate(&args(&["doctor", "--fix"])).is_ok());
+        assert!(policy.validate(&args(&["gateway", "--force", "--token", "abc123"])).is_ok());
+        assert_eq!(policy.subcommands["gateway"].flags, ["--verbose", "--force"]);
+        // The manifest cannot widen an option the policy already defines.
+        assert!(policy.validate(&args(&["gateway", "--port", "80"])).is_err());
+    }
+
+    #[test]
+    fn intersect_only_narrows() {
+        let mut policy = parse(GATEWAY);
+        policy.extend(parse(r#"{"version": 1, "subcommands": {"doctor": {}}}"#));
+        policy.intersect(&parse(
+            r#"{"version": 1, "subcommands": {"gateway": {"flags": ["--force"], "options": {
+                "--port": {"type": "integer", "min": 18000, "max": 70000},
+                "--bind": {"type": "enum", "values": ["loopback", "all"]},
+                "--name": {"type": "string", "pattern": "[a-z0-9]+"}
+            }}}}"#,
+        ));
+        assert!(!policy.subcommands.contains_key("doctor"));
+        let gateway = &policy.subcommands["gateway"];
+        assert!(gateway.flags.is_empty());
+        assert_eq!(gateway.options["--port"], ValueSchema::Integer { min: 18000, max: 65535 });
+        assert_eq!(gateway.options["--bind"], ValueSchema::Enum { values: vec!["loopback".to_string()] });
+        // Differing patterns can't be combined, so the option is dropped.
+        assert!(!gateway.options.contains_key("--name"));
+    }
+
+    #[test]
+    fn intersect_drops_options_with_disjoint_ranges() {
+        let mut policy = parse(GATEWAY);
+        policy.intersect(&parse(
+            r#"{"version": 1, "subcommands": {"gateway": {"options": {
+                "--port": {"type": "integer", "min": 1, "max": 1023},
+                "--bind": {"type": "enum", "values": ["all"]}
+            }}}}"#,
+        ));
+        assert!(policy.subcommands["gateway"].options.is_empty());
+        assert!(policy.validate(&args(&["gateway", "--port", "18789"])).is_err());
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        assert!(verify_with_key(&wrap(TEST_PUBKEY), b"test", &wrap(TEST_SIGNATURE)).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_data() {
+        let err = verify_with_key(&wrap(TEST_PUBKEY), b"Test", &wrap(TEST_SIGNATURE)).unwrap_err();
+        assert!(err.contains("verification failed"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_a_signature_from_another_key() {
+        assert!(verify_signature(b"test", &wrap(TEST_SIGNATURE)).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        assert!(verify_signature(b"test", "not base64!").is_err());
+        assert!(verify_signature(b"test", &wrap("untrusted comment: x\ngarbage\n")).is_err());
+        assert!(verify_signature(b"test", "").is_err());
+    }
+
+    #[test]
+    fn policy_key_decodes() {
+        let key = decode_base64(POLICY_PUBKEY).unwrap();
+        assert!(PublicKey::decode(&key).is_ok());
+    }
+}
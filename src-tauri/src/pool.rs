@@ -0,0 +1,303 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::openclaw;
+use crate::paths;
+use crate::pty_manager::{CloseReason, ExitCallback, SessionOptions};
+use crate::AppState;
+
+const MAX_POOL_SIZE: usize = 16;
+/// First worker port when a pool is created without an explicit base port.
+/// The default gateway listens on 18789, so pools start just above it.
+const DEFAULT_BASE_PORT: u16 = openclaw::DEFAULT_GATEWAY_PORT + 1;
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+const WORKER_COLS: u16 = 120;
+const WORKER_ROWS: u16 = 40;
+
+struct Worker {
+    /// `None` while the worker is not running (exited, or waiting to restart).
+    session_id: Option<u64>,
+    restarts: u32,
+}
+
+struct Pool {
+    base_port: u16,
+    workers: Vec<Worker>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolHealth {
+    /// Every worker is running.
+    Healthy,
+    /// Some workers are down or restarting.
+    Degraded,
+    /// No workers are running.
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerStatus {
+    pub index: usize,
+    pub session_id: Option<u64>,
+    pub port: u16,
+    pub running: bool,
+    pub restarts: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+    pub name: String,
+    pub size: usize,
+    pub running: usize,
+    pub health: PoolHealth,
+    pub workers: Vec<WorkerStatus>,
+}
+
+/// Named pools of supervised gateway workers. Each worker gets its own port
+/// and state sub-directory and is restarted if it exits on its own.
+pub struct PoolManager {
+    pools: Mutex<HashMap<String, Pool>>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Returns (and creates) the state directory for one worker:
+/// <data dir>/pools/<pool>/<index>/
+fn worker_state_dir(name: &str, index: usize) -> Result<PathBuf, String> {
+    let dir = paths::data_dir()?
+        .join("pools")
+        .join(name)
+        .join(index.to_string());
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create worker state dir: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700));
+    }
+
+    Ok(dir)
+}
+
+fn pool_status(name: &str, pool: &Pool) -> PoolStatus {
+    let workers: Vec<WorkerStatus> = pool
+        .workers
+        .iter()
+        .enumerate()
+        .map(|(index, w)| WorkerStatus {
+            index,
+            session_id: w.session_id,
+            port: pool.base_port + index as u16,
+            running: w.session_id.is_some(),
+            restarts: w.restarts,
+        })
+        .collect();
+    let running = workers.iter().filter(|w| w.running).count();
+    let health = if running == 0 {
+        PoolHealth::Down
+    } else if running < workers.len() {
+        PoolHealth::Degraded
+    } else {
+        PoolHealth::Healthy
+    };
+    PoolStatus {
+        name: name.to_string(),
+        size: workers.len(),
+        running,
+        health,
+        workers,
+    }
+}
+
+fn emit_status(app: &AppHandle, name: &str) {
+    if let Some(status) = app.state::<PoolManager>().status(name) {
+        let _ = app.emit("pool:status", status);
+    }
+}
+
+/// Spawns one gateway worker on its own port and state directory.
+fn spawn_worker(app: &AppHandle, name: &str, index: usize, port: u16) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.for_profile(None)?;
+    let args = vec!["gateway".to_string(), "--port".to_string(), port.to_string()];
+    let mut cmd = openclaw::build_openclaw_command(app, &settings, &args)?;
+    let state_dir = worker_state_dir(name, index)?;
+    cmd.env("OPENCLAW_STATE_DIR", state_dir.to_string_lossy().as_ref());
+
+    let app_handle = app.clone();
+    let pool_name = name.to_string();
+    let on_exit: ExitCallback = Box::new(move |session_id, reason, _exit_code| {
+        on_worker_exit(&app_handle, &pool_name, index, session_id, reason);
+    });
+    let options = SessionOptions {
+        idle_exempt: true,
+        args,
+        ..Default::default()
+    };
+    state.pty.spawn(app, cmd, WORKER_COLS, WORKER_ROWS, options, on_exit)
+}
+
+/// Marks the worker as down and, if it exited on its own and the pool still
+/// wants it, restarts it after a short delay.
+fn on_worker_exit(app: &AppHandle, name: &str, index: usize, session_id: u64, reason: CloseReason) {
+    let manager = app.state::<PoolManager>();
+    let port = {
+        let Ok(mut pools) = manager.pools.lock() else { return };
+        let Some(pool) = pools.get_mut(name) else { return };
+        let Some(worker) = pool.workers.get_mut(index) else { return };
+        if worker.session_id != Some(session_id) {
+            return;
+        }
+        worker.session_id = None;
+        pool.base_port + index as u16
+    };
+    emit_status(app, name);
+
+    if reason != CloseReason::ChildExit {
+        return;
+    }
+
+    let app = app.clone();
+    let name = name.to_string();
+    thread::spawn(move || {
+        thread::sleep(RESTART_DELAY);
+        let manager = app.state::<PoolManager>();
+        let still_wanted = manager
+            .pools
+            .lock()
+            .ok()
+            .and_then(|pools| pools.get(&name).map(|p| index < p.workers.len()))
+            .unwrap_or(false);
+        if !still_wanted {
+            return;
+        }
+        match spawn_worker(&app, &name, index, port) {
+            Ok(new_id) => {
+                if let Ok(mut pools) = manager.pools.lock() {
+                    if let Some(worker) = pools.get_mut(&name).and_then(|p| p.workers.get_mut(index)) {
+                        worker.session_id = Some(new_id);
+                        worker.restarts += 1;
+                    }
+                }
+            }
+            Err(e) => eprintln!("[pool] Failed to restart {}#{}: {}", name, index, e),
+        }
+        emit_status(&app, &name);
+    });
+}
+
+impl PoolManager {
+    pub fn new() -> Self {
+        Self {
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn status(&self, name: &str) -> Option<PoolStatus> {
+        let pools = self.pools.lock().ok()?;
+        pools.get(name).map(|p| pool_status(name, p))
+    }
+
+    pub fn list(&self) -> Vec<PoolStatus> {
+        match self.pools.lock() {
+            Ok(pools) => pools.iter().map(|(n, p)| pool_status(n, p)).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Creates the pool if needed and scales it to `size` workers.
+    /// Workers are added/removed at the highest indices.
+    pub fn scale(
+        &self,
+        app: &AppHandle,
+        name: &str,
+        size: usize,
+        base_port: Option<u16>,
+    ) -> Result<PoolStatus, String> {
+        if !is_valid_name(name) {
+            return Err(format!("Invalid pool name: {}", name));
+        }
+        if size > MAX_POOL_SIZE {
+            return Err(format!("Pool size must be at most {}", MAX_POOL_SIZE));
+        }
+
+        // Adjust the desired worker list under the lock; spawn/kill outside it,
+        // since killing awaits reader tasks whose exit callbacks take this lock.
+        let (to_spawn, to_kill) = {
+            let mut pools = self.pools.lock().map_err(|e| e.to_string())?;
+            let pool = pools.entry(name.to_string()).or_insert_with(|| Pool {
+                base_port: base_port.unwrap_or(DEFAULT_BASE_PORT),
+                workers: Vec::new(),
+            });
+            if base_port.is_some_and(|p| p != pool.base_port) && !pool.workers.is_empty() {
+                return Err("Cannot change base port of a running pool".to_string());
+            }
+            if let Some(p) = base_port {
+                pool.base_port = p;
+            }
+            if pool.base_port < 1024 || pool.base_port as usize + size > u16::MAX as usize {
+                return Err("Pool ports must be within 1024-65535".to_string());
+            }
+
+            let current = pool.workers.len();
+            let to_kill: Vec<u64> = if size < current {
+                pool.workers.drain(size..).filter_map(|w| w.session_id).collect()
+            } else {
+                Vec::new()
+            };
+            let to_spawn: Vec<(usize, u16)> = (current..size)
+                .map(|i| (i, pool.base_port + i as u16))
+                .collect();
+            for _ in current..size {
+                pool.workers.push(Worker {
+                    session_id: None,
+                    restarts: 0,
+                });
+            }
+            (to_spawn, to_kill)
+        };
+
+        let state = app.state::<AppState>();
+        for session_id in to_kill {
+            let _ = tauri::async_runtime::block_on(state.pty.kill(session_id, CloseReason::UserKill));
+        }
+
+        let mut errors = Vec::new();
+        for (index, port) in to_spawn {
+            match spawn_worker(app, name, index, port) {
+                Ok(session_id) => {
+                    let mut pools = self.pools.lock().map_err(|e| e.to_string())?;
+                    if let Some(worker) = pools.get_mut(name).and_then(|p| p.workers.get_mut(index)) {
+                        worker.session_id = Some(session_id);
+                    }
+                }
+                Err(e) => errors.push(format!("worker {}: {}", index, e)),
+            }
+        }
+
+        emit_status(app, name);
+        if !errors.is_empty() {
+            return Err(format!("Failed to start workers: {}", errors.join("; ")));
+        }
+        self.status(name).ok_or_else(|| format!("No pool named {}", name))
+    }
+
+    /// Stops all workers and forgets the pool.
+    pub fn destroy(&self, app: &AppHandle, name: &str) -> Result<(), String> {
+        self.scale(app, name, 0, None)?;
+        self.pools.lock().map_err(|e| e.to_string())?.remove(name);
+        Ok(())
+    }
+}
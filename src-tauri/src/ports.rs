@@ -0,0 +1,110 @@
+//! Checks that a gateway's port is free before it is spawned, so a conflict
+//! is reported with its owner instead of as an EADDRINUSE stack trace in the
+//! terminal.
+
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, TcpListener};
+use std::process::Command;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::exec;
+
+/// How long the owner lookup (lsof / netstat) may take.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A gateway port that is already bound.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortConflict {
+    pub port: u16,
+    /// Process listening on the port, if it could be identified.
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    /// True if the owner looks like another OpenClaw gateway.
+    pub openclaw: bool,
+    pub message: String,
+    /// What the user can do about it.
+    pub remediation: String,
+}
+
+/// Returns the conflict if `port` can't be bound on loopback.
+pub fn check(port: u16) -> Option<PortConflict> {
+    match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(_) => None,
+        Err(e) if e.kind() == ErrorKind::AddrInUse => Some(conflict(port)),
+        // Permission errors and the like are left for the gateway to report.
+        Err(_) => None,
+    }
+}
+
+fn conflict(port: u16) -> PortConflict {
+    let pid = listening_pid(port);
+    let process_name = pid.and_then(process_name);
+    let openclaw = process_name
+        .as_deref()
+        .is_some_and(|n| n.to_lowercase().contains("openclaw") || n.to_lowercase().starts_with("node"));
+    let owner = match (pid, &process_name) {
+        (Some(pid), Some(name)) => format!("{} (pid {})", name, pid),
+        (Some(pid), None) => format!("process {}", pid),
+        _ => "another process".to_string(),
+    };
+    let remediation = if openclaw {
+        format!(
+            "Another OpenClaw gateway may already be running as {}. Stop it, or give this profile a different gateway port.",
+            owner
+        )
+    } else {
+        format!(
+            "Quit {} or give this profile a different gateway port in Settings.",
+            owner
+        )
+    };
+    PortConflict {
+        port,
+        pid,
+        process_name,
+        openclaw,
+        message: format!("Port {} is already in use by {}", port, owner),
+        remediation,
+    }
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    sys.process(pid).map(|p| p.name().to_string_lossy().into_owned())
+}
+
+/// Pid of the process listening on `port`, via `lsof`.
+#[cfg(unix)]
+fn listening_pid(port: u16) -> Option<u32> {
+    let mut cmd = Command::new("lsof");
+    cmd.args(["-nP", "-t", &format!("-iTCP:{}", port), "-sTCP:LISTEN"]);
+    let output = exec::run(cmd, LOOKUP_TIMEOUT).ok()?;
+    output.stdout.lines().next()?.trim().parse().ok()
+}
+
+/// Pid of the process listening on `port`, via `netstat -ano`.
+#[cfg(windows)]
+fn listening_pid(port: u16) -> Option<u32> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut cmd = Command::new("netstat");
+    cmd.args(["-ano", "-p", "TCP"]).creation_flags(CREATE_NO_WINDOW);
+    let output = exec::run(cmd, LOOKUP_TIMEOUT).ok()?;
+    // "  TCP    127.0.0.1:18789    0.0.0.0:0    LISTENING    1234"
+    output.stdout.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [_, local, _, "LISTENING", pid] if local.ends_with(&format!(":{}", port)) => pid.parse().ok(),
+            _ => None,
+        }
+    })
+}
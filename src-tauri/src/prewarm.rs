@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::openclaw;
+use crate::pty_manager::{CloseReason, SessionOptions};
+use crate::settings::Settings;
+use crate::AppState;
+
+const GATEWAY_ARGS: &[&str] = &["gateway"];
+/// Initial size of a pre-warmed session; corrected when it is claimed.
+const WARM_COLS: u16 = 120;
+const WARM_ROWS: u16 = 40;
+
+struct Warm {
+    session_id: u64,
+    args: Vec<String>,
+    /// Settings the session was spawned with. A claim with different
+    /// settings (e.g. changed API keys) discards it instead.
+    settings: serde_json::Value,
+}
+
+/// A gateway spawned at launch with its output held back, so the first
+/// "Start Gateway" attaches to an already-initialized process.
+pub struct Prewarmed {
+    slot: Mutex<Option<Warm>>,
+}
+
+impl Prewarmed {
+    pub fn new() -> Self {
+        Self {
+            slot: Mutex::new(None),
+        }
+    }
+
+    /// Spawns the gateway if enabled in settings and OpenClaw is set up.
+    pub fn start(&self, app: &AppHandle, settings: &Settings) {
+        if !settings.prewarm_gateway || !openclaw::is_configured() {
+            return;
+        }
+        let args: Vec<String> = GATEWAY_ARGS.iter().map(|a| a.to_string()).collect();
+        let options = SessionOptions {
+            hold_output: true,
+            ..Default::default()
+        };
+        let state = app.state::<AppState>();
+        match crate::spawn_openclaw_session(app, &state, settings, &args, WARM_COLS, WARM_ROWS, options) {
+            Ok(session_id) => {
+                if let Ok(mut slot) = self.slot.lock() {
+                    *slot = Some(Warm {
+                        session_id,
+                        args,
+                        settings: serde_json::to_value(settings).unwrap_or_default(),
+                    });
+                }
+            }
+            Err(e) => eprintln!("[prewarm] Cannot pre-spawn gateway: {}", e),
+        }
+    }
+
+    /// Hands the pre-warmed session to a spawn request for the same args,
+    /// resized and with its held output released (unless the caller's
+    /// options hold it until `pty_attach`). Returns `None` if there is nothing
+    /// suitable, in which case the caller spawns normally.
+    pub fn claim(
+        &self,
+        state: &AppState,
+        settings: &Settings,
+        args: &[String],
+        cols: u16,
+        rows: u16,
+        options: &SessionOptions,
+    ) -> Option<u64> {
+        let warm = {
+            let mut slot = self.slot.lock().ok()?;
+            if slot.as_ref()?.args != args {
+                return None;
+            }
+            slot.take()?
+        };
+        let same_settings = serde_json::to_value(settings).ok() == Some(warm.settings);
+        if !same_settings || !state.pty.is_running(warm.session_id) {
+            let _ = tauri::async_runtime::block_on(state.pty.kill(warm.session_id, CloseReason::UserKill));
+            return None;
+        }
+        let _ = state
+            .pty
+            .resize(warm.session_id, cols, rows, options.pixel_width, options.pixel_height);
+        if !options.hold_output {
+            state.pty.release_output(warm.session_id).ok()?;
+        }
+        Some(warm.session_id)
+    }
+}
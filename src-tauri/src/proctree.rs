@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+/// One process in a session's process tree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    /// CPU usage in percent of one core, sampled over a short interval.
+    pub cpu_percent: f32,
+    /// Resident set size in bytes.
+    pub rss_bytes: u64,
+}
+
+/// True if a process with this pid exists.
+pub fn is_alive(pid: u32) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    sys.process(pid).is_some()
+}
+
+/// Returns `root` and all of its descendants, parents before children.
+/// Blocks for `MINIMUM_CPU_UPDATE_INTERVAL` so CPU usage can be sampled;
+/// call from an async command, not the main thread.
+pub fn process_tree(root: u32) -> Vec<ProcessInfo> {
+    let refresh = ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory()
+        .without_tasks();
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+
+    let processes = sys.processes();
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in processes {
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+
+    let mut tree = Vec::new();
+    let mut queue = vec![Pid::from_u32(root)];
+    while let Some(pid) = queue.pop() {
+        let Some(process) = processes.get(&pid) else { continue };
+        tree.push(ProcessInfo {
+            pid: pid.as_u32(),
+            parent_pid: process.parent().map(|p| p.as_u32()),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+        });
+        if let Some(kids) = children.get(&pid) {
+            queue.extend(kids.iter().copied());
+        }
+    }
+    tree
+}
@@ -0,0 +1,154 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use crate::ansi::AnsiStripper;
+
+/// Complete lines kept to find a numbered choice list above a prompt.
+const MAX_CONTEXT_LINES: usize = 20;
+/// Prompts are short; longer partial lines are not examined.
+const MAX_PROMPT_LEN: usize = 512;
+
+/// One option of a numbered choice prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptOption {
+    /// What to type to pick it, e.g. "2".
+    pub key: String,
+    pub label: String,
+}
+
+/// An interactive prompt the session is waiting on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Prompt {
+    /// A y/n question. `default` is the answer Enter gives, if any.
+    #[serde(rename_all = "camelCase")]
+    YesNo { question: String, default: Option<bool> },
+    /// "Press Enter to continue" and similar.
+    #[serde(rename_all = "camelCase")]
+    Continue { text: String },
+    /// A question answered by typing one of the listed keys.
+    #[serde(rename_all = "camelCase")]
+    Choice { question: String, options: Vec<PromptOption> },
+}
+
+struct Patterns {
+    yes_no: Regex,
+    press_key: Regex,
+    option: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        yes_no: Regex::new(r"(?i)[\[(]\s*(y(?:es)?)\s*/\s*(n(?:o)?)\s*[\])]\s*[:?]?\s*$").expect("valid pattern"),
+        press_key: Regex::new(r"(?i)press\s+(?:enter|return|any key)\b.*\bcontinue").expect("valid pattern"),
+        option: Regex::new(r"^\s*[\[(]?(\d{1,2})[\].)]\s+(\S.*?)\s*$").expect("valid pattern"),
+    })
+}
+
+/// What changed after feeding output to the detector.
+pub enum PromptChange {
+    Shown(Prompt),
+    /// The session moved past the prompt (new output arrived).
+    Cleared,
+}
+
+/// Recognizes common interactive prompts at the end of a session's output.
+/// Prompts sit on the incomplete last line, so detection runs on it; a
+/// numbered list just above the prompt makes it a choice.
+pub struct PromptDetector {
+    stripper: AnsiStripper,
+    lines: VecDeque<String>,
+    current: Option<Prompt>,
+}
+
+impl PromptDetector {
+    pub fn new() -> Self {
+        Self {
+            stripper: AnsiStripper::new(),
+            lines: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    pub fn feed(&mut self, data: &str) -> Option<PromptChange> {
+        for line in self.stripper.feed(data) {
+            if self.lines.len() == MAX_CONTEXT_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line);
+        }
+        let detected = self
+            .stripper
+            .partial()
+            .filter(|p| p.len() <= MAX_PROMPT_LEN)
+            .and_then(|p| detect(p, &self.lines));
+        match (detected, &self.current) {
+            (Some(prompt), Some(current)) if prompt == *current => None,
+            (Some(prompt), _) => {
+                self.current = Some(prompt.clone());
+                Some(PromptChange::Shown(prompt))
+            }
+            (None, Some(_)) => {
+                self.current = None;
+                Some(PromptChange::Cleared)
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+fn detect(partial: &str, lines: &VecDeque<String>) -> Option<Prompt> {
+    let text = partial.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let patterns = patterns();
+    if let Some(caps) = patterns.yes_no.captures(text) {
+        let yes_default = caps[1].starts_with('Y');
+        let no_default = caps[2].starts_with('N');
+        let default = match (yes_default, no_default) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            _ => None,
+        };
+        return Some(Prompt::YesNo {
+            question: text.to_string(),
+            default,
+        });
+    }
+    if patterns.press_key.is_match(text) {
+        return Some(Prompt::Continue { text: text.to_string() });
+    }
+    if text.ends_with([':', '?', '>']) {
+        let options = trailing_options(lines);
+        if options.len() >= 2 {
+            return Some(Prompt::Choice {
+                question: text.to_string(),
+                options,
+            });
+        }
+    }
+    None
+}
+
+/// The numbered options directly above the prompt, ignoring blank lines.
+fn trailing_options(lines: &VecDeque<String>) -> Vec<PromptOption> {
+    let option = &patterns().option;
+    let mut options = Vec::new();
+    for line in lines.iter().rev() {
+        if line.trim().is_empty() && options.is_empty() {
+            continue;
+        }
+        let Some(caps) = option.captures(line) else { break };
+        options.push(PromptOption {
+            key: caps[1].to_string(),
+            label: caps[2].to_string(),
+        });
+    }
+    options.reverse();
+    options
+}
@@ -0,0 +1,398 @@
+//! The model providers (and other services) OpenClaw can be configured for:
+//! the env var carrying each one's key, what keys look like, and the
+//! non-secret settings (base URLs, org ids) that may be set alongside.
+//! The settings UI renders from `providers_list`, and launches only accept
+//! the non-key variables listed here. `validate_api_key` checks a key with
+//! the cheapest authenticated request the provider offers.
+
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::settings::Settings;
+
+/// Longest org/project id or API version accepted.
+const MAX_ID_LEN: usize = 128;
+/// Characters of a set key shown at each end when masked.
+const MASK_PREFIX: usize = 4;
+const MASK_SUFFIX: usize = 4;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    /// Serves models to the agent.
+    Model,
+    /// Used by tools and skills, e.g. text-to-speech.
+    Service,
+}
+
+/// How a provider setting's value is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValueKind {
+    /// An http(s) URL.
+    Url,
+    /// A plain token: letters, digits, '-', '_' and '.'.
+    Id,
+}
+
+/// A non-secret provider variable.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSetting {
+    pub env_var: &'static str,
+    pub label: &'static str,
+    pub kind: ValueKind,
+    pub placeholder: &'static str,
+}
+
+/// How a probe request carries the key.
+#[derive(Debug, Clone, Copy)]
+pub enum Auth {
+    Bearer,
+    Header(&'static str),
+}
+
+/// An authenticated request that succeeds for any valid key and costs
+/// nothing: usually listing models.
+#[derive(Debug, Clone, Copy)]
+pub struct Probe {
+    /// Setting that overrides `default_base`, for proxies and self-hosting.
+    pub base_var: Option<&'static str>,
+    pub default_base: &'static str,
+    pub path: &'static str,
+    pub auth: Auth,
+    pub headers: &'static [(&'static str, &'static str)],
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provider {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub category: Category,
+    /// `None` for providers that need no key (local servers).
+    pub key_var: Option<&'static str>,
+    /// What keys start with, if they have a recognizable prefix.
+    pub key_prefix: Option<&'static str>,
+    pub settings: &'static [ProviderSetting],
+    /// `None` if the key can't be checked without side effects.
+    #[serde(skip)]
+    pub probe: Option<Probe>,
+}
+
+const fn url(env_var: &'static str, label: &'static str, placeholder: &'static str) -> ProviderSetting {
+    ProviderSetting {
+        env_var,
+        label,
+        kind: ValueKind::Url,
+        placeholder,
+    }
+}
+
+const fn probe(base_var: Option<&'static str>, default_base: &'static str, path: &'static str, auth: Auth) -> Probe {
+    Probe {
+        base_var,
+        default_base,
+        path,
+        auth,
+        headers: &[],
+    }
+}
+
+const fn id(env_var: &'static str, label: &'static str, placeholder: &'static str) -> ProviderSetting {
+    ProviderSetting {
+        env_var,
+        label,
+        kind: ValueKind::Id,
+        placeholder,
+    }
+}
+
+pub const PROVIDERS: &[Provider] = &[
+    Provider {
+        id: "openai",
+        name: "OpenAI",
+        category: Category::Model,
+        key_var: Some("OPENAI_API_KEY"),
+        key_prefix: Some("sk-"),
+        settings: &[
+            url("OPENAI_BASE_URL", "Base URL", "https://api.openai.com/v1"),
+            id("OPENAI_ORG_ID", "Organization ID", "org-..."),
+            id("OPENAI_ORGANIZATION", "Organization (legacy name)", "org-..."),
+            id("OPENAI_PROJECT_ID", "Project ID", "proj_..."),
+        ],
+        probe: Some(probe(Some("OPENAI_BASE_URL"), "https://api.openai.com/v1", "/models", Auth::Bearer)),
+    },
+    Provider {
+        id: "anthropic",
+        name: "Anthropic",
+        category: Category::Model,
+        key_var: Some("ANTHROPIC_API_KEY"),
+        key_prefix: Some("sk-ant-"),
+        settings: &[url("ANTHROPIC_BASE_URL", "Base URL", "https://api.anthropic.com")],
+        probe: Some(Probe {
+            headers: &[("anthropic-version", "2023-06-01")],
+            ..probe(Some("ANTHROPIC_BASE_URL"), "https://api.anthropic.com", "/v1/models", Auth::Header("x-api-key"))
+        }),
+    },
+    Provider {
+        id: "openrouter",
+        name: "OpenRouter",
+        category: Category::Model,
+        key_var: Some("OPENROUTER_API_KEY"),
+        key_prefix: Some("sk-or-"),
+        settings: &[url("OPENROUTER_BASE_URL", "Base URL", "https://openrouter.ai/api/v1")],
+        probe: Some(probe(Some("OPENROUTER_BASE_URL"), "https://openrouter.ai/api/v1", "/key", Auth::Bearer)),
+    },
+    Provider {
+        id: "google",
+        name: "Google Gemini",
+        category: Category::Model,
+        key_var: Some("GEMINI_API_KEY"),
+        key_prefix: Some("AIza"),
+        settings: &[url("GEMINI_BASE_URL", "Base URL", "https://generativelanguage.googleapis.com")],
+        probe: Some(probe(
+            Some("GEMINI_BASE_URL"),
+            "https://generativelanguage.googleapis.com",
+            "/v1beta/models",
+            Auth::Header("x-goog-api-key"),
+        )),
+    },
+    Provider {
+        id: "mistral",
+        name: "Mistral",
+        category: Category::Model,
+        key_var: Some("MISTRAL_API_KEY"),
+        key_prefix: None,
+        settings: &[url("MISTRAL_BASE_URL", "Base URL", "https://api.mistral.ai/v1")],
+        probe: Some(probe(Some("MISTRAL_BASE_URL"), "https://api.mistral.ai/v1", "/models", Auth::Bearer)),
+    },
+    Provider {
+        id: "groq",
+        name: "Groq",
+        category: Category::Model,
+        key_var: Some("GROQ_API_KEY"),
+        key_prefix: Some("gsk_"),
+        settings: &[url("GROQ_BASE_URL", "Base URL", "https://api.groq.com/openai/v1")],
+        probe: Some(probe(Some("GROQ_BASE_URL"), "https://api.groq.com/openai/v1", "/models", Auth::Bearer)),
+    },
+    Provider {
+        id: "deepseek",
+        name: "DeepSeek",
+        category: Category::Model,
+        key_var: Some("DEEPSEEK_API_KEY"),
+        key_prefix: Some("sk-"),
+        settings: &[url("DEEPSEEK_BASE_URL", "Base URL", "https://api.deepseek.com")],
+        probe: Some(probe(Some("DEEPSEEK_BASE_URL"), "https://api.deepseek.com", "/models", Auth::Bearer)),
+    },
+    Provider {
+        id: "xai",
+        name: "xAI",
+        category: Category::Model,
+        key_var: Some("XAI_API_KEY"),
+        key_prefix: Some("xai-"),
+        settings: &[],
+        probe: Some(probe(None, "https://api.x.ai/v1", "/models", Auth::Bearer)),
+    },
+    Provider {
+        id: "azure-openai",
+        name: "Azure OpenAI",
+        category: Category::Model,
+        key_var: Some("AZURE_OPENAI_API_KEY"),
+        key_prefix: None,
+        settings: &[
+            url("AZURE_OPENAI_ENDPOINT", "Endpoint", "https://<resource>.openai.azure.com"),
+            id("AZURE_OPENAI_API_VERSION", "API Version", "2024-10-21"),
+        ],
+        probe: None,
+    },
+    Provider {
+        id: "ollama",
+        name: "Ollama",
+        category: Category::Model,
+        key_var: None,
+        key_prefix: None,
+        settings: &[url("OLLAMA_HOST", "Host", "http://127.0.0.1:11434")],
+        probe: None,
+    },
+    Provider {
+        id: "elevenlabs",
+        name: "ElevenLabs",
+        category: Category::Service,
+        key_var: Some("ELEVENLABS_API_KEY"),
+        key_prefix: Some("sk_"),
+        settings: &[],
+        probe: Some(probe(None, "https://api.elevenlabs.io", "/v1/user", Auth::Header("xi-api-key"))),
+    },
+    Provider {
+        id: "google-maps",
+        name: "Google Maps",
+        category: Category::Service,
+        key_var: Some("GOOGLE_MAPS_API_KEY"),
+        key_prefix: Some("AIza"),
+        settings: &[],
+        probe: None,
+    },
+];
+
+/// A provider as `providers_list` reports it for one profile.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    #[serde(flatten)]
+    pub provider: Provider,
+    pub key_set: bool,
+    /// The set key with its middle hidden, e.g. "sk-a…wxyz".
+    pub masked_key: Option<String>,
+    /// False if a key is set but lacks the expected prefix.
+    pub key_format_ok: bool,
+    /// Whether `validate_api_key` can check this provider's key.
+    pub checkable: bool,
+}
+
+/// The registered non-key setting stored under `env_var`, if any.
+pub fn setting(env_var: &str) -> Option<&'static ProviderSetting> {
+    PROVIDERS
+        .iter()
+        .flat_map(|p| p.settings)
+        .find(|s| s.env_var == env_var)
+}
+
+/// Checks the value of a non-key provider variable. Other keys (API keys)
+/// aren't checked.
+pub fn check_value(env_var: &str, value: &str) -> Result<(), String> {
+    let Some(setting) = setting(env_var) else { return Ok(()) };
+    match setting.kind {
+        ValueKind::Url => {
+            let url = tauri::Url::parse(value).map_err(|e| format!("{} is not a valid URL: {}", env_var, e))?;
+            if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+                return Err(format!("{} must be an http or https URL", env_var));
+            }
+        }
+        ValueKind::Id => {
+            let valid = value.len() <= MAX_ID_LEN
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                return Err(format!("{} may only contain letters, digits, '-', '_' and '.'", env_var));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rejects invalid provider values in the top-level and per-profile keys
+/// before settings are saved.
+pub fn validate(settings: &Settings) -> Result<(), String> {
+    let maps = std::iter::once(&settings.api_keys).chain(settings.profiles.iter().map(|p| &p.api_keys));
+    for keys in maps {
+        for (key, value) in keys {
+            if !value.is_empty() {
+                check_value(key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= MASK_PREFIX + MASK_SUFFIX {
+        return "•".repeat(chars.len().min(8));
+    }
+    let prefix: String = chars[..MASK_PREFIX].iter().collect();
+    let suffix: String = chars[chars.len() - MASK_SUFFIX..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+/// Every provider with whether `settings` (already resolved for a
+/// profile) sets its key.
+pub fn status(settings: &Settings) -> Vec<ProviderStatus> {
+    PROVIDERS
+        .iter()
+        .map(|provider| {
+            let key = provider
+                .key_var
+                .and_then(|var| settings.api_keys.get(var))
+                .map(|k| k.trim())
+                .filter(|k| !k.is_empty());
+            ProviderStatus {
+                provider: *provider,
+                key_set: key.is_some(),
+                masked_key: key.map(mask),
+                key_format_ok: match (key, provider.key_prefix) {
+                    (Some(key), Some(prefix)) => key.starts_with(prefix),
+                    _ => true,
+                },
+                checkable: provider.probe.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// Outcome of `validate_api_key`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum KeyCheck {
+    Ok,
+    /// The provider rejected the key.
+    Unauthorized { message: String },
+    /// The provider couldn't be reached or answered with an error unrelated
+    /// to the key.
+    NetworkError { message: String },
+}
+
+pub fn find(id: &str) -> Option<&'static Provider> {
+    PROVIDERS.iter().find(|p| p.id == id)
+}
+
+/// Checks `key` against `provider` with its probe request, sent to the base
+/// URL `settings` configures if any.
+pub async fn check_key(provider: &Provider, key: &str, settings: &Settings) -> Result<KeyCheck, String> {
+    let probe = provider
+        .probe
+        .ok_or_else(|| format!("{} keys can't be checked from the app", provider.name))?;
+    let base = probe
+        .base_var
+        .and_then(|var| settings.api_keys.get(var))
+        .map(|b| b.trim())
+        .filter(|b| !b.is_empty())
+        .unwrap_or(probe.default_base);
+    let url = format!("{}{}", base.trim_end_matches('/'), probe.path);
+
+    // reqwest is built without a bundled crypto provider; the updater
+    // installs the same one.
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .user_agent(concat!("clawrunner/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(&url);
+    request = match probe.auth {
+        Auth::Bearer => request.bearer_auth(key),
+        Auth::Header(name) => request.header(name, key),
+    };
+    for (name, value) in probe.headers {
+        request = request.header(*name, *value);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(KeyCheck::NetworkError { message: e.to_string() }),
+    };
+    let status = response.status();
+    Ok(match status.as_u16() {
+        200..=299 => KeyCheck::Ok,
+        // Rate limiting only happens to authenticated requests.
+        429 => KeyCheck::Ok,
+        // Some providers (Gemini) answer a malformed key with 400.
+        400 | 401 | 403 => KeyCheck::Unauthorized {
+            message: format!("{} rejected the key ({})", provider.name, status),
+        },
+        _ => KeyCheck::NetworkError {
+            message: format!("{} answered {}", url, status),
+        },
+    })
+}
@@ -0,0 +1,190 @@
+//! Loopback reverse proxy in front of the gateway's HTTP API. The webview
+//! calls the proxy with a per-launch token; the proxy swaps it for the
+//! gateway's own credentials, which never reach the frontend, and answers
+//! CORS for the app's origin.
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::health::GatewayMonitor;
+use crate::http;
+use crate::AppState;
+
+/// Header the frontend sends the proxy token in.
+const TOKEN_HEADER: &str = "x-clawrunner-token";
+/// Fixed so the CSP's `connect-src` can name it (tauri.conf.json) instead
+/// of opening every loopback port to the webview. Keep the two in sync.
+pub const PROXY_PORT: u16 = 18782;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+/// Bound on a request body passed on to the gateway.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// Origins the webview loads the app from (production and dev server).
+const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:1420",
+];
+/// Request headers not passed on to the gateway: hop-by-hop headers, and
+/// ones the proxy sets itself.
+const DROPPED_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "authorization",
+    "cookie",
+    "origin",
+    "content-length",
+    TOKEN_HEADER,
+];
+
+/// Where the frontend reaches the proxy; see `gateway_proxy`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyInfo {
+    pub base_url: String,
+    /// Send as the `X-ClawRunner-Token` header.
+    pub token: String,
+}
+
+pub struct GatewayProxy {
+    /// Why the proxy isn't available, if binding its port failed.
+    info: Result<ProxyInfo, String>,
+}
+
+impl GatewayProxy {
+    /// Binds `PROXY_PORT` on loopback and serves requests on background
+    /// threads. If the port is taken, the app still starts and `info`
+    /// reports the error.
+    pub fn start(app: &AppHandle) -> Self {
+        let info = Self::listen(app);
+        if let Err(e) = &info {
+            eprintln!("[proxy] {}", e);
+        }
+        Self { info }
+    }
+
+    fn listen(app: &AppHandle) -> Result<ProxyInfo, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, PROXY_PORT))
+            .map_err(|e| format!("Cannot bind gateway proxy on port {}: {}", PROXY_PORT, e))?;
+        let token = http::random_token();
+
+        let app = app.clone();
+        let expected = token.clone();
+        let limit = http::ConnectionLimit::default();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let Some(slot) = limit.acquire() else {
+                    eprintln!("[proxy] Too many connections; closing one");
+                    continue;
+                };
+                let app = app.clone();
+                let expected = expected.clone();
+                thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(e) = serve(&app, stream, &expected) {
+                        eprintln!("[proxy] {}", e);
+                    }
+                });
+            }
+        });
+        Ok(ProxyInfo {
+            base_url: format!("http://127.0.0.1:{}", PROXY_PORT),
+            token,
+        })
+    }
+
+    pub fn info(&self) -> Result<ProxyInfo, String> {
+        self.info.clone()
+    }
+}
+
+fn cors_headers(origin: Option<&str>) -> String {
+    match origin.filter(|o| APP_ORIGINS.contains(o)) {
+        Some(origin) => format!(
+            "Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n\
+             Access-Control-Allow-Headers: Content-Type, X-ClawRunner-Token\r\n\
+             Access-Control-Allow-Methods: GET, POST, PUT, PATCH, DELETE, OPTIONS\r\n",
+            origin
+        ),
+        None => String::new(),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, cors: &str, body: &str) -> Result<(), String> {
+    http::respond(stream, status, cors, "text/plain; charset=utf-8", body.as_bytes())
+}
+
+/// Handles one request per connection.
+fn serve(app: &AppHandle, mut stream: TcpStream, expected: &str) -> Result<(), String> {
+    stream.set_read_timeout(Some(IO_TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(IO_TIMEOUT)).map_err(|e| e.to_string())?;
+    let mut request = match http::read_request(&stream) {
+        Ok(request) => request,
+        Err(e) => return respond(&mut stream, "400 Bad Request", "", &e),
+    };
+    let cors = cors_headers(request.header("origin"));
+    if request.method == "OPTIONS" {
+        return respond(&mut stream, "204 No Content", &cors, "");
+    }
+    if !http::tokens_match(request.header(TOKEN_HEADER), expected) {
+        return respond(&mut stream, "401 Unauthorized", &cors, "Missing or invalid proxy token");
+    }
+    if let Err(e) = request.read_body(MAX_BODY_BYTES) {
+        return respond(&mut stream, "413 Payload Too Large", &cors, &e);
+    }
+    if !request.target.starts_with('/') {
+        return respond(&mut stream, "400 Bad Request", &cors, "Only origin-form targets are proxied");
+    }
+    let Some(port) = app.state::<GatewayMonitor>().healthy_port() else {
+        return respond(&mut stream, "502 Bad Gateway", &cors, "No gateway is running");
+    };
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut upstream = match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+        Ok(upstream) => upstream,
+        Err(e) => return respond(&mut stream, "502 Bad Gateway", &cors, &e.to_string()),
+    };
+    upstream.set_read_timeout(Some(IO_TIMEOUT)).map_err(|e| e.to_string())?;
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        request.method,
+        request.target,
+        port,
+        request.body.len()
+    );
+    for (name, value) in &request.headers {
+        if !DROPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    if let Some(token) = gateway_token(app) {
+        head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    head.push_str("\r\n");
+    upstream.write_all(head.as_bytes()).map_err(|e| e.to_string())?;
+    upstream.write_all(&request.body).map_err(|e| e.to_string())?;
+
+    // Pass the response through, adding CORS headers after the status line.
+    let mut reader = BufReader::new(upstream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).map_err(|e| e.to_string())?;
+    stream.write_all(status_line.as_bytes()).map_err(|e| e.to_string())?;
+    stream.write_all(cors.as_bytes()).map_err(|e| e.to_string())?;
+    std::io::copy(&mut reader, &mut stream).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn gateway_token(app: &AppHandle) -> Option<String> {
+    let settings = app.state::<AppState>().settings.lock().ok()?.for_profile(None).ok()?;
+    config::gateway_token(&settings)
+}
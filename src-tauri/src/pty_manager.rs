@@ -1,32 +1,464 @@
 use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
 use std::thread;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::{self, JoinHandle};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::ansi::{AnsiStripper, EventScanner, ModeTracker, TermEvent};
+use crate::autorespond::{self, AutoAction, AutoResponder, AutoResponseRule, Fired, SharedRules};
+use crate::capture::RawCapture;
+use crate::links;
+use crate::notify;
+use crate::prompts::{PromptChange, PromptDetector};
+use crate::recording::Recording;
+use crate::scrollback::{Scrollback, DEFAULT_SCROLLBACK_BYTES};
+use crate::transcript::{self, TranscriptLog, TranscriptPolicy};
+use crate::urls::{DetectedUrl, UrlDetector};
+use tokio::sync::{mpsc, watch};
 
 static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Whether the main window's title follows a session's title.
+static SYNC_WINDOW_TITLE: AtomicBool = AtomicBool::new(false);
+/// Session whose title the window shows (the frontend's visible tab); 0 for none.
+static TITLE_SOURCE: AtomicU64 = AtomicU64::new(0);
+const APP_TITLE: &str = "ClawRunner";
+
+/// Default time a child gets to exit after SIGTERM before it is SIGKILLed.
+pub const DEFAULT_KILL_GRACE: Duration = Duration::from_secs(3);
+/// Upper bound for the configurable grace period.
+pub const MAX_KILL_GRACE: Duration = Duration::from_secs(30);
+/// Current grace period in milliseconds; shared by `kill()` and the watchdog.
+static KILL_GRACE_MS: AtomicU64 = AtomicU64::new(DEFAULT_KILL_GRACE.as_millis() as u64);
+/// Quiet time after which a session is reported idle (`pty:idle`).
+pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(60);
+static IDLE_THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_IDLE_THRESHOLD.as_millis() as u64);
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Default PTY read buffer size (8 KB).
+pub const DEFAULT_READ_BUFFER: usize = 8 * 1024;
+/// Bounds for the configurable read buffer size.
+pub const MIN_READ_BUFFER: usize = 1024;
+pub const MAX_READ_BUFFER: usize = 1024 * 1024;
+/// Default cap on undecodable bytes buffered between reads (64 KB). If
+/// exceeded, they are flushed with lossy conversion.
+pub const DEFAULT_LEFTOVER_CAP: usize = 64 * 1024;
+/// Bounds for the configurable leftover cap.
+pub const MIN_LEFTOVER_CAP: usize = 4 * 1024;
+pub const MAX_LEFTOVER_CAP: usize = 4 * 1024 * 1024;
+
+/// Output reads are batched for up to this long before being emitted.
+const COALESCE_INTERVAL: Duration = Duration::from_millis(6);
+/// A batch is emitted early once it reaches this size.
+const COALESCE_MAX_BYTES: usize = 64 * 1024;
+/// Reads buffered between a session's pump and its output task.
+const PUMP_QUEUE_DEPTH: usize = 64;
+
+/// Resizes are applied once no newer one has arrived for this long, so a
+/// window drag sends the child one SIGWINCH instead of hundreds.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+/// Minimum spacing of native notifications from one session's bells.
+const ALERT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Writes larger than this are streamed to the PTY in chunks.
+const WRITE_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Bracketed paste markers.
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Reads pause once this many `pty:data` events are unacknowledged.
+const MAX_UNACKED_EVENTS: u64 = 256;
+/// How often a throttled reader re-checks its window while waiting.
+const FLOW_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum output held for a session nobody is showing yet (1 MB).
+/// Output past the cap is dropped.
+const MAX_HELD_OUTPUT: usize = 1024 * 1024;
+/// How long `enqueue` waits for the prompt before each input.
+const PROMPT_WAIT: Duration = Duration::from_secs(60);
+
+/// How long the output task polls for the child's exit code after EOF.
+const EXIT_CODE_POLL_ATTEMPTS: u32 = 10;
+const EXIT_CODE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the watchdog checks readers for stalls.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+/// A reader is considered stalled if the PTY has had readable data for this
+/// long without the reader making progress.
+const READER_STALL_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Why a session ended, reported in `pty:status` so the frontend can pick
+/// the right UX (silent close, error dialog, restart prompt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// Killed explicitly via `pty_kill`.
+    UserKill,
+    /// Killed because the main window was closed.
+    WindowClose,
+    /// Killed by the supervisor to restart the session.
+    SupervisorRestart,
+    /// Killed by a scheduled task to restart the session.
+    ScheduledRestart,
+    /// The child exited (or closed the PTY) on its own.
+    ChildExit,
+    /// Killed by a watchdog after the session became unresponsive.
+    Watchdog,
+    /// Killed by the idle policy after no input or output for too long.
+    IdleTimeout,
+}
+
+/// What the idle policy does to a session that has gone quiet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Kill the session.
+    #[default]
+    Terminate,
+    /// Suspend the child (SIGSTOP) and keep the session and its terminal
+    /// contents; the next write resumes it. Unix-only; terminates elsewhere.
+    Hibernate,
+}
+
+/// Idle auto-termination policy, derived from settings.
+#[derive(Debug, Clone, Copy)]
+pub struct IdlePolicy {
+    pub timeout: Duration,
+    pub action: IdleAction,
+}
+
+/// Per-session output mode for screen-reader-friendly views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessibilityMode {
+    /// Raw `pty:data` only.
+    #[default]
+    Off,
+    /// Raw `pty:data` plus ANSI-stripped `pty:text` lines.
+    Both,
+    /// `pty:text` lines only.
+    TextOnly,
+}
+
+/// Per-session options chosen at spawn time.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionOptions {
+    pub accessibility: AccessibilityMode,
+    /// Set by the backend for supervised gateways, which are expected to sit
+    /// idle for long periods and must never be reaped by the idle policy.
+    #[serde(skip)]
+    pub idle_exempt: bool,
+    /// Id to spawn under, reserved with `next_session_id` so the backend
+    /// can register it (e.g. with the supervisor) before the child can exit.
+    #[serde(skip)]
+    pub session_id: Option<u64>,
+    /// Buffer output instead of emitting it until `release_output` is
+    /// called. Frontends set this so they can subscribe to the session's
+    /// `pty:data:{id}` channel before any output flows; the backend sets it
+    /// for sessions started before any terminal is showing them.
+    pub hold_output: bool,
+    /// Arguments the session was spawned with, reported by `list()`.
+    #[serde(skip)]
+    pub args: Vec<String>,
+    /// Initial size of the text area in pixels, reported to the child via
+    /// TIOCGWINSZ for image protocols (sixel, kitty). 0 when unknown.
+    pub pixel_width: u16,
+    pub pixel_height: u16,
+    /// TERM for this session instead of the `term` setting.
+    pub term: Option<String>,
+    /// Working directory instead of the home directory. Canonicalized by
+    /// the backend before spawning.
+    pub cwd: Option<PathBuf>,
+    /// API keys and provider settings for this session on top of the
+    /// profile's, e.g. a different `OPENAI_API_KEY`.
+    pub env: HashMap<String, String>,
+}
 
-/// Maximum leftover buffer size (64 KB). If exceeded, flush with lossy conversion.
-const MAX_LEFTOVER_SIZE: usize = 65536;
+/// Signals `pty_signal` can deliver. On Unix they go to the child's
+/// process group; Windows has no signals, so they are emulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PtySignal {
+    #[serde(rename = "SIGINT")]
+    Int,
+    #[serde(rename = "SIGTERM")]
+    Term,
+    #[serde(rename = "SIGHUP")]
+    Hup,
+    #[serde(rename = "SIGWINCH")]
+    Winch,
+    #[serde(rename = "SIGKILL")]
+    Kill,
+}
+
+/// Longest display name accepted for a session label.
+const MAX_LABEL_NAME: usize = 128;
+/// Largest serialized metadata accepted for a session label (16 KB).
+const MAX_LABEL_METADATA: usize = 16 * 1024;
+
+/// Display information the frontend attaches to a session. The backend
+/// stores it but never interprets it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionLabel {
+    pub name: Option<String>,
+    /// CSS color for the session's tab.
+    pub color: Option<String>,
+    /// Arbitrary JSON owned by the frontend.
+    pub metadata: serde_json::Value,
+}
+
+/// Metadata about a live session, returned by `pty_list`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub session_id: u64,
+    pub args: Vec<String>,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: u64,
+    pub cols: u16,
+    pub rows: u16,
+    pub pid: Option<u32>,
+    pub paused: bool,
+    pub label: SessionLabel,
+    /// Milliseconds since the Unix epoch of the last output, if any.
+    pub last_output_at: Option<u64>,
+    /// Milliseconds since the Unix epoch of the last input, if any.
+    pub last_input_at: Option<u64>,
+    /// No input or output for the idle threshold.
+    pub idle: bool,
+}
+
+/// Outcome of one session's write in `pty_broadcast`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastResult {
+    pub session_id: u64,
+    /// `None` if the write succeeded.
+    pub error: Option<String>,
+}
+
+/// A session's rendered screen, as returned by `pty_get_screen`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenSnapshot {
+    pub rows: u16,
+    pub cols: u16,
+    /// Visible text, one entry per screen row, trailing blanks trimmed.
+    pub lines: Vec<String>,
+    /// Escape sequences that redraw the screen (contents and attributes)
+    /// when written to a terminal of the same size.
+    pub formatted: String,
+    pub cursor_row: u16,
+    pub cursor_col: u16,
+    pub cursor_hidden: bool,
+    pub alternate_screen: bool,
+}
+
+type SharedChild = Arc<Mutex<Box<dyn portable_pty::Child + Send + Sync>>>;
+type SharedMaster = Arc<Mutex<Box<dyn MasterPty + Send>>>;
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Called once from the output task after a session's final `pty:status`.
+pub type ExitCallback = Box<dyn FnOnce(u64, CloseReason, Option<u32>) + Send + 'static>;
+
+/// Reader liveness and input/output activity, shared between a session's
+/// output task, `write()` and the watchdog.
+struct SessionActivity {
+    started: Instant,
+    /// Bumped whenever the reader is replaced; superseded readers exit quietly.
+    generation: AtomicU64,
+    /// Milliseconds since `started` at which the reader last returned from read().
+    last_output_ms: AtomicU64,
+    /// Milliseconds since `started` of the last write to the session.
+    last_input_ms: AtomicU64,
+    /// Set by the watchdog once the session has been quiet for the idle
+    /// threshold; cleared by the next input or output.
+    idle: AtomicBool,
+}
+
+impl SessionActivity {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            generation: AtomicU64::new(0),
+            last_output_ms: AtomicU64::new(0),
+            last_input_ms: AtomicU64::new(0),
+            idle: AtomicBool::new(false),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn touch_output(&self) {
+        self.last_output_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    fn touch_input(&self) {
+        self.last_input_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    fn output_idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_output_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last)
+    }
+
+    /// Time since the last input or output, whichever is more recent.
+    fn idle_for(&self) -> Duration {
+        let last = self
+            .last_output_ms
+            .load(Ordering::Relaxed)
+            .max(self.last_input_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(Duration::from_millis(last))
+    }
+}
+
+/// Ack-based backpressure between a session's reader and the
+/// frontend. Each `pty:data` event carries a sequence number; the frontend
+/// acknowledges progress with `pty_ack`. Throttling only starts once the
+/// frontend has acked at least once, so clients that never ack are unaffected.
+struct FlowControl {
+    /// Sequence number of the last `pty:data` event emitted.
+    sent: AtomicU64,
+    /// Highest sequence number acknowledged.
+    acked: AtomicU64,
+    enabled: AtomicBool,
+    /// Set on kill so a throttled reader can exit.
+    closed: AtomicBool,
+    lock: Mutex<()>,
+    cond: Condvar,
+}
+
+impl FlowControl {
+    fn new() -> Self {
+        Self {
+            sent: AtomicU64::new(0),
+            acked: AtomicU64::new(0),
+            enabled: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            cond: Condvar::new(),
+        }
+    }
+
+    fn next_seq(&self) -> u64 {
+        self.sent.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn ack(&self, seq: u64) {
+        self.acked.fetch_max(seq, Ordering::Relaxed);
+        self.enabled.store(true, Ordering::Relaxed);
+        self.cond.notify_all();
+    }
+
+    /// Starts counting afresh for a new frontend, which numbers messages
+    /// from 1 and hasn't acked anything yet.
+    fn reset(&self) {
+        self.sent.store(0, Ordering::Relaxed);
+        self.acked.store(0, Ordering::Relaxed);
+        self.enabled.store(false, Ordering::Relaxed);
+        self.cond.notify_all();
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.cond.notify_all();
+    }
+
+    fn is_throttled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+            && !self.closed.load(Ordering::Relaxed)
+            && self.sent.load(Ordering::Relaxed).saturating_sub(self.acked.load(Ordering::Relaxed))
+                >= MAX_UNACKED_EVENTS
+    }
+
+    /// Blocks while too many events are unacknowledged.
+    fn wait_for_window(&self) {
+        let Ok(mut guard) = self.lock.lock() else { return };
+        while self.is_throttled() {
+            guard = match self.cond.wait_timeout(guard, FLOW_WAIT_INTERVAL) {
+                Ok((g, _)) => g,
+                Err(_) => return,
+            };
+        }
+    }
+}
+
+/// Everything a reader task needs. Cloned when the watchdog replaces a
+/// stalled reader, so the new reader continues the same output stream.
+#[derive(Clone)]
+struct ReaderContext {
+    session_id: u64,
+    sink: Arc<Mutex<OutputSink>>,
+    child: SharedChild,
+    close_reason: Arc<Mutex<Option<CloseReason>>>,
+    on_exit: Arc<Mutex<Option<ExitCallback>>>,
+    activity: Arc<SessionActivity>,
+    /// Debug tee of the raw byte stream, before any UTF-8 handling.
+    capture: Arc<Mutex<Option<RawCapture>>>,
+    flow: Arc<FlowControl>,
+    read_buffer: usize,
+    leftover_cap: usize,
+}
+
+/// What the prompt detector last saw, for `enqueue` to wait on.
+#[derive(Debug, Clone, Copy, Default)]
+struct PromptState {
+    /// Bumped each time a prompt is shown.
+    generation: u64,
+    /// Whether the latest prompt is still waiting for an answer.
+    showing: bool,
+}
 
 struct PtyInstance {
-    writer: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
-    master: Option<Arc<Mutex<Box<dyn MasterPty + Send>>>>,
-    child: Box<dyn portable_pty::Child + Send + Sync>,
-    reader_thread: Option<thread::JoinHandle<()>>,
+    writer: Option<SharedWriter>,
+    master: Option<SharedMaster>,
+    child: SharedChild,
+    /// Set by `kill()` before teardown; read by the output task when it
+    /// emits the final status. `None` means the child exited on its own.
+    close_reason: Arc<Mutex<Option<CloseReason>>>,
+    accessibility: Arc<Mutex<AccessibilityMode>>,
+    reader: ReaderContext,
+    reader_task: Option<JoinHandle<()>>,
+    idle_exempt: bool,
+    /// True while the idle policy has the child suspended.
+    hibernated: bool,
+    /// True while the user has the child stopped via `pause()`. Unlike
+    /// hibernation, writes don't resume it.
+    paused: bool,
+    args: Vec<String>,
+    label: SessionLabel,
+    /// Bumped by every `resize()`; a pending resize only applies if it is
+    /// still the latest when its debounce delay ends.
+    resize_generation: Arc<AtomicU64>,
+    /// Held by `enqueue` while it feeds inputs, so queues run one at a time.
+    queue: Arc<tokio::sync::Mutex<()>>,
+    /// Process group of the child (its pid), for tearing down descendants.
+    #[cfg(unix)]
+    pgid: Option<u32>,
+    /// Job containing the child and its descendants; dropping it kills them.
+    #[cfg(windows)]
+    job: Option<crate::job::JobObject>,
+    created_at: u64,
 }
 
 /// Safety net: kills child process on drop if not explicitly cleaned up.
 /// The explicit kill() already does kill+wait+join; Drop is for unclean exits only.
-/// reader_thread is not joined here to avoid blocking in Drop; it will exit
+/// reader_task is not awaited here to avoid blocking in Drop; it will exit
 /// once the master PTY fd is closed (which happens when `master`/`writer` are dropped).
 impl Drop for PtyInstance {
     fn drop(&mut self) {
         // Safe to call multiple times; portable-pty handles double-kill gracefully.
-        let _ = self.child.kill();
-        let _ = self.child.wait();
+        cleanup_shared_child(&self.child);
     }
 }
 
@@ -36,14 +468,248 @@ fn cleanup_child(child: &mut Box<dyn portable_pty::Child + Send + Sync>) {
     let _ = child.wait();
 }
 
+/// Same as `cleanup_child`, for a child shared with the output task.
+fn cleanup_shared_child(child: &SharedChild) {
+    if let Ok(mut c) = child.lock() {
+        cleanup_child(&mut c);
+    }
+}
+
+/// Shows `title` (or just the app name) as the main window's title, if
+/// window title syncing is enabled.
+fn set_window_title(app: &AppHandle, title: Option<&str>) {
+    if !SYNC_WINDOW_TITLE.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else { return };
+    let text = match title {
+        Some(title) => format!("{} — {}", title, APP_TITLE),
+        None => APP_TITLE.to_string(),
+    };
+    let _ = window.set_title(&text);
+}
+
+/// A session's command line as shown to the user, e.g. "openclaw gateway".
+fn command_title(args: &[String]) -> String {
+    format!("openclaw {}", args.join(" ")).trim_end().to_string()
+}
+
+/// Allocates a session ID. IDs start at 1; 0 is reserved as the "kill all"
+/// sentinel. Replays draw from the same counter so their events never
+/// collide with a live session's.
+pub(crate) fn next_session_id() -> u64 {
+    loop {
+        let id = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+/// Emits a terminal `pty:status` event for a session.
+pub(crate) fn emit_closed(
+    app_handle: &AppHandle,
+    session_id: u64,
+    reason: CloseReason,
+    exit_code: Option<u32>,
+    error_msg: Option<String>,
+) {
+    let status_str = if error_msg.is_some() { "error" } else { "stopped" };
+    let mut status = serde_json::json!({
+        "sessionId": session_id,
+        "status": status_str,
+        "reason": reason,
+    });
+    if let Some(code) = exit_code {
+        status["exitCode"] = serde_json::Value::from(code);
+    }
+    if let Some(err) = error_msg {
+        status["errorMessage"] = serde_json::Value::String(err);
+    }
+    let _ = app_handle.emit("pty:status", status);
+}
+
+/// Emits `pty:paused` when the user pauses or resumes a session.
+fn emit_paused(ctx: &ReaderContext, paused: bool) {
+    if let Ok(sink) = ctx.sink.lock() {
+        let _ = sink.app_handle.emit("pty:paused", serde_json::json!({
+            "sessionId": ctx.session_id,
+            "paused": paused,
+        }));
+    }
+}
+
+/// Clears a session's idle flag, emitting `pty:active` if it was set.
+fn mark_active(ctx: &ReaderContext) {
+    if !ctx.activity.idle.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(sink) = ctx.sink.lock() {
+        let _ = sink.app_handle.emit("pty:active", serde_json::json!({
+            "sessionId": ctx.session_id,
+        }));
+    }
+}
+
+/// Flags sessions that have been quiet for the idle threshold and emits
+/// `pty:idle` for each newly idle one.
+fn update_idle_flags(sessions: &SessionMap) {
+    let threshold = Duration::from_millis(IDLE_THRESHOLD_MS.load(Ordering::Relaxed));
+    for (session_id, inst) in snapshot(sessions) {
+        let Some(ctx) = inst.lock().ok().map(|inst| inst.reader.clone()) else { continue };
+        let idle_for = ctx.activity.idle_for();
+        if idle_for < threshold || ctx.activity.idle.swap(true, Ordering::Relaxed) {
+            continue;
+        }
+        let Ok(sink) = ctx.sink.lock() else { continue };
+        let _ = sink.app_handle.emit("pty:idle", serde_json::json!({
+            "sessionId": session_id,
+            "idleForMs": idle_for.as_millis() as u64,
+        }));
+    }
+}
+
+/// Converts a `SessionActivity` timestamp to milliseconds since the Unix
+/// epoch; `None` if nothing has happened yet.
+fn activity_time(created_at: u64, since_start_ms: &AtomicU64) -> Option<u64> {
+    let ms = since_start_ms.load(Ordering::Relaxed);
+    (ms > 0).then(|| created_at + ms)
+}
+
+/// Emits `pty:hibernated` when the idle policy suspends or resumes a session.
+fn emit_hibernated(ctx: &ReaderContext, hibernated: bool) {
+    if let Ok(sink) = ctx.sink.lock() {
+        let _ = sink.app_handle.emit("pty:hibernated", serde_json::json!({
+            "sessionId": ctx.session_id,
+            "hibernated": hibernated,
+        }));
+    }
+}
+
+/// Each session has its own lock, so a slow operation on one (e.g. a kill
+/// waiting out its grace period) never blocks the others. The map lock is
+/// only held to look sessions up, insert or remove them.
+type SharedInstance = Arc<Mutex<PtyInstance>>;
+type SessionMap = Arc<Mutex<HashMap<u64, SharedInstance>>>;
+type SharedIdlePolicy = Arc<Mutex<Option<IdlePolicy>>>;
+
 pub struct PtyManager {
-    sessions: Arc<Mutex<HashMap<u64, PtyInstance>>>,
+    sessions: SessionMap,
+    idle_policy: SharedIdlePolicy,
+    /// Scrollback size for newly spawned sessions, in bytes.
+    scrollback_limit: AtomicUsize,
+    /// Reader tunables for newly spawned sessions, in bytes.
+    read_buffer: AtomicUsize,
+    leftover_cap: AtomicUsize,
+    /// Auto-response rules, shared with every session's output sink.
+    auto_rules: SharedRules,
+    /// Transcript logging for newly spawned sessions; `None` disables it.
+    transcript_policy: Mutex<Option<TranscriptPolicy>>,
 }
 
 impl PtyManager {
     pub fn new() -> Self {
+        let sessions: SessionMap = Arc::new(Mutex::new(HashMap::new()));
+        let idle_policy: SharedIdlePolicy = Arc::new(Mutex::new(None));
+        start_watchdog(Arc::clone(&sessions), Arc::clone(&idle_policy));
         Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            sessions,
+            idle_policy,
+            scrollback_limit: AtomicUsize::new(DEFAULT_SCROLLBACK_BYTES),
+            read_buffer: AtomicUsize::new(DEFAULT_READ_BUFFER),
+            leftover_cap: AtomicUsize::new(DEFAULT_LEFTOVER_CAP),
+            transcript_policy: Mutex::new(None),
+            auto_rules: Arc::new(Mutex::new(Arc::new(Vec::new()))),
+        }
+    }
+
+    /// Sets the scrollback size used by sessions spawned from now on.
+    pub fn set_scrollback_limit(&self, bytes: usize) {
+        self.scrollback_limit.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Sets the read buffer size and leftover cap used by sessions spawned
+    /// from now on.
+    pub fn set_reader_limits(&self, read_buffer: usize, leftover_cap: usize) {
+        self.read_buffer.store(read_buffer, Ordering::Relaxed);
+        self.leftover_cap.store(leftover_cap, Ordering::Relaxed);
+    }
+
+    /// Replaces the auto-response rules; applies to running sessions too.
+    pub fn set_auto_responses(&self, rules: &[AutoResponseRule]) -> Result<(), String> {
+        let compiled = autorespond::compile(rules)?;
+        *self.auto_rules.lock().map_err(|e| e.to_string())? = Arc::new(compiled);
+        Ok(())
+    }
+
+    /// Sets transcript logging for sessions spawned from now on.
+    pub fn set_transcript_policy(&self, policy: Option<TranscriptPolicy>) {
+        if let Ok(mut p) = self.transcript_policy.lock() {
+            *p = policy;
+        }
+    }
+
+    /// Enables or disables mirroring the title source's title on the
+    /// window. Takes effect with the next `refresh_window_title`.
+    pub fn set_window_title_sync(&self, enabled: bool) {
+        SYNC_WINDOW_TITLE.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Makes `session_id` (0 for none) the session whose title the window
+    /// shows.
+    pub fn set_title_source(&self, app: &AppHandle, session_id: u64) -> Result<(), String> {
+        if session_id != 0 {
+            self.with_session(session_id, |_| ())?;
+        }
+        TITLE_SOURCE.store(session_id, Ordering::Relaxed);
+        self.refresh_window_title(app);
+        Ok(())
+    }
+
+    /// Sets the window title from the title source's current title, or back
+    /// to the app name if syncing is off.
+    pub fn refresh_window_title(&self, app: &AppHandle) {
+        if !SYNC_WINDOW_TITLE.load(Ordering::Relaxed) {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_title(APP_TITLE);
+            }
+            return;
+        }
+        let source = TITLE_SOURCE.load(Ordering::Relaxed);
+        let title = self
+            .with_session(source, |inst| Arc::clone(&inst.reader.sink))
+            .ok()
+            .and_then(|sink| sink.lock().ok().and_then(|s| s.title.clone()));
+        set_window_title(app, title.as_deref());
+    }
+
+    /// Sets how long a session must be quiet before it is reported idle.
+    /// Checked by the watchdog, so transitions lag by up to its interval.
+    pub fn set_idle_threshold(&self, threshold: Duration) {
+        IDLE_THRESHOLD_MS.store(threshold.max(WATCHDOG_INTERVAL).as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Sets how long `kill()` waits after SIGTERM before escalating.
+    pub fn set_kill_grace(&self, grace: Duration) {
+        KILL_GRACE_MS.store(grace.min(MAX_KILL_GRACE).as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Runs `f` on a session, holding only that session's lock.
+    fn with_session<R>(&self, session_id: u64, f: impl FnOnce(&mut PtyInstance) -> R) -> Result<R, String> {
+        let inst = {
+            let lock = self.sessions.lock().map_err(|e| e.to_string())?;
+            lock.get(&session_id)
+                .map(Arc::clone)
+                .ok_or_else(|| format!("No PTY session with id {}", session_id))?
+        };
+        let mut inst = inst.lock().map_err(|e| e.to_string())?;
+        Ok(f(&mut inst))
+    }
+
+    /// Replaces the idle policy. `None` disables idle auto-termination.
+    pub fn set_idle_policy(&self, policy: Option<IdlePolicy>) {
+        if let Ok(mut p) = self.idle_policy.lock() {
+            *p = policy;
         }
     }
 
@@ -53,22 +719,35 @@ impl PtyManager {
         cmd: CommandBuilder,
         cols: u16,
         rows: u16,
+        mut options: SessionOptions,
+        on_exit: ExitCallback,
     ) -> Result<u64, String> {
-        // Session IDs start at 1; 0 is reserved as the "kill all" sentinel.
-        let session_id = loop {
-            let id = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
-            if id != 0 { break id; }
+        let session_id = options.session_id.take().unwrap_or_else(next_session_id);
+
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width: options.pixel_width,
+            pixel_height: options.pixel_height,
         };
+        // A failure goes back to the caller; the frontend never learned this
+        // id, so a status event for it would go nowhere.
+        self.spawn_inner(app, session_id, cmd, size, options, on_exit)
+    }
 
+    fn spawn_inner(
+        &self,
+        app: &AppHandle,
+        session_id: u64,
+        cmd: CommandBuilder,
+        size: PtySize,
+        options: SessionOptions,
+        on_exit: ExitCallback,
+    ) -> Result<u64, String> {
         let pty_system = native_pty_system();
 
         let pair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
+            .openpty(size)
             .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
         let mut child = pair
@@ -79,8 +758,8 @@ impl PtyManager {
         // Explicitly drop slave after spawning to ensure proper EOF on master
         drop(pair.slave);
 
-        let writer = match pair.master.take_writer() {
-            Ok(w) => w,
+        let writer: SharedWriter = match pair.master.take_writer() {
+            Ok(w) => Arc::new(Mutex::new(w)),
             Err(e) => {
                 cleanup_child(&mut child);
                 return Err(format!("Failed to get PTY writer: {}", e));
@@ -95,159 +774,1300 @@ impl PtyManager {
             }
         };
 
-        let app_handle = app.clone();
-        let reader_thread = spawn_reader_thread(reader, app_handle, session_id);
+        let child_pid = child.process_id();
+        #[cfg(windows)]
+        let job = child_pid.and_then(|pid| {
+            crate::job::JobObject::contain(pid)
+                .inspect_err(|e| eprintln!("[pty] Session {} not contained in a job: {}", session_id, e))
+                .ok()
+        });
+        let child: SharedChild = Arc::new(Mutex::new(child));
+        let close_reason = Arc::new(Mutex::new(None));
+        let accessibility = Arc::new(Mutex::new(options.accessibility));
+
+        let flow = Arc::new(FlowControl::new());
+        let sink = OutputSink {
+            app_handle: app.clone(),
+            session_id,
+            accessibility: Arc::clone(&accessibility),
+            stripper: AnsiStripper::new(),
+            modes: ModeTracker::new(),
+            held: options.hold_output.then(String::new),
+            scrollback: Scrollback::new(self.scrollback_limit.load(Ordering::Relaxed)),
+            flow: Arc::clone(&flow),
+            channel: None,
+            files: Arc::new(Mutex::new(SessionFiles {
+                recording: None,
+                transcript: self.transcript_policy.lock().ok().and_then(|p| *p).and_then(|policy| {
+                    TranscriptLog::open(session_id, &options.args, policy)
+                        .map_err(|e| eprintln!("[pty] Transcript disabled for session {}: {}", session_id, e))
+                        .ok()
+                }),
+            })),
+            unwritten: String::new(),
+            events: EventScanner::new(),
+            urls: UrlDetector::new(),
+            prompts: PromptDetector::new(),
+            prompt_state: watch::Sender::new(PromptState::default()),
+            responder: AutoResponder::new(Arc::clone(&self.auto_rules), &options.args),
+            writer: Arc::downgrade(&writer),
+            screen: vt100::Parser::new(size.rows, size.cols, 0),
+            title: None,
+            alert_title: command_title(&options.args),
+            last_alert: None,
+        };
+        let reader_ctx = ReaderContext {
+            session_id,
+            sink: Arc::new(Mutex::new(sink)),
+            child: Arc::clone(&child),
+            close_reason: Arc::clone(&close_reason),
+            on_exit: Arc::new(Mutex::new(Some(on_exit))),
+            activity: Arc::new(SessionActivity::new()),
+            capture: Arc::new(Mutex::new(None)),
+            flow,
+            read_buffer: self.read_buffer.load(Ordering::Relaxed),
+            leftover_cap: self.leftover_cap.load(Ordering::Relaxed),
+        };
+        let reader_task = spawn_reader_task(reader, reader_ctx.clone(), 0);
 
         let instance = PtyInstance {
-            writer: Some(Arc::new(Mutex::new(writer))),
+            writer: Some(writer),
             master: Some(Arc::new(Mutex::new(pair.master))),
             child,
-            reader_thread: Some(reader_thread),
+            close_reason,
+            accessibility,
+            reader: reader_ctx,
+            reader_task: Some(reader_task),
+            idle_exempt: options.idle_exempt,
+            hibernated: false,
+            paused: false,
+            args: options.args,
+            label: SessionLabel::default(),
+            resize_generation: Arc::new(AtomicU64::new(0)),
+            queue: Arc::new(tokio::sync::Mutex::new(())),
+            #[cfg(unix)]
+            pgid: child_pid,
+            #[cfg(windows)]
+            job,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
         };
 
         let mut lock = self.sessions.lock().map_err(|e| e.to_string())?;
-        lock.insert(session_id, instance);
+        lock.insert(session_id, Arc::new(Mutex::new(instance)));
 
         Ok(session_id)
     }
 
-    pub fn write(&self, session_id: u64, data: &str) -> Result<(), String> {
-        // Get a clone of the writer Arc, then release the global lock before I/O.
-        // This prevents blocking other sessions if write_all blocks.
-        let writer = {
-            let lock = self.sessions.lock().map_err(|e| e.to_string())?;
-            lock.get(&session_id)
-                .and_then(|inst| inst.writer.as_ref().map(Arc::clone))
-                .ok_or_else(|| format!("No PTY session with id {}", session_id))?
+    pub async fn write(&self, session_id: u64, data: &str) -> Result<(), String> {
+        self.write_bytes(session_id, data.as_bytes()).await
+    }
+
+    /// Writes `data` to each session in `session_ids`, or to every session
+    /// if `None`. A failed write is reported in its result rather than
+    /// stopping the others.
+    pub async fn broadcast(&self, session_ids: Option<&[u64]>, data: &str) -> Result<Vec<BroadcastResult>, String> {
+        let mut ids = match session_ids {
+            Some(ids) => ids.to_vec(),
+            None => self.sessions.lock().map_err(|e| e.to_string())?.keys().copied().collect(),
+        };
+        ids.sort_unstable();
+        ids.dedup();
+        let mut results = Vec::with_capacity(ids.len());
+        for session_id in ids {
+            let error = self.write(session_id, data).await.err();
+            results.push(BroadcastResult { session_id, error });
+        }
+        Ok(results)
+    }
+
+    /// Writes `inputs` one at a time, each once the session shows an
+    /// interactive prompt (see `prompts.rs`) that appeared after the previous
+    /// input; the first goes out right away if a prompt is already showing.
+    /// Inputs are written as-is, so answers need their own "\r". Queues for
+    /// the same session run one after another.
+    pub async fn enqueue(&self, session_id: u64, inputs: &[String]) -> Result<(), String> {
+        let (queue, mut prompts) = self.with_session(session_id, |inst| {
+            let sink = inst.reader.sink.lock().map_err(|e| e.to_string())?;
+            Ok::<_, String>((Arc::clone(&inst.queue), sink.prompt_state.subscribe()))
+        })??;
+        let _turn = queue.lock().await;
+        let mut answered = {
+            let state = *prompts.borrow();
+            // A prompt already showing still needs its answer.
+            state.generation - u64::from(state.showing)
         };
-        let mut w = writer.lock().map_err(|e| e.to_string())?;
-        w.write_all(data.as_bytes())
-            .map_err(|e| format!("Write error: {}", e))?;
-        w.flush()
-            .map_err(|e| format!("Flush error: {}", e))?;
+        for (index, input) in inputs.iter().enumerate() {
+            let ready = prompts.wait_for(|state| state.showing && state.generation > answered);
+            let generation = match tokio::time::timeout(PROMPT_WAIT, ready).await {
+                Ok(Ok(state)) => state.generation,
+                Ok(Err(_)) => return Err(format!("Session {} closed", session_id)),
+                Err(_) => {
+                    return Err(format!(
+                        "No prompt appeared within {}s for input {} of {}",
+                        PROMPT_WAIT.as_secs(),
+                        index + 1,
+                        inputs.len()
+                    ))
+                }
+            };
+            self.write(session_id, input).await?;
+            answered = generation;
+        }
         Ok(())
     }
 
-    pub fn resize(&self, session_id: u64, cols: u16, rows: u16) -> Result<(), String> {
-        // Get a clone of the master Arc, then release the global lock before I/O.
-        let master = {
+    /// Writes raw bytes, for input that isn't valid UTF-8.
+    pub async fn write_bytes(&self, session_id: u64, data: &[u8]) -> Result<(), String> {
+        // Get a clone of the writer Arc, then release the session lock before
+        // I/O, so a blocked write_all doesn't stall resizes or status queries.
+        let writer = self
+            .with_session(session_id, |inst| {
+                inst.reader.activity.touch_input();
+                mark_active(&inst.reader);
+                if inst.hibernated {
+                    resume_child(&inst.child);
+                    inst.hibernated = false;
+                    emit_hibernated(&inst.reader, false);
+                }
+                inst.writer.as_ref().map(Arc::clone)
+            })?
+            .ok_or_else(|| format!("No PTY session with id {}", session_id))?;
+        let data = data.to_vec();
+        async_runtime::spawn_blocking(move || {
+            // Large payloads go out in chunks, each flushed before the next,
+            // so the PTY's own buffer limits how far ahead of the child we
+            // get. The writer stays locked throughout to keep the payload
+            // contiguous.
+            let mut w = writer.lock().map_err(|e| e.to_string())?;
+            for chunk in data.chunks(WRITE_CHUNK_SIZE) {
+                w.write_all(chunk)
+                    .map_err(|e| format!("Write error: {}", e))?;
+                w.flush()
+                    .map_err(|e| format!("Flush error: {}", e))?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    /// Pastes text the way a terminal would: newlines become CR, and if the
+    /// application enabled bracketed paste the payload is wrapped in paste
+    /// markers (with any end marker inside it removed, so pasted text can't
+    /// break out early).
+    pub async fn paste(&self, session_id: u64, text: &str) -> Result<(), String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let bracketed = sink.lock().map_err(|e| e.to_string())?.modes.bracketed_paste();
+        let text = text.replace("\r\n", "\r").replace('\n', "\r");
+        let payload = if bracketed {
+            format!("{}{}{}", PASTE_START, text.replace(PASTE_END, ""), PASTE_END)
+        } else {
+            text
+        };
+        self.write(session_id, &payload).await
+    }
+
+    /// Schedules a resize. Bursts are coalesced into the last size, applied
+    /// `RESIZE_DEBOUNCE` after the final call; errors are only logged.
+    pub fn resize(
+        &self,
+        session_id: u64,
+        cols: u16,
+        rows: u16,
+        pixel_width: u16,
+        pixel_height: u16,
+    ) -> Result<(), String> {
+        let (master, latest, sink) = self.with_session(session_id, |inst| {
+            (
+                inst.master.as_ref().map(Arc::clone),
+                Arc::clone(&inst.resize_generation),
+                Arc::clone(&inst.reader.sink),
+            )
+        })?;
+        let master = master.ok_or_else(|| format!("No PTY session with id {}", session_id))?;
+        let generation = latest.fetch_add(1, Ordering::Relaxed) + 1;
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width,
+            pixel_height,
+        };
+        async_runtime::spawn(async move {
+            tokio::time::sleep(RESIZE_DEBOUNCE).await;
+            if latest.load(Ordering::Relaxed) != generation {
+                return;
+            }
+            let result = match master.lock() {
+                Ok(m) => m.resize(size).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match result {
+                Ok(()) => {
+                    let files = sink.lock().ok().map(|mut sink| {
+                        sink.screen.screen_mut().set_size(rows, cols);
+                        Arc::clone(&sink.files)
+                    });
+                    if let Some(files) = files {
+                        if let Ok(mut files) = files.lock() {
+                            if let Some(recording) = files.recording.as_mut() {
+                                recording.resize(cols, rows);
+                            }
+                        }
+                    }
+                }
+                Err(e) => eprintln!("[pty] Resize error for session {}: {}", session_id, e),
+            }
+        });
+        Ok(())
+    }
+
+    /// Lists all live sessions, oldest first.
+    pub fn list(&self) -> Result<Vec<SessionInfo>, String> {
+        let instances: Vec<(u64, SharedInstance)> = {
             let lock = self.sessions.lock().map_err(|e| e.to_string())?;
-            lock.get(&session_id)
-                .and_then(|inst| inst.master.as_ref().map(Arc::clone))
-                .ok_or_else(|| format!("No PTY session with id {}", session_id))?
+            lock.iter().map(|(id, inst)| (*id, Arc::clone(inst))).collect()
         };
-        let m = master.lock().map_err(|e| e.to_string())?;
-        m.resize(PtySize {
+        let mut sessions: Vec<SessionInfo> = instances
+            .iter()
+            .filter_map(|(id, inst)| {
+                let inst = inst.lock().ok()?;
+                let size = inst
+                    .master
+                    .as_ref()
+                    .and_then(|m| m.lock().ok())
+                    .and_then(|m| m.get_size().ok());
+                let activity = &inst.reader.activity;
+                Some(SessionInfo {
+                    session_id: *id,
+                    args: inst.args.clone(),
+                    created_at: inst.created_at,
+                    cols: size.map(|s| s.cols).unwrap_or(0),
+                    rows: size.map(|s| s.rows).unwrap_or(0),
+                    pid: inst.child.lock().ok().and_then(|c| c.process_id()),
+                    paused: inst.paused,
+                    label: inst.label.clone(),
+                    last_output_at: activity_time(inst.created_at, &activity.last_output_ms),
+                    last_input_at: activity_time(inst.created_at, &activity.last_input_ms),
+                    idle: activity.idle.load(Ordering::Relaxed),
+                })
+            })
+            .collect();
+        sessions.sort_by_key(|s| s.session_id);
+        Ok(sessions)
+    }
+
+    /// Replaces a session's label.
+    pub fn set_label(&self, session_id: u64, label: SessionLabel) -> Result<(), String> {
+        if label.name.as_ref().is_some_and(|n| n.chars().count() > MAX_LABEL_NAME) {
+            return Err(format!("Label name exceeds {} characters", MAX_LABEL_NAME));
+        }
+        if label.metadata.to_string().len() > MAX_LABEL_METADATA {
+            return Err(format!("Label metadata exceeds {} bytes", MAX_LABEL_METADATA));
+        }
+        let (sink, alert_title) = self.with_session(session_id, |inst| {
+            let title = match label.name.as_deref().filter(|n| !n.is_empty()) {
+                Some(name) => name.to_string(),
+                None => command_title(&inst.args),
+            };
+            inst.label = label;
+            (Arc::clone(&inst.reader.sink), title)
+        })?;
+        sink.lock().map_err(|e| e.to_string())?.alert_title = alert_title;
+        Ok(())
+    }
+
+    /// Emits any output held back since spawn and switches the session to
+    /// normal streaming.
+    pub fn release_output(&self, session_id: u64) -> Result<(), String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let mut sink = sink.lock().map_err(|e| e.to_string())?;
+        if let Some(held) = sink.held.take() {
+            if !held.is_empty() {
+                sink.emit(&held);
+            }
+        }
+        flush_files(sink);
+        Ok(())
+    }
+
+    /// Routes the session's output to a binary channel instead of
+    /// `pty:data` events. Held output is still delivered by `release_output`.
+    pub fn set_output_channel(
+        &self,
+        session_id: u64,
+        channel: Channel<InvokeResponseBody>,
+    ) -> Result<(), String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        sink.lock().map_err(|e| e.to_string())?.channel = Some(channel);
+        Ok(())
+    }
+
+    /// Hands a running session to a new frontend (e.g. after a webview
+    /// reload): replays its scrollback over `channel`, then streams live
+    /// output there instead of to the previous, now dead, channel.
+    pub fn reattach(
+        &self,
+        session_id: u64,
+        channel: Channel<InvokeResponseBody>,
+    ) -> Result<(), String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let mut sink = sink.lock().map_err(|e| e.to_string())?;
+        sink.flow.reset();
+        let replay = sink.scrollback.contents().as_bytes().to_vec();
+        if !replay.is_empty() {
+            sink.flow.next_seq();
+            let _ = channel.send(InvokeResponseBody::Raw(replay));
+        }
+        sink.channel = Some(channel);
+        if let Some(held) = sink.held.take() {
+            if !held.is_empty() {
+                sink.emit(&held);
+            }
+        }
+        flush_files(sink);
+        Ok(())
+    }
+
+    /// Returns what the session's screen currently shows.
+    pub fn screen(&self, session_id: u64) -> Result<ScreenSnapshot, String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let sink = sink.lock().map_err(|e| e.to_string())?;
+        let screen = sink.screen.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+        Ok(ScreenSnapshot {
             rows,
             cols,
-            pixel_width: 0,
-            pixel_height: 0,
+            lines: screen.rows(0, cols).collect(),
+            formatted: String::from_utf8_lossy(&screen.contents_formatted()).into_owned(),
+            cursor_row,
+            cursor_col,
+            cursor_hidden: screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+        })
+    }
+
+    /// Returns the URLs the session printed recently, oldest first.
+    pub fn links(&self, session_id: u64) -> Result<Vec<DetectedUrl>, String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let sink = sink.lock().map_err(|e| e.to_string())?;
+        Ok(sink.urls.recent())
+    }
+
+    /// Returns the session's recent output, for repopulating a terminal.
+    pub fn scrollback(&self, session_id: u64) -> Result<String, String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let sink = sink.lock().map_err(|e| e.to_string())?;
+        Ok(sink.scrollback.contents().to_string())
+    }
+
+    /// Returns the session's output as ANSI-stripped lines. Sessions with a
+    /// transcript log are read from it, so output that scrolled out of the
+    /// scrollback is included; otherwise the scrollback is used.
+    pub fn output_lines(&self, session_id: u64) -> Result<Vec<String>, String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let files = Arc::clone(&sink.lock().map_err(|e| e.to_string())?.files);
+        let transcript = files
+            .lock()
+            .map_err(|e| e.to_string())?
+            .transcript
+            .as_ref()
+            .map(|t| (t.paths().to_vec(), t.partial().map(str::to_string)));
+        let Some((paths, partial)) = transcript else {
+            let scrollback = sink.lock().map_err(|e| e.to_string())?.scrollback.contents().to_string();
+            let mut stripper = AnsiStripper::new();
+            let mut lines = stripper.feed(&scrollback);
+            lines.extend(stripper.partial().map(str::to_string));
+            return Ok(lines);
+        };
+        let mut lines = transcript::read_lines(&paths);
+        lines.extend(partial);
+        Ok(lines)
+    }
+
+    /// True if the session exists and its child has not exited.
+    pub fn is_running(&self, session_id: u64) -> bool {
+        self.with_session(session_id, |inst| {
+            inst.child.lock().ok().map(|mut c| matches!(c.try_wait(), Ok(None)))
+        })
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+    }
+
+    /// A session's terminal size as (cols, rows).
+    pub fn size(&self, session_id: u64) -> Result<(u16, u16), String> {
+        let master = self
+            .with_session(session_id, |inst| inst.master.as_ref().map(Arc::clone))?
+            .ok_or_else(|| format!("Session {} has no terminal", session_id))?;
+        let size = master
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_size()
+            .map_err(|e| format!("Cannot read terminal size: {}", e))?;
+        Ok((size.cols, size.rows))
+    }
+
+    /// Returns the OS process id of a session's child, if it is still known.
+    pub fn child_pid(&self, session_id: u64) -> Result<Option<u32>, String> {
+        let child = self.with_session(session_id, |inst| Arc::clone(&inst.child))?;
+        let child = child.lock().map_err(|e| e.to_string())?;
+        Ok(child.process_id())
+    }
+
+    /// The child pid of every session whose child is still running.
+    pub fn child_pids(&self) -> Vec<(u64, u32)> {
+        let mut pids: Vec<(u64, u32)> = snapshot(&self.sessions)
+            .into_iter()
+            .filter_map(|(id, inst)| {
+                let child = Arc::clone(&inst.lock().ok()?.child);
+                let pid = child.lock().ok()?.process_id()?;
+                Some((id, pid))
+            })
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    /// Records that the frontend has processed `pty:data` events up to `seq`,
+    /// letting a throttled reader continue.
+    pub fn ack(&self, session_id: u64, seq: u64) -> Result<(), String> {
+        self.with_session(session_id, |inst| inst.reader.flow.ack(seq))
+    }
+
+    /// Stops the session's process group (SIGSTOP) until `resume()`.
+    pub fn pause(&self, session_id: u64) -> Result<(), String> {
+        self.with_session(session_id, |inst| {
+            if inst.paused {
+                return Ok(());
+            }
+            if !suspend_child(&inst.child) {
+                return Err("Pausing sessions is not supported on this platform".to_string());
+            }
+            inst.paused = true;
+            emit_paused(&inst.reader, true);
+            Ok(())
+        })?
+    }
+
+    /// Continues a session stopped by `pause()` or by idle hibernation.
+    pub fn resume(&self, session_id: u64) -> Result<(), String> {
+        self.with_session(session_id, |inst| {
+            if !inst.paused && !inst.hibernated {
+                return;
+            }
+            resume_child(&inst.child);
+            inst.reader.activity.touch_input();
+            mark_active(&inst.reader);
+            if inst.hibernated {
+                inst.hibernated = false;
+                emit_hibernated(&inst.reader, false);
+            }
+            if inst.paused {
+                inst.paused = false;
+                emit_paused(&inst.reader, false);
+            }
         })
-        .map_err(|e| format!("Resize error: {}", e))?;
+    }
+
+    /// Delivers a signal to the session's child process group.
+    #[cfg(unix)]
+    pub async fn signal(&self, session_id: u64, signal: PtySignal) -> Result<(), String> {
+        let child = self.with_session(session_id, |inst| Arc::clone(&inst.child))?;
+        let signo = match signal {
+            PtySignal::Int => libc::SIGINT,
+            PtySignal::Term => libc::SIGTERM,
+            PtySignal::Hup => libc::SIGHUP,
+            PtySignal::Winch => libc::SIGWINCH,
+            PtySignal::Kill => libc::SIGKILL,
+        };
+        if signal_child(&child, signo) {
+            Ok(())
+        } else {
+            Err(format!("Cannot signal session {}", session_id))
+        }
+    }
+
+    /// Windows emulation: SIGINT becomes Ctrl+C on the console input (ConPTY
+    /// turns it into CTRL_C_EVENT), SIGWINCH is a no-op since ConPTY reports
+    /// resizes itself, and everything else terminates the child.
+    #[cfg(not(unix))]
+    pub async fn signal(&self, session_id: u64, signal: PtySignal) -> Result<(), String> {
+        match signal {
+            PtySignal::Int => self.write(session_id, "\x03").await,
+            PtySignal::Winch => Ok(()),
+            PtySignal::Term | PtySignal::Hup | PtySignal::Kill => {
+                let child = self.with_session(session_id, |inst| Arc::clone(&inst.child))?;
+                let mut child = child.lock().map_err(|e| e.to_string())?;
+                child.kill().map_err(|e| format!("Cannot terminate session {}: {}", session_id, e))
+            }
+        }
+    }
+
+    /// Starts or stops teeing a session's raw output to a capture file.
+    /// Returns the capture file path (when starting or stopping an active capture).
+    pub fn set_raw_capture(&self, session_id: u64, enabled: bool) -> Result<Option<String>, String> {
+        let capture = self.with_session(session_id, |inst| Arc::clone(&inst.reader.capture))?;
+        let mut capture = capture.lock().map_err(|e| e.to_string())?;
+        if enabled && capture.is_none() {
+            *capture = Some(RawCapture::start(session_id)?);
+        }
+        let path = capture.as_ref().map(|c| c.path().to_string_lossy().into_owned());
+        if !enabled {
+            *capture = None;
+        }
+        Ok(path)
+    }
+
+    /// Starts recording a session's output as asciicast v2. Returns the
+    /// recording's path; starting an active recording returns its path.
+    pub fn record_start(&self, session_id: u64) -> Result<String, String> {
+        let (sink, master, args) = self.with_session(session_id, |inst| {
+            (Arc::clone(&inst.reader.sink), inst.master.as_ref().map(Arc::clone), inst.args.clone())
+        })?;
+        let size = master
+            .as_ref()
+            .and_then(|m| m.lock().ok())
+            .and_then(|m| m.get_size().ok());
+        let files = Arc::clone(&sink.lock().map_err(|e| e.to_string())?.files);
+        let mut files = files.lock().map_err(|e| e.to_string())?;
+        if files.recording.is_none() {
+            let (cols, rows) = size.map(|s| (s.cols, s.rows)).unwrap_or((80, 24));
+            files.recording = Some(Recording::start(session_id, cols, rows, &command_title(&args))?);
+        }
+        let path = files.recording.as_ref().map(|r| r.path().to_string_lossy().into_owned());
+        Ok(path.unwrap_or_default())
+    }
+
+    /// Stops a session's recording. Returns the finished file's path, if
+    /// one was recording.
+    pub fn record_stop(&self, session_id: u64) -> Result<Option<String>, String> {
+        let sink = self.with_session(session_id, |inst| Arc::clone(&inst.reader.sink))?;
+        let files = Arc::clone(&sink.lock().map_err(|e| e.to_string())?.files);
+        let recording = files.lock().map_err(|e| e.to_string())?.recording.take();
+        Ok(recording.map(|r| r.path().to_string_lossy().into_owned()))
+    }
+
+    /// Switches a session between raw output and the plain-text stream.
+    pub fn set_accessibility(&self, session_id: u64, mode: AccessibilityMode) -> Result<(), String> {
+        let accessibility = self.with_session(session_id, |inst| Arc::clone(&inst.accessibility))?;
+        *accessibility.lock().map_err(|e| e.to_string())? = mode;
         Ok(())
     }
 
     /// Kills a PTY session by session_id.
     /// Pass session_id=0 to kill all sessions (used for window close).
-    pub fn kill(&self, session_id: u64) -> Result<(), String> {
-        // Remove from map while holding lock, then clean up outside lock
-        // to avoid blocking other operations during process wait/thread join.
-        let removed: Vec<PtyInstance> = {
-            let mut lock = self.sessions.lock().map_err(|e| e.to_string())?;
-            if session_id == 0 {
-                let ids: Vec<u64> = lock.keys().copied().collect();
-                ids.into_iter().filter_map(|id| lock.remove(&id)).collect()
-            } else {
-                lock.remove(&session_id).into_iter().collect()
+    /// `reason` is reported to the frontend in the final `pty:status` event.
+    /// Teardown runs on the blocking pool; sync callers use `block_on`.
+    pub async fn kill(&self, session_id: u64, reason: CloseReason) -> Result<(), String> {
+        let sessions = Arc::clone(&self.sessions);
+        async_runtime::spawn_blocking(move || kill_sessions(&sessions, session_id, reason))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
+
+/// Removes sessions from the map and tears them down. Shared by `kill()`
+/// and the watchdog. session_id=0 kills all sessions.
+///
+/// Children are asked to exit first (SIGTERM to the process group, or
+/// closing their input on Windows) so OpenClaw can flush state files. Once
+/// the child exits or the grace period ends, the rest of its process group
+/// is SIGKILLed so no grandchildren are orphaned. All sessions being killed
+/// share one deadline, so closing the window waits at most one period.
+fn kill_sessions(sessions: &SessionMap, session_id: u64, reason: CloseReason) -> Result<(), String> {
+    // Remove from map while holding lock, then clean up outside lock
+    // to avoid blocking other operations during process wait/thread join.
+    let removed: Vec<SharedInstance> = {
+        let mut lock = sessions.lock().map_err(|e| e.to_string())?;
+        if session_id == 0 {
+            lock.drain().map(|(_, inst)| inst).collect()
+        } else {
+            lock.remove(&session_id).into_iter().collect()
+        }
+    };
+    // Only the sessions being killed are locked for the teardown.
+    let mut removed: Vec<_> = removed.iter().filter_map(|inst| inst.lock().ok()).collect();
+    for inst in removed.iter_mut() {
+        if let Ok(mut r) = inst.close_reason.lock() {
+            *r = Some(reason);
+        }
+        request_exit(inst);
+    }
+
+    let grace = Duration::from_millis(KILL_GRACE_MS.load(Ordering::Relaxed));
+    let deadline = Instant::now() + grace;
+    for inst in &removed {
+        while !child_exited(&inst.child) && Instant::now() < deadline {
+            thread::sleep(KILL_POLL_INTERVAL);
+        }
+    }
+
+    for inst in removed.iter_mut() {
+        kill_process_group(inst);
+        cleanup_shared_child(&inst.child);
+        // Drop master and writer BEFORE awaiting the reader task.
+        // This closes the PTY fd, which unblocks the pump's read()
+        // even if grandchild processes still hold the slave fd open.
+        drop(inst.writer.take());
+        drop(inst.master.take());
+        inst.reader.flow.close();
+        if let Some(handle) = inst.reader_task.take() {
+            let _ = async_runtime::block_on(handle);
+        }
+    }
+    Ok(())
+}
+
+/// True once the child has exited (or can no longer be queried).
+fn child_exited(child: &SharedChild) -> bool {
+    child
+        .lock()
+        .map(|mut c| !matches!(c.try_wait(), Ok(None)))
+        .unwrap_or(true)
+}
+
+/// Asks a child to exit: SIGTERM, resuming it first if hibernated or
+/// paused so it can act on the signal.
+#[cfg(unix)]
+fn request_exit(inst: &mut PtyInstance) {
+    signal_child(&inst.child, libc::SIGTERM);
+    if inst.hibernated || inst.paused {
+        resume_child(&inst.child);
+    }
+}
+
+/// ConPTY has no SIGTERM; closing the input pipe lets the child see EOF.
+#[cfg(not(unix))]
+fn request_exit(inst: &mut PtyInstance) {
+    drop(inst.writer.take());
+}
+
+/// A session's recording and transcript files. Locked separately from
+/// `OutputSink` so their disk writes don't stall resizes, pastes and
+/// everything else that needs the sink.
+struct SessionFiles {
+    /// Asciicast recording in progress, if any.
+    recording: Option<Recording>,
+    /// Automatic transcript log, if enabled in settings.
+    transcript: Option<TranscriptLog>,
+}
+
+impl SessionFiles {
+    fn write(&mut self, data: &str) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.output(data);
+        }
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.write(data);
+        }
+    }
+}
+
+/// Writes the output the sink accepted since the last call to the
+/// session's files. The file lock is taken before the sink lock is
+/// released, so writes keep output order, but the disk I/O runs with only
+/// the file lock held.
+fn flush_files(mut sink: MutexGuard<'_, OutputSink>) {
+    if sink.unwritten.is_empty() {
+        return;
+    }
+    let data = std::mem::take(&mut sink.unwritten);
+    let files = Arc::clone(&sink.files);
+    let Ok(mut files) = files.lock() else { return };
+    drop(sink);
+    files.write(&data);
+}
+
+/// Fans PTY output out to the frontend: raw `pty:data:{id}` and/or
+/// line-oriented, ANSI-stripped `pty:text:{id}`, depending on the session's mode.
+struct OutputSink {
+    app_handle: AppHandle,
+    session_id: u64,
+    accessibility: Arc<Mutex<AccessibilityMode>>,
+    stripper: AnsiStripper,
+    /// Modes the application has switched on, e.g. bracketed paste.
+    modes: ModeTracker,
+    /// Output held back while `Some` (see `SessionOptions::hold_output`).
+    held: Option<String>,
+    scrollback: Scrollback,
+    flow: Arc<FlowControl>,
+    /// Binary output path; replaces `pty:data` events once attached.
+    channel: Option<Channel<InvokeResponseBody>>,
+    /// Recording and transcript; written by `flush_files`.
+    files: Arc<Mutex<SessionFiles>>,
+    /// Output accepted since the last `flush_files`.
+    unwritten: String,
+    /// Finds bells and notification requests in the output.
+    events: EventScanner,
+    /// URLs printed recently, for the links panel.
+    urls: UrlDetector,
+    prompts: PromptDetector,
+    prompt_state: watch::Sender<PromptState>,
+    responder: AutoResponder,
+    /// For auto-responses. Weak, so the writer still closes on kill.
+    writer: Weak<Mutex<Box<dyn Write + Send>>>,
+    /// Server-side terminal emulator tracking what the screen shows.
+    screen: vt100::Parser,
+    /// Last title the application set (OSC 0/2), if any.
+    title: Option<String>,
+    /// Heading for this session's notifications: its label, else its command.
+    alert_title: String,
+    last_alert: Option<Instant>,
+}
+
+impl OutputSink {
+    fn emit(&mut self, data: &str) {
+        self.emit_output(data.as_bytes(), data);
+    }
+
+    /// Emits `pty:bell` for a bell or notification request and raises a
+    /// native notification, at most once per `ALERT_INTERVAL`.
+    fn on_term_event(&mut self, event: TermEvent) {
+        match event {
+            TermEvent::Bell => self.alert(None),
+            TermEvent::Notify(message) => self.alert(Some(message)),
+            TermEvent::Title(title) => self.set_title(title),
+            TermEvent::Hyperlink { url, text } => {
+                let _ = self.app_handle.emit("pty:link", serde_json::json!({
+                    "sessionId": self.session_id,
+                    "allowed": links::is_allowed(&url),
+                    "url": url,
+                    "text": text,
+                }));
             }
-        };
-        for mut inst in removed {
-            cleanup_child(&mut inst.child);
-            // Drop master and writer BEFORE joining reader thread.
-            // This closes the PTY fd, which unblocks the reader thread's read()
-            // even if grandchild processes still hold the slave fd open.
-            drop(inst.writer.take());
-            drop(inst.master.take());
-            if let Some(handle) = inst.reader_thread.take() {
-                let _ = handle.join();
+        }
+    }
+
+    /// Emits `pty:title` and, if this session is the title source, updates
+    /// the window title.
+    fn set_title(&mut self, title: String) {
+        let _ = self.app_handle.emit("pty:title", serde_json::json!({
+            "sessionId": self.session_id,
+            "title": title,
+        }));
+        let title = (!title.is_empty()).then_some(title);
+        if TITLE_SOURCE.load(Ordering::Relaxed) == self.session_id {
+            set_window_title(&self.app_handle, title.as_deref());
+        }
+        self.title = title;
+    }
+
+    /// Carries out an auto-response rule that matched this session's output.
+    fn auto_respond(&mut self, fired: Fired) {
+        let _ = self.app_handle.emit("pty:auto-response", serde_json::json!({
+            "sessionId": self.session_id,
+            "pattern": fired.pattern,
+            "action": fired.action,
+        }));
+        match fired.action {
+            AutoAction::Notify => notify::session_alert(&self.app_handle, &self.alert_title, &fired.text),
+            AutoAction::Write => {
+                let Some(writer) = self.writer.upgrade() else { return };
+                let session_id = self.session_id;
+                // Off the output task: the child may not be reading yet.
+                async_runtime::spawn_blocking(move || {
+                    let Ok(mut w) = writer.lock() else { return };
+                    if let Err(e) = w.write_all(fired.text.as_bytes()).and_then(|_| w.flush()) {
+                        eprintln!("[pty] Auto-response write failed for session {}: {}", session_id, e);
+                    }
+                });
             }
         }
-        Ok(())
     }
+
+    fn alert(&mut self, message: Option<String>) {
+        let _ = self.app_handle.emit("pty:bell", serde_json::json!({
+            "sessionId": self.session_id,
+            "message": message,
+        }));
+        if self.last_alert.is_some_and(|t| t.elapsed() < ALERT_INTERVAL) {
+            return;
+        }
+        self.last_alert = Some(Instant::now());
+        let body = message.unwrap_or_else(|| "Waiting for input".to_string());
+        notify::session_alert(&self.app_handle, &self.alert_title, &body);
+    }
+
+    /// Forwards one read: `raw` goes unmodified to the output channel, if
+    /// attached; `data` is its decoded text (possibly lagging `raw` by an
+    /// incomplete UTF-8 sequence) for events, scrollback and the text stream.
+    fn emit_output(&mut self, raw: &[u8], data: &str) {
+        self.modes.feed(data);
+        if let Some(held) = self.held.as_mut() {
+            let room = MAX_HELD_OUTPUT.saturating_sub(held.len());
+            let mut end = data.len().min(room);
+            while !data.is_char_boundary(end) {
+                end -= 1;
+            }
+            held.push_str(&data[..end]);
+            return;
+        }
+        // Held output passes through here again once released.
+        for event in self.events.feed(data) {
+            self.on_term_event(event);
+        }
+        self.screen.process(data.as_bytes());
+        if let Some(change) = self.prompts.feed(data) {
+            let prompt = match change {
+                PromptChange::Shown(prompt) => Some(prompt),
+                PromptChange::Cleared => None,
+            };
+            self.prompt_state.send_modify(|state| {
+                state.generation += u64::from(prompt.is_some());
+                state.showing = prompt.is_some();
+            });
+            let _ = self.app_handle.emit("pty:prompt", serde_json::json!({
+                "sessionId": self.session_id,
+                "prompt": prompt,
+            }));
+        }
+        for fired in self.responder.feed(data) {
+            self.auto_respond(fired);
+        }
+        let urls = self.urls.feed(data);
+        if !urls.is_empty() {
+            let _ = self.app_handle.emit("pty:links", serde_json::json!({
+                "sessionId": self.session_id,
+                "urls": urls,
+            }));
+        }
+        self.unwritten.push_str(data);
+        let mode = self.accessibility.lock().map(|m| *m).unwrap_or_default();
+        if mode != AccessibilityMode::TextOnly {
+            if let Some(channel) = &self.channel {
+                if !raw.is_empty() {
+                    self.flow.next_seq();
+                    let _ = channel.send(InvokeResponseBody::Raw(raw.to_vec()));
+                }
+            }
+        }
+        if data.is_empty() {
+            return;
+        }
+        self.scrollback.push(data);
+        // Output goes to per-session channels so each terminal only
+        // receives (and parses) its own stream.
+        if mode != AccessibilityMode::TextOnly && self.channel.is_none() {
+            let _ = self.app_handle.emit(&format!("pty:data:{}", self.session_id), serde_json::json!({
+                "sessionId": self.session_id,
+                "seq": self.flow.next_seq(),
+                "data": data,
+            }));
+        }
+        if mode != AccessibilityMode::Off {
+            let lines = self.stripper.feed(data);
+            let _ = self.app_handle.emit(&format!("pty:text:{}", self.session_id), serde_json::json!({
+                "sessionId": self.session_id,
+                "lines": lines,
+                "partial": self.stripper.partial(),
+            }));
+        }
+    }
+}
+
+/// One `read()` from the PTY, handed from the pump to the output task.
+enum ReadEvent {
+    Data(Vec<u8>),
+    Eof,
+    Error(String),
 }
 
-/// Spawns a reader thread that forwards PTY output to frontend via Tauri events.
+/// Spawns the async task that forwards PTY output to the frontend, raw over
+/// the session's output channel or decoded via Tauri events. The blocking
+/// reads themselves happen in a pump on the blocking pool (portable-pty
+/// readers have no async interface).
+/// Reads arriving within `COALESCE_INTERVAL` of each other (up to
+/// `COALESCE_MAX_BYTES`) are batched into one emit, so fast-scrolling output
+/// doesn't flood IPC with one message per `read()`.
 /// Handles multi-byte UTF-8 sequences that may be split across reads.
 /// Events are tagged with session_id so the frontend can ignore stale events.
-fn spawn_reader_thread(
-    mut reader: Box<dyn Read + Send>,
-    app_handle: AppHandle,
-    session_id: u64,
-) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        let mut buf = [0u8; 8192];
+/// On EOF, emits a final `pty:status` with the close reason and exit code,
+/// then invokes `on_exit`. A reader whose `generation` has been superseded by
+/// the watchdog forwards what it read and exits without reporting status.
+fn spawn_reader_task(
+    reader: Box<dyn Read + Send>,
+    ctx: ReaderContext,
+    generation: u64,
+) -> JoinHandle<()> {
+    async_runtime::spawn(async move {
+        let mut reads = spawn_pump(reader, ctx.clone());
+        let mut pending = Vec::new();
         let mut leftover = Vec::new();
         let mut error_msg: Option<String> = None;
+        let superseded = || ctx.activity.generation.load(Ordering::Relaxed) != generation;
 
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    leftover.extend_from_slice(&buf[..n]);
-
-                    // Cap leftover to prevent unbounded growth from binary output
-                    if leftover.len() > MAX_LEFTOVER_SIZE {
-                        let data = String::from_utf8_lossy(&leftover).to_string();
-                        let _ = app_handle.emit("pty:data", serde_json::json!({
-                            "sessionId": session_id,
-                            "data": data,
-                        }));
-                        leftover.clear();
-                        continue;
-                    }
-
-                    // Find the last valid UTF-8 boundary
-                    let valid_up_to = match std::str::from_utf8(&leftover) {
-                        Ok(s) => s.len(),
-                        Err(e) => e.valid_up_to(),
-                    };
-
-                    if valid_up_to > 0 {
-                        // unwrap is safe: from_utf8 validated [0..valid_up_to] above
-                        let text = std::str::from_utf8(&leftover[..valid_up_to]).unwrap();
-                        let _ = app_handle.emit("pty:data", serde_json::json!({
-                            "sessionId": session_id,
-                            "data": text,
-                        }));
+        let mut done = false;
+        while !done {
+            let mut next = reads.recv().await.unwrap_or(ReadEvent::Eof);
+            let deadline = tokio::time::Instant::now() + COALESCE_INTERVAL;
+            loop {
+                match next {
+                    ReadEvent::Data(data) => pending.extend_from_slice(&data),
+                    ReadEvent::Eof => done = true,
+                    ReadEvent::Error(e) => {
+                        error_msg = Some(e);
+                        done = true;
                     }
-
-                    // Keep incomplete bytes for next read
-                    leftover = leftover[valid_up_to..].to_vec();
                 }
-                Err(e) => {
-                    error_msg = Some(e.to_string());
+                if done || pending.len() >= COALESCE_MAX_BYTES {
                     break;
                 }
+                match tokio::time::timeout_at(deadline, reads.recv()).await {
+                    Ok(event) => next = event.unwrap_or(ReadEvent::Eof),
+                    Err(_) => break,
+                }
             }
+            if pending.is_empty() {
+                continue;
+            }
+            leftover.extend_from_slice(&pending);
+
+            // Cap leftover to prevent unbounded growth from binary output,
+            // keeping a trailing incomplete character for the next read.
+            let text = if leftover.len() > ctx.leftover_cap {
+                let rest = leftover.split_off(leftover.len() - incomplete_tail_len(&leftover));
+                String::from_utf8_lossy(&std::mem::replace(&mut leftover, rest)).into_owned()
+            } else {
+                // Find the last valid UTF-8 boundary
+                let valid_up_to = match std::str::from_utf8(&leftover) {
+                    Ok(s) => s.len(),
+                    Err(e) => e.valid_up_to(),
+                };
+                // Keep incomplete bytes for next read
+                let rest = leftover.split_off(valid_up_to);
+                // unwrap is safe: from_utf8 validated [0..valid_up_to] above
+                String::from_utf8(std::mem::replace(&mut leftover, rest)).unwrap()
+            };
+            if let Ok(mut sink) = ctx.sink.lock() {
+                sink.emit_output(&pending, &text);
+                flush_files(sink);
+            }
+            pending.clear();
+
+            if superseded() {
+                return;
+            }
+        }
+
+        if superseded() {
+            return;
         }
 
-        // Flush any remaining bytes
+        // Flush any remaining bytes (already sent raw on the channel)
         if !leftover.is_empty() {
-            let data = String::from_utf8_lossy(&leftover).to_string();
-            let _ = app_handle.emit("pty:data", serde_json::json!({
+            if let Ok(mut sink) = ctx.sink.lock() {
+                sink.emit_output(&[], &String::from_utf8_lossy(&leftover));
+                flush_files(sink);
+            }
+        }
+
+        let reason = ctx
+            .close_reason
+            .lock()
+            .ok()
+            .and_then(|r| *r)
+            .unwrap_or(CloseReason::ChildExit);
+        let exit_code = wait_exit_code(&ctx.child).await;
+        if let Ok(sink) = ctx.sink.lock() {
+            emit_closed(&sink.app_handle, ctx.session_id, reason, exit_code, error_msg);
+        }
+        let on_exit = ctx.on_exit.lock().ok().and_then(|mut f| f.take());
+        if let Some(on_exit) = on_exit {
+            // Exit callbacks may run hooks or spawn processes.
+            let session_id = ctx.session_id;
+            let _ = async_runtime::spawn_blocking(move || on_exit(session_id, reason, exit_code)).await;
+        }
+    })
+}
+
+/// Length of a multi-byte UTF-8 sequence at the end of `bytes` that is
+/// still missing continuation bytes.
+fn incomplete_tail_len(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let b = bytes[bytes.len() - back];
+        if b & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match b {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 0,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+/// Blocks on the PTY and hands each read to the output task. Flow control,
+/// activity tracking and raw capture happen here, at read time. Exits after
+/// EOF or once the output task has gone away.
+fn spawn_pump(mut reader: Box<dyn Read + Send>, ctx: ReaderContext) -> mpsc::Receiver<ReadEvent> {
+    let (tx, rx) = mpsc::channel(PUMP_QUEUE_DEPTH);
+    async_runtime::spawn_blocking(move || {
+        let mut buf = vec![0u8; ctx.read_buffer];
+        loop {
+            ctx.flow.wait_for_window();
+            let result = reader.read(&mut buf);
+            ctx.activity.touch_output();
+            if matches!(result, Ok(n) if n > 0) {
+                mark_active(&ctx);
+            }
+            let event = match result {
+                Ok(0) => ReadEvent::Eof,
+                Ok(n) => {
+                    if let Ok(mut capture) = ctx.capture.lock() {
+                        if let Some(capture) = capture.as_mut() {
+                            capture.write(&buf[..n]);
+                        }
+                    }
+                    ReadEvent::Data(buf[..n].to_vec())
+                }
+                Err(e) => ReadEvent::Error(e.to_string()),
+            };
+            let last = !matches!(event, ReadEvent::Data(_));
+            if tx.blocking_send(event).is_err() || last {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Returns true if the PTY master has data waiting to be read.
+#[cfg(unix)]
+fn master_readable(master: &(dyn MasterPty + Send)) -> bool {
+    let Some(fd) = master.as_raw_fd() else { return false };
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: pfd is a valid pollfd for the duration of the call; a zero
+    // timeout makes this a non-blocking readiness check.
+    let rc = unsafe { libc::poll(&mut pfd, 1, 0) };
+    rc > 0 && (pfd.revents & libc::POLLIN) != 0
+}
+
+/// Readiness can't be probed on ConPTY pipes; stall detection is Unix-only.
+#[cfg(not(unix))]
+fn master_readable(_master: &(dyn MasterPty + Send)) -> bool {
+    false
+}
+
+/// A reader is stalled if it hasn't returned from read() for a long time
+/// even though the child is alive and the PTY has readable data.
+fn is_reader_stalled(inst: &mut PtyInstance) -> bool {
+    if inst.reader.activity.output_idle_for() < READER_STALL_THRESHOLD {
+        return false;
+    }
+    // A reader held back by backpressure is waiting on the frontend, not stuck.
+    if inst.reader.flow.is_throttled() {
+        return false;
+    }
+    let child_alive = inst
+        .child
+        .lock()
+        .map(|mut c| matches!(c.try_wait(), Ok(None)))
+        .unwrap_or(false);
+    if !child_alive {
+        return false;
+    }
+    match inst.master.as_ref().and_then(|m| m.lock().ok()) {
+        Some(m) => master_readable(m.as_ref()),
+        None => false,
+    }
+}
+
+/// Suspends a child process. Returns false if that isn't possible here.
+#[cfg(unix)]
+fn suspend_child(child: &SharedChild) -> bool {
+    signal_child(child, libc::SIGSTOP)
+}
+
+#[cfg(not(unix))]
+fn suspend_child(_child: &SharedChild) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn resume_child(child: &SharedChild) {
+    signal_child(child, libc::SIGCONT);
+}
+
+#[cfg(not(unix))]
+fn resume_child(_child: &SharedChild) {}
+
+/// Signals the child's whole process group. portable-pty makes every child
+/// a session (and so process group) leader via setsid(), so the group id is
+/// the child's pid and includes everything it started that hasn't moved
+/// itself into a new session.
+#[cfg(unix)]
+fn signal_child(child: &SharedChild, signal: libc::c_int) -> bool {
+    let Some(pid) = child.lock().ok().and_then(|c| c.process_id()) else {
+        return false;
+    };
+    signal_group(pid, signal)
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: libc::c_int) -> bool {
+    // SAFETY: killpg() has no memory-safety preconditions.
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
+}
+
+/// Force-kills whatever is left of a session's process group, including
+/// grandchildren that outlived the child itself.
+#[cfg(unix)]
+fn kill_process_group(inst: &mut PtyInstance) {
+    if let Some(pgid) = inst.pgid {
+        signal_group(pgid, libc::SIGKILL);
+    }
+}
+
+/// Closing the job handle kills every process still in it.
+#[cfg(windows)]
+fn kill_process_group(inst: &mut PtyInstance) {
+    drop(inst.job.take());
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_process_group(_inst: &mut PtyInstance) {}
+
+/// Copies out the current sessions so they can be visited one at a time
+/// without holding the map lock.
+fn snapshot(sessions: &SessionMap) -> Vec<(u64, SharedInstance)> {
+    sessions
+        .lock()
+        .map(|lock| lock.iter().map(|(id, inst)| (*id, Arc::clone(inst))).collect())
+        .unwrap_or_default()
+}
+
+/// Applies the idle policy to every non-exempt session that has had no
+/// input or output for longer than the timeout. Hibernated sessions are
+/// left alone until the next write resumes them.
+fn enforce_idle_policy(sessions: &SessionMap, policy: IdlePolicy) {
+    let mut to_kill = Vec::new();
+    {
+        for (id, inst) in snapshot(sessions) {
+            let Ok(mut inst) = inst.lock() else { continue };
+            if inst.idle_exempt || inst.hibernated || inst.paused {
+                continue;
+            }
+            let idle_for = inst.reader.activity.idle_for();
+            if idle_for < policy.timeout {
+                continue;
+            }
+            if policy.action == IdleAction::Hibernate && suspend_child(&inst.child) {
+                eprintln!("[pty] Session {} idle for {:?}; hibernating", id, idle_for);
+                inst.hibernated = true;
+                emit_hibernated(&inst.reader, true);
+            } else {
+                eprintln!("[pty] Session {} idle for {:?}; terminating", id, idle_for);
+                to_kill.push(id);
+            }
+        }
+    }
+    // Each kill waits out the grace period, so run them side by side.
+    std::thread::scope(|scope| {
+        for id in to_kill {
+            scope.spawn(move || kill_sessions(sessions, id, CloseReason::IdleTimeout));
+        }
+    });
+}
+
+/// Periodically applies the idle policy and checks every session's reader.
+/// A stalled reader is replaced with a freshly cloned one; if that fails,
+/// the session is killed so the frontend gets a final status instead of a
+/// permanently frozen terminal.
+fn start_watchdog(sessions: SessionMap, idle_policy: SharedIdlePolicy) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let sessions = Arc::clone(&sessions);
+            let idle_policy = Arc::clone(&idle_policy);
+            // Checks take session locks and kills wait out a grace period.
+            let _ = async_runtime::spawn_blocking(move || watchdog_tick(&sessions, &idle_policy)).await;
+        }
+    });
+}
+
+fn watchdog_tick(sessions: &SessionMap, idle_policy: &SharedIdlePolicy) {
+    update_idle_flags(sessions);
+    let policy = idle_policy.lock().ok().and_then(|p| *p);
+    if let Some(policy) = policy {
+        enforce_idle_policy(sessions, policy);
+    }
+
+    let stalled: Vec<(u64, ReaderContext, Option<SharedMaster>)> = snapshot(sessions)
+        .into_iter()
+        .filter_map(|(id, inst)| {
+            let mut inst = inst.lock().ok()?;
+            is_reader_stalled(&mut inst).then(|| (id, inst.reader.clone(), inst.master.clone()))
+        })
+        .collect();
+
+    for (session_id, ctx, master) in stalled {
+        let stalled_for = ctx.activity.output_idle_for();
+        let pid = ctx.child.lock().ok().and_then(|c| c.process_id());
+        eprintln!(
+            "[pty] Reader for session {} stalled for {:?} with data pending (child pid {:?}); re-cloning reader",
+            session_id, stalled_for, pid
+        );
+
+        let new_reader = master
+            .as_ref()
+            .and_then(|m| m.lock().ok())
+            .and_then(|m| m.try_clone_reader().ok());
+        let recovered = new_reader.is_some();
+
+        if let Ok(sink) = ctx.sink.lock() {
+            let _ = sink.app_handle.emit("pty:reader-stalled", serde_json::json!({
                 "sessionId": session_id,
-                "data": data,
+                "stalledForMs": stalled_for.as_millis() as u64,
+                "recovered": recovered,
             }));
         }
 
-        let status_str = if error_msg.is_some() { "error" } else { "stopped" };
-        let mut status = serde_json::json!({
-            "sessionId": session_id,
-            "status": status_str,
-        });
-        if let Some(err) = error_msg {
-            status["errorMessage"] = serde_json::Value::String(err);
+        match new_reader {
+            Some(reader) => {
+                let generation = ctx.activity.generation.fetch_add(1, Ordering::Relaxed) + 1;
+                ctx.activity.touch_output();
+                let handle = spawn_reader_task(reader, ctx, generation);
+                // The stalled task's handle is dropped (detached); it exits
+                // on its own if its read() ever returns.
+                let inst = sessions.lock().ok().and_then(|lock| lock.get(&session_id).map(Arc::clone));
+                if let Some(inst) = inst {
+                    if let Ok(mut inst) = inst.lock() {
+                        inst.reader_task = Some(handle);
+                    }
+                }
+            }
+            None => {
+                eprintln!("[pty] Could not re-clone reader for session {}; killing it", session_id);
+                let _ = kill_sessions(sessions, session_id, CloseReason::Watchdog);
+            }
         }
-        let _ = app_handle.emit("pty:status", status);
-    })
+    }
+}
+
+/// Polls briefly for the child's exit code. EOF on the master usually means
+/// the child has exited, but the status may not be reapable immediately.
+async fn wait_exit_code(child: &SharedChild) -> Option<u32> {
+    for _ in 0..EXIT_CODE_POLL_ATTEMPTS {
+        {
+            let mut c = child.lock().ok()?;
+            if let Ok(Some(status)) = c.try_wait() {
+                return Some(status.exit_code());
+            }
+        }
+        tokio::time::sleep(EXIT_CODE_POLL_INTERVAL).await;
+    }
+    None
 }
@@ -0,0 +1,232 @@
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::pty_manager::{self, CloseReason};
+use crate::settings;
+
+/// Maximum size of a single recording (64 MB). Events past the cap are dropped.
+const MAX_RECORDING_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Records a session's output with timestamps as an asciicast v2 file under
+/// <data dir>/recordings, so a transcript can be shared and replayed.
+/// Recordings can contain anything the session printed, so files are 0600.
+pub struct Recording {
+    file: File,
+    path: PathBuf,
+    started: Instant,
+    written: u64,
+}
+
+impl Recording {
+    pub fn start(session_id: u64, cols: u16, rows: u16, title: &str) -> Result<Self, String> {
+        let dir = recordings_dir()?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("session-{}-{}.cast", session_id, stamp));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&path)
+            .map_err(|e| format!("Cannot create recording file: {}", e))?;
+        let mut recording = Self {
+            file,
+            path,
+            started: Instant::now(),
+            written: 0,
+        };
+        recording.write_line(&json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": stamp,
+            "title": title,
+            "env": { "TERM": "xterm-256color" },
+        }));
+        Ok(recording)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Appends an output event.
+    pub fn output(&mut self, data: &str) {
+        let event = json!([self.elapsed(), "o", data]);
+        self.write_line(&event);
+    }
+
+    /// Appends a resize event.
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let event = json!([self.elapsed(), "r", format!("{}x{}", cols, rows)]);
+        self.write_line(&event);
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    /// Writes one JSON line, up to the size cap. Write errors are ignored so
+    /// a full disk never disturbs the session itself.
+    fn write_line(&mut self, value: &serde_json::Value) {
+        let mut line = value.to_string();
+        line.push('\n');
+        if self.written + line.len() as u64 > MAX_RECORDING_SIZE {
+            return;
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.written += line.len() as u64;
+        }
+    }
+}
+
+pub fn recordings_dir() -> Result<PathBuf, String> {
+    let dir = settings::ensure_settings_dir()?.join("recordings");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create recordings dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Resolves a path from the webview to a `.cast` file inside the
+/// recordings dir, so the frontend can't make the app read anything else.
+pub fn resolve_recording(path: &str) -> Result<PathBuf, String> {
+    let dir = recordings_dir()?
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve recordings dir: {}", e))?;
+    let resolved = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Cannot resolve {}: {}", path, e))?;
+    if !resolved.starts_with(&dir) || !resolved.is_file() {
+        return Err("Only recordings in the recordings folder can be replayed".to_string());
+    }
+    if resolved.extension().and_then(|e| e.to_str()) != Some("cast") {
+        return Err("Not an asciicast recording".to_string());
+    }
+    Ok(resolved)
+}
+
+/// Slowest and fastest playback speeds accepted by `replay`.
+pub(crate) const MIN_REPLAY_SPEED: f64 = 0.1;
+pub(crate) const MAX_REPLAY_SPEED: f64 = 100.0;
+/// Terminal size assumed when a header doesn't give one.
+const DEFAULT_COLS: u16 = 80;
+const DEFAULT_ROWS: u16 = 24;
+
+/// A parsed asciicast file: its initial terminal size and events.
+pub(crate) struct Cast {
+    pub cols: u16,
+    pub rows: u16,
+    /// Seconds since the start of the recording, and the event.
+    pub events: Vec<(f64, CastEvent)>,
+}
+
+/// One parsed asciicast event.
+pub(crate) enum CastEvent {
+    Output(String),
+    Resize(u16, u16),
+}
+
+/// Plays an asciicast file back over the `pty:data:{id}` events of a fresh
+/// session ID, so the regular terminal view doubles as a player. Resizes are
+/// sent as `pty:replay-resize` and the end of playback as a `pty:status`
+/// stop. `speed` multiplies the recorded pace (1.0 = real time).
+pub fn replay(app: &AppHandle, path: &Path, speed: f64) -> Result<u64, String> {
+    let events = load_cast(path)?.events;
+    let speed = if speed.is_finite() {
+        speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED)
+    } else {
+        1.0
+    };
+    let session_id = pty_manager::next_session_id();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = tokio::time::Instant::now();
+        let mut seq: u64 = 0;
+        for (at, event) in events {
+            let due = started + Duration::from_secs_f64(at / speed);
+            tokio::time::sleep_until(due).await;
+            match event {
+                CastEvent::Output(data) => {
+                    seq += 1;
+                    let _ = app.emit(&format!("pty:data:{}", session_id), json!({
+                        "sessionId": session_id,
+                        "seq": seq,
+                        "data": data,
+                    }));
+                }
+                CastEvent::Resize(cols, rows) => {
+                    let _ = app.emit("pty:replay-resize", json!({
+                        "sessionId": session_id,
+                        "cols": cols,
+                        "rows": rows,
+                    }));
+                }
+            }
+        }
+        pty_manager::emit_closed(&app, session_id, CloseReason::ChildExit, None, None);
+    });
+    Ok(session_id)
+}
+
+/// Reads and validates an asciicast v2 file. Input and marker events are
+/// skipped; anything malformed rejects the whole file.
+pub(crate) fn load_cast(path: &Path) -> Result<Cast, String> {
+    if path.extension().and_then(|e| e.to_str()) != Some("cast") {
+        return Err("Not an asciicast (.cast) file".to_string());
+    }
+    let file = File::open(path).map_err(|e| format!("Cannot open recording: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines
+        .next()
+        .ok_or("Recording is empty")?
+        .map_err(|e| format!("Cannot read recording: {}", e))?;
+    let header: serde_json::Value =
+        serde_json::from_str(&header).map_err(|e| format!("Invalid recording header: {}", e))?;
+    if header.get("version").and_then(|v| v.as_u64()) != Some(2) {
+        return Err("Unsupported asciicast version (expected 2)".to_string());
+    }
+    let dimension = |key: &str, default: u16| {
+        header
+            .get(key)
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u16::try_from(v).ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default)
+    };
+    let cols = dimension("width", DEFAULT_COLS);
+    let rows = dimension("height", DEFAULT_ROWS);
+
+    let mut events = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Cannot read recording: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || format!("Invalid event on line {}", index + 2);
+        let (at, kind, data): (f64, String, String) =
+            serde_json::from_str(&line).map_err(|_| invalid())?;
+        let event = match kind.as_str() {
+            "o" => CastEvent::Output(data),
+            "r" => {
+                let (cols, rows) = data.split_once('x').ok_or_else(invalid)?;
+                let cols = cols.parse().map_err(|_| invalid())?;
+                let rows = rows.parse().map_err(|_| invalid())?;
+                CastEvent::Resize(cols, rows)
+            }
+            _ => continue,
+        };
+        events.push((at.max(0.0), event));
+    }
+    Ok(Cast { cols, rows, events })
+}
@@ -0,0 +1,429 @@
+//! Runs OpenClaw tasks on cron schedules kept in settings (`scheduledTasks`),
+//! e.g. a nightly gateway restart or periodic housekeeping commands.
+//! Commands go through the same argument policy and state lock as
+//! `openclaw_exec`. Every run is kept in `scheduler-history.json` in the
+//! settings dir and announced as `scheduler:run`.
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::exec;
+use crate::policy::ArgPolicy;
+use crate::pty_manager::CloseReason;
+use crate::settings;
+use crate::supervisor::Supervisor;
+use crate::AppState;
+
+const HISTORY_FILE: &str = "scheduler-history.json";
+/// Runs kept in the history, across all tasks.
+const MAX_HISTORY: usize = 200;
+const MAX_TASKS: usize = 32;
+/// Output kept per run; earlier output is dropped.
+const MAX_RUN_OUTPUT: usize = 4096;
+
+/// A task in settings. `schedule` is a five-field cron expression
+/// (minute hour day-of-month month day-of-week) in local time, or one of
+/// `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub schedule: String,
+    #[serde(flatten)]
+    pub action: TaskAction,
+    /// Kept in settings but not run.
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum TaskAction {
+    /// Runs `openclaw <args>` to completion.
+    Exec { args: Vec<String> },
+    /// Restarts the supervised gateways.
+    RestartGateway,
+}
+
+/// One recorded run; the payload of `scheduler:run`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub task_id: String,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    /// The end of stdout and stderr, or what the action did.
+    #[serde(default)]
+    pub output: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A parsed cron expression, each field as a bit set.
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether day-of-month and day-of-week were `*`. If both are
+    /// restricted, a day matching either one matches (as in cron).
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("{:?} is not a five-field cron expression", expr));
+        };
+        let weekdays = field(weekday, 0, 7, "day-of-week")?;
+        Ok(Self {
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")?,
+            days: field(day, 1, 31, "day-of-month")?,
+            months: field(month, 1, 12, "month")?,
+            // 7 is another name for Sunday.
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches(&self, at: &DateTime<Local>) -> bool {
+        let has = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = has(self.days, at.day());
+        let weekday = has(self.weekdays, at.weekday().num_days_from_sunday());
+        let day_matches = if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        };
+        has(self.minutes, at.minute()) && has(self.hours, at.hour()) && has(self.months, at.month()) && day_matches
+    }
+}
+
+/// Parses one cron field: `*`, `n`, `a-b`, any of those with `/step`, or a
+/// comma-separated list of them.
+fn field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid {} field {:?}", name, spec);
+    let number = |s: &str| s.parse::<u32>().ok().filter(|n| (min..=max).contains(n)).ok_or_else(invalid);
+    let mut set = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // `n/step` runs from n to the end of the range.
+                None if part.contains('/') => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn history_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join(HISTORY_FILE))
+}
+
+fn load_history() -> Vec<TaskRun> {
+    history_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Rejects tasks that could never run, before settings are saved.
+pub fn validate(tasks: &[ScheduledTask], policy: &ArgPolicy) -> Result<(), String> {
+    if tasks.len() > MAX_TASKS {
+        return Err(format!("Too many scheduled tasks (max {})", MAX_TASKS));
+    }
+    let mut ids = HashSet::new();
+    for task in tasks {
+        if task.id.trim().is_empty() {
+            return Err("Scheduled task id must not be empty".to_string());
+        }
+        if !ids.insert(task.id.as_str()) {
+            return Err(format!("Duplicate scheduled task id {:?}", task.id));
+        }
+        Cron::parse(&task.schedule).map_err(|e| format!("Task {:?}: {}", task.id, e))?;
+        if let TaskAction::Exec { args } = &task.action {
+            policy.validate(args).map_err(|e| format!("Task {:?}: {}", task.id, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the scheduled tasks once a minute and runs the ones that are due.
+pub struct Scheduler {
+    history: Mutex<Vec<TaskRun>>,
+    /// Ids of tasks with a run in progress; a task never overlaps itself.
+    running: Mutex<HashSet<String>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            history: Mutex::new(load_history()),
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn start(&self, app: &AppHandle) {
+        let app = app.clone();
+        thread::spawn(move || {
+            let mut last_minute = None;
+            loop {
+                // Wake just after each minute starts.
+                let now = Local::now();
+                thread::sleep(Duration::from_millis(u64::from(60 - now.second()) * 1000 + 100));
+                let now = Local::now();
+                let minute = now.timestamp() / 60;
+                if last_minute == Some(minute) {
+                    continue;
+                }
+                last_minute = Some(minute);
+                let tasks = match app.state::<AppState>().settings.lock() {
+                    Ok(settings) => settings.scheduled_tasks.clone(),
+                    Err(_) => continue,
+                };
+                for task in tasks.into_iter().filter(|t| !t.paused) {
+                    match Cron::parse(&task.schedule) {
+                        Ok(cron) if cron.matches(&now) => app.state::<Scheduler>().launch(&app, task),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("[scheduler] Task {:?}: {}", task.id, e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Runs a task on a background thread, unless it is already running.
+    pub fn launch(&self, app: &AppHandle, task: ScheduledTask) {
+        let started = self
+            .running
+            .lock()
+            .map(|mut running| running.insert(task.id.clone()))
+            .unwrap_or(false);
+        if !started {
+            eprintln!("[scheduler] Task {:?} is still running; skipped", task.id);
+            return;
+        }
+        let app = app.clone();
+        thread::spawn(move || {
+            let run = run_task(&app, &task);
+            let scheduler = app.state::<Scheduler>();
+            if let Ok(mut running) = scheduler.running.lock() {
+                running.remove(&task.id);
+            }
+            scheduler.record(&run);
+            let _ = app.emit("scheduler:run", run);
+        });
+    }
+
+    /// Recorded runs, oldest first.
+    pub fn history(&self) -> Vec<TaskRun> {
+        self.history.lock().map(|h| h.clone()).unwrap_or_default()
+    }
+
+    fn record(&self, run: &TaskRun) {
+        let Ok(mut history) = self.history.lock() else { return };
+        history.push(run.clone());
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+        history.drain(..excess);
+        let saved = serde_json::to_vec_pretty(&*history)
+            .map_err(|e| e.to_string())
+            .and_then(|content| settings::write_private_file(&history_path()?, &content));
+        if let Err(e) = saved {
+            eprintln!("[scheduler] Cannot save history: {}", e);
+        }
+    }
+}
+
+fn run_task(app: &AppHandle, task: &ScheduledTask) -> TaskRun {
+    let started = Instant::now();
+    let mut run = TaskRun {
+        task_id: task.id.clone(),
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        duration_ms: 0,
+        ok: false,
+        exit_code: None,
+        timed_out: false,
+        output: String::new(),
+        error: None,
+    };
+    match &task.action {
+        TaskAction::Exec { args } => {
+            let timeout = task
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(exec::DEFAULT_EXEC_TIMEOUT)
+                .min(exec::MAX_EXEC_TIMEOUT);
+            match crate::run_openclaw(app, args, timeout) {
+                Ok(output) => {
+                    run.ok = output.exit_code == Some(0);
+                    run.exit_code = output.exit_code;
+                    run.timed_out = output.timed_out;
+                    run.output = tail(&format!("{}{}", output.stdout, output.stderr));
+                }
+                Err(e) => run.error = Some(e),
+            }
+        }
+        TaskAction::RestartGateway => match restart_gateways(app) {
+            Ok(0) => run.error = Some("No supervised gateway is running".to_string()),
+            Ok(count) => {
+                run.ok = true;
+                run.output = format!("Restarting {} gateway session(s)", count);
+            }
+            Err(e) => run.error = Some(e),
+        },
+    }
+    run.duration_ms = started.elapsed().as_millis() as u64;
+    if let Some(e) = &run.error {
+        eprintln!("[scheduler] Task {:?} failed: {}", task.id, e);
+    }
+    run
+}
+
+/// Kills each supervised gateway so the supervisor starts it again.
+/// Returns how many were restarted.
+fn restart_gateways(app: &AppHandle) -> Result<usize, String> {
+    let pty = &app.state::<AppState>().pty;
+    let mut restarted = 0;
+    for session_id in app.state::<Supervisor>().supervised() {
+        // One already waiting for a restart is skipped.
+        if tauri::async_runtime::block_on(pty.kill(session_id, CloseReason::ScheduledRestart)).is_ok() {
+            restarted += 1;
+        }
+    }
+    Ok(restarted)
+}
+
+/// The last `MAX_RUN_OUTPUT` bytes of `output`, cut at a char boundary.
+fn tail(output: &str) -> String {
+    let mut start = output.len().saturating_sub(MAX_RUN_OUTPUT);
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    output[start..].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn bits(values: &[u32]) -> u64 {
+        values.iter().fold(0, |set, v| set | 1 << v)
+    }
+
+    #[test]
+    fn parses_field_syntax() {
+        assert_eq!(field("*", 1, 12, "month").unwrap(), bits(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]));
+        assert_eq!(field("5", 0, 59, "minute").unwrap(), bits(&[5]));
+        assert_eq!(field("1-3,7", 0, 23, "hour").unwrap(), bits(&[1, 2, 3, 7]));
+        assert_eq!(field("*/15", 0, 59, "minute").unwrap(), bits(&[0, 15, 30, 45]));
+        assert_eq!(field("10/20", 0, 59, "minute").unwrap(), bits(&[10, 30, 50]));
+        assert_eq!(field("0-10/5", 0, 59, "minute").unwrap(), bits(&[0, 5, 10]));
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        for spec in ["", "60", "5-1", "*/0", "*/x", "a", "1,", "-1", "1-"] {
+            assert!(field(spec, 0, 59, "minute").is_err(), "{:?}", spec);
+        }
+        assert!(field("0", 1, 31, "day-of-month").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(Cron::parse("* * * *").is_err());
+        assert!(Cron::parse("* * * * * *").is_err());
+        assert!(Cron::parse("@often").is_err());
+        assert!(Cron::parse("0 24 * * *").is_err());
+    }
+
+    #[test]
+    fn matches_minute_and_hour() {
+        let cron = Cron::parse("30 3 * * *").unwrap();
+        assert!(cron.matches(&at(10, 16, 3, 30)));
+        assert!(!cron.matches(&at(10, 16, 3, 31)));
+        assert!(!cron.matches(&at(10, 16, 4, 30)));
+    }
+
+    #[test]
+    fn expands_shorthands() {
+        assert!(Cron::parse("@monthly").unwrap().matches(&at(11, 1, 0, 0)));
+        assert!(!Cron::parse("@monthly").unwrap().matches(&at(11, 2, 0, 0)));
+        // 2026-10-18 is a Sunday.
+        assert!(Cron::parse("@weekly").unwrap().matches(&at(10, 18, 0, 0)));
+        assert!(Cron::parse(" @hourly ").unwrap().matches(&at(10, 16, 13, 0)));
+    }
+
+    #[test]
+    fn treats_seven_as_sunday() {
+        let cron = Cron::parse("0 0 * * 7").unwrap();
+        assert!(cron.matches(&at(10, 18, 0, 0)));
+        assert!(!cron.matches(&at(10, 17, 0, 0)));
+    }
+
+    #[test]
+    fn restricted_weekday_alone_must_match() {
+        let cron = Cron::parse("0 9 * * 1-5").unwrap();
+        assert!(cron.matches(&at(10, 16, 9, 0)));
+        assert!(!cron.matches(&at(10, 18, 9, 0)));
+    }
+
+    #[test]
+    fn restricted_day_and_weekday_match_either() {
+        // The 13th, or any Friday.
+        let cron = Cron::parse("0 0 13 * 5").unwrap();
+        assert!(cron.matches(&at(10, 13, 0, 0)));
+        assert!(cron.matches(&at(10, 16, 0, 0)));
+        assert!(!cron.matches(&at(10, 14, 0, 0)));
+    }
+}
@@ -0,0 +1,42 @@
+/// Default scrollback kept per session (4 MB).
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 4 * 1024 * 1024;
+/// Bounds for the configurable scrollback size.
+pub const MIN_SCROLLBACK_BYTES: usize = 64 * 1024;
+pub const MAX_SCROLLBACK_BYTES: usize = 32 * 1024 * 1024;
+
+/// Ring buffer of a session's most recent output, so a terminal that
+/// (re)subscribes late can be repopulated. Trimming happens in batches, at
+/// a character boundary, once the buffer overshoots its limit by a quarter.
+pub struct Scrollback {
+    buf: String,
+    limit: usize,
+}
+
+impl Scrollback {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buf: String::new(),
+            limit,
+        }
+    }
+
+    pub fn push(&mut self, data: &str) {
+        self.buf.push_str(data);
+        if self.buf.len() > self.limit + self.limit / 4 {
+            let mut cut = self.buf.len() - self.limit;
+            while !self.buf.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.buf.drain(..cut);
+        }
+    }
+
+    /// Returns at most `limit` bytes of the most recent output.
+    pub fn contents(&self) -> &str {
+        let mut start = self.buf.len().saturating_sub(self.limit);
+        while !self.buf.is_char_boundary(start) {
+            start += 1;
+        }
+        &self.buf[start..]
+    }
+}
@@ -0,0 +1,69 @@
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+/// Upper bound on matches returned by one search.
+const MAX_MATCHES: usize = 1000;
+/// Context lines included on each side of a match by default.
+pub const DEFAULT_CONTEXT_LINES: usize = 2;
+const MAX_CONTEXT_LINES: usize = 20;
+
+/// One occurrence of the pattern in a session's output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// Zero-based line index into the searched output.
+    pub line: usize,
+    /// Character offsets of the match within the line.
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub matches: Vec<SearchMatch>,
+    /// More matches exist than were returned.
+    pub truncated: bool,
+}
+
+/// Searches ANSI-stripped output lines for `pattern`, literally or as a
+/// regular expression. Literal searches are case-insensitive.
+pub fn search(lines: &[String], pattern: &str, regex: bool, context: usize) -> Result<SearchResult, String> {
+    if pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    let matcher: Regex = if regex {
+        Regex::new(pattern).map_err(|e| format!("Invalid regular expression: {}", e))?
+    } else {
+        RegexBuilder::new(&regex::escape(pattern))
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| e.to_string())?
+    };
+    let context = context.min(MAX_CONTEXT_LINES);
+
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        for m in matcher.find_iter(line) {
+            if m.start() == m.end() {
+                continue;
+            }
+            if matches.len() == MAX_MATCHES {
+                return Ok(SearchResult { matches, truncated: true });
+            }
+            let start = line[..m.start()].chars().count();
+            matches.push(SearchMatch {
+                line: index,
+                start,
+                end: start + m.as_str().chars().count(),
+                text: line.clone(),
+                before: lines[index.saturating_sub(context)..index].to_vec(),
+                after: lines[index + 1..(index + 1 + context).min(lines.len())].to_vec(),
+            });
+        }
+    }
+    Ok(SearchResult { matches, truncated: false })
+}
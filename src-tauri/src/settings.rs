@@ -1,23 +1,316 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::autorespond::AutoResponseRule;
+use crate::network::MeteredPolicy;
+use crate::openclaw::ColorMode;
+use crate::paths;
+use crate::pty_manager::{
+    IdleAction, IdlePolicy, DEFAULT_IDLE_THRESHOLD, DEFAULT_KILL_GRACE, DEFAULT_LEFTOVER_CAP, DEFAULT_READ_BUFFER,
+    MAX_LEFTOVER_CAP, MAX_READ_BUFFER, MIN_LEFTOVER_CAP, MIN_READ_BUFFER,
+};
+use crate::scheduler::ScheduledTask;
+use crate::scrollback::{DEFAULT_SCROLLBACK_BYTES, MAX_SCROLLBACK_BYTES, MIN_SCROLLBACK_BYTES};
+use crate::shortcuts::ShortcutBinding;
+use crate::state_crypt::DEFAULT_CACHE_TTL;
+use crate::stats::DEFAULT_STATS_INTERVAL;
+use crate::transcript::{
+    TranscriptPolicy, DEFAULT_TRANSCRIPT_FILES, DEFAULT_TRANSCRIPT_FILE_BYTES,
+    DEFAULT_TRANSCRIPT_RETENTION_DAYS, MAX_TRANSCRIPT_FILE_BYTES, MIN_TRANSCRIPT_FILE_BYTES,
+};
+
+/// Longest profile id accepted.
+const MAX_PROFILE_ID_LEN: usize = 32;
+
+/// A named setup with its own OpenClaw state, API keys and launch defaults,
+/// e.g. separate "work" and "personal" gateways.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Profile {
+    /// Lowercase letters, digits, `-` and `_`; names the state dir.
+    pub id: String,
+    pub name: String,
+    /// Replace the top-level `apiKeys` for this profile's sessions.
+    #[serde(default, rename = "apiKeys")]
+    pub api_keys: HashMap<String, String>,
+    /// Gateway port used when a launch doesn't set one, so gateways of
+    /// different profiles can run side by side.
+    #[serde(default, rename = "gatewayPort", skip_serializing_if = "Option::is_none")]
+    pub gateway_port: Option<u16>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Settings {
     #[serde(default, rename = "apiKeys")]
     pub api_keys: HashMap<String, String>,
+    /// Extra variables passed from the app's environment to OpenClaw, on top
+    /// of the built-in `PASSTHROUGH_ENV_VARS` (e.g. `GIT_SSH_COMMAND`).
+    #[serde(default, rename = "envPassthrough", skip_serializing_if = "Vec::is_empty")]
+    pub env_passthrough: Vec<String>,
+    /// Directories put in OpenClaw's PATH before and after the inherited
+    /// one, for tools a GUI launch doesn't see (Homebrew, ~/.local/bin).
+    /// Absolute paths of existing directories only.
+    #[serde(default, rename = "pathPrepend", skip_serializing_if = "Vec::is_empty")]
+    pub path_prepend: Vec<String>,
+    #[serde(default, rename = "pathAppend", skip_serializing_if = "Vec::is_empty")]
+    pub path_append: Vec<String>,
+    /// Node.js heap limit for OpenClaw (`--max-old-space-size`), in MB.
+    #[serde(default, rename = "nodeHeapMb", skip_serializing_if = "Option::is_none")]
+    pub node_heap_mb: Option<u32>,
+    /// Run OpenClaw with `--enable-source-maps` for readable stack traces.
+    #[serde(default, rename = "nodeSourceMaps")]
+    pub node_source_maps: bool,
+    /// Loopback port for the Node.js inspector of interactive sessions;
+    /// only used with `developerMode`.
+    #[serde(default, rename = "nodeInspectPort", skip_serializing_if = "Option::is_none")]
+    pub node_inspect_port: Option<u16>,
+    /// Locale (`LANG` and `LC_ALL`) for OpenClaw instead of the app's, e.g.
+    /// `en_US.UTF-8`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Timezone (`TZ`) for OpenClaw instead of the app's, e.g.
+    /// `Europe/Berlin`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// TERM for OpenClaw sessions, from a fixed set; `xterm-256color` if
+    /// unset. Spawn options can override it per session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    /// Color env vars set for OpenClaw (`COLORTERM`, `FORCE_COLOR`,
+    /// `NO_COLOR`).
+    #[serde(default, rename = "colorMode")]
+    pub color_mode: ColorMode,
+    /// Enables debugging aids that shouldn't be on by accident: the Node.js
+    /// inspector and developer shell sessions.
+    #[serde(default, rename = "developerMode")]
+    pub developer_mode: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// Profile used when a launch doesn't name one; `None` is the default
+    /// profile, which uses the top-level API keys.
+    #[serde(default, rename = "activeProfile", skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Keyboard shortcut bindings. `None` means use the built-in defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcuts: Option<Vec<ShortcutBinding>>,
+    /// Minutes without input or output before an interactive session is
+    /// terminated or hibernated. `None` or 0 disables the policy.
+    #[serde(default, rename = "idleTimeoutMinutes", skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_minutes: Option<u32>,
+    #[serde(default, rename = "idleAction")]
+    pub idle_action: IdleAction,
+    /// Seconds without input or output before a session is reported idle
+    /// (`pty:idle`). Reporting only; see `idleTimeoutMinutes` for the policy.
+    #[serde(default, rename = "idleThresholdSeconds", skip_serializing_if = "Option::is_none")]
+    pub idle_threshold_seconds: Option<u64>,
+    /// Files inside the OpenClaw state dir (relative paths) kept encrypted
+    /// at rest and only decrypted while the app is running sessions.
+    #[serde(default, rename = "encryptedStateFiles", skip_serializing_if = "Vec::is_empty")]
+    pub encrypted_state_files: Vec<String>,
+    /// Seconds the state encryption key stays cached in memory after a
+    /// keychain read; 0 reads the keychain on every launch.
+    #[serde(default, rename = "credentialCacheSeconds", skip_serializing_if = "Option::is_none")]
+    pub credential_cache_seconds: Option<u64>,
+    /// Directory holding `openclaw-state/` instead of the data dir, e.g. on a
+    /// larger or unsynced disk. Changed through `state_dir_move`, which moves
+    /// the existing state along.
+    #[serde(default, rename = "stateDirectory", skip_serializing_if = "Option::is_none")]
+    pub state_directory: Option<String>,
+    /// How to behave on metered or roaming connections.
+    #[serde(default, rename = "meteredPolicy")]
+    pub metered_policy: MeteredPolicy,
+    /// Closing the window hides it to the tray and keeps sessions running.
+    #[serde(default, rename = "closeToTray")]
+    pub close_to_tray: bool,
+    /// Show the visible session's title (OSC 0/2) in the window title.
+    #[serde(default, rename = "syncWindowTitle")]
+    pub sync_window_title: bool,
+    /// Serve the local automation API (see `automation.rs`).
+    #[serde(default, rename = "automationApi")]
+    pub automation_api: bool,
+    /// Loopback port of the automation API.
+    #[serde(default, rename = "automationApiPort", skip_serializing_if = "Option::is_none")]
+    pub automation_api_port: Option<u16>,
+    /// Serve the MCP server that lets agents drive sessions (see `mcp.rs`).
+    #[serde(default, rename = "mcpServer")]
+    pub mcp_server: bool,
+    /// Loopback port of the MCP server.
+    #[serde(default, rename = "mcpServerPort", skip_serializing_if = "Option::is_none")]
+    pub mcp_server_port: Option<u16>,
+    /// Restart gateway sessions that crash or stop answering; on unless
+    /// set to false.
+    #[serde(default, rename = "autoRestartGateway", skip_serializing_if = "Option::is_none")]
+    pub auto_restart_gateway: Option<bool>,
+    /// Spawn the gateway at launch so opening it attaches instantly.
+    #[serde(default, rename = "prewarmGateway")]
+    pub prewarm_gateway: bool,
+    /// Per-session scrollback kept by the backend, in bytes.
+    #[serde(default, rename = "scrollbackBytes", skip_serializing_if = "Option::is_none")]
+    pub scrollback_bytes: Option<usize>,
+    /// Seconds between `pty:stats` resource usage events; 0 disables them.
+    #[serde(default, rename = "statsIntervalSeconds", skip_serializing_if = "Option::is_none")]
+    pub stats_interval_seconds: Option<u64>,
+    /// How long sessions get to exit cleanly before being force-killed.
+    #[serde(default, rename = "killGraceMs", skip_serializing_if = "Option::is_none")]
+    pub kill_grace_ms: Option<u64>,
+    /// Size of each PTY read, in bytes.
+    #[serde(default, rename = "readBufferBytes", skip_serializing_if = "Option::is_none")]
+    pub read_buffer_bytes: Option<usize>,
+    /// Undecodable output buffered before it is flushed lossily, in bytes.
+    #[serde(default, rename = "leftoverCapBytes", skip_serializing_if = "Option::is_none")]
+    pub leftover_cap_bytes: Option<usize>,
+    /// Rules that answer prompts or raise notifications on matching output.
+    #[serde(default, rename = "autoResponses", skip_serializing_if = "Vec::is_empty")]
+    pub auto_responses: Vec<AutoResponseRule>,
+    /// OpenClaw commands and gateway restarts run on cron schedules.
+    #[serde(default, rename = "scheduledTasks", skip_serializing_if = "Vec::is_empty")]
+    pub scheduled_tasks: Vec<ScheduledTask>,
+    /// Write every session's output to rotating logs under transcripts/.
+    #[serde(default, rename = "transcriptLogging")]
+    pub transcript_logging: bool,
+    /// Size at which a transcript rolls over to a new file, in bytes.
+    #[serde(default, rename = "transcriptMaxFileBytes", skip_serializing_if = "Option::is_none")]
+    pub transcript_max_file_bytes: Option<u64>,
+    /// Number of transcript files kept.
+    #[serde(default, rename = "transcriptMaxFiles", skip_serializing_if = "Option::is_none")]
+    pub transcript_max_files: Option<usize>,
+    /// Days a transcript is kept.
+    #[serde(default, rename = "transcriptRetentionDays", skip_serializing_if = "Option::is_none")]
+    pub transcript_retention_days: Option<u32>,
+}
+
+impl Settings {
+    pub fn profile(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// The settings a session of `profile` (or the active profile, if
+    /// `None`) runs with: `activeProfile` names it and its API keys replace
+    /// the top-level ones.
+    pub fn for_profile(&self, profile: Option<&str>) -> Result<Settings, String> {
+        let mut resolved = self.clone();
+        match profile.or(self.active_profile.as_deref()) {
+            Some(id) => {
+                let profile = self.profile(id).ok_or_else(|| format!("Unknown profile: {}", id))?;
+                resolved.api_keys = profile.api_keys.clone();
+                resolved.active_profile = Some(profile.id.clone());
+            }
+            None => resolved.active_profile = None,
+        }
+        Ok(resolved)
+    }
+
+    /// Checks that profile ids are valid and unique and that the active
+    /// profile exists.
+    pub fn validate_profiles(&self) -> Result<(), String> {
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let valid = !profile.id.is_empty()
+                && profile.id.len() <= MAX_PROFILE_ID_LEN
+                && profile
+                    .id
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+            if !valid {
+                return Err(format!("Invalid profile id: {:?}", profile.id));
+            }
+            if self.profiles[..i].iter().any(|p| p.id == profile.id) {
+                return Err(format!("Duplicate profile id: {}", profile.id));
+            }
+            if profile.name.trim().is_empty() {
+                return Err(format!("Profile {} needs a name", profile.id));
+            }
+        }
+        match &self.active_profile {
+            Some(id) if self.profile(id).is_none() => Err(format!("Unknown profile: {}", id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The idle policy these settings describe, if enabled.
+    pub fn idle_policy(&self) -> Option<IdlePolicy> {
+        let minutes = self.idle_timeout_minutes.filter(|m| *m > 0)?;
+        Some(IdlePolicy {
+            timeout: std::time::Duration::from_secs(u64::from(minutes) * 60),
+            action: self.idle_action,
+        })
+    }
+
+    pub fn idle_threshold(&self) -> std::time::Duration {
+        self.idle_threshold_seconds
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(DEFAULT_IDLE_THRESHOLD)
+    }
+
+    /// Interval of periodic resource stats, or `None` if disabled.
+    pub fn stats_interval(&self) -> Option<std::time::Duration> {
+        match self.stats_interval_seconds {
+            Some(0) => None,
+            Some(secs) => Some(std::time::Duration::from_secs(secs)),
+            None => Some(DEFAULT_STATS_INTERVAL),
+        }
+    }
+
+    pub fn credential_cache_ttl(&self) -> std::time::Duration {
+        self.credential_cache_seconds
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(DEFAULT_CACHE_TTL)
+    }
+
+    pub fn auto_restart_gateway(&self) -> bool {
+        self.auto_restart_gateway.unwrap_or(true)
+    }
+
+    pub fn kill_grace(&self) -> std::time::Duration {
+        self.kill_grace_ms
+            .map(std::time::Duration::from_millis)
+            .unwrap_or(DEFAULT_KILL_GRACE)
+    }
+
+    /// Scrollback size for new sessions, clamped to sane bounds.
+    pub fn scrollback_limit(&self) -> usize {
+        self.scrollback_bytes
+            .unwrap_or(DEFAULT_SCROLLBACK_BYTES)
+            .clamp(MIN_SCROLLBACK_BYTES, MAX_SCROLLBACK_BYTES)
+    }
+
+    pub fn read_buffer_size(&self) -> usize {
+        self.read_buffer_bytes
+            .unwrap_or(DEFAULT_READ_BUFFER)
+            .clamp(MIN_READ_BUFFER, MAX_READ_BUFFER)
+    }
+
+    pub fn leftover_cap(&self) -> usize {
+        self.leftover_cap_bytes
+            .unwrap_or(DEFAULT_LEFTOVER_CAP)
+            .clamp(MIN_LEFTOVER_CAP, MAX_LEFTOVER_CAP)
+    }
+
+    /// The transcript policy these settings describe, if logging is enabled.
+    pub fn transcript_policy(&self) -> Option<TranscriptPolicy> {
+        if !self.transcript_logging {
+            return None;
+        }
+        let days = self.transcript_retention_days.unwrap_or(DEFAULT_TRANSCRIPT_RETENTION_DAYS).max(1);
+        Some(TranscriptPolicy {
+            max_file_bytes: self
+                .transcript_max_file_bytes
+                .unwrap_or(DEFAULT_TRANSCRIPT_FILE_BYTES)
+                .clamp(MIN_TRANSCRIPT_FILE_BYTES, MAX_TRANSCRIPT_FILE_BYTES),
+            max_files: self.transcript_max_files.unwrap_or(DEFAULT_TRANSCRIPT_FILES).max(1),
+            retention: std::time::Duration::from_secs(u64::from(days) * 24 * 60 * 60),
+        })
+    }
 }
 
-/// Returns the path to the settings file (~/.clawrunner/settings.json).
+/// Returns the path to the settings file (<data dir>/settings.json).
 fn settings_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    Ok(home.join(".clawrunner").join("settings.json"))
+    Ok(paths::data_dir()?.join("settings.json"))
 }
 
 /// Ensures the settings directory exists with restricted permissions.
-fn ensure_settings_dir() -> Result<(), String> {
-    let home = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    let config_dir = home.join(".clawrunner");
+/// Returns the directory path (see `paths::data_dir`).
+pub fn ensure_settings_dir() -> Result<PathBuf, String> {
+    let config_dir = paths::data_dir()?;
 
     if !config_dir.exists() {
         std::fs::create_dir_all(&config_dir)
@@ -32,7 +325,7 @@ fn ensure_settings_dir() -> Result<(), String> {
         let _ = std::fs::set_permissions(&config_dir, perms);
     }
 
-    Ok(())
+    Ok(config_dir)
 }
 
 /// Loads settings from disk. Returns default if file doesn't exist.
@@ -53,13 +346,20 @@ pub fn load_settings() -> Settings {
 }
 
 /// Saves settings to disk atomically with restricted permissions.
-/// Writes to a temp file first, then renames to prevent corruption on crash.
 pub fn save_settings_to_disk(settings: &Settings) -> Result<(), String> {
     ensure_settings_dir()?;
     let path = settings_path()?;
-    let tmp_path = path.with_extension("json.tmp");
     let content =
         serde_json::to_string_pretty(settings).map_err(|e| format!("Serialize error: {}", e))?;
+    write_private_file(&path, content.as_bytes())
+}
+
+/// Writes a file atomically with owner-only permissions.
+/// Writes to a temp file first, then renames to prevent corruption on crash.
+pub fn write_private_file(path: &Path, content: &[u8]) -> Result<(), String> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
 
     #[cfg(unix)]
     {
@@ -73,17 +373,17 @@ pub fn save_settings_to_disk(settings: &Settings) -> Result<(), String> {
             .mode(0o600)
             .open(&tmp_path)
             .map_err(|e| format!("Write error: {}", e))?;
-        file.write_all(content.as_bytes())
+        file.write_all(content)
             .map_err(|e| format!("Write error: {}", e))?;
         file.sync_all().map_err(|e| format!("Sync error: {}", e))?;
     }
 
     #[cfg(not(unix))]
     {
-        std::fs::write(&tmp_path, &content).map_err(|e| format!("Write error: {}", e))?;
+        std::fs::write(&tmp_path, content).map_err(|e| format!("Write error: {}", e))?;
     }
 
-    std::fs::rename(&tmp_path, &path).map_err(|e| format!("Rename error: {}", e))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("Rename error: {}", e))?;
 
     // Sync parent directory to ensure rename is durable (important on Linux/ext4)
     #[cfg(unix)]
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuItemBuilder, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::settings::Settings;
+
+const MENU_ID_PREFIX: &str = "shortcut:";
+
+/// Semantic actions emitted to the frontend as `shortcut:action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortcutAction {
+    NewSession,
+    ToggleWindow,
+    KillSession,
+}
+
+impl ShortcutAction {
+    const ALL: [ShortcutAction; 3] = [
+        ShortcutAction::NewSession,
+        ShortcutAction::ToggleWindow,
+        ShortcutAction::KillSession,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShortcutAction::NewSession => "new-session",
+            ShortcutAction::ToggleWindow => "toggle-window",
+            ShortcutAction::KillSession => "kill-session",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ShortcutAction::NewSession => "New Session",
+            ShortcutAction::ToggleWindow => "Toggle Window",
+            ShortcutAction::KillSession => "Kill Session",
+        }
+    }
+}
+
+/// A single accelerator binding. Global bindings work while the app is in
+/// the background; others are window-level menu accelerators.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    pub accelerator: String,
+    #[serde(default)]
+    pub global: bool,
+}
+
+pub fn default_bindings() -> Vec<ShortcutBinding> {
+    vec![
+        ShortcutBinding {
+            action: ShortcutAction::NewSession,
+            accelerator: "CmdOrCtrl+Shift+T".to_string(),
+            global: false,
+        },
+        ShortcutBinding {
+            action: ShortcutAction::KillSession,
+            accelerator: "CmdOrCtrl+Shift+W".to_string(),
+            global: false,
+        },
+        ShortcutBinding {
+            action: ShortcutAction::ToggleWindow,
+            accelerator: "CmdOrCtrl+Shift+Space".to_string(),
+            global: true,
+        },
+    ]
+}
+
+/// Bindings from settings, or the defaults if the user never customized them.
+pub fn effective_bindings(settings: &Settings) -> Vec<ShortcutBinding> {
+    settings.shortcuts.clone().unwrap_or_else(default_bindings)
+}
+
+/// Parses every accelerator and rejects duplicate actions and conflicting keys.
+pub fn validate(bindings: &[ShortcutBinding]) -> Result<(), String> {
+    let mut seen_keys: HashMap<Shortcut, ShortcutAction> = HashMap::new();
+    let mut seen_actions = Vec::new();
+    for binding in bindings {
+        if seen_actions.contains(&binding.action) {
+            return Err(format!("Action bound more than once: {}", binding.action.as_str()));
+        }
+        seen_actions.push(binding.action);
+
+        let shortcut = Shortcut::from_str(&binding.accelerator)
+            .map_err(|e| format!("Invalid shortcut {:?}: {}", binding.accelerator, e))?;
+        if let Some(other) = seen_keys.insert(shortcut, binding.action) {
+            return Err(format!(
+                "Shortcut {} conflicts: bound to both {} and {}",
+                binding.accelerator,
+                other.as_str(),
+                binding.action.as_str()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Maps registered global shortcut ids to their actions.
+pub struct ShortcutRegistry {
+    global: Mutex<HashMap<u32, ShortcutAction>>,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self {
+            global: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// (Re)registers all shortcuts from settings: global ones via the
+/// global-shortcut plugin, window-level ones as app menu accelerators.
+pub fn apply(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let bindings = effective_bindings(settings);
+    validate(&bindings)?;
+
+    let registry = app.state::<ShortcutRegistry>();
+    let mut global = registry.global.lock().map_err(|e| e.to_string())?;
+    let gs = app.global_shortcut();
+    gs.unregister_all().map_err(|e| format!("Cannot unregister shortcuts: {}", e))?;
+    global.clear();
+
+    let mut submenu = SubmenuBuilder::new(app, "Session");
+    for binding in &bindings {
+        if binding.global {
+            let shortcut = Shortcut::from_str(&binding.accelerator)
+                .map_err(|e| format!("Invalid shortcut {:?}: {}", binding.accelerator, e))?;
+            gs.register(shortcut)
+                .map_err(|e| format!("Cannot register {}: {}", binding.accelerator, e))?;
+            global.insert(shortcut.id(), binding.action);
+        } else {
+            let item = MenuItemBuilder::with_id(
+                format!("{}{}", MENU_ID_PREFIX, binding.action.as_str()),
+                binding.action.label(),
+            )
+            .accelerator(&binding.accelerator)
+            .build(app)
+            .map_err(|e| format!("Cannot build menu item: {}", e))?;
+            submenu = submenu.item(&item);
+        }
+    }
+
+    let menu = Menu::default(app).map_err(|e| format!("Cannot build menu: {}", e))?;
+    let submenu = submenu
+        .build()
+        .map_err(|e| format!("Cannot build menu: {}", e))?;
+    menu.append(&submenu)
+        .map_err(|e| format!("Cannot build menu: {}", e))?;
+    app.set_menu(menu)
+        .map_err(|e| format!("Cannot set menu: {}", e))?;
+    Ok(())
+}
+
+/// Performs backend-side handling (window toggling) and emits the action.
+fn dispatch(app: &AppHandle, action: ShortcutAction) {
+    if action == ShortcutAction::ToggleWindow {
+        match app.get_webview_window("main") {
+            Some(window) if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) => {
+                let _ = window.hide();
+            }
+            _ => crate::headless::show_main_window(app),
+        }
+    }
+    let _ = app.emit("shortcut:action", serde_json::json!({ "action": action }));
+}
+
+pub fn on_global_shortcut(app: &AppHandle, shortcut: &Shortcut) {
+    let action = app
+        .state::<ShortcutRegistry>()
+        .global
+        .lock()
+        .ok()
+        .and_then(|g| g.get(&shortcut.id()).copied());
+    if let Some(action) = action {
+        dispatch(app, action);
+    }
+}
+
+pub fn on_menu_event(app: &AppHandle, id: &str) {
+    let Some(name) = id.strip_prefix(MENU_ID_PREFIX) else { return };
+    if let Some(action) = ShortcutAction::ALL.iter().find(|a| a.as_str() == name) {
+        dispatch(app, *action);
+    }
+}
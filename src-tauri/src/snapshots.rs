@@ -0,0 +1,124 @@
+//! Automatic state snapshots taken before a newly updated OpenClaw bundle
+//! first runs, so a bad upgrade that corrupts state can be rolled back.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::backup::{self, BackupManifest};
+use crate::settings;
+
+const SNAPSHOT_DIR: &str = "snapshots";
+/// The OpenClaw version that last ran, to detect updates.
+const LAST_VERSION_FILE: &str = "last-openclaw-version";
+/// Snapshots kept; older ones are deleted when a new one is taken.
+const MAX_SNAPSHOTS: usize = 5;
+
+/// Serializes version checks so concurrent launches snapshot only once.
+static CHECK_LOCK: Mutex<()> = Mutex::new(());
+
+/// A pre-update snapshot and the versions it sits between.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub id: String,
+    pub created_at: u64,
+    pub from_version: String,
+    pub to_version: String,
+}
+
+fn snapshot_dir() -> Result<PathBuf, String> {
+    let dir = settings::ensure_settings_dir()?.join(SNAPSHOT_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {:?}: {}", dir, e))?;
+    Ok(dir)
+}
+
+/// Reads the bundle's version from the `package.json` next to its entry point.
+fn bundle_version(entry_path: &Path) -> Option<String> {
+    let data = std::fs::read(entry_path.parent()?.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_slice(&data).ok()?;
+    package.get("version")?.as_str().map(str::to_string)
+}
+
+/// Called before every OpenClaw launch. If the bundle's version differs from
+/// the one that last ran, snapshots the state dir first. Failures are logged
+/// and retried on the next launch rather than blocking this one.
+pub fn before_launch(app_version: &str, encrypted_files: &[String], entry_path: &Path) {
+    let Some(current) = bundle_version(entry_path) else { return };
+    let Ok(_guard) = CHECK_LOCK.lock() else { return };
+    let result = snapshot_dir().and_then(|dir| {
+        let marker = dir.join(LAST_VERSION_FILE);
+        let last = std::fs::read_to_string(&marker).ok().map(|v| v.trim().to_string());
+        match last {
+            Some(last) if last == current => return Ok(()),
+            // First run: there is no older state worth snapshotting.
+            None => {}
+            Some(last) => {
+                take(&dir, app_version, encrypted_files, &last, &current)?;
+            }
+        }
+        std::fs::write(&marker, &current).map_err(|e| format!("Cannot write {:?}: {}", marker, e))
+    });
+    if let Err(e) = result {
+        eprintln!("[snapshots] Pre-update snapshot failed: {}", e);
+    }
+}
+
+fn take(dir: &Path, app_version: &str, encrypted_files: &[String], from: &str, to: &str) -> Result<(), String> {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let id = format!("snapshot-{}", created_at);
+    backup::write_archive(app_version, encrypted_files, &dir.join(format!("{}.zip", id)))?;
+    let snapshot = Snapshot {
+        id: id.clone(),
+        created_at,
+        from_version: from.to_string(),
+        to_version: to.to_string(),
+    };
+    let meta = serde_json::to_vec_pretty(&snapshot).map_err(|e| e.to_string())?;
+    settings::write_private_file(&dir.join(format!("{}.json", id)), &meta)?;
+    eprintln!("[snapshots] Saved {} before OpenClaw {} -> {}", id, from, to);
+    prune(dir);
+    Ok(())
+}
+
+/// Lists snapshots, newest first.
+pub fn list() -> Result<Vec<Snapshot>, String> {
+    let dir = snapshot_dir()?;
+    let entries = std::fs::read_dir(&dir).map_err(|e| format!("Cannot read {:?}: {}", dir, e))?;
+    let mut snapshots: Vec<Snapshot> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| serde_json::from_slice(&std::fs::read(e.path()).ok()?).ok())
+        .filter(|s: &Snapshot| dir.join(format!("{}.zip", s.id)).exists())
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(snapshots)
+}
+
+fn prune(dir: &Path) {
+    let Ok(snapshots) = list() else { return };
+    for old in snapshots.iter().skip(MAX_SNAPSHOTS) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.zip", old.id)));
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", old.id)));
+    }
+}
+
+/// Restores the state dir from a snapshot. Callers must make sure no
+/// OpenClaw process is using it.
+pub fn rollback(snapshot_id: &str) -> Result<BackupManifest, String> {
+    let valid = snapshot_id
+        .strip_prefix("snapshot-")
+        .is_some_and(|ts| !ts.is_empty() && ts.bytes().all(|b| b.is_ascii_digit()));
+    if !valid {
+        return Err(format!("Invalid snapshot id: {}", snapshot_id));
+    }
+    let path = snapshot_dir()?.join(format!("{}.zip", snapshot_id));
+    if !path.exists() {
+        return Err(format!("No snapshot {}", snapshot_id));
+    }
+    backup::restore_archive(&path)
+}
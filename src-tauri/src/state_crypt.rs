@@ -0,0 +1,251 @@
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::settings;
+
+/// Keychain entry holding the state encryption key (base64).
+const KEYRING_SERVICE: &str = "ClawRunner";
+const KEYRING_USER: &str = "state-encryption-key";
+
+/// Suffix of the encrypted copy of a protected file.
+pub const SEALED_SUFFIX: &str = ".enc";
+const NONCE_LEN: usize = 24;
+
+/// How long the key stays cached after a keychain read, unless settings
+/// say otherwise (`credentialCacheSeconds`), and the most they may ask for.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+const MAX_CACHE_TTL: Duration = Duration::from_secs(8 * 60 * 60);
+
+/// The key as last read from the keychain, so every launch doesn't prompt
+/// for keychain access. Pinned in RAM where the OS allows and zeroed when
+/// it expires or is locked.
+struct CachedKey {
+    key: Zeroizing<Vec<u8>>,
+    expires: Instant,
+}
+
+impl CachedKey {
+    fn new(key: Zeroizing<Vec<u8>>, ttl: Duration) -> Self {
+        pin_memory(&key, true);
+        Self {
+            key,
+            expires: Instant::now() + ttl,
+        }
+    }
+}
+
+impl Drop for CachedKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        pin_memory(&self.key, false);
+    }
+}
+
+static CACHE: Mutex<Option<CachedKey>> = Mutex::new(None);
+static CACHE_TTL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_CACHE_TTL.as_secs());
+
+/// Keeps the key's pages out of swap (best effort).
+#[cfg(unix)]
+fn pin_memory(bytes: &[u8], pin: bool) {
+    if bytes.is_empty() {
+        return;
+    }
+    // SAFETY: the range is a live allocation owned by the caller.
+    unsafe {
+        if pin {
+            libc::mlock(bytes.as_ptr().cast(), bytes.len());
+        } else {
+            libc::munlock(bytes.as_ptr().cast(), bytes.len());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn pin_memory(_bytes: &[u8], _pin: bool) {}
+
+/// Sets how long a key read from the keychain stays cached. Zero turns the
+/// cache off and drops anything cached.
+pub fn set_cache_ttl(ttl: Duration) {
+    let ttl = ttl.min(MAX_CACHE_TTL);
+    CACHE_TTL_SECS.store(ttl.as_secs(), Ordering::Relaxed);
+    if ttl.is_zero() {
+        lock();
+    }
+}
+
+/// Drops the cached key; the next unseal reads it from the keychain again.
+pub fn lock() {
+    if let Ok(mut cache) = CACHE.lock() {
+        *cache = None;
+    }
+}
+
+fn expire() {
+    if let Ok(mut cache) = CACHE.lock() {
+        if cache.as_ref().is_some_and(|c| Instant::now() >= c.expires) {
+            *cache = None;
+        }
+    }
+}
+
+/// Resolves a protected file's path inside the state dir. Only plain
+/// relative paths are accepted, so settings can't point outside it.
+fn resolve(state_dir: &Path, rel: &str) -> Result<PathBuf, String> {
+    let rel_path = Path::new(rel);
+    let plain = rel_path.components().all(|c| matches!(c, Component::Normal(_)));
+    if rel.is_empty() || !plain {
+        return Err(format!("Invalid encrypted state file path: {}", rel));
+    }
+    Ok(state_dir.join(rel_path))
+}
+
+/// Paths of the protected files inside `state_dir`. Invalid entries are
+/// left out (`seal` and `unseal` reject them).
+pub fn protected_paths(state_dir: &Path, files: &[String]) -> Vec<PathBuf> {
+    files.iter().filter_map(|rel| resolve(state_dir, rel).ok()).collect()
+}
+
+pub fn sealed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(SEALED_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Returns the encryption key, from the cache while it is fresh and from
+/// the keychain otherwise. The cache stays locked during a keychain read so
+/// concurrent launches prompt at most once.
+fn load_key() -> Result<Zeroizing<Vec<u8>>, String> {
+    let mut cache = CACHE.lock().map_err(|e| e.to_string())?;
+    if let Some(cached) = cache.as_ref().filter(|c| Instant::now() < c.expires) {
+        return Ok(cached.key.clone());
+    }
+    *cache = None;
+    let key = read_keychain()?;
+    let ttl = Duration::from_secs(CACHE_TTL_SECS.load(Ordering::Relaxed));
+    if !ttl.is_zero() {
+        *cache = Some(CachedKey::new(key.clone(), ttl));
+        // Zero the key on time even if nothing asks for it again.
+        std::thread::spawn(move || {
+            std::thread::sleep(ttl);
+            expire();
+        });
+    }
+    Ok(key)
+}
+
+/// Loads the encryption key from the OS keychain, creating it on first use.
+fn read_keychain() -> Result<Zeroizing<Vec<u8>>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("Keychain unavailable: {}", e))?;
+    let engine = base64::engine::general_purpose::STANDARD;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let encoded = Zeroizing::new(encoded);
+            let key = Zeroizing::new(
+                engine
+                    .decode(encoded.as_bytes())
+                    .map_err(|e| format!("Corrupt state encryption key: {}", e))?,
+            );
+            if key.len() != 32 {
+                return Err("Corrupt state encryption key: wrong length".to_string());
+            }
+            Ok(key)
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = Zeroizing::new(XChaCha20Poly1305::generate_key(&mut OsRng).to_vec());
+            let encoded = Zeroizing::new(engine.encode(key.as_slice()));
+            entry
+                .set_password(&encoded)
+                .map_err(|e| format!("Cannot store state encryption key: {}", e))?;
+            Ok(key)
+        }
+        Err(e) => Err(format!("Cannot read state encryption key: {}", e)),
+    }
+}
+
+fn cipher() -> Result<XChaCha20Poly1305, String> {
+    let key = load_key()?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Overwrites a plaintext file with zeros before removing it. Best effort:
+/// copy-on-write filesystems and SSDs may still retain old blocks.
+fn scrub(path: &Path) {
+    if let Ok(meta) = std::fs::metadata(path) {
+        let _ = std::fs::write(path, vec![0u8; meta.len() as usize]);
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+/// Encrypts each protected plaintext file to `<file>.enc` and removes the
+/// plaintext. Called at startup and shutdown, when no session is using them.
+pub fn seal(state_dir: &Path, files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let cipher = cipher()?;
+    for rel in files {
+        let path = resolve(state_dir, rel)?;
+        if !path.exists() {
+            continue;
+        }
+        let sealed = encrypt(&cipher, &path)?;
+        settings::write_private_file(&sealed_path(&path), &sealed)?;
+        scrub(&path);
+    }
+    Ok(())
+}
+
+/// Encrypts a protected file's current contents as `seal` stores them,
+/// leaving the file itself alone.
+pub fn seal_contents(path: &Path) -> Result<Vec<u8>, String> {
+    encrypt(&cipher()?, path)
+}
+
+fn encrypt(cipher: &XChaCha20Poly1305, path: &Path) -> Result<Vec<u8>, String> {
+    let plaintext = Zeroizing::new(std::fs::read(path).map_err(|e| format!("Cannot read {:?}: {}", path, e))?);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| format!("Cannot encrypt {:?}", path))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypts each protected file so the OpenClaw child can read it. A
+/// plaintext copy that already exists is newer than the sealed one and wins.
+pub fn unseal(state_dir: &Path, files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let cipher = cipher()?;
+    for rel in files {
+        let path = resolve(state_dir, rel)?;
+        let sealed = sealed_path(&path);
+        if path.exists() || !sealed.exists() {
+            continue;
+        }
+        let data = std::fs::read(&sealed).map_err(|e| format!("Cannot read {:?}: {}", sealed, e))?;
+        if data.len() < NONCE_LEN {
+            return Err(format!("Corrupt encrypted file {:?}", sealed));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(XNonce::from_slice(nonce), ciphertext)
+                .map_err(|_| format!("Cannot decrypt {:?}", sealed))?,
+        );
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {:?}: {}", parent, e))?;
+        }
+        settings::write_private_file(&path, &plaintext)?;
+    }
+    Ok(())
+}
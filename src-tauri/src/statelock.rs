@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::detached;
+use crate::openclaw;
+use crate::proctree::is_alive;
+use crate::settings;
+
+const LOCK_FILE: &str = "clawrunner.lock";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HolderKind {
+    /// Another instance of this app.
+    Desktop,
+    /// A standalone OpenClaw process pointed at our state dir.
+    Cli,
+}
+
+/// Who holds (or last held) the state directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockHolder {
+    pub pid: u32,
+    pub version: String,
+    pub kind: HolderKind,
+}
+
+/// Reported to the frontend so it can offer "take over" or "read-only".
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LockStatus {
+    /// This process owns the state directory.
+    Owned,
+    /// Another live process holds it; nothing may write until resolved.
+    Conflict { holder: LockHolder },
+    /// The user chose to continue without writing, or was taken over.
+    ReadOnly { holder: Option<LockHolder> },
+}
+
+fn lock_path() -> Result<PathBuf, String> {
+    Ok(settings::ensure_settings_dir()?.join(LOCK_FILE))
+}
+
+fn read_holder() -> Option<LockHolder> {
+    let content = std::fs::read_to_string(lock_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_holder(holder: &LockHolder) -> Result<(), String> {
+    let content = serde_json::to_string(holder).map_err(|e| format!("Serialize error: {}", e))?;
+    settings::write_private_file(&lock_path()?, content.as_bytes())
+}
+
+fn own_holder() -> LockHolder {
+    LockHolder {
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        kind: HolderKind::Desktop,
+    }
+}
+
+/// Finds a process (not ours) whose environment points OpenClaw at our
+/// state dir, e.g. a standalone CLI started with OPENCLAW_STATE_DIR. Our own
+/// detached gateway is not foreign, even though a previous run started it.
+fn find_foreign_cli() -> Option<LockHolder> {
+    let state_dir = openclaw::openclaw_state_dir().ok()?;
+    let mut needle = std::ffi::OsString::from("OPENCLAW_STATE_DIR=");
+    needle.push(state_dir.as_os_str());
+    let own_pid = Pid::from_u32(std::process::id());
+    let detached_pid = detached::recorded_pid().map(Pid::from_u32);
+
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_environ(UpdateKind::OnlyIfNotSet),
+    );
+    sys.processes()
+        .iter()
+        .find(|(pid, process)| {
+            **pid != own_pid
+                && process.parent() != Some(own_pid)
+                && Some(**pid) != detached_pid
+                && process.environ().iter().any(|e| e.as_os_str() == needle.as_os_str() as &OsStr)
+        })
+        .map(|(pid, _)| LockHolder {
+            pid: pid.as_u32(),
+            version: String::new(),
+            kind: HolderKind::Cli,
+        })
+}
+
+/// Advisory lock on the data directory, so two writers (two app instances,
+/// or the app and a standalone CLI) don't corrupt each other's state.
+pub struct StateLock {
+    status: Mutex<LockStatus>,
+}
+
+impl StateLock {
+    /// Takes the lock unless another live process holds it. Stale lock files
+    /// left by a crashed instance are silently replaced.
+    pub fn acquire() -> Self {
+        let own_pid = std::process::id();
+        let conflict = read_holder()
+            .filter(|h| h.pid != own_pid && is_alive(h.pid))
+            .or_else(find_foreign_cli);
+        let status = match conflict {
+            Some(holder) => {
+                eprintln!(
+                    "[statelock] State directory in use by {:?} process {}",
+                    holder.kind, holder.pid
+                );
+                LockStatus::Conflict { holder }
+            }
+            None => match write_holder(&own_holder()) {
+                Ok(()) => LockStatus::Owned,
+                Err(e) => {
+                    eprintln!("[statelock] Cannot write lock file: {}", e);
+                    LockStatus::ReadOnly { holder: None }
+                }
+            },
+        };
+        Self {
+            status: Mutex::new(status),
+        }
+    }
+
+    pub fn status(&self) -> LockStatus {
+        self.status
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or(LockStatus::ReadOnly { holder: None })
+    }
+
+    /// Claims the lock even though another process holds it. That process
+    /// notices on its next write attempt and drops to read-only.
+    pub fn take_over(&self) -> Result<LockStatus, String> {
+        write_holder(&own_holder())?;
+        let mut status = self.status.lock().map_err(|e| e.to_string())?;
+        *status = LockStatus::Owned;
+        Ok(status.clone())
+    }
+
+    /// Continues without the lock; every write path is refused.
+    pub fn open_read_only(&self) -> Result<LockStatus, String> {
+        let mut status = self.status.lock().map_err(|e| e.to_string())?;
+        let holder = match &*status {
+            LockStatus::Conflict { holder } => Some(holder.clone()),
+            LockStatus::ReadOnly { holder } => holder.clone(),
+            LockStatus::Owned => None,
+        };
+        *status = LockStatus::ReadOnly { holder };
+        Ok(status.clone())
+    }
+
+    /// Fails unless this process may write to the state directory. Also
+    /// detects being taken over by another instance since the last check.
+    pub fn ensure_writer(&self) -> Result<(), String> {
+        let mut status = self.status.lock().map_err(|e| e.to_string())?;
+        match &*status {
+            LockStatus::Owned => {
+                let own_pid = std::process::id();
+                match read_holder() {
+                    Some(holder) if holder.pid != own_pid && is_alive(holder.pid) => {
+                        let msg = format!(
+                            "State directory was taken over by process {}; now read-only",
+                            holder.pid
+                        );
+                        *status = LockStatus::ReadOnly { holder: Some(holder) };
+                        Err(msg)
+                    }
+                    Some(holder) if holder.pid == own_pid => Ok(()),
+                    // Lock file missing or stale: quietly re-claim it.
+                    _ => write_holder(&own_holder()),
+                }
+            }
+            LockStatus::Conflict { holder } => Err(format!(
+                "State directory is in use by process {}",
+                holder.pid
+            )),
+            LockStatus::ReadOnly { .. } => Err("State directory is open read-only".to_string()),
+        }
+    }
+
+    /// Removes the lock file if this process still owns it.
+    pub fn release(&self) {
+        let owned = matches!(self.status.lock().as_deref(), Ok(LockStatus::Owned));
+        let still_ours = read_holder().is_some_and(|h| h.pid == std::process::id());
+        if owned && still_ours {
+            if let Ok(path) = lock_path() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
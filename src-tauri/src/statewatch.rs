@@ -0,0 +1,94 @@
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::path::{Component, Path};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::config::CONFIG_FILE;
+use crate::openclaw;
+
+/// Changes within this window are reported as one event.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Payload of `openclaw:config-changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChanged {
+    /// Changed paths, relative to the state dir.
+    pub paths: Vec<String>,
+}
+
+/// Watches the OpenClaw state dir and emits `openclaw:config-changed` when
+/// the config or credentials change, including edits made by OpenClaw
+/// itself (e.g. from the TUI). Dropping it stops watching.
+pub struct StateWatcher {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+}
+
+impl StateWatcher {
+    pub fn start(app: &AppHandle) -> Self {
+        let watcher = Self {
+            debouncer: Mutex::new(None),
+        };
+        watcher.restart(app);
+        watcher
+    }
+
+    /// Watches the state dir afresh, e.g. after it was replaced or moved.
+    pub fn restart(&self, app: &AppHandle) {
+        let Ok(mut slot) = self.debouncer.lock() else { return };
+        // Stop the old watcher first so no events for the old dir follow.
+        *slot = None;
+        match watch(app.clone()) {
+            Ok(debouncer) => *slot = Some(debouncer),
+            Err(e) => eprintln!("[statewatch] Cannot watch OpenClaw state: {}", e),
+        }
+    }
+}
+
+fn watch(app: AppHandle) -> Result<Debouncer<RecommendedWatcher>, String> {
+    let state_dir = openclaw::openclaw_state_dir()?;
+    let root = state_dir.canonicalize().unwrap_or_else(|_| state_dir.clone());
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("[statewatch] {}", e);
+                return;
+            }
+        };
+        let mut paths: Vec<String> = events
+            .iter()
+            .filter_map(|event| event.path.strip_prefix(&root).ok())
+            .filter(|rel| is_watched(rel))
+            .map(|rel| rel.to_string_lossy().into_owned())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        if !paths.is_empty() {
+            let _ = app.emit("openclaw:config-changed", ConfigChanged { paths });
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    debouncer
+        .watcher()
+        .watch(&state_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Cannot watch {:?}: {}", state_dir, e))?;
+    Ok(debouncer)
+}
+
+/// The config, `.env`, anything under `credentials/`, and agent auth
+/// profiles. Session logs and other busy files are ignored.
+fn is_watched(rel: &Path) -> bool {
+    let name = rel.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if name.ends_with(".tmp") {
+        return false;
+    }
+    let first = rel.components().next();
+    rel == Path::new(CONFIG_FILE)
+        || rel == Path::new(".env")
+        || first == Some(Component::Normal("credentials".as_ref()))
+        || name == "auth-profiles.json"
+}
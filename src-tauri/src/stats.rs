@@ -0,0 +1,125 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+/// Default spacing of `pty:stats` events.
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(5);
+/// How often a disabled sampler checks whether it was re-enabled.
+const DISABLED_POLL: Duration = Duration::from_secs(1);
+
+/// Resource usage of one session: its child and everything it spawned.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    pub session_id: u64,
+    pub pid: u32,
+    /// Processes in the tree, including the child itself.
+    pub process_count: usize,
+    /// Summed CPU usage in percent of one core since the previous sample.
+    pub cpu_percent: f32,
+    /// Summed resident set size in bytes.
+    pub rss_bytes: u64,
+    /// Summed open file descriptors/handles; `None` where unsupported.
+    pub open_files: Option<usize>,
+}
+
+/// Samples per-session resource usage. Keeps one `System` between samples
+/// so CPU usage covers the time since the last one, without sleeping.
+pub struct StatsSampler {
+    sys: Mutex<Option<System>>,
+    /// Milliseconds between `pty:stats` events; 0 disables them.
+    interval_ms: AtomicU64,
+}
+
+impl StatsSampler {
+    pub fn new() -> Self {
+        Self {
+            sys: Mutex::new(None),
+            interval_ms: AtomicU64::new(DEFAULT_STATS_INTERVAL.as_millis() as u64),
+        }
+    }
+
+    /// Sets the event interval; `None` disables periodic events (`pty_stats`
+    /// still works).
+    pub fn set_interval(&self, interval: Option<Duration>) {
+        let ms = interval.map(|i| i.max(MINIMUM_CPU_UPDATE_INTERVAL).as_millis() as u64);
+        self.interval_ms.store(ms.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Emits `pty:stats` with every session's usage at the configured
+    /// interval, while there are sessions.
+    pub fn start(&self, app: &AppHandle) {
+        let app = app.clone();
+        thread::spawn(move || loop {
+            let sampler = app.state::<StatsSampler>();
+            let interval = sampler.interval_ms.load(Ordering::Relaxed);
+            if interval == 0 {
+                thread::sleep(DISABLED_POLL);
+                continue;
+            }
+            let pids = app.state::<AppState>().pty.child_pids();
+            if !pids.is_empty() {
+                let _ = app.emit("pty:stats", sampler.sample(&pids));
+            }
+            thread::sleep(Duration::from_millis(interval));
+        });
+    }
+
+    /// Usage of each `(session_id, child pid)`. The first sample blocks for
+    /// `MINIMUM_CPU_UPDATE_INTERVAL` so CPU usage has a baseline.
+    pub fn sample(&self, sessions: &[(u64, u32)]) -> Vec<SessionStats> {
+        let refresh = ProcessRefreshKind::nothing()
+            .with_cpu()
+            .with_memory()
+            .without_tasks();
+        let Ok(mut guard) = self.sys.lock() else { return Vec::new() };
+        let sys = guard.get_or_insert_with(|| {
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+            thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            sys
+        });
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+
+        let processes = sys.processes();
+        let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+        for (pid, process) in processes {
+            if let Some(parent) = process.parent() {
+                children.entry(parent).or_default().push(*pid);
+            }
+        }
+
+        sessions
+            .iter()
+            .map(|&(session_id, root)| {
+                let mut stats = SessionStats {
+                    session_id,
+                    pid: root,
+                    process_count: 0,
+                    cpu_percent: 0.0,
+                    rss_bytes: 0,
+                    open_files: Some(0),
+                };
+                let mut queue = vec![Pid::from_u32(root)];
+                while let Some(pid) = queue.pop() {
+                    let Some(process) = processes.get(&pid) else { continue };
+                    stats.process_count += 1;
+                    stats.cpu_percent += process.cpu_usage();
+                    stats.rss_bytes += process.memory();
+                    stats.open_files = stats.open_files.zip(process.open_files()).map(|(a, b)| a + b);
+                    if let Some(kids) = children.get(&pid) {
+                        queue.extend(kids.iter().copied());
+                    }
+                }
+                stats
+            })
+            .collect()
+    }
+}
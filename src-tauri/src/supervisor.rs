@@ -0,0 +1,451 @@
+//! Restarts gateway sessions that exit on their own (or stop answering
+//! health probes) with the same settings and arguments, backing off
+//! exponentially between attempts. A gateway that keeps dying right after
+//! it starts is considered crash-looping and is given up on.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::health::{GatewayHealth, HealthStatus};
+use crate::pty_manager::{next_session_id, CloseReason, SessionOptions};
+use crate::settings::Settings;
+use crate::AppState;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A gateway that ran at least this long before exiting starts over at the
+/// initial backoff.
+const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+/// Consecutive failed health probes before a running gateway is restarted.
+const MAX_DOWN_PROBES: u32 = 3;
+/// A gateway that exits within this long of starting exited immediately.
+const IMMEDIATE_EXIT: Duration = Duration::from_secs(10);
+/// This many immediate exits within `CRASHLOOP_WINDOW` stop restarts.
+const CRASHLOOP_EXITS: usize = 5;
+const CRASHLOOP_WINDOW: Duration = Duration::from_secs(120);
+/// Exit codes of a gateway that was interrupted or terminated on purpose.
+const DELIBERATE_EXIT_CODES: [u32; 3] = [0, 130, 143];
+
+struct Supervised {
+    settings: Settings,
+    args: Vec<String>,
+    cols: u16,
+    rows: u16,
+    /// Restarts since the gateway last ran for `STABLE_RUNTIME`.
+    attempt: u32,
+    started: Instant,
+    down_probes: u32,
+    /// When recent immediate exits happened, oldest first.
+    immediate_exits: Vec<Instant>,
+}
+
+/// Payload of `gateway:restarting`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayRestarting {
+    session_id: u64,
+    attempt: u32,
+    delay_ms: u64,
+}
+
+/// Payload of `gateway:restarted`. Frontends showing `previous_session_id`
+/// reattach to `session_id`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayRestarted {
+    previous_session_id: u64,
+    session_id: u64,
+    attempt: u32,
+}
+
+/// Payload of `gateway:crashloop`, sent instead of another restart.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayCrashloop {
+    session_id: u64,
+    /// Immediate exits within the window, including this one.
+    exits: usize,
+    window_seconds: u64,
+    exit_code: Option<u32>,
+    /// The last screenful of the final attempt's output, ANSI-stripped.
+    output: Vec<String>,
+}
+
+/// Payload of `gateway:restart-failed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayRestartFailed {
+    session_id: u64,
+    error: String,
+}
+
+/// What the supervisor does about a crashed gateway.
+#[derive(Debug, PartialEq, Eq)]
+enum Next {
+    Restart(Duration),
+    /// Crash-looping: stop restarting and report the last `rows` lines.
+    GiveUp { exits: usize, rows: usize },
+}
+
+/// Gateway sessions to restart when they go down, keyed by session id.
+/// An entry stays under the old id while its restart is pending, so
+/// `forget` can still cancel it.
+pub struct Supervisor {
+    sessions: Mutex<HashMap<u64, Supervised>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts supervising a gateway session spawned with `settings`
+    /// (already resolved for its profile) and `args`.
+    pub fn watch(&self, session_id: u64, settings: &Settings, args: &[String], cols: u16, rows: u16) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(
+                session_id,
+                Supervised {
+                    settings: settings.clone(),
+                    args: args.to_vec(),
+                    cols,
+                    rows,
+                    attempt: 0,
+                    started: Instant::now(),
+                    down_probes: 0,
+                    immediate_exits: Vec::new(),
+                },
+            );
+        }
+    }
+
+    /// Ids of the supervised gateway sessions.
+    pub fn supervised(&self) -> Vec<u64> {
+        self.sessions
+            .lock()
+            .map(|s| s.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Stops supervising a session, cancelling any pending restart.
+    pub fn forget(&self, session_id: u64) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(&session_id);
+        }
+    }
+
+    /// Makes restarts of supervised gateways use a new Node.js heap size,
+    /// so a gateway that ran out of memory isn't restarted into the same
+    /// limit.
+    pub fn set_node_heap(&self, heap_mb: Option<u32>) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            for entry in sessions.values_mut() {
+                entry.settings.node_heap_mb = heap_mb;
+            }
+        }
+    }
+
+    /// Keeps the size a restarted gateway is spawned with current.
+    pub fn note_resize(&self, session_id: u64, cols: u16, rows: u16) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            if let Some(entry) = sessions.get_mut(&session_id) {
+                entry.cols = cols;
+                entry.rows = rows;
+            }
+        }
+    }
+
+    /// Called when any session ends. Schedules a restart if it was a
+    /// supervised gateway that crashed or was killed for failing its
+    /// health checks, unless it is crash-looping; otherwise stops
+    /// supervising it.
+    pub fn on_exit(&self, app: &AppHandle, session_id: u64, reason: CloseReason, exit_code: Option<u32>) {
+        match self.next(session_id, reason, exit_code) {
+            Some(Next::Restart(delay)) => schedule_restart(app, session_id, delay),
+            Some(Next::GiveUp { exits, rows }) => report_crashloop(app, session_id, exits, rows, exit_code),
+            None => {}
+        }
+    }
+
+    /// Updates a session's restart bookkeeping for an exit and decides what
+    /// to do next; `None` if it is not (or no longer) supervised.
+    fn next(&self, session_id: u64, reason: CloseReason, exit_code: Option<u32>) -> Option<Next> {
+        let crashed = match reason {
+            CloseReason::ChildExit => !exit_code.is_some_and(|c| DELIBERATE_EXIT_CODES.contains(&c)),
+            CloseReason::SupervisorRestart | CloseReason::ScheduledRestart => true,
+            _ => false,
+        };
+        let mut sessions = self.sessions.lock().ok()?;
+        if !crashed {
+            sessions.remove(&session_id);
+            return None;
+        }
+        let entry = sessions.get_mut(&session_id)?;
+        let runtime = entry.started.elapsed();
+        if runtime >= STABLE_RUNTIME || reason == CloseReason::ScheduledRestart {
+            entry.attempt = 0;
+        }
+        if reason == CloseReason::ChildExit && runtime < IMMEDIATE_EXIT {
+            let now = Instant::now();
+            entry.immediate_exits.retain(|t| now.duration_since(*t) < CRASHLOOP_WINDOW);
+            entry.immediate_exits.push(now);
+        }
+        if entry.immediate_exits.len() >= CRASHLOOP_EXITS {
+            let exits = entry.immediate_exits.len();
+            let rows = usize::from(entry.rows);
+            sessions.remove(&session_id);
+            Some(Next::GiveUp { exits, rows })
+        } else {
+            entry.attempt += 1;
+            Some(Next::Restart(backoff(entry.attempt)))
+        }
+    }
+
+    /// Counts consecutive `Down` probes of supervised gateways and kills
+    /// any that reach `MAX_DOWN_PROBES`, which restarts them via `on_exit`.
+    pub fn on_health(&self, app: &AppHandle, results: &[GatewayHealth]) {
+        let mut unresponsive = Vec::new();
+        if let Ok(mut sessions) = self.sessions.lock() {
+            for health in results {
+                let Some(session_id) = health.session_id else { continue };
+                let Some(entry) = sessions.get_mut(&session_id) else { continue };
+                if health.status != HealthStatus::Down {
+                    entry.down_probes = 0;
+                    continue;
+                }
+                entry.down_probes += 1;
+                if entry.down_probes == MAX_DOWN_PROBES {
+                    unresponsive.push(session_id);
+                }
+            }
+        }
+        for session_id in unresponsive {
+            eprintln!("[supervisor] Gateway session {} stopped answering; restarting", session_id);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                if let Err(e) = state.pty.kill(session_id, CloseReason::SupervisorRestart).await {
+                    eprintln!("[supervisor] Failed to kill session {}: {}", session_id, e);
+                }
+            });
+        }
+    }
+}
+
+/// `INITIAL_BACKOFF` doubled for each attempt after the first, capped at
+/// `MAX_BACKOFF`.
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+/// Emits `gateway:crashloop` with the tail of the gateway's final output.
+/// The exited session is still listed, so its output can be read.
+fn report_crashloop(app: &AppHandle, session_id: u64, exits: usize, rows: usize, exit_code: Option<u32>) {
+    eprintln!(
+        "[supervisor] Gateway session {} exited {} times within {}s; not restarting",
+        session_id,
+        exits,
+        CRASHLOOP_WINDOW.as_secs()
+    );
+    let mut output = app
+        .state::<AppState>()
+        .pty
+        .output_lines(session_id)
+        .unwrap_or_default();
+    output.drain(..output.len().saturating_sub(rows));
+    let _ = app.emit(
+        "gateway:crashloop",
+        GatewayCrashloop {
+            session_id,
+            exits,
+            window_seconds: CRASHLOOP_WINDOW.as_secs(),
+            exit_code,
+            output,
+        },
+    );
+}
+
+/// Respawns `session_id`'s gateway after `delay`, unless it was forgotten
+/// in the meantime.
+fn schedule_restart(app: &AppHandle, session_id: u64, delay: Duration) {
+    let attempt = app
+        .state::<Supervisor>()
+        .sessions
+        .lock()
+        .ok()
+        .and_then(|s| s.get(&session_id).map(|e| e.attempt))
+        .unwrap_or(0);
+    let _ = app.emit(
+        "gateway:restarting",
+        GatewayRestarting {
+            session_id,
+            attempt,
+            delay_ms: delay.as_millis() as u64,
+        },
+    );
+
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let supervisor = app.state::<Supervisor>();
+        let Some(mut entry) = supervisor.sessions.lock().ok().and_then(|mut s| s.remove(&session_id)) else {
+            return;
+        };
+        let state = app.state::<AppState>();
+        // Registered under the new id before spawning, so an immediate
+        // exit of the restarted gateway is counted.
+        let new_id = next_session_id();
+        let attempt = entry.attempt;
+        let (settings, args, cols, rows) = (entry.settings.clone(), entry.args.clone(), entry.cols, entry.rows);
+        entry.started = Instant::now();
+        entry.down_probes = 0;
+        if let Ok(mut sessions) = supervisor.sessions.lock() {
+            sessions.insert(new_id, entry);
+        }
+        let options = SessionOptions {
+            session_id: Some(new_id),
+            ..Default::default()
+        };
+        let spawned = crate::spawn_openclaw_session(&app, &state, &settings, &args, cols, rows, options);
+        match spawned {
+            Ok(_) => {
+                eprintln!("[supervisor] Restarted gateway session {} as {}", session_id, new_id);
+                let _ = app.emit(
+                    "gateway:restarted",
+                    GatewayRestarted {
+                        previous_session_id: session_id,
+                        session_id: new_id,
+                        attempt,
+                    },
+                );
+            }
+            Err(error) => {
+                supervisor.forget(new_id);
+                eprintln!("[supervisor] Failed to restart gateway session {}: {}", session_id, error);
+                let _ = app.emit("gateway:restart-failed", GatewayRestartFailed { session_id, error });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supervising(session_id: u64) -> Supervisor {
+        let supervisor = Supervisor::new();
+        supervisor.watch(session_id, &Settings::default(), &["gateway".to_string()], 80, 24);
+        supervisor
+    }
+
+    fn edit(supervisor: &Supervisor, session_id: u64, f: impl FnOnce(&mut Supervised)) {
+        f(supervisor.sessions.lock().unwrap().get_mut(&session_id).unwrap());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let delays: Vec<u64> = (0..=8).map(|a| backoff(a).as_secs()).collect();
+        assert_eq!(delays, [1, 1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn restarts_back_off_between_attempts() {
+        let supervisor = supervising(1);
+        let delays: Vec<Option<Next>> =
+            (0..3).map(|_| supervisor.next(1, CloseReason::SupervisorRestart, None)).collect();
+        assert_eq!(
+            delays,
+            [
+                Some(Next::Restart(Duration::from_secs(1))),
+                Some(Next::Restart(Duration::from_secs(2))),
+                Some(Next::Restart(Duration::from_secs(4))),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_stable_run_resets_the_backoff() {
+        let supervisor = supervising(1);
+        supervisor.next(1, CloseReason::SupervisorRestart, None);
+        supervisor.next(1, CloseReason::SupervisorRestart, None);
+        edit(&supervisor, 1, |entry| entry.started = Instant::now().checked_sub(STABLE_RUNTIME).unwrap());
+        assert_eq!(
+            supervisor.next(1, CloseReason::ChildExit, Some(1)),
+            Some(Next::Restart(INITIAL_BACKOFF))
+        );
+    }
+
+    #[test]
+    fn a_scheduled_restart_resets_the_backoff() {
+        let supervisor = supervising(1);
+        supervisor.next(1, CloseReason::SupervisorRestart, None);
+        supervisor.next(1, CloseReason::SupervisorRestart, None);
+        assert_eq!(
+            supervisor.next(1, CloseReason::ScheduledRestart, None),
+            Some(Next::Restart(INITIAL_BACKOFF))
+        );
+    }
+
+    #[test]
+    fn deliberate_exits_stop_supervision() {
+        for (reason, exit_code) in [
+            (CloseReason::ChildExit, Some(0)),
+            (CloseReason::ChildExit, Some(143)),
+            (CloseReason::UserKill, None),
+        ] {
+            let supervisor = supervising(1);
+            assert_eq!(supervisor.next(1, reason, exit_code), None);
+            assert!(supervisor.supervised().is_empty());
+        }
+    }
+
+    #[test]
+    fn gives_up_on_a_crash_loop() {
+        let supervisor = supervising(1);
+        for _ in 1..CRASHLOOP_EXITS {
+            assert!(matches!(supervisor.next(1, CloseReason::ChildExit, Some(1)), Some(Next::Restart(_))));
+        }
+        assert_eq!(
+            supervisor.next(1, CloseReason::ChildExit, Some(1)),
+            Some(Next::GiveUp { exits: CRASHLOOP_EXITS, rows: 24 })
+        );
+        assert!(supervisor.supervised().is_empty());
+    }
+
+    #[test]
+    fn forgets_immediate_exits_outside_the_window() {
+        let supervisor = supervising(1);
+        let long_ago = Instant::now().checked_sub(CRASHLOOP_WINDOW).unwrap();
+        edit(&supervisor, 1, |entry| entry.immediate_exits = vec![long_ago; CRASHLOOP_EXITS - 1]);
+        assert!(matches!(supervisor.next(1, CloseReason::ChildExit, Some(1)), Some(Next::Restart(_))));
+        edit(&supervisor, 1, |entry| assert_eq!(entry.immediate_exits.len(), 1));
+    }
+
+    #[test]
+    fn only_early_crashes_count_towards_a_loop() {
+        let supervisor = supervising(1);
+        for _ in 0..CRASHLOOP_EXITS {
+            assert!(matches!(supervisor.next(1, CloseReason::SupervisorRestart, None), Some(Next::Restart(_))));
+        }
+        edit(&supervisor, 1, |entry| entry.started = Instant::now().checked_sub(IMMEDIATE_EXIT).unwrap());
+        for _ in 0..CRASHLOOP_EXITS {
+            assert!(matches!(supervisor.next(1, CloseReason::ChildExit, Some(1)), Some(Next::Restart(_))));
+        }
+    }
+
+    #[test]
+    fn ignores_unsupervised_sessions() {
+        let supervisor = supervising(1);
+        assert_eq!(supervisor.next(2, CloseReason::ChildExit, Some(1)), None);
+        assert_eq!(supervisor.supervised(), [1]);
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::ansi::AnsiStripper;
+use crate::settings;
+
+pub const DEFAULT_TRANSCRIPT_FILE_BYTES: u64 = 10 * 1024 * 1024;
+pub const MIN_TRANSCRIPT_FILE_BYTES: u64 = 64 * 1024;
+pub const MAX_TRANSCRIPT_FILE_BYTES: u64 = 1024 * 1024 * 1024;
+pub const DEFAULT_TRANSCRIPT_FILES: usize = 50;
+pub const DEFAULT_TRANSCRIPT_RETENTION_DAYS: u32 = 14;
+
+/// Size caps and retention for transcript logs.
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptPolicy {
+    /// A session's log rolls over to a new file past this size.
+    pub max_file_bytes: u64,
+    /// Oldest transcripts beyond this count are deleted.
+    pub max_files: usize,
+    /// Transcripts older than this are deleted.
+    pub retention: Duration,
+}
+
+/// Writes a session's output, stripped of escape sequences, to log files
+/// under <data dir>/transcripts. Each file is named
+/// `session-<id>-<timestamp>.log`; when one fills up the next part starts
+/// (`...-<timestamp>.1.log`, `.2.log`, ...) and old files are pruned.
+pub struct TranscriptLog {
+    session_id: u64,
+    stamp: u64,
+    policy: TranscriptPolicy,
+    stripper: AnsiStripper,
+    file: File,
+    /// Every part written so far, oldest first. Pruned parts are skipped
+    /// when read back.
+    paths: Vec<PathBuf>,
+    part: u32,
+    written: u64,
+}
+
+impl TranscriptLog {
+    pub fn open(session_id: u64, args: &[String], policy: TranscriptPolicy) -> Result<Self, String> {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let (file, path) = create_part(session_id, stamp, 0)?;
+        let mut log = Self {
+            session_id,
+            stamp,
+            policy,
+            stripper: AnsiStripper::new(),
+            file,
+            paths: vec![path],
+            part: 0,
+            written: 0,
+        };
+        log.write_line(&format!("# openclaw {} (session {}, started {})", args.join(" "), session_id, stamp));
+        prune(&policy);
+        Ok(log)
+    }
+
+    /// Paths of the log's parts, oldest first.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// The line currently being assembled, not yet written.
+    pub fn partial(&self) -> Option<&str> {
+        self.stripper.partial()
+    }
+
+    /// Appends the complete lines in `data`. Write errors are ignored so a
+    /// full disk never disturbs the session itself.
+    pub fn write(&mut self, data: &str) {
+        for line in self.stripper.feed(data) {
+            self.write_line(&line);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.written > 0 && self.written + line.len() as u64 + 1 > self.policy.max_file_bytes {
+            self.rotate();
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.written += line.len() as u64 + 1;
+        }
+    }
+
+    fn rotate(&mut self) {
+        match create_part(self.session_id, self.stamp, self.part + 1) {
+            Ok((file, path)) => {
+                self.file = file;
+                self.paths.push(path);
+                self.part += 1;
+                self.written = 0;
+                prune(&self.policy);
+            }
+            Err(e) => eprintln!("[transcript] Cannot rotate log for session {}: {}", self.session_id, e),
+        }
+    }
+}
+
+impl Drop for TranscriptLog {
+    fn drop(&mut self) {
+        if let Some(partial) = self.stripper.partial().map(str::to_string) {
+            self.write_line(&partial);
+        }
+    }
+}
+
+fn create_part(session_id: u64, stamp: u64, part: u32) -> Result<(File, PathBuf), String> {
+    let dir = transcripts_dir()?;
+    let name = if part == 0 {
+        format!("session-{}-{}.log", session_id, stamp)
+    } else {
+        format!("session-{}-{}.{}.log", session_id, stamp, part)
+    };
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let path = dir.join(name);
+    let file = options
+        .open(&path)
+        .map_err(|e| format!("Cannot create transcript file: {}", e))?;
+    Ok((file, path))
+}
+
+/// Reads back a transcript's lines, minus the header line of each session.
+pub fn read_lines(paths: &[PathBuf]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (index, path) in paths.iter().enumerate() {
+        let Ok(content) = std::fs::read_to_string(path) else { continue };
+        let skip = usize::from(index == 0);
+        lines.extend(content.lines().skip(skip).map(str::to_string));
+    }
+    lines
+}
+
+fn transcripts_dir() -> Result<PathBuf, String> {
+    let dir = settings::ensure_settings_dir()?.join("transcripts");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create transcripts dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Deletes transcripts past the retention period, then the oldest ones
+/// beyond the file count.
+fn prune(policy: &TranscriptPolicy) {
+    let Ok(dir) = transcripts_dir() else { return };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return };
+    let now = SystemTime::now();
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("log"))
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            Some((modified, e.path()))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (index, (modified, path)) in files.iter().enumerate() {
+        let expired = now
+            .duration_since(*modified)
+            .map(|age| age > policy.retention)
+            .unwrap_or(false);
+        if expired || index >= policy.max_files {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("[transcript] Cannot remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
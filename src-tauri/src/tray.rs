@@ -0,0 +1,121 @@
+//! Background mode: with `closeToTray` set, closing the window hides it and
+//! parks the app in the system tray instead of killing the gateway. A
+//! `--headless` launch starts out parked there.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::AppState;
+
+const TRAY_ID: &str = "main";
+const MENU_SHOW: &str = "show";
+const MENU_QUIT: &str = "quit";
+/// Prefix of the profile menu items' ids; the rest is the profile id
+/// (empty for the default profile).
+const MENU_PROFILE: &str = "profile:";
+
+/// Set once the user picks "Quit" so the resulting window close shuts
+/// down for real instead of hiding again.
+static QUITTING: AtomicBool = AtomicBool::new(false);
+
+pub fn is_quitting() -> bool {
+    QUITTING.load(Ordering::Relaxed)
+}
+
+/// Makes sure the tray icon exists, so a hidden window can be brought back.
+pub fn park(app: &AppHandle) -> Result<(), String> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    let err = |e: tauri::Error| format!("Cannot create tray icon: {}", e);
+    let menu = build_menu(app).map_err(err)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("ClawRunner")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            MENU_SHOW => crate::headless::show_main_window(app),
+            MENU_QUIT => {
+                QUITTING.store(true, Ordering::Relaxed);
+                crate::shutdown(app);
+                app.exit(0);
+            }
+            id => {
+                if let Some(profile) = id.strip_prefix(MENU_PROFILE) {
+                    let profile = (!profile.is_empty()).then(|| profile.to_string());
+                    if let Err(e) = crate::switch_profile(app, profile) {
+                        eprintln!("[tray] {}", e);
+                    }
+                }
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                crate::headless::show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app).map_err(err)?;
+    Ok(())
+}
+
+/// Rebuilds the tray menu, e.g. after profiles changed.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else { return };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("[tray] Cannot rebuild menu: {}", e),
+    }
+}
+
+/// "Show" and "Quit", plus a profile switcher once profiles are set up.
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let show = MenuItem::with_id(app, MENU_SHOW, "Show ClawRunner", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, "Quit", true, None::<&str>)?;
+    let settings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    if settings.profiles.is_empty() {
+        return Menu::with_items(app, &[&show, &quit]);
+    }
+
+    let active = settings.active_profile.as_deref();
+    let mut items = vec![CheckMenuItem::with_id(
+        app,
+        MENU_PROFILE,
+        "Default",
+        true,
+        active.is_none(),
+        None::<&str>,
+    )?];
+    for profile in &settings.profiles {
+        items.push(CheckMenuItem::with_id(
+            app,
+            format!("{}{}", MENU_PROFILE, profile.id),
+            &profile.name,
+            true,
+            active == Some(profile.id.as_str()),
+            None::<&str>,
+        )?);
+    }
+    let items: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|i| i as &dyn IsMenuItem<Wry>).collect();
+    let profiles = Submenu::with_items(app, "Profile", true, &items)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    Menu::with_items(app, &[&show, &profiles, &separator, &quit])
+}
+
@@ -0,0 +1,107 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ansi::AnsiStripper;
+
+/// Links remembered per session for `pty_get_links`.
+const MAX_RECENT_LINKS: usize = 50;
+
+/// A URL seen in a session's output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedUrl {
+    pub url: String,
+    /// Milliseconds since the Unix epoch when the URL was last printed.
+    pub seen_at: u64,
+}
+
+fn url_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r#"https?://[^\s<>"'`]+"#).expect("valid URL pattern"))
+}
+
+/// Finds http(s) URLs in a session's output. Escape sequences are stripped
+/// first and matching runs on whole lines, so a URL split across reads (or
+/// interleaved with color codes) is still found intact. URLs on the
+/// incomplete last line count once whitespace ends them.
+pub struct UrlDetector {
+    stripper: AnsiStripper,
+    recent: VecDeque<DetectedUrl>,
+}
+
+impl UrlDetector {
+    pub fn new() -> Self {
+        Self {
+            stripper: AnsiStripper::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Feeds output and returns URLs not seen before, in order.
+    pub fn feed(&mut self, data: &str) -> Vec<DetectedUrl> {
+        let mut found = Vec::new();
+        for line in self.stripper.feed(data) {
+            found.extend(find_urls(&line, true));
+        }
+        if let Some(partial) = self.stripper.partial() {
+            found.extend(find_urls(partial, false));
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let mut new = Vec::new();
+        for url in found {
+            if let Some(index) = self.recent.iter().position(|u| u.url == url) {
+                // Re-printed: refresh it without reporting it again.
+                if let Some(mut existing) = self.recent.remove(index) {
+                    existing.seen_at = now;
+                    self.recent.push_back(existing);
+                }
+                continue;
+            }
+            let detected = DetectedUrl { url, seen_at: now };
+            if self.recent.len() == MAX_RECENT_LINKS {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(detected.clone());
+            new.push(detected);
+        }
+        new
+    }
+
+    /// URLs seen recently, oldest first.
+    pub fn recent(&self) -> Vec<DetectedUrl> {
+        self.recent.iter().cloned().collect()
+    }
+}
+
+/// URLs in `line`. On an incomplete line, a match running to the end may
+/// still be growing and is skipped.
+fn find_urls(line: &str, complete: bool) -> Vec<String> {
+    url_pattern()
+        .find_iter(line)
+        .filter(|m| complete || m.end() < line.len())
+        .map(|m| trim_trailing_punctuation(m.as_str()).to_string())
+        .filter(|url| url.len() > "https://".len())
+        .collect()
+}
+
+/// Drops sentence punctuation after a URL, keeping a closing parenthesis
+/// that balances one inside it (e.g. Wikipedia links).
+fn trim_trailing_punctuation(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else { return url };
+        let unbalanced_paren = last == ')' && url.matches('(').count() < url.matches(')').count();
+        if matches!(last, '.' | ',' | ';' | ':' | '!' | '?' | ']' | '}') || unbalanced_paren {
+            url = &url[..url.len() - last.len_utf8()];
+        } else {
+            return url;
+        }
+    }
+}
@@ -0,0 +1,496 @@
+//! Renders asciicast recordings to shareable videos: an animated GIF,
+//! encoded in-process, or an MP4 encoded by an `ffmpeg` found on the
+//! system. The recording is replayed through the same vt100 screen model
+//! sessions use and drawn with a monospace system font.
+
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::thread::JoinHandle;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::html;
+use crate::recording::{self, CastEvent};
+
+const DEFAULT_FPS: u32 = 10;
+const MAX_FPS: u32 = 30;
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+const MIN_FONT_SIZE: f32 = 8.0;
+const MAX_FONT_SIZE: f32 = 48.0;
+/// Pauses longer than this (in seconds) are shortened to it by default.
+const DEFAULT_MAX_IDLE: f64 = 2.0;
+const MIN_MAX_IDLE: f64 = 0.1;
+/// How long the final screen stays up, in seconds.
+const END_HOLD: f64 = 1.0;
+/// Longest video rendered, in frames (30 minutes at the default rate).
+const MAX_FRAMES: u64 = 18_000;
+/// Largest canvas side, in pixels.
+const MAX_CANVAS_SIDE: u32 = 4096;
+/// Blank margin around the terminal, in pixels.
+const PADDING: u32 = 8;
+const DEFAULT_FG: (u8, u8, u8) = (0xd4, 0xd4, 0xd4);
+const DEFAULT_BG: (u8, u8, u8) = (0x1e, 0x1e, 0x1e);
+/// GIF quantizer speed (1-30); only used when a frame has over 256 colors.
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+/// Monospace fonts tried in order; the first one that loads is used.
+const FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/Menlo.ttc",
+    "/System/Library/Fonts/Monaco.ttf",
+    "C:\\Windows\\Fonts\\consola.ttf",
+    "C:\\Windows\\Fonts\\cour.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+    "/usr/share/fonts/liberation-mono/LiberationMono-Regular.ttf",
+    "/usr/share/fonts/truetype/noto/NotoSansMono-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSansMono-Regular.ttf",
+];
+/// Where ffmpeg is looked for besides `PATH`, which is minimal for apps
+/// started from the Finder.
+const FFMPEG_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoFormat {
+    Gif,
+    /// H.264; needs ffmpeg installed.
+    Mp4,
+}
+
+impl VideoFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Mp4 => "mp4",
+        }
+    }
+
+    fn filter_name(self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "GIF",
+            VideoFormat::Mp4 => "MP4 video",
+        }
+    }
+}
+
+/// Rendering options; anything omitted uses the default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoOptions {
+    /// Frames per second (default 10, at most 30).
+    pub fps: Option<u32>,
+    /// Multiplies the recorded pace (default 1.0).
+    pub speed: Option<f64>,
+    /// Pauses longer than this many seconds are cut down to it (default 2).
+    pub max_idle: Option<f64>,
+    /// Font size in pixels (default 16).
+    pub font_size: Option<f32>,
+}
+
+/// Payload of `recording:export-progress`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgress {
+    /// The recording being rendered.
+    pub path: String,
+    pub frame: u64,
+    pub frames: u64,
+}
+
+/// A glyph's covered pixels, relative to the top-left of its cell.
+type GlyphMask = Vec<(i32, i32)>;
+
+/// Draws vt100 screens onto an RGB canvas.
+struct Renderer {
+    font: FontVec,
+    scale: PxScale,
+    cell_width: u32,
+    cell_height: u32,
+    ascent: f32,
+    width: u32,
+    height: u32,
+    glyphs: HashMap<char, GlyphMask>,
+}
+
+impl Renderer {
+    fn new(font: FontVec, font_size: f32, cols: u16, rows: u16) -> Result<Self, String> {
+        let scale = PxScale::from(font_size);
+        let scaled = font.as_scaled(scale);
+        let cell_width = scaled.h_advance(font.glyph_id('M')).ceil().max(1.0) as u32;
+        let cell_height = (scaled.ascent() - scaled.descent() + scaled.line_gap()).ceil().max(1.0) as u32;
+        let ascent = scaled.ascent();
+        // Even sides, which yuv420p video requires.
+        let even = |v: u32| v + v % 2;
+        let width = even(u32::from(cols) * cell_width + 2 * PADDING);
+        let height = even(u32::from(rows) * cell_height + 2 * PADDING);
+        if width > MAX_CANVAS_SIDE || height > MAX_CANVAS_SIDE {
+            return Err("Recording is too large to render at this font size".to_string());
+        }
+        Ok(Self {
+            font,
+            scale,
+            cell_width,
+            cell_height,
+            ascent,
+            width,
+            height,
+            glyphs: HashMap::new(),
+        })
+    }
+
+    /// Rasterizes a glyph once, without anti-aliasing, so a GIF frame keeps
+    /// to the terminal's own colors.
+    fn glyph(&mut self, c: char) -> &GlyphMask {
+        let (font, scale, ascent) = (&self.font, self.scale, self.ascent);
+        self.glyphs.entry(c).or_insert_with(|| {
+            let glyph = font.glyph_id(c).with_scale_and_position(scale, point(0.0, ascent));
+            let mut mask = Vec::new();
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|x, y, coverage| {
+                    if coverage >= 0.5 {
+                        mask.push((bounds.min.x as i32 + x as i32, bounds.min.y as i32 + y as i32));
+                    }
+                });
+            }
+            mask
+        })
+    }
+
+    fn fill(&self, canvas: &mut [u8], x0: u32, y0: u32, w: u32, h: u32, rgb: (u8, u8, u8)) {
+        for y in y0..(y0 + h).min(self.height) {
+            for x in x0..(x0 + w).min(self.width) {
+                let i = ((y * self.width + x) * 3) as usize;
+                canvas[i..i + 3].copy_from_slice(&[rgb.0, rgb.1, rgb.2]);
+            }
+        }
+    }
+
+    fn render(&mut self, screen: &vt100::Screen, canvas: &mut [u8]) {
+        self.fill(canvas, 0, 0, self.width, self.height, DEFAULT_BG);
+        let (rows, cols) = screen.size();
+        let cursor = (!screen.hide_cursor()).then(|| screen.cursor_position());
+        for row in 0..rows {
+            for col in 0..cols {
+                let Some(cell) = screen.cell(row, col) else { continue };
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+                let mut fg = color(cell.fgcolor(), DEFAULT_FG, cell.bold());
+                let mut bg = color(cell.bgcolor(), DEFAULT_BG, false);
+                if cell.inverse() != (cursor == Some((row, col))) {
+                    std::mem::swap(&mut fg, &mut bg);
+                }
+                let span = if cell.is_wide() { 2 } else { 1 };
+                let x = PADDING + u32::from(col) * self.cell_width;
+                let y = PADDING + u32::from(row) * self.cell_height;
+                self.fill(canvas, x, y, span * self.cell_width, self.cell_height, bg);
+                let Some(c) = cell.contents().chars().next().filter(|c| !c.is_whitespace()) else {
+                    continue;
+                };
+                // Bold is drawn by overstriking one pixel to the right.
+                let strikes: &[i32] = if cell.bold() { &[0, 1] } else { &[0] };
+                let (width, height) = (self.width as i32, self.height as i32);
+                let mask = self.glyph(c).clone();
+                for (dx, dy) in mask {
+                    for shift in strikes {
+                        let (px, py) = (x as i32 + dx + shift, y as i32 + dy);
+                        if px >= 0 && py >= 0 && px < width && py < height {
+                            let i = ((py * width + px) * 3) as usize;
+                            canvas[i..i + 3].copy_from_slice(&[fg.0, fg.1, fg.2]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolves a cell color; bold brightens the 8 base colors, as xterm does.
+fn color(color: vt100::Color, default: (u8, u8, u8), bold: bool) -> (u8, u8, u8) {
+    match color {
+        vt100::Color::Default => default,
+        vt100::Color::Idx(i) if bold && i < 8 => html::indexed_rgb(i + 8),
+        vt100::Color::Idx(i) => html::indexed_rgb(i),
+        vt100::Color::Rgb(r, g, b) => (r, g, b),
+    }
+}
+
+fn load_font() -> Result<FontVec, String> {
+    FONT_CANDIDATES
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find_map(|data| FontVec::try_from_vec_and_index(data, 0).ok())
+        .ok_or_else(|| "No monospace font found to render the recording with".to_string())
+}
+
+fn find_ffmpeg() -> Option<PathBuf> {
+    let name = if cfg!(target_os = "windows") { "ffmpeg.exe" } else { "ffmpeg" };
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(FFMPEG_DIRS.iter().map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Where rendered frames go.
+enum Encoder {
+    /// Identical consecutive frames are merged into one with a longer delay.
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        pending: Option<(Vec<u8>, u32)>,
+    },
+    Mp4 {
+        child: Child,
+        stdin: ChildStdin,
+        stderr: Option<JoinHandle<String>>,
+    },
+}
+
+impl Encoder {
+    fn gif(dest: &Path, width: u32, height: u32) -> Result<Self, String> {
+        let file = File::create(dest).map_err(|e| format!("Cannot create {}: {}", dest.display(), e))?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+            .map_err(|e| format!("Cannot write GIF: {}", e))?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|e| format!("Cannot write GIF: {}", e))?;
+        Ok(Encoder::Gif { encoder, pending: None })
+    }
+
+    fn mp4(dest: &Path, width: u32, height: u32, fps: u32) -> Result<Self, String> {
+        let ffmpeg = find_ffmpeg().ok_or("MP4 export needs ffmpeg; install it or export a GIF")?;
+        let mut child = Command::new(ffmpeg)
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", width, height), "-r", &fps.to_string(), "-i", "-"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-movflags", "+faststart"])
+            .arg(dest)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Cannot start ffmpeg: {}", e))?;
+        let stdin = child.stdin.take().ok_or("ffmpeg stdin unavailable")?;
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut out = String::new();
+                let _ = pipe.read_to_string(&mut out);
+                out
+            })
+        });
+        Ok(Encoder::Mp4 { child, stdin, stderr })
+    }
+
+    fn frame(&mut self, canvas: &[u8], changed: bool, width: u32, height: u32, fps: u32) -> Result<(), String> {
+        match self {
+            Encoder::Gif { encoder, pending } => {
+                if let Some((_, frames)) = pending.as_mut().filter(|_| !changed) {
+                    *frames += 1;
+                    return Ok(());
+                }
+                if let Some((previous, frames)) = pending.take() {
+                    write_gif_frame(encoder, &previous, frames, width, height, fps)?;
+                }
+                *pending = Some((canvas.to_vec(), 1));
+                Ok(())
+            }
+            Encoder::Mp4 { stdin, .. } => stdin
+                .write_all(canvas)
+                .map_err(|e| format!("ffmpeg stopped accepting frames: {}", e)),
+        }
+    }
+
+    fn finish(self, width: u32, height: u32, fps: u32) -> Result<(), String> {
+        match self {
+            Encoder::Gif { mut encoder, pending } => {
+                if let Some((previous, frames)) = pending {
+                    write_gif_frame(&mut encoder, &previous, frames, width, height, fps)?;
+                }
+                encoder
+                    .into_inner()
+                    .map_err(|e| format!("Cannot write GIF: {}", e))?
+                    .flush()
+                    .map_err(|e| format!("Cannot write GIF: {}", e))
+            }
+            Encoder::Mp4 { mut child, stdin, stderr } => {
+                drop(stdin);
+                let status = child.wait().map_err(|e| format!("ffmpeg failed: {}", e))?;
+                let stderr = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("ffmpeg failed: {}", stderr.trim()))
+                }
+            }
+        }
+    }
+
+    /// Gives up on the output. ffmpeg is killed and reaped, so it can't
+    /// still be writing the file when the caller removes it.
+    fn abort(self) {
+        if let Encoder::Mp4 { mut child, stdin, stderr } = self {
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+            if let Some(stderr) = stderr {
+                let _ = stderr.join();
+            }
+        }
+    }
+}
+
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<BufWriter<File>>,
+    canvas: &[u8],
+    frames: u32,
+    width: u32,
+    height: u32,
+    fps: u32,
+) -> Result<(), String> {
+    let mut frame = gif::Frame::from_rgb_speed(width as u16, height as u16, canvas, GIF_QUANTIZER_SPEED);
+    // GIF delays are in hundredths of a second.
+    frame.delay = (frames * 100 / fps).clamp(1, u32::from(u16::MAX)) as u16;
+    encoder
+        .write_frame(&frame)
+        .map_err(|e| format!("Cannot write GIF: {}", e))
+}
+
+/// Renders the recording at `source` to `dest`, emitting
+/// `recording:export-progress` as frames are produced. A partial file is
+/// removed on failure.
+pub fn render(
+    app: &AppHandle,
+    source: &Path,
+    dest: &Path,
+    format: VideoFormat,
+    options: &VideoOptions,
+) -> Result<(), String> {
+    let cast = recording::load_cast(source)?;
+    let fps = options.fps.unwrap_or(DEFAULT_FPS).clamp(1, MAX_FPS);
+    let speed = options
+        .speed
+        .filter(|s| s.is_finite())
+        .unwrap_or(1.0)
+        .clamp(recording::MIN_REPLAY_SPEED, recording::MAX_REPLAY_SPEED);
+    let max_idle = options
+        .max_idle
+        .filter(|s| s.is_finite())
+        .unwrap_or(DEFAULT_MAX_IDLE)
+        .max(MIN_MAX_IDLE);
+    let font_size = options
+        .font_size
+        .filter(|s| s.is_finite())
+        .unwrap_or(DEFAULT_FONT_SIZE)
+        .clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+
+    // Video time of each event, with long pauses shortened.
+    let mut timeline = Vec::with_capacity(cast.events.len());
+    let (mut previous, mut elapsed) = (0.0, 0.0);
+    for (at, event) in &cast.events {
+        elapsed += (at - previous).clamp(0.0, max_idle) / speed;
+        previous = *at;
+        timeline.push((elapsed, event));
+    }
+    let frames = ((elapsed + END_HOLD) * f64::from(fps)).ceil() as u64 + 1;
+    if frames > MAX_FRAMES {
+        return Err("Recording is too long to export; raise the speed or lower the frame rate".to_string());
+    }
+
+    // The canvas fits the largest size the terminal had.
+    let (cols, rows) = cast.events.iter().fold((cast.cols, cast.rows), |(c, r), (_, event)| match event {
+        CastEvent::Resize(cols, rows) => (c.max(*cols), r.max(*rows)),
+        CastEvent::Output(_) => (c, r),
+    });
+    let mut renderer = Renderer::new(load_font()?, font_size, cols, rows)?;
+    let (width, height) = (renderer.width, renderer.height);
+
+    let mut encoder = match format {
+        VideoFormat::Gif => Encoder::gif(dest, width, height)?,
+        VideoFormat::Mp4 => Encoder::mp4(dest, width, height, fps)?,
+    };
+    let rendered = (|| {
+        let mut parser = vt100::Parser::new(cast.rows, cast.cols, 0);
+        let mut canvas = vec![0u8; (width * height * 3) as usize];
+        let mut next = 0;
+        let mut last_percent = None;
+        for frame in 0..frames {
+            let now = frame as f64 / f64::from(fps);
+            let mut changed = frame == 0;
+            while let Some((_, event)) = timeline.get(next).filter(|(at, _)| *at <= now) {
+                match event {
+                    CastEvent::Output(data) => parser.process(data.as_bytes()),
+                    CastEvent::Resize(cols, rows) => parser.screen_mut().set_size(*rows, *cols),
+                }
+                changed = true;
+                next += 1;
+            }
+            if changed {
+                renderer.render(parser.screen(), &mut canvas);
+            }
+            encoder.frame(&canvas, changed, width, height, fps)?;
+
+            let percent = (frame + 1) * 100 / frames;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit(
+                    "recording:export-progress",
+                    ExportProgress {
+                        path: source.to_string_lossy().into_owned(),
+                        frame: frame + 1,
+                        frames,
+                    },
+                );
+            }
+        }
+        Ok::<(), String>(())
+    })();
+    let result = match rendered {
+        Ok(()) => encoder.finish(width, height, fps),
+        Err(e) => {
+            encoder.abort();
+            Err(e)
+        }
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest);
+    }
+    result
+}
+
+/// Asks where to save the video, then renders it. Returns the path, or
+/// `None` if the dialog was cancelled. Blocks on the dialog and the
+/// encoder, so call from a blocking task.
+pub fn export_with_dialog(
+    app: &AppHandle,
+    source: &Path,
+    format: VideoFormat,
+    options: &VideoOptions,
+) -> Result<Option<String>, String> {
+    let extension = format.extension();
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "recording".to_string());
+    let Some(dest) = app
+        .dialog()
+        .file()
+        .set_title("Export Recording")
+        .set_file_name(format!("{}.{}", stem, extension))
+        .add_filter(format.filter_name(), &[extension])
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let dest: PathBuf = dest.into_path().map_err(|e| format!("Invalid export path: {}", e))?;
+    render(app, source, &dest, format, options)?;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
@@ -1,15 +1,25 @@
 import { useState, useCallback, useEffect, useMemo } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { WelcomePage } from "./components/WelcomePage.tsx";
 import { TerminalView } from "./components/TerminalView.tsx";
 import { StatusBar } from "./components/StatusBar.tsx";
 import { SettingsPanel } from "./components/SettingsPanel.tsx";
 import { TabBar } from "./components/TabBar.tsx";
 import { WebUIView } from "./components/WebUIView.tsx";
-import type { PtyState, Settings, AppMode, TabId } from "./types/index.ts";
+import type {
+  PtyState,
+  Settings,
+  AppMode,
+  TabId,
+  LaunchMode,
+  ConfigStatus,
+  GatewayCrashloop,
+  OomEvent,
+} from "./types/index.ts";
 import { useUpdater } from "./hooks/useUpdater.ts";
 
-const CHAT_ARGS = ["tui"];
+const CHAT_MODE: LaunchMode = { mode: "tui" };
 
 function resetChatState(
   setChatSpawned: (v: boolean) => void,
@@ -31,20 +41,59 @@ function App() {
   const [restartKey, setRestartKey] = useState(0);
   const [activeTab, setActiveTab] = useState<TabId>("gateway");
   const [chatSpawned, setChatSpawned] = useState(false);
+  const [oom, setOom] = useState<OomEvent | null>(null);
   const updater = useUpdater();
 
   useEffect(() => {
-    invoke<boolean>("check_openclaw_configured").then(setIsConfigured).catch(() => {});
+    invoke<ConfigStatus>("openclaw_config_status").then((s) => setIsConfigured(s.configured)).catch(() => {});
     invoke<Settings>("load_settings_cmd").then(setSettings).catch(() => {});
   }, []);
 
+  // Pick up setup done outside the app (or in the TUI) without a restart
+  useEffect(() => {
+    const unlisten = listen("openclaw:config-changed", () => {
+      invoke<ConfigStatus>("openclaw_config_status").then((s) => setIsConfigured(s.configured)).catch(() => {});
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
+  // The tray can switch profiles; reload settings and setup state for it
+  useEffect(() => {
+    const unlisten = listen("profile:changed", () => {
+      invoke<Settings>("load_settings_cmd").then(setSettings).catch(() => {});
+      invoke<ConfigStatus>("openclaw_config_status").then((s) => setIsConfigured(s.configured)).catch(() => {});
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
+  // The supervisor gave up on a gateway that keeps crashing; show why
+  useEffect(() => {
+    const unlisten = listen<GatewayCrashloop>("gateway:crashloop", (event) => {
+      const { exits, windowSeconds, exitCode, output } = event.payload;
+      const summary = `Gateway crashed ${exits} times within ${windowSeconds}s and was not restarted`;
+      setGatewayPtyState({
+        status: "error",
+        errorMessage: [summary, ...output.slice(-5)].join("\n"),
+        reason: "child_exit",
+        exitCode: exitCode ?? undefined,
+      });
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
+  // A session ran out of Node.js heap; offer to restart with a bigger one
+  useEffect(() => {
+    const unlisten = listen<OomEvent>("openclaw:oom", (event) => setOom(event.payload));
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
   // Handle process exit transitions (onboard -> gateway)
   useEffect(() => {
     if (gatewayPtyState.status !== "stopped" && gatewayPtyState.status !== "error") return;
     if (mode !== "onboard") return;
 
     let cancelled = false;
-    invoke<boolean>("check_openclaw_configured").then((configured) => {
+    invoke<ConfigStatus>("openclaw_config_status").then(({ configured }) => {
       if (cancelled) return;
       if (configured) {
         setIsConfigured(true);
@@ -72,7 +121,7 @@ function App() {
     // Re-check on click in case OpenClaw was configured externally
     let configured = isConfigured;
     try {
-      configured = await invoke<boolean>("check_openclaw_configured");
+      configured = (await invoke<ConfigStatus>("openclaw_config_status")).configured;
       setIsConfigured(configured);
     } catch {
       // Fall back to cached value
@@ -90,11 +139,22 @@ function App() {
     setRestartKey((k) => k + 1);
   }, []);
 
+  const handleOomRestart = useCallback(async (heapMb: number) => {
+    try {
+      setSettings(await invoke<Settings>("openclaw_set_heap", { heapMb }));
+    } catch (err) {
+      setOom((prev) => prev && { ...prev, message: `Failed to save heap size: ${String(err)}` });
+      return;
+    }
+    setOom(null);
+    handleRestart();
+  }, [handleRestart]);
+
   const handleBackToWelcome = useCallback(() => {
     setGatewayPtyState({ status: "starting" });
     resetChatState(setChatSpawned, setChatPtyState, setActiveTab);
     setMode("welcome");
-    invoke<boolean>("check_openclaw_configured").then(setIsConfigured).catch(() => {});
+    invoke<ConfigStatus>("openclaw_config_status").then((s) => setIsConfigured(s.configured)).catch(() => {});
   }, []);
 
   const handleSettingsSave = useCallback((newSettings: Settings) => {
@@ -107,8 +167,8 @@ function App() {
     setChatSpawned((prev) => prev || tab === "chat");
   }, []);
 
-  const gatewayArgs = useMemo(
-    () => mode === "onboard" ? ["onboard", "--skip-daemon"] : ["gateway"],
+  const gatewayMode = useMemo<LaunchMode>(
+    () => mode === "onboard" ? { mode: "onboard", skipDaemon: true } : { mode: "gateway" },
     [mode],
   );
 
@@ -149,7 +209,7 @@ function App() {
             key={`gateway-${restartKey}`}
             onStatusChange={setGatewayPtyState}
             settings={settings}
-            args={gatewayArgs}
+            mode={gatewayMode}
             active={!showTabs || activeTab === "gateway"}
           />
         </div>
@@ -161,7 +221,7 @@ function App() {
               key={`chat-${restartKey}`}
               onStatusChange={setChatPtyState}
               settings={settings}
-              args={CHAT_ARGS}
+              mode={CHAT_MODE}
               active={activeTab === "chat"}
             />
           </div>
@@ -175,6 +235,18 @@ function App() {
         )}
       </div>
 
+      {oom && (
+        <div className="oom-notice" role="alert">
+          <span>{oom.message}</span>
+          <button type="button" className="status-btn status-btn-primary" onClick={() => handleOomRestart(oom.suggestedHeapMb)}>
+            Restart with {oom.suggestedHeapMb} MB
+          </button>
+          <button type="button" className="status-btn" onClick={() => setOom(null)}>
+            Dismiss
+          </button>
+        </div>
+      )}
+
       <StatusBar
         status={activePtyState}
         mode={mode}
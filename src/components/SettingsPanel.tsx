@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { Settings } from "../types/index.ts";
+import type { KeyCheck, ProviderStatus, Settings } from "../types/index.ts";
 import { useAutostart } from "../hooks/useAutostart.ts";
 
 interface SettingsPanelProps {
@@ -9,18 +9,18 @@ interface SettingsPanelProps {
   onClose: () => void;
 }
 
-const API_KEY_FIELDS = [
-  { key: "OPENAI_API_KEY", label: "OpenAI API Key" },
-  { key: "ANTHROPIC_API_KEY", label: "Anthropic API Key" },
-  { key: "ELEVENLABS_API_KEY", label: "ElevenLabs API Key" },
-  { key: "GOOGLE_MAPS_API_KEY", label: "Google Maps API Key" },
-];
-
 export function SettingsPanel({ settings, onSave, onClose }: SettingsPanelProps) {
   const [apiKeys, setApiKeys] = useState<Record<string, string>>({
     ...settings.apiKeys,
   });
+  const [closeToTray, setCloseToTray] = useState(settings.closeToTray ?? false);
+  const [transcriptLogging, setTranscriptLogging] = useState(settings.transcriptLogging ?? false);
+  const [syncWindowTitle, setSyncWindowTitle] = useState(settings.syncWindowTitle ?? false);
+  const [developerMode, setDeveloperMode] = useState(settings.developerMode ?? false);
   const [saveError, setSaveError] = useState<string | null>(null);
+  const [providers, setProviders] = useState<ProviderStatus[]>([]);
+  /** Per provider id: a check in flight (null) or its result. */
+  const [keyChecks, setKeyChecks] = useState<Record<string, KeyCheck | null>>({});
   const autostart = useAutostart();
   const panelRef = useRef<HTMLDivElement>(null);
 
@@ -28,6 +28,12 @@ export function SettingsPanel({ settings, onSave, onClose }: SettingsPanelProps)
     panelRef.current?.focus();
   }, []);
 
+  useEffect(() => {
+    invoke<ProviderStatus[]>("providers_list")
+      .then(setProviders)
+      .catch((err) => setSaveError(`Failed to load providers: ${String(err)}`));
+  }, []);
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       if (e.key === "Escape") {
@@ -59,8 +65,27 @@ export function SettingsPanel({ settings, onSave, onClose }: SettingsPanelProps)
     setSaveError(null);
   }, []);
 
+  const checkKey = async (provider: string, key: string) => {
+    setKeyChecks((prev) => ({ ...prev, [provider]: null }));
+    let result: KeyCheck;
+    try {
+      result = await invoke<KeyCheck>("validate_api_key", { provider, key });
+    } catch (err) {
+      result = { status: "network-error", message: String(err) };
+    }
+    setKeyChecks((prev) => ({ ...prev, [provider]: result }));
+  };
+
   const handleSave = async () => {
-    const newSettings: Settings = { apiKeys };
+    // Preserve backend-managed fields (e.g. shortcuts) the panel doesn't edit.
+    const newSettings: Settings = {
+      ...settings,
+      apiKeys,
+      closeToTray,
+      transcriptLogging,
+      syncWindowTitle,
+      developerMode,
+    };
     try {
       await invoke("save_settings", { settings: newSettings });
     } catch (err) {
@@ -106,19 +131,114 @@ export function SettingsPanel({ settings, onSave, onClose }: SettingsPanelProps)
             {autostart.error}
           </div>
         )}
+        <div className="settings-toggle-field">
+          <label htmlFor="close-to-tray-toggle">Keep Running in Tray When Closed</label>
+          <input
+            id="close-to-tray-toggle"
+            type="checkbox"
+            checked={closeToTray}
+            onChange={(e) => setCloseToTray(e.target.checked)}
+            aria-describedby="close-to-tray-hint"
+          />
+        </div>
+        <span className="settings-toggle-hint" id="close-to-tray-hint">
+          The gateway keeps running; quit from the tray icon
+        </span>
+        <div className="settings-toggle-field">
+          <label htmlFor="sync-window-title-toggle">Show Session Title in Window Title</label>
+          <input
+            id="sync-window-title-toggle"
+            type="checkbox"
+            checked={syncWindowTitle}
+            onChange={(e) => setSyncWindowTitle(e.target.checked)}
+            aria-describedby="sync-window-title-hint"
+          />
+        </div>
+        <span className="settings-toggle-hint" id="sync-window-title-hint">
+          Uses the title OpenClaw sets for the visible tab
+        </span>
+        <div className="settings-toggle-field">
+          <label htmlFor="transcript-logging-toggle">Save Session Transcripts</label>
+          <input
+            id="transcript-logging-toggle"
+            type="checkbox"
+            checked={transcriptLogging}
+            onChange={(e) => setTranscriptLogging(e.target.checked)}
+            aria-describedby="transcript-logging-hint"
+          />
+        </div>
+        <span className="settings-toggle-hint" id="transcript-logging-hint">
+          Rotating logs in ~/.openclaw-desktop/transcripts, applies to new sessions
+        </span>
+        <h2 className="settings-section-heading">Advanced</h2>
+        <div className="settings-toggle-field">
+          <label htmlFor="developer-mode-toggle">Developer Mode</label>
+          <input
+            id="developer-mode-toggle"
+            type="checkbox"
+            checked={developerMode}
+            onChange={(e) => setDeveloperMode(e.target.checked)}
+            aria-describedby="developer-mode-hint"
+          />
+        </div>
+        <span className="settings-toggle-hint" id="developer-mode-hint">
+          Allows shell sessions with OpenClaw's environment and the Node.js inspector; leave off unless debugging
+        </span>
         <h2 className="settings-section-heading">API Keys</h2>
-        {API_KEY_FIELDS.map(({ key, label }) => (
-          <div className="settings-field" key={key}>
-            <label htmlFor={`settings-${key}`}>{label}</label>
-            <input
-              id={`settings-${key}`}
-              type="password"
-              value={apiKeys[key] || ""}
-              onChange={(e) => handleChange(key, e.target.value)}
-              placeholder={`Enter ${label}...`}
-            />
-          </div>
-        ))}
+        {providers.map(({ id, name, keyVar, keyPrefix, checkable }) => {
+          if (!keyVar) return null;
+          const value = apiKeys[keyVar] || "";
+          const badFormat = keyPrefix !== null && value !== "" && !value.startsWith(keyPrefix);
+          const check = keyChecks[id];
+          return (
+            <div className="settings-field" key={id}>
+              <label htmlFor={`settings-${keyVar}`}>{name} API Key</label>
+              <input
+                id={`settings-${keyVar}`}
+                type="password"
+                value={value}
+                onChange={(e) => handleChange(keyVar, e.target.value)}
+                placeholder={keyPrefix ? `${keyPrefix}...` : `Enter ${name} API Key...`}
+                aria-invalid={badFormat}
+              />
+              {checkable && (
+                <button
+                  className="btn-secondary"
+                  disabled={value === "" || check === null}
+                  onClick={() => checkKey(id, value)}
+                >
+                  {check === null ? "Checking..." : "Test"}
+                </button>
+              )}
+              {badFormat && (
+                <span className="settings-toggle-hint">{name} keys usually start with {keyPrefix}</span>
+              )}
+              {check && (
+                <span className="settings-toggle-hint" role="status">
+                  {check.status === "ok" ? "Key works" : check.message}
+                </span>
+              )}
+            </div>
+          );
+        })}
+        <h2 className="settings-section-heading">Provider Endpoints</h2>
+        {providers.flatMap(({ name, settings: fields }) =>
+          fields.map(({ envVar, label, placeholder }) => (
+            <div className="settings-field" key={envVar}>
+              <label htmlFor={`settings-${envVar}`}>
+                {name} {label}
+              </label>
+              <input
+                id={`settings-${envVar}`}
+                type="text"
+                spellCheck={false}
+                value={apiKeys[envVar] || ""}
+                onChange={(e) => handleChange(envVar, e.target.value.trim())}
+                placeholder={placeholder}
+              />
+            </div>
+          ))
+        )}
         {saveError && (
           <div className="settings-error" role="alert">{saveError}</div>
         )}
@@ -1,35 +1,47 @@
-import { useTerminal } from "../hooks/useTerminal.ts";
+import { useTerminal, type TerminalSize } from "../hooks/useTerminal.ts";
 import { usePtySession } from "../hooks/usePtySession.ts";
-import type { PtyState, Settings } from "../types/index.ts";
+import type { LaunchMode, PtyState, Settings } from "../types/index.ts";
 
 interface TerminalViewProps {
   onStatusChange: (state: PtyState) => void;
   settings: Settings;
-  args: string[];
+  mode: LaunchMode;
   active: boolean;
+  /** Working directory for the session; defaults to the home directory. */
+  cwd?: string;
+  /** API keys and provider settings for this session only. */
+  env?: Record<string, string>;
 }
 
-export function TerminalView({ onStatusChange, settings, args, active }: TerminalViewProps) {
+export function TerminalView({ onStatusChange, settings, mode, active, cwd, env }: TerminalViewProps) {
   const { containerRef, writeToTerminal, initialSize } = useTerminal({
     onData: handleUserInput,
+    onPaste: handlePaste,
     onResize: handleResize,
     active,
   });
 
-  const { write, resize } = usePtySession({
+  const { write, paste, resize } = usePtySession({
     onData: writeToTerminal,
     onStatusChange,
     settings,
-    args,
+    mode,
     initialSize,
+    active,
+    cwd,
+    env,
   });
 
   function handleUserInput(data: string) {
     write(data);
   }
 
-  function handleResize(cols: number, rows: number) {
-    resize(cols, rows);
+  function handlePaste(text: string) {
+    paste(text);
+  }
+
+  function handleResize(size: TerminalSize) {
+    resize(size);
   }
 
   return <div ref={containerRef} className="terminal-container" />;
@@ -0,0 +1,23 @@
+import { useCallback, useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { ProxyInfo } from "../types/index.ts";
+
+/**
+ * Calls the gateway's HTTP API through the backend's loopback proxy, which
+ * adds the gateway credentials; the frontend only holds the proxy token.
+ */
+export function useGatewayApi() {
+  const proxyRef = useRef<Promise<ProxyInfo> | null>(null);
+
+  useEffect(() => {
+    proxyRef.current = invoke<ProxyInfo>("gateway_proxy");
+  }, []);
+
+  return useCallback(async (path: string, init: RequestInit = {}) => {
+    proxyRef.current ??= invoke<ProxyInfo>("gateway_proxy");
+    const { baseUrl, token } = await proxyRef.current;
+    const headers = new Headers(init.headers);
+    headers.set("X-ClawRunner-Token", token);
+    return fetch(`${baseUrl}${path.startsWith("/") ? path : `/${path}`}`, { ...init, headers });
+  }, []);
+}
@@ -1,35 +1,103 @@
 import { useEffect, useRef, useCallback } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { PtyState, PtyStatus, Settings } from "../types/index.ts";
-
-interface PtyDataEvent {
-  sessionId: number;
-  data: string;
-}
+import type {
+  ExternalGatewayError,
+  GatewayRestarted,
+  LaunchMode,
+  PortConflict,
+  PtyCloseReason,
+  PtyState,
+  PtyStatus,
+  SessionInfo,
+  Settings,
+} from "../types/index.ts";
+import type { TerminalSize } from "./useTerminal.ts";
 
 interface PtyStatusEvent {
   sessionId: number;
   status: string;
   errorMessage?: string;
+  reason?: PtyCloseReason;
+  exitCode?: number;
+}
+
+/**
+ * pty_spawn rejects with a string, a PortConflict for a busy gateway port,
+ * or an ExternalGatewayError when a gateway is already running elsewhere.
+ */
+function spawnErrorMessage(err: unknown): string {
+  if (typeof err === "object" && err !== null && "remediation" in err) {
+    const conflict = err as PortConflict;
+    return `${conflict.message}. ${conflict.remediation}`;
+  }
+  if (typeof err === "object" && err !== null && "gateway" in err) {
+    return (err as ExternalGatewayError).message;
+  }
+  return String(err);
+}
+
+/**
+ * pty_write accepts at most 1 MB per call. 256K UTF-16 units are at most
+ * 768 KB of UTF-8.
+ */
+const WRITE_CHUNK_UNITS = 256 * 1024;
+
+/** Splits `data` into pty_write-sized pieces without breaking surrogate pairs. */
+function writeChunks(data: string): string[] {
+  const chunks: string[] = [];
+  let start = 0;
+  while (start < data.length) {
+    let end = Math.min(start + WRITE_CHUNK_UNITS, data.length);
+    const last = data.charCodeAt(end - 1);
+    if (end < data.length && last >= 0xd800 && last <= 0xdbff) end -= 1;
+    chunks.push(data.slice(start, end));
+    start = end;
+  }
+  return chunks;
+}
+
+/** Acknowledge output every N messages; the backend pauses reads at 256 unacked. */
+const ACK_EVERY = 32;
+
+/**
+ * Session IDs are kept in sessionStorage, which survives a webview reload
+ * (unlike React state), so a reloaded frontend can reattach to its sessions.
+ */
+function sessionStorageKey(mode: LaunchMode): string {
+  return `pty-session:${JSON.stringify(mode)}`;
 }
 
 const VALID_PTY_STATUSES: ReadonlySet<string> = new Set<PtyStatus>(["starting", "running", "stopped", "error"]);
 
 interface UsePtySessionOptions {
-  onData: (data: string) => void;
+  onData: (data: Uint8Array) => void;
   onStatusChange: (state: PtyState) => void;
   settings: Settings;
-  args: string[];
-  initialSize: { cols: number; rows: number } | null;
+  mode: LaunchMode;
+  initialSize: TerminalSize | null;
+  /** The visible tab's session is the one the window title follows. */
+  active: boolean;
+  /** Working directory for the session instead of the home directory; not for gateways. */
+  cwd?: string;
+  /** API keys and provider settings for this session only, e.g. another OPENAI_API_KEY. */
+  env?: Record<string, string>;
 }
 
-export function usePtySession({ onData, onStatusChange, settings, args, initialSize }: UsePtySessionOptions) {
+function claimWindowTitle(sessionId: number) {
+  invoke("pty_set_title_source", { sessionId }).catch(() => {});
+}
+
+export function usePtySession({ onData, onStatusChange, settings, mode, initialSize, active, cwd, env }: UsePtySessionOptions) {
   const settingsRef = useRef(settings);
   settingsRef.current = settings;
 
-  const argsRef = useRef(args);
-  argsRef.current = args;
+  const modeRef = useRef(mode);
+  modeRef.current = mode;
+  const cwdRef = useRef(cwd);
+  cwdRef.current = cwd;
+  const envRef = useRef(env);
+  envRef.current = env;
 
   const onDataRef = useRef(onData);
   onDataRef.current = onData;
@@ -37,6 +105,9 @@ export function usePtySession({ onData, onStatusChange, settings, args, initialS
   const onStatusChangeRef = useRef(onStatusChange);
   onStatusChangeRef.current = onStatusChange;
 
+  const activeRef = useRef(active);
+  activeRef.current = active;
+
   const spawnedRef = useRef(false);
   const sessionIdRef = useRef<number>(0);
 
@@ -47,25 +118,15 @@ export function usePtySession({ onData, onStatusChange, settings, args, initialS
     let cancelled = false;
     const unlistenFns: Array<() => void> = [];
     const listenPromises: Array<Promise<() => void>> = [];
-    const { cols, rows } = initialSize;
+    const { cols, rows, pixelWidth, pixelHeight } = initialSize;
+    const storageKey = sessionStorageKey(modeRef.current);
 
-    // Buffer events received before session ID is known (fast-exit race).
+    // Buffer status events received before session ID is known (fast-exit race).
     // Once the session ID is set, buffered events matching the ID are replayed.
-    const pendingDataEvents: PtyDataEvent[] = [];
+    // Output needs no buffering: it arrives on a channel handed to pty_spawn.
     const pendingStatusEvents: PtyStatusEvent[] = [];
     let sessionKnown = false;
 
-    function handleDataEvent(payload: PtyDataEvent) {
-      if (cancelled) return;
-      if (!sessionKnown) {
-        pendingDataEvents.push(payload);
-        return;
-      }
-      if (payload.sessionId === sessionIdRef.current) {
-        onDataRef.current(payload.data);
-      }
-    }
-
     function handleStatusEvent(payload: PtyStatusEvent) {
       if (cancelled) return;
       if (!sessionKnown) {
@@ -76,71 +137,125 @@ export function usePtySession({ onData, onStatusChange, settings, args, initialS
         const status: PtyStatus = VALID_PTY_STATUSES.has(payload.status)
           ? (payload.status as PtyStatus)
           : "error";
-        onStatusChangeRef.current({ status, errorMessage: payload.errorMessage });
+        onStatusChangeRef.current({
+          status,
+          errorMessage: payload.errorMessage,
+          reason: payload.reason,
+          exitCode: payload.exitCode,
+        });
       }
     }
 
     function drainPendingEvents(sid: number) {
       sessionKnown = true;
-      for (const evt of pendingDataEvents) {
-        if (!cancelled && evt.sessionId === sid) {
-          onDataRef.current(evt.data);
-        }
-      }
-      pendingDataEvents.length = 0;
       for (const evt of pendingStatusEvents) {
         if (!cancelled && evt.sessionId === sid) {
           const status: PtyStatus = VALID_PTY_STATUSES.has(evt.status)
             ? (evt.status as PtyStatus)
             : "error";
-          onStatusChangeRef.current({ status, errorMessage: evt.errorMessage });
+          onStatusChangeRef.current({
+            status,
+            errorMessage: evt.errorMessage,
+            reason: evt.reason,
+            exitCode: evt.exitCode,
+          });
         }
       }
       pendingStatusEvents.length = 0;
     }
 
     async function setup() {
-      // Register listeners FIRST so no events are lost between spawn and listen.
-      // Events arriving before the session ID is known are buffered and replayed.
-      const p1 = listen<PtyDataEvent>("pty:data", (event) => {
-        handleDataEvent(event.payload);
+      // Register the status listener FIRST so no status is lost between spawn
+      // and listen. Statuses arriving before the session ID is known are
+      // buffered and replayed.
+      const p1 = listen<PtyStatusEvent>("pty:status", (event) => {
+        handleStatusEvent(event.payload);
       });
       listenPromises.push(p1);
       const unlisten1 = await p1;
       if (cancelled) { unlisten1(); return; }
       unlistenFns.push(unlisten1);
 
-      const p2 = listen<PtyStatusEvent>("pty:status", (event) => {
-        handleStatusEvent(event.payload);
+      if (cancelled) return;
+      // Raw output bytes; the n-th message has sequence number n.
+      let seq = 0;
+      const output = new Channel<ArrayBuffer>();
+      output.onmessage = (data) => {
+        if (cancelled) return;
+        seq += 1;
+        onDataRef.current(new Uint8Array(data));
+        if (seq % ACK_EVERY === 0 && sessionIdRef.current > 0) {
+          invoke("pty_ack", { sessionId: sessionIdRef.current, seq }).catch(() => {});
+        }
+      };
+      // Follow a gateway the supervisor restarted under a new session ID.
+      const p2 = listen<GatewayRestarted>("gateway:restarted", async (event) => {
+        const { previousSessionId, sessionId } = event.payload;
+        if (cancelled || previousSessionId !== sessionIdRef.current) return;
+        try {
+          seq = 0;
+          await invoke("pty_reattach", { sessionId, output });
+          if (cancelled) return;
+          sessionIdRef.current = sessionId;
+          sessionStorage.setItem(storageKey, String(sessionId));
+          if (activeRef.current) claimWindowTitle(sessionId);
+          onStatusChangeRef.current({ status: "running" });
+        } catch (err) {
+          console.error("[pty] Reattach after restart failed:", err);
+        }
       });
       listenPromises.push(p2);
       const unlisten2 = await p2;
-      if (cancelled) { unlisten2(); unlisten1(); return; }
+      if (cancelled) { unlisten2(); return; }
       unlistenFns.push(unlisten2);
 
-      // Now spawn - events emitted after this will be caught by listeners above.
-      if (cancelled) return;
+      // Reattach to a session that survived a reload, if there is one.
+      async function reattach(): Promise<number | null> {
+        const saved = Number(sessionStorage.getItem(storageKey)) || await runningGateway();
+        if (!saved) return null;
+        try {
+          await invoke("pty_reattach", { sessionId: saved, output });
+          return saved;
+        } catch {
+          sessionStorage.removeItem(storageKey);
+          return null;
+        }
+      }
+
+      // A window opened after a headless start adopts the gateway the
+      // backend already runs instead of spawning a second one.
+      async function runningGateway(): Promise<number | null> {
+        if (modeRef.current.mode !== "gateway") return null;
+        const sessions = await invoke<SessionInfo[]>("pty_list").catch(() => []);
+        return sessions.find((s) => s.args[0] === "gateway")?.sessionId ?? null;
+      }
+
       try {
-        const sid = await invoke<number>("pty_spawn", {
+        const sid = (await reattach()) ?? await invoke<number>("pty_spawn", {
           settings: settingsRef.current,
-          args: argsRef.current,
+          mode: modeRef.current,
           cols,
           rows,
+          options: { pixelWidth, pixelHeight, cwd: cwdRef.current, env: envRef.current },
+          output,
         });
         if (cancelled) {
           invoke("pty_kill", { sessionId: sid }).catch(() => {});
           return;
         }
         sessionIdRef.current = sid;
+        sessionStorage.setItem(storageKey, String(sid));
+        if (activeRef.current) claimWindowTitle(sid);
+
         onStatusChangeRef.current({ status: "running" });
-        // Replay any events that arrived before the session ID was known
+        // Replay any statuses that arrived before the session ID was known
         drainPendingEvents(sid);
       } catch (err) {
         if (cancelled) return;
         console.error("[pty] Spawn failed:", err);
         onStatusChangeRef.current({
           status: "error",
-          errorMessage: String(err),
+          errorMessage: spawnErrorMessage(err),
         });
       }
     }
@@ -157,30 +272,54 @@ export function usePtySession({ onData, onStatusChange, settings, args, initialS
         if (!alreadyCalled.has(fn)) fn();
       }).catch(() => {}));
       if (sessionIdRef.current > 0) {
+        sessionStorage.removeItem(storageKey);
         invoke("pty_kill", { sessionId: sessionIdRef.current }).catch(() => {});
       }
     };
   }, [initialSize]); // eslint-disable-line react-hooks/exhaustive-deps
 
+  useEffect(() => {
+    if (active && sessionIdRef.current > 0) claimWindowTitle(sessionIdRef.current);
+  }, [active]);
+
   const write = useCallback(async (data: string) => {
     const sid = sessionIdRef.current;
     if (sid === 0) return;
     try {
-      await invoke("pty_write", { sessionId: sid, data });
+      for (const chunk of writeChunks(data)) {
+        await invoke("pty_write", { sessionId: sid, data: chunk });
+      }
     } catch {
       // PTY may be dead; status event will handle it
     }
   }, []);
 
-  const resize = useCallback(async (cols: number, rows: number) => {
+  const paste = useCallback(async (text: string) => {
+    const sid = sessionIdRef.current;
+    if (sid === 0) return;
+    try {
+      await invoke("pty_paste", { sessionId: sid, text });
+    } catch {
+      // PTY may be dead; status event will handle it
+    }
+  }, []);
+
+  /** Answers prompts in order; rejects if a prompt doesn't show up in time. */
+  const enqueue = useCallback(async (inputs: string[]) => {
+    const sid = sessionIdRef.current;
+    if (sid === 0) return;
+    await invoke("pty_enqueue", { sessionId: sid, inputs });
+  }, []);
+
+  const resize = useCallback(async ({ cols, rows, pixelWidth, pixelHeight }: TerminalSize) => {
     const sid = sessionIdRef.current;
     if (sid === 0) return;
     try {
-      await invoke("pty_resize", { sessionId: sid, cols, rows });
+      await invoke("pty_resize", { sessionId: sid, cols, rows, pixelWidth, pixelHeight });
     } catch {
       // Ignore resize errors
     }
   }, []);
 
-  return { write, resize };
+  return { write, paste, enqueue, resize };
 }
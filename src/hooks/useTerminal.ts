@@ -4,22 +4,44 @@ import { FitAddon } from "@xterm/addon-fit";
 import { WebLinksAddon } from "@xterm/addon-web-links";
 import "@xterm/xterm/css/xterm.css";
 
+export interface TerminalSize {
+  cols: number;
+  rows: number;
+  /** Size of the text area in CSS pixels, for image protocols. */
+  pixelWidth: number;
+  pixelHeight: number;
+}
+
 interface UseTerminalOptions {
   onData: (data: string) => void;
-  onResize: (cols: number, rows: number) => void;
+  onPaste: (text: string) => void;
+  onResize: (size: TerminalSize) => void;
   active: boolean;
 }
 
-export function useTerminal({ onData, onResize, active }: UseTerminalOptions) {
+function measure(term: Terminal): TerminalSize {
+  const screen = term.element?.querySelector(".xterm-screen");
+  const rect = screen?.getBoundingClientRect();
+  return {
+    cols: term.cols,
+    rows: term.rows,
+    pixelWidth: Math.round(rect?.width ?? 0),
+    pixelHeight: Math.round(rect?.height ?? 0),
+  };
+}
+
+export function useTerminal({ onData, onPaste, onResize, active }: UseTerminalOptions) {
   const containerRef = useRef<HTMLDivElement>(null);
   const termRef = useRef<Terminal | null>(null);
   const fitAddonRef = useRef<FitAddon | null>(null);
   const resizeObserverRef = useRef<ResizeObserver | null>(null);
-  const [initialSize, setInitialSize] = useState<{ cols: number; rows: number } | null>(null);
+  const [initialSize, setInitialSize] = useState<TerminalSize | null>(null);
 
   // Keep callbacks and active state in refs so mount-time closures use latest values
   const onDataRef = useRef(onData);
   onDataRef.current = onData;
+  const onPasteRef = useRef(onPaste);
+  onPasteRef.current = onPaste;
   const onResizeRef = useRef(onResize);
   onResizeRef.current = onResize;
   const activeRef = useRef(active);
@@ -56,13 +78,25 @@ export function useTerminal({ onData, onResize, active }: UseTerminalOptions) {
     let initialRafId = requestAnimationFrame(() => {
       initialRafId = 0;
       fitAddon.fit();
-      setInitialSize({ cols: term.cols, rows: term.rows });
-      onResizeRef.current(term.cols, term.rows);
+      const size = measure(term);
+      setInitialSize(size);
+      onResizeRef.current(size);
     });
 
     // Handle user input via ref to always use latest callback
     const dataDisposable = term.onData((data) => onDataRef.current(data));
 
+    // Route pastes to the backend, which applies bracketed paste and line
+    // ending normalization based on the modes the application enabled.
+    const handlePaste = (event: ClipboardEvent) => {
+      const text = event.clipboardData?.getData("text/plain");
+      if (!text) return;
+      event.preventDefault();
+      event.stopPropagation();
+      onPasteRef.current(text);
+    };
+    container.addEventListener("paste", handlePaste, true);
+
     // Auto-focus the terminal
     term.focus();
 
@@ -78,7 +112,7 @@ export function useTerminal({ onData, onResize, active }: UseTerminalOptions) {
           resizeRafId = 0;
           if (disposed || !activeRef.current) return;
           fitAddon.fit();
-          onResizeRef.current(term.cols, term.rows);
+          onResizeRef.current(measure(term));
         });
       }, 50);
     });
@@ -91,6 +125,7 @@ export function useTerminal({ onData, onResize, active }: UseTerminalOptions) {
       cancelAnimationFrame(resizeRafId);
       clearTimeout(resizeTimer);
       dataDisposable.dispose();
+      container.removeEventListener("paste", handlePaste, true);
       observer.disconnect();
       term.dispose();
       termRef.current = null;
@@ -111,13 +146,13 @@ export function useTerminal({ onData, onResize, active }: UseTerminalOptions) {
     if (!fitAddon) return;
     const rafId = requestAnimationFrame(() => {
       fitAddon.fit();
-      onResizeRef.current(term.cols, term.rows);
+      onResizeRef.current(measure(term));
       term.focus();
     });
     return () => cancelAnimationFrame(rafId);
   }, [active]);
 
-  const writeToTerminal = useCallback((data: string) => {
+  const writeToTerminal = useCallback((data: string | Uint8Array) => {
     termRef.current?.write(data);
   }, []);
 
@@ -2,6 +2,8 @@ import { useState, useEffect, useCallback, useRef } from "react";
 import { check } from "@tauri-apps/plugin-updater";
 import type { Update } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
+import { invoke } from "@tauri-apps/api/core";
+import type { NetworkAdvice } from "../types/index.ts";
 
 export type UpdateStatus =
   | "idle"
@@ -41,6 +43,14 @@ export function useUpdater(): UpdaterState {
 
   const doCheck = useCallback(async () => {
     if (statusRef.current !== "idle" && statusRef.current !== "error") return;
+    try {
+      // Automatic checks wait for an unmetered connection if the user asked.
+      const network = await invoke<NetworkAdvice>("network_status");
+      if (network.deferDownloads) return;
+    } catch {
+      // Unknown network state never blocks updates.
+    }
+    if (unmountedRef.current) return;
     statusRef.current = "checking";
     setStatus("checking");
     setVersion(null);
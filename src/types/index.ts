@@ -1,16 +1,553 @@
 export const APP_VERSION = "0.0.1";
 
+export type ShortcutAction = "new-session" | "toggle-window" | "kill-session";
+
+export interface ShortcutBinding {
+  action: ShortcutAction;
+  accelerator: string;
+  global?: boolean;
+}
+
 export interface Settings {
   apiKeys: Record<string, string>;
+  /**
+   * Extra environment variables passed through to OpenClaw. Loader hooks
+   * (LD_*, DYLD_*, NODE_OPTIONS), PATH and OPENCLAW_* are refused.
+   */
+  envPassthrough?: string[];
+  /** Absolute directories put before the inherited PATH for OpenClaw. */
+  pathPrepend?: string[];
+  /** Absolute directories put after the inherited PATH for OpenClaw. */
+  pathAppend?: string[];
+  /** Node.js heap limit for OpenClaw, in MB (512–65536). */
+  nodeHeapMb?: number;
+  nodeSourceMaps?: boolean;
+  /** Loopback inspector port for interactive sessions; needs `developerMode`. */
+  nodeInspectPort?: number;
+  /** Locale (LANG, LC_ALL) for OpenClaw instead of the app's, e.g. "en_US.UTF-8". */
+  locale?: string;
+  /** Timezone (TZ) for OpenClaw instead of the app's, e.g. "Europe/Berlin". */
+  timezone?: string;
+  /** TERM for OpenClaw sessions; defaults to "xterm-256color". */
+  term?: TermValue;
+  /** Color env vars for OpenClaw; defaults to "truecolor". */
+  colorMode?: ColorMode;
+  /** Allows `pty_spawn_shell` and the Node.js inspector. */
+  developerMode?: boolean;
+  profiles?: Profile[];
+  /** Profile used when a launch doesn't name one; unset for the default profile. */
+  activeProfile?: string;
+  /** Parent of `openclaw-state/`; change it with `state_dir_move`. */
+  stateDirectory?: string;
+  shortcuts?: ShortcutBinding[];
+  idleTimeoutMinutes?: number;
+  idleAction?: IdleAction;
+  idleThresholdSeconds?: number;
+  encryptedStateFiles?: string[];
+  /** Seconds the state key stays cached after a keychain read (default 900); 0 disables. `lock_credentials` drops it. */
+  credentialCacheSeconds?: number;
+  meteredPolicy?: MeteredPolicy;
+  closeToTray?: boolean;
+  syncWindowTitle?: boolean;
+  /** Serve the token-protected local automation API. Off by default. */
+  automationApi?: boolean;
+  automationApiPort?: number;
+  /** Serve the MCP server that lets agents drive sessions. Off by default. */
+  mcpServer?: boolean;
+  mcpServerPort?: number;
+  scheduledTasks?: ScheduledTask[];
+  /** Restart crashed or unresponsive gateways; defaults to true. */
+  autoRestartGateway?: boolean;
+  prewarmGateway?: boolean;
+  scrollbackBytes?: number;
+  killGraceMs?: number;
+  statsIntervalSeconds?: number;
+  readBufferBytes?: number;
+  leftoverCapBytes?: number;
+  autoResponses?: AutoResponseRule[];
+  transcriptLogging?: boolean;
+  transcriptMaxFileBytes?: number;
+  transcriptMaxFiles?: number;
+  transcriptRetentionDays?: number;
+}
+
+export type MeteredPolicy = "ignore" | "warn" | "defer";
+
+export type ExportFormat = "plain" | "html";
+
+export interface NetworkAdvice {
+  metered: boolean | null;
+  roaming: boolean;
+  deferDownloads: boolean;
+  warn: boolean;
 }
 
+export type IdleAction = "terminate" | "hibernate";
+
+/** TERM values a session may be started with (settings or `term` spawn option). */
+export type TermValue = "xterm-256color" | "xterm" | "xterm-direct";
+
+/** "truecolor" sets COLORTERM, "force" adds FORCE_COLOR, "off" sets NO_COLOR. */
+export type ColorMode = "truecolor" | "force" | "off";
+
 export type AppMode = "welcome" | "onboard" | "gateway";
 
 export type TabId = "gateway" | "chat" | "webui";
 
 export type PtyStatus = "starting" | "running" | "stopped" | "error";
 
+export type PtyCloseReason =
+  | "user_kill"
+  | "window_close"
+  | "supervisor_restart"
+  | "scheduled_restart"
+  | "child_exit"
+  | "watchdog"
+  | "idle_timeout";
+
+export type PtySignal = "SIGINT" | "SIGTERM" | "SIGHUP" | "SIGWINCH" | "SIGKILL";
+
 export interface PtyState {
   status: PtyStatus;
   errorMessage?: string;
+  reason?: PtyCloseReason;
+  exitCode?: number;
+}
+
+export interface LockHolder {
+  pid: number;
+  version: string;
+  kind: "desktop" | "cli";
+}
+
+export type StateLockStatus =
+  | { status: "owned" }
+  | { status: "conflict"; holder: LockHolder }
+  | { status: "read_only"; holder: LockHolder | null };
+
+/** What a terminal session runs; expanded into OpenClaw args by the backend. */
+export type LaunchMode =
+  | { mode: "onboard"; skipDaemon?: boolean }
+  | { mode: "gateway"; port?: number | null; verbose?: boolean }
+  | { mode: "tui"; session?: string | null };
+
+/** A gateway running outside the app, surviving restarts. */
+export interface DetachedSession {
+  pid: number;
+  args: string[];
+  startedAt: number;
+  logPath: string;
+  /** Process start time in seconds since the epoch, used to detect pid reuse. */
+  processStartedAt: number;
+  exe: string | null;
+}
+
+export interface LogChunk {
+  data: string;
+  nextOffset: number;
+}
+
+/** Display information the frontend attaches to a session. */
+export interface SessionLabel {
+  name?: string | null;
+  color?: string | null;
+  metadata?: unknown;
+}
+
+/** A live session, as returned by `pty_list`. */
+export interface SessionInfo {
+  sessionId: number;
+  args: string[];
+  createdAt: number;
+  cols: number;
+  rows: number;
+  pid: number | null;
+  paused: boolean;
+  label: SessionLabel;
+  lastOutputAt: number | null;
+  lastInputAt: number | null;
+  idle: boolean;
+}
+
+/** One session's outcome in the result of `pty_broadcast`/`pty_broadcast_all`. */
+export interface BroadcastResult {
+  sessionId: number;
+  /** Null if the write succeeded. */
+  error: string | null;
+}
+
+/** Payload of `pty:link`, emitted for each OSC 8 hyperlink in the output. */
+export interface PtyLinkEvent {
+  sessionId: number;
+  url: string;
+  text: string;
+  /** Whether `open_link` will accept the URL's scheme. */
+  allowed: boolean;
+}
+
+/** A session's rendered screen, as returned by `pty_get_screen`. */
+export interface ScreenSnapshot {
+  rows: number;
+  cols: number;
+  lines: string[];
+  /** Escape sequences that redraw the screen in a terminal of the same size. */
+  formatted: string;
+  cursorRow: number;
+  cursorCol: number;
+  cursorHidden: boolean;
+  alternateScreen: boolean;
+}
+
+/** A URL a session printed, from `pty_get_links` or `pty:links`. */
+export interface DetectedUrl {
+  url: string;
+  /** Milliseconds since the Unix epoch. */
+  seenAt: number;
+}
+
+/** An interactive prompt a session is waiting on (`pty:prompt`). */
+export type Prompt =
+  | { kind: "yesNo"; question: string; default: boolean | null }
+  | { kind: "continue"; text: string }
+  | { kind: "choice"; question: string; options: { key: string; label: string }[] };
+
+/** Payload of `pty:prompt`; `prompt` is null once the session moves on. */
+export interface PtyPromptEvent {
+  sessionId: number;
+  prompt: Prompt | null;
+}
+
+/** Reacts to session output matching `pattern` (a regular expression). */
+export interface AutoResponseRule {
+  pattern: string;
+  action: "write" | "notify";
+  /** Text to write (verbatim, "\r" for Enter) or the notification body. */
+  text?: string;
+  /** Only apply to sessions running this subcommand, e.g. "onboard". */
+  subcommand?: string;
+  enabled?: boolean;
+}
+
+/** Resource usage of a session's process tree (`pty_stats`, `pty:stats`). */
+export interface SessionStats {
+  sessionId: number;
+  pid: number;
+  processCount: number;
+  cpuPercent: number;
+  rssBytes: number;
+  openFiles: number | null;
+}
+
+/** What the app runs, from `openclaw_version`. */
+export interface ConfigFile {
+  name: string;
+  exists: boolean;
+  sealed: boolean;
+}
+
+/** Result of `openclaw_config_status`. */
+export interface ConfigStatus {
+  configured: boolean;
+  files: ConfigFile[];
+  providers: string[];
+  channels: string[];
+  lastOnboardAt: string | null;
+  schemaVersion: string | null;
+  errors: string[];
+}
+
+/** Payload of `openclaw:config-changed`. */
+export interface ConfigChanged {
+  /** Changed paths, relative to the OpenClaw state dir. */
+  paths: string[];
+}
+
+export type ImportMode = "copy" | "link";
+
+export interface ImportEntry {
+  name: string;
+  isDir: boolean;
+  conflict: boolean;
+}
+
+/** Result of `openclaw_import_scan`: the standalone CLI's state, if any. */
+export interface ImportScan {
+  source: string;
+  found: boolean;
+  entries: ImportEntry[];
+}
+
+export interface ImportReport {
+  imported: string[];
+  skipped: string[];
+}
+
+/** Describes a state backup; returned by `state_restore`. */
+export interface BackupManifest {
+  version: number;
+  createdAt: number;
+  appVersion: string;
+}
+
+/** State snapshot taken before an OpenClaw update. */
+export interface Snapshot {
+  id: string;
+  createdAt: number;
+  fromVersion: string;
+  toVersion: string;
+}
+
+/** A named setup with its own OpenClaw state, API keys and launch defaults. */
+export interface Profile {
+  id: string;
+  name: string;
+  apiKeys: Record<string, string>;
+  gatewayPort?: number;
+}
+
+/** Payload of `profile:changed`. */
+export interface ProfileChanged {
+  profile: string | null;
+}
+
+export type HealthStatus = "up" | "degraded" | "down";
+
+/** Payload of `gateway:health` and entries of `gateway_health`. */
+export interface GatewayHealth {
+  /** `null` for the detached gateway and external gateways. */
+  sessionId: number | null;
+  /** Started outside the app against the same state dir. */
+  external: boolean;
+  port: number;
+  status: HealthStatus;
+  latencyMs: number | null;
+  version: string | null;
+  checkedAt: number;
+}
+
+/** Payload of `gateway:message`, relayed from the gateway's event stream. */
+export interface GatewayMessage {
+  channel: string | null;
+  sessionKey: string | null;
+  from: string | null;
+  text: string;
+}
+
+/** Payload of `gateway:channel`. */
+export interface ChannelState {
+  channel: string;
+  account: string | null;
+  connected: boolean;
+}
+
+/** Payload of `gateway:error`. */
+export interface GatewayError {
+  message: string;
+  code: string | null;
+}
+
+/** Result of `automation_api_info`. */
+export interface AutomationInfo {
+  running: boolean;
+  url: string | null;
+  /** File holding the bearer token scripts must send. */
+  tokenPath: string;
+}
+
+/** Events a hook script registered via `hook_register` can run on. */
+export type HookEvent =
+  | "session-start"
+  | "gateway-start"
+  | "gateway-stop"
+  | "gateway-crash"
+  | "onboarding-complete";
+
+/**
+ * A task run on a cron schedule (five fields, local time, or `@daily` etc.):
+ * an allowlisted `openclaw` command, or a restart of the supervised gateways.
+ */
+export type ScheduledTask = {
+  id: string;
+  name?: string;
+  schedule: string;
+  paused?: boolean;
+  timeoutMs?: number;
+} & ({ action: "exec"; args: string[] } | { action: "restartGateway" });
+
+/** A recorded scheduled run; payload of `scheduler:run`. */
+export interface TaskRun {
+  taskId: string;
+  /** Seconds since the Unix epoch. */
+  startedAt: number;
+  durationMs: number;
+  ok: boolean;
+  exitCode?: number;
+  timedOut: boolean;
+  output: string;
+  error?: string;
+}
+
+/** Result of `mcp_server_info`. */
+export interface McpInfo {
+  running: boolean;
+  /** Streamable HTTP endpoint to configure in the agent. */
+  url: string | null;
+  tokenPath: string;
+}
+
+export type ProviderCategory = "model" | "service";
+
+/** A non-secret provider variable, such as a base URL, stored in `apiKeys`. */
+export interface ProviderSetting {
+  envVar: string;
+  label: string;
+  kind: "url" | "id";
+  placeholder: string;
+}
+
+/** One entry of `providers_list`. */
+export interface ProviderStatus {
+  id: string;
+  name: string;
+  category: ProviderCategory;
+  /** Null for providers that need no key (local servers). */
+  keyVar: string | null;
+  keyPrefix: string | null;
+  settings: ProviderSetting[];
+  keySet: boolean;
+  /** The set key with its middle hidden. */
+  maskedKey: string | null;
+  /** False if a key is set but lacks the expected prefix. */
+  keyFormatOk: boolean;
+  /** Whether `validate_api_key` can check this provider's key. */
+  checkable: boolean;
+}
+
+/** Result of `validate_api_key`. */
+export type KeyCheck =
+  | { status: "ok" }
+  | { status: "unauthorized"; message: string }
+  | { status: "network-error"; message: string };
+
+/** Result of `gateway_proxy`: where to reach the gateway API from the webview. */
+export interface ProxyInfo {
+  baseUrl: string;
+  /** Sent as the `X-ClawRunner-Token` header. */
+  token: string;
+}
+
+/** Payload of `gateway:ws-status` and result of `gateway_events_status`. */
+export interface GatewayConnectionStatus {
+  connected: boolean;
+  port: number | null;
+}
+
+/** Gateway started outside the app against its state dir; payload of `gateway:external`. */
+export interface ExternalGateway {
+  pid: number;
+  port: number;
+  startedAt: number;
+  command: string[];
+}
+
+/** `pty_spawn` error when an external gateway is used instead of spawning one. */
+export interface ExternalGatewayError {
+  message: string;
+  gateway: ExternalGateway;
+}
+
+/** `pty_spawn` error when a gateway's port is already bound. */
+export interface PortConflict {
+  port: number;
+  pid: number | null;
+  processName: string | null;
+  /** The owner looks like another OpenClaw gateway. */
+  openclaw: boolean;
+  message: string;
+  remediation: string;
+}
+
+/** Payload of `gateway:restarting`, sent before a supervised gateway is respawned. */
+export interface GatewayRestarting {
+  sessionId: number;
+  attempt: number;
+  delayMs: number;
+}
+
+/** Payload of `gateway:restarted`; views showing `previousSessionId` reattach. */
+export interface GatewayRestarted {
+  previousSessionId: number;
+  sessionId: number;
+  attempt: number;
+}
+
+/** Payload of `gateway:crashloop`, sent when the supervisor stops restarting a gateway. */
+export interface GatewayCrashloop {
+  sessionId: number;
+  exits: number;
+  windowSeconds: number;
+  exitCode: number | null;
+  /** Last screenful of the final attempt's output, ANSI-stripped. */
+  output: string[];
+}
+
+/** Payload of `openclaw:oom`, sent when a session dies of Node.js heap exhaustion. */
+export interface OomEvent {
+  sessionId: number;
+  /** e.g. "gateway" or "tui". */
+  subcommand: string;
+  /** The heap size the session ran with; null for Node's default. */
+  heapMb: number | null;
+  suggestedHeapMb: number;
+  message: string;
+}
+
+/** Payload of `gateway:restart-failed`. */
+export interface GatewayRestartFailed {
+  sessionId: number;
+  error: string;
+}
+
+export interface ExecOutput {
+  stdout: string;
+  stderr: string;
+  exitCode: number | null;
+  timedOut: boolean;
+}
+
+export type ExecEvent =
+  | { event: "line"; stream: "stdout" | "stderr"; line: string }
+  | {
+      event: "exit";
+      exitCode: number | null;
+      timedOut: boolean;
+      cancelled: boolean;
+      error: string | null;
+    };
+
+export interface OpenClawVersion {
+  openclawVersion: string | null;
+  nodeVersion: string | null;
+  bundled: boolean;
+}
+
+/** Output of `recording_export_video`; MP4 needs ffmpeg installed. */
+export type VideoFormat = "gif" | "mp4";
+
+export interface VideoOptions {
+  /** Frames per second (default 10, at most 30). */
+  fps?: number;
+  /** Multiplies the recorded pace (default 1). */
+  speed?: number;
+  /** Pauses longer than this many seconds are cut down to it (default 2). */
+  maxIdle?: number;
+  /** Font size in pixels (default 16). */
+  fontSize?: number;
+}
+
+/** Payload of `recording:export-progress`. */
+export interface ExportProgress {
+  path: string;
+  frame: number;
+  frames: number;
 }